@@ -0,0 +1,95 @@
+//! Short animations in review: the answer pane unfolding from its middle
+//! when a card is flipped, and the card sliding in when moving to another.
+//!
+//! An animation only changes the area a pane is drawn in, over time, so
+//! panes render as usual and land where they would have been without it.
+
+use std::time::{Duration, Instant};
+
+use ratatui::layout::Rect;
+
+// Time between frames while something is moving
+pub const FRAME_TIME: Duration = Duration::from_millis(16);
+const DURATION: Duration = Duration::from_millis(180);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    // The answer is revealed
+    Flip,
+    // Another card comes up, after this one or before it
+    Slide { forward: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Animation {
+    pub transition: Transition,
+    started: Instant,
+}
+
+impl Animation {
+    pub fn start(transition: Transition, now: Instant) -> Animation {
+        Animation {
+            transition,
+            started: now,
+        }
+    }
+
+    /// How far along the animation is, from 0 to 1, `None` once it's over.
+    pub fn progress(&self, now: Instant) -> Option<f64> {
+        let elapsed = now.saturating_duration_since(self.started);
+        (elapsed < DURATION).then(|| elapsed.as_secs_f64() / DURATION.as_secs_f64())
+    }
+
+    /// The part of `area` to draw in at `progress`.
+    pub fn area(&self, area: Rect, progress: f64) -> Rect {
+        // Fast at first, slowing down at the end
+        let eased = 1.0 - (1.0 - progress.clamp(0.0, 1.0)).powi(3);
+        match self.transition {
+            Transition::Flip => {
+                let width = ((area.width as f64 * eased).round() as u16).max(2);
+                Rect {
+                    x: area.x + (area.width.saturating_sub(width)) / 2,
+                    width: width.min(area.width),
+                    ..area
+                }
+            }
+            Transition::Slide { forward } => {
+                // A quarter of the width, shrinking to nothing
+                let offset = (area.width as f64 / 4.0 * (1.0 - eased)).round() as u16;
+                Rect {
+                    x: if forward { area.x + offset } else { area.x },
+                    width: area.width - offset,
+                    ..area
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_area() {
+        let area = Rect::new(0, 0, 80, 10);
+        let flip = Animation::start(Transition::Flip, Instant::now());
+        assert_eq!(flip.area(area, 0.0), Rect::new(39, 0, 2, 10));
+        assert_eq!(flip.area(area, 1.0), area);
+
+        let slide = Animation::start(Transition::Slide { forward: true }, Instant::now());
+        assert_eq!(slide.area(area, 0.0), Rect::new(20, 0, 60, 10));
+        assert_eq!(slide.area(area, 1.0), area);
+
+        let back = Animation::start(Transition::Slide { forward: false }, Instant::now());
+        assert_eq!(back.area(area, 0.0), Rect::new(0, 0, 60, 10));
+    }
+
+    #[test]
+    fn test_progress() {
+        let now = Instant::now();
+        let animation = Animation::start(Transition::Flip, now);
+        assert_eq!(animation.progress(now), Some(0.0));
+        assert_eq!(animation.progress(now + DURATION), None);
+    }
+}
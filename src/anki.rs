@@ -0,0 +1,194 @@
+//! Two-way sync of topics with a running Anki through the AnkiConnect
+//! add-on, for capturing cards in the terminal and reviewing them in Anki.
+//!
+//! A topic is synced with the Anki deck of the same name. Notes are read
+//! by their first two fields, and new cards go into Anki as "Basic" notes.
+//! Anki keeps fields as HTML, so line breaks and the common entities are
+//! converted both ways and other markup is dropped on the way in.
+
+use std::io;
+
+use mem_flip::deck::{Flashcard, Topics};
+use serde_json::{Value, json};
+
+use crate::import::{self, Imported, MergeStrategy};
+
+// Where AnkiConnect listens unless configured otherwise
+pub static DEFAULT_URL: &str = "http://127.0.0.1:8765";
+// AnkiConnect API version the requests are written for
+const VERSION: u32 = 6;
+static NOTE_TYPE: &str = "Basic";
+
+/// A running Anki with AnkiConnect.
+#[derive(Debug, Clone)]
+pub struct Anki {
+    url: String,
+}
+
+/// What a sync changed on both sides.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Synced {
+    pub pulled: Imported,
+    pub pushed: usize,
+}
+
+impl Anki {
+    pub fn new(url: &str) -> Anki {
+        Anki {
+            url: url.to_string(),
+        }
+    }
+
+    // Calls one AnkiConnect action, returning its result
+    fn call(&self, action: &str, params: Value) -> io::Result<Value> {
+        let request = json!({ "action": action, "version": VERSION, "params": params });
+        let mut response = ureq::post(&self.url)
+            .header("Content-Type", "application/json")
+            .send(request.to_string())
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    format!(
+                        "can't reach Anki at {}, is it running with AnkiConnect? {}",
+                        self.url, e
+                    ),
+                )
+            })?;
+        let body = response
+            .body_mut()
+            .read_to_string()
+            .map_err(ureq::Error::into_io)?;
+        let body: Value = serde_json::from_str(&body)?;
+        match &body["error"] {
+            Value::Null => Ok(body["result"].clone()),
+            error => Err(io::Error::other(format!(
+                "Anki refused {}: {}",
+                action,
+                error.as_str().unwrap_or_default()
+            ))),
+        }
+    }
+
+    // The cards in the Anki deck `deck`
+    fn pull(&self, deck: &str) -> io::Result<Vec<Flashcard>> {
+        let query = format!("deck:\"{}\"", deck.replace('"', "\\\""));
+        let ids = self.call("findNotes", json!({ "query": query }))?;
+        let notes = self.call("notesInfo", json!({ "notes": ids }))?;
+        let cards = notes
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|note| {
+                // Fields are keyed by name, `order` says which comes first
+                let mut fields: Vec<(u64, &str)> = note["fields"]
+                    .as_object()?
+                    .values()
+                    .map(|field| {
+                        (
+                            field["order"].as_u64().unwrap_or_default(),
+                            field["value"].as_str().unwrap_or_default(),
+                        )
+                    })
+                    .collect();
+                fields.sort();
+                let [(_, front), (_, back), ..] = fields.as_slice() else {
+                    return None;
+                };
+                Some(Flashcard::new(from_html(front), from_html(back)))
+            })
+            .collect();
+        Ok(cards)
+    }
+
+    fn push(&self, deck: &str, cards: &[&Flashcard]) -> io::Result<()> {
+        self.call("createDeck", json!({ "deck": deck }))?;
+        let notes: Vec<Value> = cards
+            .iter()
+            .map(|card| {
+                json!({
+                    "deckName": deck,
+                    "modelName": NOTE_TYPE,
+                    "fields": { "Front": to_html(&card.question), "Back": to_html(&card.answer) },
+                    "options": { "allowDuplicate": false },
+                })
+            })
+            .collect();
+        self.call("addNotes", json!({ "notes": notes }))?;
+        Ok(())
+    }
+}
+
+/// Syncs each topic in `names` with the Anki deck of the same name: cards
+/// only in Anki are added to the topic, answers edited in Anki are taken
+/// over, and cards only in the topic are added to Anki.
+pub fn sync(anki: &Anki, topics: &mut Topics, names: &[String]) -> io::Result<Synced> {
+    let mut synced = Synced::default();
+    for name in names {
+        let theirs = anki.pull(name)?;
+        let mut pulled = Topics::default();
+        pulled.topics_map.insert(name.clone(), theirs.clone());
+        let result = import::apply(topics, pulled, MergeStrategy::Overwrite);
+        synced.pulled.added += result.added;
+        synced.pulled.updated += result.updated;
+
+        let missing: Vec<&Flashcard> = topics
+            .topics_map
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(|card| !theirs.iter().any(|c| c.question == card.question))
+            .collect();
+        if !missing.is_empty() {
+            anki.push(name, &missing)?;
+            synced.pushed += missing.len();
+        }
+    }
+    Ok(synced)
+}
+
+fn to_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+fn from_html(html: &str) -> String {
+    let html = html
+        .replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("</div>", "\n");
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html() {
+        assert_eq!(to_html("a < b\nb & c"), "a &lt; b<br>b &amp; c");
+        assert_eq!(
+            from_html("<div><b>perro</b></div><div>a &lt; b&nbsp;&amp; c</div>"),
+            "perro\na < b & c"
+        );
+        assert_eq!(from_html(&to_html("x > y\nz")), "x > y\nz");
+    }
+}
@@ -0,0 +1,76 @@
+//! Replacing files whole without ever leaving one half written.
+//!
+//! The new contents go to a temporary file in the same directory, which is
+//! flushed to disk and then renamed over the old file. A crash or a quit
+//! partway through leaves the old file as it was.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Tells apart the temporary files of writes running at once
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Replaces the file at `path` with `contents`, like [`fs::write`].
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_with(path, |writer| writer.write_all(contents.as_ref()))
+}
+
+/// Replaces the file at `path` with what `fill` writes. The file is left
+/// as it was if `fill` fails.
+pub fn write_with(
+    path: &Path,
+    fill: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let temp = temp_path(path);
+    let result = (|| {
+        let file = File::create(&temp)?;
+        // Keeps a deck that was made private private
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        let mut writer = BufWriter::new(file);
+        fill(&mut writer)?;
+        let file = writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+// `.deck.json.1234-0.tmp` next to `deck.json`
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let number = NEXT_TEMP.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}.{}-{}.tmp", name, process::id(), number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("deck.json");
+        write(&path, "old").unwrap();
+        write(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+
+        let failed = write_with(&path, |writer| {
+            writer.write_all(b"half")?;
+            Err(io::Error::other("disk full"))
+        });
+        assert!(failed.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
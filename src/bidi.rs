@@ -0,0 +1,358 @@
+//! Right-to-left text (Arabic, Hebrew) for display.
+//!
+//! Terminals lay cells out strictly left to right, so lines containing RTL
+//! characters are wrapped in logical order first and each row is then
+//! reordered into visual order with the Unicode bidirectional algorithm.
+//! Paragraphs whose first strong character is RTL are right-aligned.
+
+use std::ops::Range;
+
+use ratatui::text::{Line, Span};
+use unicode_bidi::{BidiClass, ParagraphBidiInfo, bidi_class};
+use unicode_width::UnicodeWidthChar;
+
+/// Rows of a wrapped line in visual order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct VisualLine {
+    pub rows: Vec<String>,
+    /// Paragraph direction, right-to-left rows should be right-aligned
+    pub rtl: bool,
+    // Places for the cursor on each row from left to right, as byte offsets
+    // into the line: one per character shown, then the break spaces dropped
+    // from the row and, on the last row, the end of the line, all on the
+    // side the paragraph ends
+    stops: Vec<Vec<usize>>,
+}
+
+impl VisualLine {
+    /// Where the cursor before byte `offset` of the line is drawn: its row,
+    /// and the column of the character it is on counted from the left of
+    /// the row, or `None` when it is past the row's text.
+    pub fn cursor(&self, offset: usize) -> (usize, Option<usize>) {
+        let Some((r, i)) = self.find(offset) else {
+            return (self.rows.len() - 1, None);
+        };
+        let row = &self.rows[r];
+        let shown = row.chars().count();
+        let i = if self.rtl {
+            i.checked_sub(self.stops[r].len() - shown)
+        } else {
+            Some(i).filter(|&i| i < shown)
+        };
+        let column = i.map(|i| row.chars().take(i).map(|c| c.width().unwrap_or(0)).sum());
+        (r, column)
+    }
+
+    /// The cursor offset one cell left of `offset` as the line is shown, or
+    /// right unless `left`. Off the side of a row the cursor goes on to the
+    /// next or previous row in reading order, and `None` is returned when
+    /// that leaves the line.
+    pub fn step(&self, offset: usize, left: bool) -> Option<usize> {
+        let (r, i) = self.find(offset)?;
+        let row = &self.stops[r];
+        let next = if left {
+            i.checked_sub(1)
+        } else {
+            Some(i + 1).filter(|&i| i < row.len())
+        };
+        if let Some(next) = next {
+            return Some(row[next]);
+        }
+        // Onto the side of the next row where it starts, or of the previous
+        // one where it ends
+        if left == self.rtl {
+            let next = self.stops.get(r + 1)?;
+            if self.rtl { next.last() } else { next.first() }.copied()
+        } else {
+            let previous = &self.stops[r.checked_sub(1)?];
+            if self.rtl {
+                previous.first()
+            } else {
+                previous.last()
+            }
+            .copied()
+        }
+    }
+
+    // Row and index among its stops of the cursor place at `offset`
+    fn find(&self, offset: usize) -> Option<(usize, usize)> {
+        self.stops
+            .iter()
+            .enumerate()
+            .find_map(|(r, stops)| Some((r, stops.iter().position(|&s| s == offset)?)))
+    }
+}
+
+/// Whether `text` contains any strong right-to-left characters.
+pub fn has_rtl(text: &str) -> bool {
+    text.chars()
+        .any(|c| matches!(bidi_class(c), BidiClass::R | BidiClass::AL))
+}
+
+/// Wraps a single logical line (no newlines) to `width` columns and
+/// reorders every row for display.
+pub fn layout(line: &str, width: usize) -> VisualLine {
+    let info = ParagraphBidiInfo::new(line, None);
+    let rtl = info.paragraph_level.is_rtl();
+    let ranges = wrap(line, width);
+    let last = ranges.len() - 1;
+    let mut rows = Vec::new();
+    let mut stops = Vec::new();
+    for (i, range) in ranges.into_iter().enumerate() {
+        // Drop break spaces first, reversed they would lead the row. Spaces
+        // at the very end are content the cursor has to move past.
+        let shown = if i < last {
+            range.start..range.start + line[range.clone()].trim_end().len()
+        } else {
+            range.clone()
+        };
+        let mut row = String::new();
+        let mut row_stops = Vec::new();
+        if !shown.is_empty() {
+            let (levels, runs) = info.visual_runs(shown.clone());
+            for run in runs {
+                let chars = line[run.clone()]
+                    .char_indices()
+                    .map(|(j, c)| (run.start + j, c));
+                if levels[run.start].is_rtl() {
+                    for (offset, c) in chars.rev() {
+                        row.push(mirror(c));
+                        row_stops.push(offset);
+                    }
+                } else {
+                    for (offset, c) in chars {
+                        row.push(c);
+                        row_stops.push(offset);
+                    }
+                }
+            }
+        }
+
+        let mut hidden: Vec<usize> = line[shown.end..range.end]
+            .char_indices()
+            .map(|(j, _)| shown.end + j)
+            .collect();
+        if i == last {
+            hidden.push(line.len());
+        }
+        if rtl {
+            hidden.reverse();
+            hidden.append(&mut row_stops);
+            row_stops = hidden;
+        } else {
+            row_stops.append(&mut hidden);
+        }
+        rows.push(row);
+        stops.push(row_stops);
+    }
+
+    VisualLine { rows, rtl, stops }
+}
+
+/// Lays out a styled line containing RTL text, each character keeping the
+/// style of the span it came from. Lines without RTL text are returned as
+/// they are and left to the paragraph's own wrapping.
+pub fn reflow(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    let text = line.to_string();
+    if !has_rtl(&text) {
+        return vec![line];
+    }
+
+    // Where each span ends in the text, to find the span of a character
+    let ends: Vec<usize> = line
+        .spans
+        .iter()
+        .scan(0, |end, span| {
+            *end += span.content.len();
+            Some(*end)
+        })
+        .collect();
+    let span_at = |offset: usize| ends.partition_point(|&end| end <= offset);
+
+    let visual = layout(&text, width);
+    visual
+        .rows
+        .iter()
+        .zip(&visual.stops)
+        .map(|(row, stops)| {
+            let shown = row.chars().count();
+            let offsets = if visual.rtl {
+                &stops[stops.len() - shown..]
+            } else {
+                &stops[..shown]
+            };
+            // Runs of characters from the same span
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            let mut last = None;
+            for (c, &offset) in row.chars().zip(offsets) {
+                let index = span_at(offset);
+                match spans.last_mut() {
+                    Some(span) if last == Some(index) => span.content.to_mut().push(c),
+                    _ => spans.push(Span::styled(c.to_string(), line.spans[index].style)),
+                }
+                last = Some(index);
+            }
+            let row = Line {
+                spans,
+                style: line.style,
+                alignment: line.alignment,
+            };
+            if visual.rtl { row.right_aligned() } else { row }
+        })
+        .collect()
+}
+
+/// Byte ranges of rows at most `width` display columns wide (wide CJK and
+/// emoji characters count as two), breaking after the last space that fits
+/// and mid-word only when a word is wider than a row.
+pub fn wrap(line: &str, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut row_width = 0;
+    let mut last_break = None;
+
+    for (i, c) in line.char_indices() {
+        let w = c.width().unwrap_or(0);
+        if c == ' ' && row_width + w > width {
+            // A space that overflows ends the row instead of starting the next
+            rows.push(start..i + 1);
+            start = i + 1;
+            row_width = 0;
+            last_break = None;
+            continue;
+        }
+        if row_width + w > width && i > start {
+            let end = last_break.filter(|&b| b > start).unwrap_or(i);
+            rows.push(start..end);
+            start = end;
+            row_width = line[start..i].chars().map(|c| c.width().unwrap_or(0)).sum();
+            last_break = None;
+        }
+        row_width += w;
+        if c == ' ' {
+            last_break = Some(i + c.len_utf8());
+        }
+    }
+
+    rows.push(start..line.len());
+    rows
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Alignment;
+    use ratatui::style::{Modifier, Style};
+
+    use super::*;
+
+    #[test]
+    fn test_ltr_is_untouched() {
+        assert!(!has_rtl("hello (world)"));
+        let visual = layout("hello world", 20);
+        assert_eq!(visual.rows, vec!["hello world".to_string()]);
+        assert!(!visual.rtl);
+    }
+
+    #[test]
+    fn test_rtl_paragraph() {
+        // "shalom olam" in Hebrew, reversed for display and right-aligned
+        let visual = layout("שלום עולם", 20);
+        assert!(visual.rtl);
+        assert_eq!(visual.rows, vec!["םלוע םולש".to_string()]);
+    }
+
+    #[test]
+    fn test_mixed_direction_wraps_before_reordering() {
+        let visual = layout("שלום (hello) עולם", 14);
+        assert!(visual.rtl);
+        assert_eq!(
+            visual.rows,
+            vec!["(hello) םולש".to_string(), "םלוע".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_cursor_moves_visually() {
+        // The first letter is drawn rightmost, Left goes on through the text
+        let visual = layout("שלום עולם", 20);
+        assert_eq!(visual.cursor(0), (0, Some(8)));
+        assert_eq!(visual.step(0, true), Some(2));
+        assert_eq!(visual.step(2, false), Some(0));
+        assert_eq!(visual.step(0, false), None);
+        // The end of the line is left of the text
+        assert_eq!(visual.cursor(17), (0, None));
+        assert_eq!(visual.step(15, true), Some(17));
+        assert_eq!(visual.step(17, true), None);
+
+        let ltr = layout("ab", 20);
+        assert_eq!(ltr.step(0, false), Some(1));
+        assert_eq!(ltr.step(2, false), None);
+        assert_eq!(ltr.cursor(1), (0, Some(1)));
+    }
+
+    #[test]
+    fn test_cursor_crosses_rows_in_reading_order() {
+        let line = "שלום (hello) עולם";
+        let visual = layout(line, 14);
+        let mut walk = vec![0];
+        while let Some(next) = visual.step(*walk.last().unwrap(), true) {
+            walk.push(next);
+        }
+        // The English word reads left to right inside the Hebrew, and off
+        // the left of the first row the cursor goes to the right of the next
+        assert_eq!(
+            walk,
+            [
+                0, 2, 4, 6, 8, 9, 14, 13, 12, 11, 10, 15, 16, 17, 19, 21, 23, 25
+            ]
+        );
+        assert_eq!(visual.cursor(10), (0, Some(1)));
+        // The break space isn't drawn, the cursor on it is left of the row
+        assert_eq!(visual.cursor(16), (0, None));
+        assert_eq!(visual.cursor(17), (1, Some(3)));
+
+        let mut back = vec![line.len()];
+        while let Some(next) = visual.step(*back.last().unwrap(), false) {
+            back.push(next);
+        }
+        back.reverse();
+        assert_eq!(back, walk);
+    }
+
+    #[test]
+    fn test_reflow_keeps_span_styles() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let line = Line::from(vec![Span::raw("שלום "), Span::styled("עולם", bold)]);
+        let rows = reflow(line, 20);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].alignment, Some(Alignment::Right));
+        let spans: Vec<(&str, Style)> = rows[0]
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style))
+            .collect();
+        assert_eq!(spans, [("םלוע", bold), (" םולש", Style::default())]);
+
+        let line = Line::from(vec![Span::raw("plain "), Span::styled("text", bold)]);
+        assert_eq!(reflow(line.clone(), 20), vec![line]);
+    }
+}
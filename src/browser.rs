@@ -0,0 +1,204 @@
+//! The card browser: the cards of one topic as a list, in a chosen order,
+//! for looking over and tidying up big topics.
+
+use std::cmp::Reverse;
+
+use mem_flip::deck::Flashcard;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, StatefulWidget, Widget, Wrap},
+};
+
+use crate::l10n::{hint, tr};
+use crate::markup;
+use crate::theme;
+
+// Width from which the preview goes beside the list rather than below it
+const PREVIEW_BESIDE_WIDTH: u16 = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CardSort {
+    // The order the cards were added in, which is the order of the topic
+    #[default]
+    Added,
+    Newest,
+    Question,
+    // Most difficult first, cards without a difficulty last
+    Hardest,
+}
+
+impl CardSort {
+    /// The sort after this one, back to the first after the last.
+    pub fn next(self) -> CardSort {
+        match self {
+            CardSort::Added => CardSort::Newest,
+            CardSort::Newest => CardSort::Question,
+            CardSort::Question => CardSort::Hardest,
+            CardSort::Hardest => CardSort::Added,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CardSort::Added => "oldest first",
+            CardSort::Newest => "newest first",
+            CardSort::Question => "question",
+            CardSort::Hardest => "hardest first",
+        }
+    }
+}
+
+/// Indexes of `cards` in the order `sort` asks for.
+pub fn sorted(cards: &[Flashcard], difficulty: &[Option<u8>], sort: CardSort) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..cards.len()).collect();
+    match sort {
+        CardSort::Added => {}
+        CardSort::Newest => order.reverse(),
+        CardSort::Question => {
+            order.sort_by_cached_key(|&i| cards[i].question.to_lowercase());
+        }
+        CardSort::Hardest => order.sort_by_key(|&i| Reverse(difficulty[i])),
+    }
+    order
+}
+
+pub struct CardBrowser<'a> {
+    pub topic: &'a str,
+    pub cards: &'a [Flashcard],
+    // From 1 to 5, see `stats::difficulty`
+    pub difficulty: &'a [Option<u8>],
+    // Indexes of the cards in the order shown
+    pub order: &'a [usize],
+    // Position in `order`
+    pub selected: usize,
+    pub sort: CardSort,
+}
+
+impl Widget for CardBrowser<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            hint("Navigate"),
+            "<↑↓>".blue().bold(),
+            hint("Review"),
+            "<Enter>".blue().bold(),
+            hint("Sort"),
+            "<O>".blue().bold(),
+            hint("Move"),
+            "<J/K>".blue().bold(),
+            hint("Edit"),
+            "<E>".blue().bold(),
+            hint("History"),
+            "<H>".blue().bold(),
+            hint("Attach"),
+            "<F>".blue().bold(),
+            hint("Duplicate"),
+            "<C>".blue().bold(),
+            hint("Delete"),
+            "<X>".blue().bold(),
+            hint("Back"),
+            "<Esc> ".blue().bold(),
+        ]);
+        let block = Block::bordered()
+            .title(format!(" 🗂 {} ", self.topic).bold())
+            .title(Line::from(format!(" ↕ {} ", tr(self.sort.label()))).right_aligned())
+            .title_bottom(instructions);
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let (list_area, preview_area, preview_border) = if area.width >= PREVIEW_BESIDE_WIDTH {
+            let [list, preview] =
+                Layout::horizontal([Constraint::Percentage(55), Constraint::Fill(1)]).areas(inner);
+            (list, preview, Borders::LEFT)
+        } else {
+            let [list, preview] =
+                Layout::vertical([Constraint::Percentage(40), Constraint::Fill(1)]).areas(inner);
+            (list, preview, Borders::TOP)
+        };
+
+        let items: Vec<ListItem> = self
+            .order
+            .iter()
+            .map(|&i| {
+                let card = &self.cards[i];
+                let difficulty = match self.difficulty[i] {
+                    Some(level) => format!("◆{} ", level),
+                    None => "–  ".to_string(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:>4}. ", i + 1),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(difficulty, Style::default().fg(Color::DarkGray)),
+                    Span::raw(first_line(&card.question)),
+                    Span::styled(
+                        format!("  → {}", first_line(&card.answer)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(
+                Style::default()
+                    .bg(theme::palette().highlight)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ ");
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(list, list_area, buf, &mut state);
+
+        let preview = Block::new()
+            .borders(preview_border)
+            .title(format!(" {} ", tr("Preview")))
+            .border_style(Style::default().fg(Color::DarkGray));
+        let Some(card) = self.order.get(self.selected).map(|&i| &self.cards[i]) else {
+            preview.render(preview_area, buf);
+            return;
+        };
+        Paragraph::new(preview_lines(card))
+            .wrap(Wrap { trim: false })
+            .block(preview)
+            .render(preview_area, buf);
+    }
+}
+
+// The whole of both sides of `card`, with their Markdown styled
+fn preview_lines(card: &Flashcard) -> Vec<Line<'static>> {
+    let heading = Style::default().fg(theme::palette().accent).bold();
+    let mut lines = vec![Line::styled("Question", heading)];
+    lines.extend(markup::render_markdown(&card.question));
+    lines.push(Line::styled("Answer", heading));
+    lines.extend(markup::render_markdown(&card.answer));
+    lines
+}
+
+// First line of a card side, marked when there is more
+pub fn first_line(text: &str) -> String {
+    match text.split_once('\n') {
+        Some((first, _)) => format!("{} …", first),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted() {
+        let cards: Vec<Flashcard> = ["perro", "Gato", "ave"]
+            .into_iter()
+            .map(|question| Flashcard::new(question, ""))
+            .collect();
+        let difficulty = [Some(1), Some(3), None];
+
+        let sort = |sort| sorted(&cards, &difficulty, sort);
+        assert_eq!(sort(CardSort::Added), [0, 1, 2]);
+        assert_eq!(sort(CardSort::Newest), [2, 1, 0]);
+        assert_eq!(sort(CardSort::Question), [2, 1, 0]);
+        assert_eq!(sort(CardSort::Hardest), [1, 0, 2]);
+    }
+}
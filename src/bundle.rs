@@ -0,0 +1,251 @@
+//! Decks packed into one file together with their images, for sending to
+//! someone else.
+//!
+//! A bundle is JSON: the deck as it is saved, and each image or attached
+//! file the cards reference under its path relative to the deck, base64
+//! encoded. Unpacking
+//! writes the deck and recreates the images next to it, so the cards find
+//! them again.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Component, Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use mem_flip::deck::Topics;
+use serde::{Deserialize, Serialize};
+
+use crate::markup;
+use crate::media;
+
+static FORMAT: &str = "mem-flip-bundle";
+const VERSION: u32 = 1;
+
+// Written with a borrowed deck, read with an owned one
+#[derive(Debug, Serialize, Deserialize)]
+struct Bundle<D> {
+    format: String,
+    version: u32,
+    deck: D,
+    // Image path as written in the cards, to its contents
+    #[serde(default)]
+    media: BTreeMap<String, String>,
+}
+
+/// What went into a bundle.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Packed {
+    pub media: usize,
+    // Images the cards reference that aren't next to the deck
+    pub missing: Vec<String>,
+}
+
+/// Default bundle name for the deck at `deck_path`: `deck.bundle.json` next
+/// to `deck.json`.
+pub fn bundle_path(deck_path: &Path) -> PathBuf {
+    deck_path.with_extension("bundle.json")
+}
+
+/// Whether `path` is named like a bundle rather than a deck.
+pub fn is_bundle(path: &Path) -> bool {
+    path.to_string_lossy().ends_with(".bundle.json")
+}
+
+/// Default deck name for the bundle at `path`, the reverse of `bundle_path`.
+pub fn deck_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = name
+        .strip_suffix(".bundle.json")
+        .or_else(|| name.strip_suffix(".json"))
+        .unwrap_or(&name);
+    path.with_file_name(format!("{}.json", stem))
+}
+
+/// Writes `topics`, whose images are relative to `deck_dir`, to a bundle at
+/// `out`.
+pub fn pack(topics: &Topics, deck_dir: &Path, out: &Path) -> io::Result<Packed> {
+    let mut packed = Packed::default();
+    let mut media = BTreeMap::new();
+    for reference in media_paths(topics) {
+        // Only images inside the deck's directory can be put back on unpacking
+        let seen = media.contains_key(&reference) || packed.missing.contains(&reference);
+        if seen || !is_relative(&reference) {
+            continue;
+        }
+        match fs::read(deck_dir.join(&reference)) {
+            Ok(data) => {
+                media.insert(reference, STANDARD.encode(data));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => packed.missing.push(reference),
+            Err(e) => return Err(e),
+        }
+    }
+    packed.media = media.len();
+
+    let bundle = Bundle {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        deck: topics,
+        media,
+    };
+    let writer = BufWriter::new(File::create(out)?);
+    serde_json::to_writer(writer, &bundle)?;
+    Ok(packed)
+}
+
+/// Unpacks the bundle at `path` into a new deck at `deck_path`, with its
+/// images next to it. Returns the deck. Images already there are kept if
+/// they are the same, and are an error otherwise.
+pub fn unpack(path: &Path, deck_path: &Path) -> io::Result<Topics> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let bundle: Bundle<Topics> = serde_json::from_reader(BufReader::new(File::open(path)?))
+        .map_err(|e| invalid(format!("not a mem-flip bundle: {}", e)))?;
+    if bundle.format != FORMAT {
+        return Err(invalid("not a mem-flip bundle".to_string()));
+    }
+    if bundle.version > VERSION {
+        return Err(invalid(format!(
+            "bundle version {} needs a newer mem-flip",
+            bundle.version
+        )));
+    }
+    bundle.deck.check()?;
+    if deck_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", deck_path.display()),
+        ));
+    }
+
+    // Everything is decoded and checked before anything is written
+    let deck_dir = deck_path.parent().unwrap_or(Path::new(""));
+    let mut files = Vec::new();
+    for (reference, encoded) in &bundle.media {
+        if !is_relative(reference) {
+            return Err(invalid(format!("image outside the deck: {}", reference)));
+        }
+        let data = STANDARD
+            .decode(encoded)
+            .map_err(|e| invalid(format!("image {}: {}", reference, e)))?;
+        let target = deck_dir.join(reference);
+        match fs::read(&target) {
+            Ok(existing) if existing == data => continue,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("a different {} already exists", target.display()),
+                ));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => files.push((target, data)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    for (target, data) in files {
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(target, data)?;
+    }
+    bundle.deck.save(deck_path)?;
+    Ok(bundle.deck)
+}
+
+// Image paths referenced by questions and answers, then the files attached
+// to cards, in deck order
+fn media_paths(topics: &Topics) -> Vec<String> {
+    let cards = topics.topics_map.values().flatten();
+    let images = cards
+        .clone()
+        .flat_map(|card| [&card.question, &card.answer])
+        .flat_map(|text| markup::split_images(text).1)
+        .map(|image| image.path);
+    let attached = cards
+        .flat_map(|card| &card.attachments)
+        .filter(|attachment| !media::is_url(attachment))
+        .cloned();
+    images.chain(attached).collect()
+}
+
+/// Whether `path` is below the deck's directory, so it can't be used to
+/// reach anything elsewhere.
+pub fn is_relative(path: &str) -> bool {
+    let path = Path::new(path);
+    !path.as_os_str().is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use mem_flip::deck::Flashcard;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let from = TempDir::new().unwrap();
+        fs::create_dir(from.path().join("img")).unwrap();
+        fs::write(from.path().join("img/heart.png"), b"not really a png").unwrap();
+        let mut topics = Topics::default();
+        topics.meta.title = Some("Anatomy".to_string());
+        topics.topics_map.insert(
+            "Organs".to_string(),
+            vec![Flashcard::new(
+                "What is this?\n![diagram](img/heart.png)",
+                "The heart ![missing](img/lungs.png) ![secret](../secret.png)",
+            )],
+        );
+
+        let bundle = from.path().join("anatomy.bundle.json");
+        let packed = pack(&topics, from.path(), &bundle).unwrap();
+        assert_eq!(packed.media, 1);
+        assert_eq!(packed.missing, vec!["img/lungs.png"]);
+
+        let to = TempDir::new().unwrap();
+        let deck = to.path().join("anatomy.json");
+        assert_eq!(unpack(&bundle, &deck).unwrap(), topics);
+        assert_eq!(Topics::load(&deck).unwrap(), topics);
+        assert_eq!(
+            fs::read(to.path().join("img/heart.png")).unwrap(),
+            b"not really a png"
+        );
+
+        // The deck is never overwritten
+        let error = unpack(&bundle, &deck).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_unpack_stays_in_deck_directory() {
+        let dir = TempDir::new().unwrap();
+        let bundle = dir.path().join("evil.bundle.json");
+        fs::write(
+            &bundle,
+            r#"{"format":"mem-flip-bundle","version":1,"deck":{"topics_map":{}},
+                "media":{"../escaped.png":"aGk="}}"#,
+        )
+        .unwrap();
+        let deck = dir.path().join("decks/evil.json");
+        let error = unpack(&bundle, &deck).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(!dir.path().join("escaped.png").exists());
+        assert!(!deck.exists());
+    }
+
+    #[test]
+    fn test_bundle_names() {
+        let bundle = bundle_path(Path::new("/decks/spanish.json"));
+        assert_eq!(bundle, Path::new("/decks/spanish.bundle.json"));
+        assert!(is_bundle(&bundle));
+        assert_eq!(deck_path(&bundle), Path::new("/decks/spanish.json"));
+        assert_eq!(
+            deck_path(Path::new("shared.json")),
+            Path::new("shared.json")
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! `mem-flip check`: whether a deck file can be read, and where it can't.
+//!
+//! Unlike loading, which stops at the first error, every topic and card is
+//! checked on its own, so one run lists everything to fix. Each problem comes
+//! with the line and column it starts at and the path of the field, for
+//! pre-commit hooks and editors to point at.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use mem_flip::deck::{self, DeckMeta, Flashcard, TopicMeta, Trashed};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+/// Something wrong in a deck file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub line: usize,
+    pub column: usize,
+    // Where in the deck, such as `topics_map["Spanish"][2]`
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}:{}: {}", self.line, self.column, self.message)
+        } else {
+            write!(
+                f,
+                "{}:{}: {}: {}",
+                self.line, self.column, self.field, self.message
+            )
+        }
+    }
+}
+
+/// What a checked deck holds, when it has no problems.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Checked {
+    pub topics: usize,
+    pub cards: usize,
+    // Encrypted decks only have their envelope checked
+    pub encrypted: bool,
+}
+
+/// Checks the contents of a deck file.
+pub fn check(text: &str) -> Result<Checked, Vec<Problem>> {
+    let mut problems = Vec::new();
+    let top: HashMap<String, &RawValue> = match serde_json::from_str(text) {
+        Ok(top) => top,
+        Err(e) => return Err(vec![problem(text, 0, "", &e)]),
+    };
+    if deck::is_encrypted(text) {
+        return Ok(Checked {
+            encrypted: true,
+            ..Checked::default()
+        });
+    }
+
+    let mut checked = Checked::default();
+    match top.get("topics_map") {
+        None => problems.push(Problem {
+            line: 1,
+            column: 1,
+            field: String::new(),
+            message: "missing field `topics_map`".to_string(),
+        }),
+        Some(raw) => {
+            if let Some(topics) =
+                parse::<HashMap<String, &RawValue>>(text, raw, "topics_map", &mut problems)
+            {
+                let mut names: Vec<&String> = topics.keys().collect();
+                names.sort();
+                for name in names {
+                    let field = format!("topics_map[{:?}]", name);
+                    let Some(cards) =
+                        parse::<Vec<&RawValue>>(text, topics[name], &field, &mut problems)
+                    else {
+                        continue;
+                    };
+                    checked.topics += 1;
+                    for (i, raw) in cards.iter().enumerate() {
+                        let field = format!("topics_map[{:?}][{}]", name, i);
+                        if let Some(card) = parse::<Flashcard>(text, raw, &field, &mut problems) {
+                            checked.cards += 1;
+                            if card.rating.is_some_and(|n| !(1..=5).contains(&n)) {
+                                let (line, column) = position(text, offset(text, raw));
+                                problems.push(Problem {
+                                    line,
+                                    column,
+                                    field,
+                                    message: "the rating must be 1 to 5".to_string(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if let Some(raw) = top.get("meta") {
+        parse::<DeckMeta>(text, raw, "meta", &mut problems);
+    }
+    if let Some(raw) = top.get("topic_meta")
+        && let Some(metas) =
+            parse::<HashMap<String, &RawValue>>(text, raw, "topic_meta", &mut problems)
+    {
+        let mut names: Vec<&String> = metas.keys().collect();
+        names.sort();
+        for name in names {
+            let field = format!("topic_meta[{:?}]", name);
+            parse::<TopicMeta>(text, metas[name], &field, &mut problems);
+        }
+    }
+    if let Some(raw) = top.get("trash")
+        && let Some(trash) = parse::<Vec<&RawValue>>(text, raw, "trash", &mut problems)
+    {
+        for (i, raw) in trash.iter().enumerate() {
+            parse::<Trashed>(text, raw, &format!("trash[{}]", i), &mut problems);
+        }
+    }
+
+    problems.sort_by_key(|problem| (problem.line, problem.column));
+    if problems.is_empty() {
+        Ok(checked)
+    } else {
+        Err(problems)
+    }
+}
+
+// Reads `raw`, a part of `text`, noting where it went wrong if it can't be
+fn parse<'a, T: Deserialize<'a>>(
+    text: &str,
+    raw: &'a RawValue,
+    field: &str,
+    problems: &mut Vec<Problem>,
+) -> Option<T> {
+    match serde_json::from_str(raw.get()) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            problems.push(problem(text, offset(text, raw), field, &e));
+            None
+        }
+    }
+}
+
+// Byte offset of `raw` in `text`, which it was borrowed from
+fn offset(text: &str, raw: &RawValue) -> usize {
+    raw.get().as_ptr() as usize - text.as_ptr() as usize
+}
+
+// A problem for the error `e`, from reading the part of `text` at `start`
+fn problem(text: &str, start: usize, field: &str, e: &serde_json::Error) -> Problem {
+    let (line, column) = position(text, start);
+    // The error's position is within the part, which starts at `line:column`
+    let (line, column) = match e.line() {
+        0 => (line, column),
+        1 => (line, column + e.column().saturating_sub(1)),
+        n => (line + n - 1, e.column()),
+    };
+    let message = e.to_string();
+    let message = match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    };
+    Problem {
+        line,
+        column,
+        field: field.to_string(),
+        message,
+    }
+}
+
+// 1-based line and column, in characters, of the byte offset in `text`
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let text = r#"{
+  "topics_map": {
+    "Spanish": [
+      { "question": "perro", "answer": "dog" },
+      { "question": "gato" },
+      { "question": "pez", "answer": "fish", "rating": 9 }
+    ],
+    "French": { "question": "chat" }
+  }
+}"#;
+        let problems = check(text).unwrap_err();
+        let lines: Vec<String> = problems.iter().map(Problem::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                r#"5:28: topics_map["Spanish"][1]: missing field `answer`"#,
+                r#"6:7: topics_map["Spanish"][2]: the rating must be 1 to 5"#,
+                r#"8:15: topics_map["French"]: invalid type: map, expected a sequence"#,
+            ]
+        );
+
+        let problems = check("{\n  \"topics_map\": {,\n}").unwrap_err();
+        assert_eq!(problems[0].to_string(), "2:18: key must be a string");
+
+        let text = r#"{"topics_map": {"Spanish": [{"question": "perro", "answer": "dog"}]}}"#;
+        assert_eq!(
+            check(text),
+            Ok(Checked {
+                topics: 1,
+                cards: 1,
+                encrypted: false
+            })
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Command line arguments.
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::import::MergeStrategy;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Flashcards in the terminal")]
+pub struct Cli {
+    /// Log more to the log file: -v for info, -vv for debug, -vvv for trace
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Deck file to open, instead of the one open last time
+    #[arg(short, long, global = true, value_name = "FILE")]
+    pub deck: Option<PathBuf>,
+
+    /// Keep decks, history and settings apart from other people's
+    #[arg(short, long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Keep the decks, settings and logs next to the executable, as when
+    /// running from a USB stick. Also on when a `mem-flip.portable` file is
+    /// there.
+    #[arg(long, global = true)]
+    pub portable: bool,
+
+    /// Browse and review without ever writing to the deck or its history
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Review and add cards at a line by line prompt instead of the full
+    /// screen, for screen readers
+    #[arg(long)]
+    pub plain: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print the review history as CSV
+    Stats {
+        /// Write to this file instead of standard output
+        #[arg(long, value_name = "FILE")]
+        export: Option<PathBuf>,
+    },
+    /// Download a shared deck over HTTPS
+    Fetch {
+        /// Address of the deck file
+        url: String,
+        /// Add its cards to the open deck instead of saving a new deck
+        #[arg(long)]
+        merge: bool,
+        /// Save the new deck here, instead of next to the open deck
+        #[arg(short, long, value_name = "FILE", conflicts_with = "merge")]
+        output: Option<PathBuf>,
+    },
+    /// Pack the deck and the images it uses into one file to send to someone
+    Bundle {
+        /// Write the bundle here, instead of next to the deck
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Print a topic as a share code and its QR code, for
+    /// `mem-flip import code` on another machine
+    Share {
+        /// Topic to share
+        topic: String,
+    },
+    /// Write the deck as one web page that flips through the cards, for
+    /// people without mem-flip
+    Html {
+        /// Write the page here, instead of next to the deck
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Merge the deck and its history with the copies on the sync server
+    Sync,
+    /// Protect the deck and its history with a passphrase
+    Encrypt,
+    /// Remove the passphrase, storing the deck in plain text again
+    Decrypt,
+    /// Answer JSON requests about the deck over HTTP, for web pages and
+    /// other tools
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Address to listen on. There is no login, so only listen on
+        /// others than localhost in a network you trust.
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+    /// Check that deck files can be read, listing every problem with its
+    /// line, for pre-commit hooks. Fails if there are any.
+    Check {
+        /// Deck files to check, instead of the open deck
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+    },
+    /// Print one line about the deck, for tmux or waybar
+    Status {
+        /// What to print, with {deck}, {topics}, {cards}, {due} and {today}
+        /// (reviews given today) replaced by their values
+        #[arg(long, default_value = "{due} due")]
+        format: String,
+        /// Print all the values as a JSON object instead
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+    },
+    /// Print one card, for a shell greeting or message of the day
+    Random {
+        /// Only pick from this topic
+        #[arg(long)]
+        topic: Option<String>,
+        /// Pick the card not reviewed for the longest instead of a random one
+        #[arg(long)]
+        overdue: bool,
+    },
+    /// Show a desktop notification when cards are due, for running from
+    /// cron
+    Notify,
+    /// Add cards made in another tool to the deck, updating the ones
+    /// imported before
+    Import {
+        /// What to do with cards whose question is already in the topic
+        #[arg(long, value_enum, default_value_t)]
+        duplicates: MergeStrategy,
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+    /// Merge another deck file into this one: topics of the same name are
+    /// combined, cards already there skipped, and questions the decks answer
+    /// differently listed
+    Merge {
+        /// Deck file to take the cards from
+        other: PathBuf,
+        /// What to do with questions the decks answer differently
+        #[arg(long, value_enum, default_value_t = MergeStrategy::Skip)]
+        conflicts: MergeStrategy,
+    },
+    /// Tidy up a hand-edited deck: remove empty topics and duplicate
+    /// cards, trim stray whitespace and fix line endings
+    Doctor {
+        /// Only report what would be fixed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Sync topics with the Anki decks of the same name, through a running
+    /// Anki with the AnkiConnect add-on
+    Anki {
+        /// Topics to sync
+        #[arg(required = true)]
+        topics: Vec<String>,
+    },
+    /// Compact the review history older than some months into a line for
+    /// each card, day and grade, keeping what the stats count
+    CompactHistory {
+        /// Months of history to keep review by review, instead of
+        /// `history_months` from config.toml
+        #[arg(long)]
+        months: Option<u64>,
+        /// Keep a line for each card and grade only, without the days
+        #[arg(long)]
+        totals: bool,
+    },
+    /// Unpack a bundle into a new deck, with its images next to it
+    Unbundle {
+        /// Bundle file to unpack
+        bundle: PathBuf,
+        /// Save the deck here, instead of next to the bundle
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportSource {
+    /// `Q::`/`A::` lines and `#flashcard` notes in an Obsidian vault or
+    /// folder of Markdown notes
+    Obsidian {
+        /// Folder of the vault
+        vault: PathBuf,
+    },
+    /// Headings tagged `:drill:` in an Emacs org file, as org-drill uses
+    Org {
+        /// The org file
+        file: PathBuf,
+    },
+    /// A Quizlet set exported with a tab between term and definition
+    Quizlet {
+        /// The exported text file
+        file: PathBuf,
+        /// Topic to add the cards to, created if needed. Asked for when
+        /// not given.
+        #[arg(long)]
+        topic: Option<String>,
+    },
+    /// A Mochi export, with a topic for each deck
+    Mochi {
+        /// The `.mochi` file, or the `data.json` in it
+        file: PathBuf,
+    },
+    /// Cards in RemNote's Markdown export, with a topic for each file
+    Remnote {
+        /// The exported Markdown file, or a folder of them
+        path: PathBuf,
+    },
+    /// A share code, from `mem-flip share` or <Y> in the topic list
+    Code {
+        /// The code, starting with `mf1:`
+        code: String,
+    },
+}
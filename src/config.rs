@@ -0,0 +1,131 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use mem_flip::history::Compaction;
+use mem_flip::schedule::ReviewOrder;
+use serde::{Deserialize, Serialize};
+
+use mem_flip::atomic;
+
+use crate::graphics::GraphicsProtocol;
+use crate::l10n::Language;
+use crate::markup::RubyMode;
+use crate::paths;
+use crate::snapshot::SnapshotFormat;
+use crate::theme::ThemeSetting;
+
+static CONFIG_FILE: &str = "config.toml";
+
+// User settings read from `<config dir>/mem-flip/config.toml`.
+// Every field has a default so a partial (or missing) file is fine.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // Protocol used to draw card images: auto, kitty, sixel or none
+    pub graphics: GraphicsProtocol,
+    // Shell command that reads text on stdin and speaks it; when unset the
+    // first of `say`, `espeak-ng` or `espeak` is used
+    pub tts_command: Option<String>,
+    // Shell command that shows a desktop notification, given the title and
+    // body as `$1` and `$2`; when unset `notify-send` or `osascript` is used
+    pub notify_command: Option<String>,
+    // Where ruby readings such as `漢字[かんじ]` go: above, beside or hidden
+    pub ruby: RubyMode,
+    // Commit the deck and its review history after every save, when the
+    // deck's directory is a git repository
+    pub git_commit: bool,
+    // Folder on a WebDAV or other HTTPS server that stores files with PUT,
+    // which decks are synced with
+    pub sync_url: Option<String>,
+    // Login for `sync_url`, sent with basic authentication
+    pub sync_user: Option<String>,
+    pub sync_password: Option<String>,
+    // OpenAI-compatible API to draft cards with, such as
+    // `https://api.openai.com/v1` or `http://localhost:11434/v1` for Ollama
+    pub llm_url: Option<String>,
+    pub llm_model: Option<String>,
+    // Sent as a bearer token, when the API needs one
+    pub llm_api_key: Option<String>,
+    // Where AnkiConnect listens, `http://127.0.0.1:8765` when unset
+    pub anki_url: Option<String>,
+    // Hunspell dictionary to check card text against, such as `en_US` or
+    // `de_DE`; spell checking is off when unset
+    pub spell_language: Option<String>,
+    // Answers longer than this many characters are flagged in the editor,
+    // 250 when unset
+    pub max_answer_length: Option<usize>,
+    // Language of the interface: en, es, de or fr; when unset it follows
+    // the locale, falling back to English
+    pub language: Option<Language>,
+    // Colors for a dark or light terminal background: auto, dark or light;
+    // auto asks the terminal
+    pub theme: ThemeSetting,
+    // Turns off the flip and slide of cards in review
+    pub reduce_motion: bool,
+    // Order cards come up in for topics that don't set their own:
+    // sequential, shuffled, hardest, least_recent or lowest_accuracy
+    pub review_order: Option<ReviewOrder>,
+    // Most cards never reviewed that come up in a day, in each topic that
+    // doesn't set its own; no limit when unset
+    pub new_per_day: Option<usize>,
+    // Days of new cards pulled in when studying ahead, once a topic's limit
+    // for the day is reached, 3 when unset
+    pub study_ahead_days: Option<u64>,
+    // Days back the review of recently added cards goes, 7 when unset
+    pub recent_days: Option<u64>,
+    // Days deleted cards and topics stay in the trash, 30 when unset
+    pub trash_days: Option<u64>,
+    // Starts the pomodoro study timer when mem-flip opens
+    pub study_timer: bool,
+    // Minutes of focus and of each break of the study timer, 25 and 5 when
+    // unset
+    pub focus_minutes: Option<u64>,
+    pub break_minutes: Option<u64>,
+    // What a snapshot of a card is written as: text, ansi or svg
+    pub snapshot_format: SnapshotFormat,
+    // Months of review history kept review by review; older reviews are
+    // compacted when mem-flip opens. Kept in full when unset
+    pub history_months: Option<u64>,
+    // What old reviews are compacted into: daily, a line for each card, day
+    // and grade, or totals, a line for each card and grade
+    pub history_compaction: Compaction,
+    // Origins of the web pages that may call `mem-flip serve` from a
+    // browser, such as `http://localhost:3000`; none when unset
+    pub serve_origins: Vec<String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        paths::config_dir().map(|dir| dir.join(CONFIG_FILE))
+    }
+
+    // A missing file gives the defaults, a file that can't be read or parsed
+    // is an error the caller can report before falling back to them
+    pub fn load() -> io::Result<Config> {
+        let Some(path) = Config::path() else {
+            return Ok(Config::default());
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e),
+        };
+        toml::from_str(&text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })
+    }
+
+    // Writes the settings to `path`, replacing the file, comments and all.
+    // The old file stays whole if the write fails partway.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        atomic::write(path, text)
+    }
+}
@@ -0,0 +1,168 @@
+//! Passphrase encryption of deck and history files.
+//!
+//! A key is derived from the passphrase with Argon2id and a random salt
+//! kept in the deck file. Everything is sealed with AES-256-GCM under a
+//! fresh nonce, so a wrong passphrase or a damaged file is detected rather
+//! than read as garbage.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+use crate::deck;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// A key derived from a passphrase, able to seal and open data.
+#[derive(Clone)]
+pub struct Key {
+    salt: Vec<u8>,
+    cipher: Aes256Gcm,
+}
+
+// Never prints the key material
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Key").finish_non_exhaustive()
+    }
+}
+
+/// How an encrypted deck file looks: the salt to derive the key with and
+/// the sealed deck, both base64.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Sealed {
+    pub(crate) salt: String,
+    pub(crate) data: String,
+}
+
+/// Top level of an encrypted deck file.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Envelope {
+    pub(crate) encrypted: Sealed,
+}
+
+impl Key {
+    /// A key for a newly encrypted deck, with a new random salt.
+    pub fn new(passphrase: &str) -> io::Result<Key> {
+        let mut salt = [0u8; SALT_LEN];
+        getrandom(&mut salt)?;
+        Key::derive(passphrase, &salt)
+    }
+
+    /// The key for `passphrase` with the salt of an existing deck.
+    pub fn derive(passphrase: &str, salt: &[u8]) -> io::Result<Key> {
+        let mut bytes = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        Ok(Key {
+            salt: salt.to_vec(),
+            cipher: Aes256Gcm::new(&bytes.into()),
+        })
+    }
+
+    /// Salt the key was derived with, stored alongside what it seals.
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// Encrypts `plain`, returning the nonce followed by the ciphertext.
+    pub fn seal(&self, plain: &[u8]) -> io::Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut sealed = nonce.to_vec();
+        sealed.extend(
+            self.cipher
+                .encrypt(&nonce, plain)
+                .map_err(|_| io::Error::other("encryption failed"))?,
+        );
+        Ok(sealed)
+    }
+
+    /// Decrypts what `seal` returned.
+    pub fn open(&self, sealed: &[u8]) -> io::Result<Vec<u8>> {
+        let wrong = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "wrong passphrase or damaged file",
+            )
+        };
+        if sealed.len() < NONCE_LEN {
+            return Err(wrong());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| wrong())
+    }
+
+    /// Seals `plain` as one base64 line, for line-based files.
+    pub fn seal_line(&self, plain: &str) -> io::Result<String> {
+        Ok(STANDARD.encode(self.seal(plain.as_bytes())?))
+    }
+
+    /// Opens a line made by `seal_line`.
+    pub fn open_line(&self, line: &str) -> io::Result<String> {
+        let sealed = STANDARD
+            .decode(line.trim())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        String::from_utf8(self.open(&sealed)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Salt of the encrypted deck at `path`, `None` for a deck that isn't
+/// encrypted or doesn't exist.
+pub fn deck_salt(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if !deck::is_encrypted(&text) {
+        return Ok(None);
+    }
+    let envelope: Envelope = serde_json::from_str(&text)?;
+    let salt = STANDARD
+        .decode(envelope.encrypted.salt)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(Some(salt))
+}
+
+fn getrandom(bytes: &mut [u8]) -> io::Result<()> {
+    OsRng
+        .try_fill_bytes(bytes)
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = Key::new("correct horse").unwrap();
+        let sealed = key.seal(b"perro = dog").unwrap();
+        assert_eq!(key.open(&sealed).unwrap(), b"perro = dog");
+
+        // The same passphrase and salt give the same key
+        let again = Key::derive("correct horse", key.salt()).unwrap();
+        assert_eq!(
+            again.open_line(&key.seal_line("gato").unwrap()).unwrap(),
+            "gato"
+        );
+
+        let wrong = Key::derive("battery staple", key.salt()).unwrap();
+        let error = wrong.open(&sealed).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
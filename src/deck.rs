@@ -0,0 +1,771 @@
+//! Flashcards grouped into topics, stored as one JSON file per deck.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::crypt::{Envelope, Key, Sealed};
+use crate::journal;
+use crate::schedule::ReviewOrder;
+
+/// A card with a question on the front and the answer on the back.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Flashcard {
+    /// Front of the card
+    pub question: String,
+    /// Back of the card
+    pub answer: String,
+    /// How hard the card is from 1 (easy) to 5 (hard), when rated by hand
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    /// Earlier wordings of the card, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revisions: Vec<Revision>,
+    /// Files and links attached to the card, such as audio or a PDF: paths
+    /// relative to the deck's directory, or URLs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+    /// Seconds since the Unix epoch when the card was added to the deck,
+    /// unknown for cards added before this was kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added: Option<u64>,
+}
+
+/// Wording a card had before it was edited.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Revision {
+    /// Front of the card
+    pub question: String,
+    /// Back of the card
+    pub answer: String,
+    /// Seconds since the Unix epoch when the wording was replaced
+    pub replaced: u64,
+}
+
+/// Most earlier wordings kept of a card, older ones are dropped.
+pub const MAX_REVISIONS: usize = 20;
+
+impl Flashcard {
+    /// A card with `question` and `answer`, not yet rated, revised or
+    /// given attachments, and with no time it was added.
+    pub fn new(question: impl Into<String>, answer: impl Into<String>) -> Flashcard {
+        Flashcard {
+            question: question.into(),
+            answer: answer.into(),
+            rating: None,
+            revisions: Vec::new(),
+            attachments: Vec::new(),
+            added: None,
+        }
+    }
+
+    /// Rewords the card, keeping the wording it had as a revision. Returns
+    /// whether the wording changed.
+    pub fn revise(&mut self, question: String, answer: String, now: u64) -> bool {
+        if question == self.question && answer == self.answer {
+            return false;
+        }
+        self.revisions.push(Revision {
+            question: std::mem::replace(&mut self.question, question),
+            answer: std::mem::replace(&mut self.answer, answer),
+            replaced: now,
+        });
+        let extra = self.revisions.len().saturating_sub(MAX_REVISIONS);
+        self.revisions.drain(..extra);
+        true
+    }
+
+    /// Brings back the wording of revision `index`, which the wording it
+    /// replaces takes the place of as the newest revision.
+    pub fn restore(&mut self, index: usize, now: u64) {
+        if index >= self.revisions.len() {
+            return;
+        }
+        let revision = self.revisions.remove(index);
+        self.revise(revision.question, revision.answer, now);
+    }
+}
+
+/// What a deck is about and who made it, for sharing it. Every field is
+/// optional and left out of the file when unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeckMeta {
+    /// Name to show instead of the file name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Who made the deck
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// What the deck covers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Version of the deck's contents, in any format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Day the deck was created, as `YYYY-MM-DD`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+}
+
+impl DeckMeta {
+    /// Whether no field is set.
+    pub fn is_empty(&self) -> bool {
+        *self == DeckMeta::default()
+    }
+
+    /// Reads only the metadata of the deck at `path`, skipping its cards.
+    pub fn read(path: &Path) -> io::Result<DeckMeta> {
+        #[derive(Deserialize)]
+        struct MetaOnly {
+            #[serde(default)]
+            meta: DeckMeta,
+        }
+        let file = File::open(path)?;
+        let deck: MetaOnly = serde_json::from_reader(BufReader::new(file))?;
+        Ok(deck.meta)
+    }
+
+    /// The title, or the name of the deck file `path` without extension.
+    pub fn title_or_name(&self, path: &Path) -> String {
+        self.title.clone().unwrap_or_else(|| {
+            path.file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+}
+
+/// Settings of one topic. Every field is optional and left out of the file
+/// when unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TopicMeta {
+    /// Skeleton new cards in the topic start from, such as
+    /// `Word:\nPronunciation:\nExample:`, to keep them alike
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<Flashcard>,
+    /// Order the cards come up in, sequential when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<ReviewOrder>,
+    /// Show the answer first and ask for the question
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub reversed: bool,
+    /// Most never reviewed cards brought in a day, no limit when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_per_day: Option<usize>,
+    /// Color the topic is shown in, a name such as `blue` or `#rrggbb`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Emoji shown before the topic's name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Seconds since the Unix epoch when the topic was created, unknown for
+    /// topics made before it was recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<u64>,
+    /// Hidden from the topic list and left out of due counts, for courses
+    /// that are done with
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Seconds since the Unix epoch of the day (UTC) of an exam on the
+    /// topic, which the home screen plans the reviews towards
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exam: Option<u64>,
+}
+
+impl TopicMeta {
+    /// Whether no field is set.
+    pub fn is_empty(&self) -> bool {
+        *self == TopicMeta::default()
+    }
+}
+
+/// Cards or a whole topic removed from a deck, kept for a while so they
+/// can be restored.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Trashed {
+    /// Topic the cards were in
+    pub topic: String,
+    /// The removed cards
+    pub cards: Vec<Flashcard>,
+    /// Whether the whole topic was removed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub whole_topic: bool,
+    /// Settings of the topic, when the whole topic was removed
+    #[serde(default, skip_serializing_if = "TopicMeta::is_empty")]
+    pub meta: TopicMeta,
+    /// Seconds since the Unix epoch when they were removed
+    pub deleted: u64,
+}
+
+/// What `Topics::tidy` changed in a deck.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Tidied {
+    /// Topics removed for having no cards, by name
+    pub empty_topics: Vec<String>,
+    /// Cards that had stray whitespace or `\r\n` line endings
+    pub cleaned_cards: usize,
+    /// Cards removed for being exact copies of an earlier card in their topic
+    pub duplicates: usize,
+}
+
+impl Tidied {
+    /// Whether the deck was tidy already.
+    pub fn is_empty(&self) -> bool {
+        *self == Tidied::default()
+    }
+}
+
+/// Why a name can't be the name of a new topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopicNameError {
+    /// Nothing but whitespace
+    Empty,
+    /// Tabs or line breaks, which the topic list can't show
+    Control,
+    /// A topic of that name exists, by its name as it is in the deck
+    Taken(String),
+}
+
+impl fmt::Display for TopicNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TopicNameError::Empty => write!(f, "The name can't be empty"),
+            TopicNameError::Control => write!(f, "The name can't hold tabs or line breaks"),
+            TopicNameError::Taken(existing) => {
+                write!(f, "There is already a topic '{}'", existing)
+            }
+        }
+    }
+}
+
+/// A deck: cards by topic name, each topic's cards in the order they were
+/// added.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Topics {
+    /// About the deck as a whole
+    #[serde(default, skip_serializing_if = "DeckMeta::is_empty")]
+    pub meta: DeckMeta,
+    /// Cards of each topic
+    pub topics_map: HashMap<String, Vec<Flashcard>>,
+    /// Settings of the topics that have any
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub topic_meta: HashMap<String, TopicMeta>,
+    /// Removed cards and topics, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trash: Vec<Trashed>,
+}
+
+impl Topics {
+    /// Reads a deck, empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> io::Result<Topics> {
+        Topics::load_with(path, None)
+    }
+
+    /// Reads a deck that may be encrypted, opening it with `key`, with the
+    /// changes saved to its journal since it was last written in full. An
+    /// encrypted deck without a key is an error.
+    pub fn load_with(path: &Path, key: Option<&Key>) -> io::Result<Topics> {
+        let mut topics = Topics::read_file(path, key)?;
+        journal::replay(&mut topics, path, key)?;
+        Ok(topics)
+    }
+
+    fn read_file(path: &Path, key: Option<&Key>) -> io::Result<Topics> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Topics::default()),
+            Err(e) => return Err(e),
+        };
+        if is_encrypted(&text) && key.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} is encrypted", path.display()),
+            ));
+        }
+        Topics::from_json_with(&text, key)
+    }
+
+    /// Reads a deck from the text of a deck file, opening it with `key`
+    /// when it is encrypted.
+    pub fn from_json_with(text: &str, key: Option<&Key>) -> io::Result<Topics> {
+        if !is_encrypted(text) {
+            return Ok(serde_json::from_str(text)?);
+        }
+        let Some(key) = key else {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the deck is encrypted",
+            ));
+        };
+        let envelope: Envelope = serde_json::from_str(text)?;
+        let sealed = STANDARD
+            .decode(envelope.encrypted.data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(serde_json::from_slice(&key.open(&sealed)?)?)
+    }
+
+    /// The deck as its file holds it, encrypted with `key` if there is one.
+    pub fn to_json_with(&self, key: Option<&Key>) -> io::Result<String> {
+        match key {
+            Some(key) => Ok(serde_json::to_string(&self.seal(key)?)?),
+            None => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+
+    fn seal(&self, key: &Key) -> io::Result<Envelope> {
+        let sealed = key.seal(&serde_json::to_vec(self)?)?;
+        Ok(Envelope {
+            encrypted: Sealed {
+                salt: STANDARD.encode(key.salt()),
+                data: STANDARD.encode(sealed),
+            },
+        })
+    }
+
+    /// Writes the deck to `path`, replacing the file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.save_with(path, None)
+    }
+
+    /// Writes the deck to `path`, encrypted with `key` if there is one, and
+    /// removes its journal, which the file now includes. The old file is
+    /// only replaced once the new one is fully written.
+    pub fn save_with(&self, path: &Path, key: Option<&Key>) -> io::Result<()> {
+        atomic::write_with(path, |writer| {
+            match key {
+                Some(key) => serde_json::to_writer(&mut *writer, &self.seal(key)?)?,
+                None => serde_json::to_writer_pretty(&mut *writer, self)?,
+            }
+            Ok(())
+        })?;
+        match fs::remove_file(journal::path_for(path)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Topic names in alphabetical order.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.topics_map.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Settings of the topic `name`, the defaults if it has none.
+    pub fn meta_of(&self, name: &str) -> TopicMeta {
+        self.topic_meta.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Whether the topic `name` is archived.
+    pub fn is_archived(&self, name: &str) -> bool {
+        self.topic_meta.get(name).is_some_and(|meta| meta.archived)
+    }
+
+    /// Replaces the settings of the topic `name`, forgetting them when
+    /// they are all unset.
+    pub fn set_meta(&mut self, name: &str, meta: TopicMeta) {
+        if meta.is_empty() {
+            self.topic_meta.remove(name);
+        } else {
+            self.topic_meta.insert(name.to_string(), meta);
+        }
+    }
+
+    /// Why `name` can't be the name of a new topic, `None` if it can. Names
+    /// are trimmed, and compared to existing ones ignoring case, since
+    /// creating a topic that exists would drop its cards.
+    pub fn new_topic_error(&self, name: &str) -> Option<TopicNameError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Some(TopicNameError::Empty);
+        }
+        if name.chars().any(char::is_control) {
+            return Some(TopicNameError::Control);
+        }
+        self.topics_map
+            .keys()
+            .find(|existing| existing.to_lowercase() == name.to_lowercase())
+            .map(|existing| TopicNameError::Taken(existing.clone()))
+    }
+
+    /// Moves card `index` of `topic` to the trash. Returns whether there
+    /// was such a card.
+    pub fn trash_card(&mut self, topic: &str, index: usize, now: u64) -> bool {
+        let Some(cards) = self.topics_map.get_mut(topic) else {
+            return false;
+        };
+        if index >= cards.len() {
+            return false;
+        }
+        let card = cards.remove(index);
+        self.trash.push(Trashed {
+            topic: topic.to_string(),
+            cards: vec![card],
+            whole_topic: false,
+            meta: TopicMeta::default(),
+            deleted: now,
+        });
+        true
+    }
+
+    /// Moves the topic `name` with its cards and settings to the trash.
+    /// Returns whether there was such a topic.
+    pub fn trash_topic(&mut self, name: &str, now: u64) -> bool {
+        let Some(cards) = self.topics_map.remove(name) else {
+            return false;
+        };
+        let meta = self.topic_meta.remove(name).unwrap_or_default();
+        self.trash.push(Trashed {
+            topic: name.to_string(),
+            cards,
+            whole_topic: true,
+            meta,
+            deleted: now,
+        });
+        true
+    }
+
+    /// Takes entry `index` out of the trash and puts its cards back at the
+    /// end of their topic, which is created again if it is gone. Cards
+    /// already in the topic aren't added twice.
+    pub fn restore(&mut self, index: usize) -> Option<Trashed> {
+        if index >= self.trash.len() {
+            return None;
+        }
+        let trashed = self.trash.remove(index);
+        let exists = self.topics_map.contains_key(&trashed.topic);
+        if trashed.whole_topic && !exists {
+            self.set_meta(&trashed.topic, trashed.meta.clone());
+        }
+        let mut restored = Topics::default();
+        restored
+            .topics_map
+            .insert(trashed.topic.clone(), trashed.cards.clone());
+        self.merge(restored);
+        Some(trashed)
+    }
+
+    /// Empties the trash of what was removed before `cutoff`, in seconds
+    /// since the Unix epoch. Returns how many entries went.
+    pub fn empty_trash(&mut self, cutoff: u64) -> usize {
+        let before = self.trash.len();
+        self.trash.retain(|trashed| trashed.deleted >= cutoff);
+        before - self.trash.len()
+    }
+
+    /// Number of cards across all topics.
+    pub fn card_count(&self) -> usize {
+        self.topics_map.values().map(Vec::len).sum()
+    }
+
+    /// Parses a deck made by someone else and checks it with `check`.
+    pub fn from_shared_json(text: &str) -> io::Result<Topics> {
+        let topics: Topics = serde_json::from_str(text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("not a mem-flip deck: {}", e),
+            )
+        })?;
+        topics.check()?;
+        Ok(topics)
+    }
+
+    /// Checks that every topic has a name and every card a question, which
+    /// the app relies on but a deck from elsewhere may not have.
+    pub fn check(&self) -> io::Result<()> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        for (name, cards) in &self.topics_map {
+            if name.trim().is_empty() {
+                return Err(invalid("a topic has no name".to_string()));
+            }
+            if cards.iter().any(|card| card.question.trim().is_empty()) {
+                return Err(invalid(format!("a card in '{}' has no question", name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cleans up after hand editing: removes topics without cards, trims
+    /// whitespace around card text and at the ends of its lines, turns
+    /// `\r\n` and `\r` line endings into `\n`, and removes cards that
+    /// repeat an earlier card of their topic exactly.
+    pub fn tidy(&mut self) -> Tidied {
+        let mut tidied = Tidied::default();
+        for cards in self.topics_map.values_mut() {
+            let mut kept: Vec<Flashcard> = Vec::with_capacity(cards.len());
+            for mut card in cards.drain(..) {
+                let question = tidy_text(&card.question);
+                let answer = tidy_text(&card.answer);
+                if question != card.question || answer != card.answer {
+                    tidied.cleaned_cards += 1;
+                    card.question = question;
+                    card.answer = answer;
+                }
+                if kept
+                    .iter()
+                    .any(|c| c.question == card.question && c.answer == card.answer)
+                {
+                    tidied.duplicates += 1;
+                } else {
+                    kept.push(card);
+                }
+            }
+            *cards = kept;
+        }
+
+        tidied.empty_topics = self
+            .names()
+            .into_iter()
+            .filter(|name| self.topics_map[name].is_empty())
+            .collect();
+        for name in &tidied.empty_topics {
+            self.topics_map.remove(name);
+            self.topic_meta.remove(name);
+        }
+        tidied
+    }
+
+    /// Adds the cards of `other` to the topics of the same name, creating
+    /// topics as needed. Cards already in the topic are skipped. Returns the
+    /// number of cards added. Settings are taken from `other` only for
+    /// topics that have none.
+    pub fn merge(&mut self, other: Topics) -> usize {
+        for (name, meta) in other.topic_meta {
+            self.topic_meta.entry(name).or_insert(meta);
+        }
+        let mut added = 0;
+        for (name, cards) in other.topics_map {
+            let topic = self.topics_map.entry(name).or_default();
+            for card in cards {
+                // Ratings are the user's own, not part of what a card is
+                let known = topic
+                    .iter()
+                    .any(|c| c.question == card.question && c.answer == card.answer);
+                if !known {
+                    topic.push(card);
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+}
+
+// `text` with `\n` line endings and without whitespace at the ends of it
+// or of its lines
+fn tidy_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Whether deck file contents are an encrypted deck, which is written
+/// compactly so this only needs to look at the start.
+pub fn is_encrypted(text: &str) -> bool {
+    text.starts_with(r#"{"encrypted":"#)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deck_format() {
+        let text = r#"{"topics_map":{"es":[{"question":"perro","answer":"dog"}]}}"#;
+        let topics: Topics = serde_json::from_str(text).unwrap();
+        assert_eq!(topics.names(), vec!["es"]);
+        assert_eq!(topics.card_count(), 1);
+        // Decks without metadata are written as before
+        assert_eq!(serde_json::to_string(&topics).unwrap(), text);
+    }
+
+    #[test]
+    fn test_merge_skips_known_cards() {
+        let mut topics = Topics::default();
+        let card = |question: &str| Flashcard::new(question, "a");
+        topics
+            .topics_map
+            .insert("es".to_string(), vec![card("perro")]);
+
+        let mut other = Topics::default();
+        other
+            .topics_map
+            .insert("es".to_string(), vec![card("perro"), card("gato")]);
+        other
+            .topics_map
+            .insert("fr".to_string(), vec![card("chien")]);
+
+        assert_eq!(topics.merge(other), 2);
+        assert_eq!(topics.topics_map["es"], vec![card("perro"), card("gato")]);
+        assert_eq!(topics.names(), vec!["es", "fr"]);
+    }
+
+    #[test]
+    fn test_shared_deck_is_checked() {
+        assert!(Topics::from_shared_json(r#"{"topics_map":{"es":[]}}"#).is_ok());
+        assert!(Topics::from_shared_json("<html>").is_err());
+        let no_question = r#"{"topics_map":{"es":[{"question":" ","answer":"a"}]}}"#;
+        assert!(Topics::from_shared_json(no_question).is_err());
+    }
+
+    #[test]
+    fn test_meta() {
+        let text = r#"{"meta":{"title":"Verbs","created":"2024-02-29"},"topics_map":{}}"#;
+        let topics: Topics = serde_json::from_str(text).unwrap();
+        assert_eq!(topics.meta.title.as_deref(), Some("Verbs"));
+        assert_eq!(topics.meta.author, None);
+        assert_eq!(serde_json::to_string(&topics).unwrap(), text);
+
+        let path = Path::new("decks/spanish.json");
+        assert_eq!(topics.meta.title_or_name(path), "Verbs");
+        assert_eq!(DeckMeta::default().title_or_name(path), "spanish");
+    }
+
+    #[test]
+    fn test_topic_meta() {
+        let text = r#"{"topics_map":{"es":[]},"topic_meta":{"es":{"template":{"question":"Word:","answer":"Meaning:"}}}}"#;
+        let mut topics: Topics = serde_json::from_str(text).unwrap();
+        let template = topics.meta_of("es").template.unwrap();
+        assert_eq!(template.answer, "Meaning:");
+        assert!(topics.meta_of("fr").is_empty());
+        assert!(!topics.is_archived("es"));
+        assert_eq!(serde_json::to_string(&topics).unwrap(), text);
+
+        // Unset settings aren't kept
+        topics.set_meta("es", TopicMeta::default());
+        assert!(topics.topic_meta.is_empty());
+    }
+
+    #[test]
+    fn test_new_topic_error() {
+        let topics: Topics = serde_json::from_str(r#"{"topics_map":{"Spanish":[]}}"#).unwrap();
+        assert_eq!(topics.new_topic_error(" French "), None);
+        assert!(topics.new_topic_error("  ").is_some());
+        assert!(topics.new_topic_error("a\tb").is_some());
+        assert_eq!(
+            topics.new_topic_error("spanish ").map(|e| e.to_string()),
+            Some("There is already a topic 'Spanish'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tidy() {
+        let card = |question: &str, answer: &str| Flashcard::new(question, answer);
+        let mut topics = Topics::default();
+        topics.topics_map.insert(
+            "Spanish".to_string(),
+            vec![
+                card(" perro\r\n", "dog  \r\nbarks"),
+                card("perro", "dog\nbarks"),
+                card("gato", "cat"),
+            ],
+        );
+        topics.topics_map.insert("Empty".to_string(), Vec::new());
+
+        let tidied = topics.tidy();
+        assert_eq!(
+            tidied,
+            Tidied {
+                empty_topics: vec!["Empty".to_string()],
+                cleaned_cards: 1,
+                duplicates: 1,
+            }
+        );
+        assert_eq!(
+            topics.topics_map["Spanish"],
+            [card("perro", "dog\nbarks"), card("gato", "cat")]
+        );
+        assert!(topics.tidy().is_empty());
+    }
+
+    #[test]
+    fn test_trash() {
+        let card = |question: &str| Flashcard::new(question, "a");
+        let mut topics = Topics::default();
+        topics
+            .topics_map
+            .insert("es".to_string(), vec![card("perro"), card("gato")]);
+        topics.set_meta(
+            "es",
+            TopicMeta {
+                reversed: true,
+                ..TopicMeta::default()
+            },
+        );
+
+        assert!(topics.trash_card("es", 0, 10));
+        assert!(!topics.trash_card("es", 5, 10));
+        assert_eq!(topics.topics_map["es"], [card("gato")]);
+        assert!(topics.trash_topic("es", 20));
+        assert!(topics.topics_map.is_empty());
+        assert_eq!(topics.trash.len(), 2);
+
+        // The topic comes back with its settings, then the card after it
+        assert!(topics.restore(1).unwrap().whole_topic);
+        assert!(topics.meta_of("es").reversed);
+        topics.restore(0);
+        assert_eq!(topics.topics_map["es"], [card("gato"), card("perro")]);
+
+        topics.trash_card("es", 0, 10);
+        topics.trash_card("es", 0, 30);
+        assert_eq!(topics.empty_trash(20), 1);
+        assert_eq!(topics.trash[0].cards, [card("perro")]);
+    }
+
+    #[test]
+    fn test_revisions() {
+        let mut card = Flashcard::new("perro", "dog");
+        assert!(!card.revise("perro".to_string(), "dog".to_string(), 1));
+        assert!(card.revise("el perro".to_string(), "the dog".to_string(), 2));
+        assert_eq!(card.revisions.len(), 1);
+        assert_eq!(card.revisions[0].answer, "dog");
+        assert_eq!(card.revisions[0].replaced, 2);
+
+        card.restore(0, 3);
+        assert_eq!(card.question, "perro");
+        assert_eq!(card.revisions[0].question, "el perro");
+
+        for i in 0..MAX_REVISIONS + 5 {
+            card.revise(i.to_string(), String::new(), 4);
+        }
+        assert_eq!(card.revisions.len(), MAX_REVISIONS);
+    }
+
+    #[test]
+    fn test_encrypted_deck() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("secret.json");
+        let mut topics = Topics::default();
+        topics.topics_map.insert(
+            "Cases".to_string(),
+            vec![Flashcard::new("Patient A", "Diagnosis")],
+        );
+        let key = Key::new("passphrase").unwrap();
+        topics.save_with(&path, Some(&key)).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(is_encrypted(&text));
+        assert!(!text.contains("Patient"));
+        let error = Topics::load(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+
+        let salt = crate::crypt::deck_salt(&path).unwrap().unwrap();
+        let key = Key::derive("passphrase", &salt).unwrap();
+        assert_eq!(Topics::load_with(&path, Some(&key)).unwrap(), topics);
+    }
+}
@@ -0,0 +1,197 @@
+//! Deck files opened recently, and the screen for switching between them.
+
+use std::path::{Path, PathBuf};
+
+use mem_flip::deck::DeckMeta;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
+};
+
+use crate::l10n::{hint, tr};
+use crate::theme;
+
+const MAX_RECENT: usize = 10;
+
+/// Moves `path` to the front of the recent decks, dropping the oldest past
+/// the limit.
+pub fn remember(recent: &mut Vec<PathBuf>, path: &Path) {
+    recent.retain(|p| p != path);
+    recent.insert(0, path.to_path_buf());
+    recent.truncate(MAX_RECENT);
+}
+
+/// `path` made absolute, so decks are found again from any directory.
+pub fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeckPrompt {
+    Open,
+    New,
+    Fetch,
+    Import,
+}
+
+pub struct DeckSwitcher<'a> {
+    // Most recent first
+    pub recent: &'a [PathBuf],
+    // Metadata of each recent deck, in the same order
+    pub meta: &'a [DeckMeta],
+    pub current: &'a Path,
+    pub selected: usize,
+    // Path being typed to open or create a deck or to import, or URL to
+    // download a deck
+    pub prompt: Option<(DeckPrompt, &'a str)>,
+}
+
+impl Widget for DeckSwitcher<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            hint("Open"),
+            "<Enter>".blue().bold(),
+            hint("Open File"),
+            "<O>".blue().bold(),
+            hint("New Deck"),
+            "<N>".blue().bold(),
+            hint("Fetch"),
+            "<F>".blue().bold(),
+            hint("Import"),
+            "<I>".blue().bold(),
+            hint("Bundle"),
+            "<B>".blue().bold(),
+            hint("Web page"),
+            "<H>".blue().bold(),
+            hint("Sync"),
+            "<S>".blue().bold(),
+            hint("Back"),
+            "<Esc> ".blue().bold(),
+        ]);
+        let block = Block::bordered()
+            .title(format!(" 🗂 {} ", tr("Decks")).bold())
+            .title_bottom(instructions);
+
+        let about = self
+            .meta
+            .get(self.selected)
+            .filter(|meta| !meta.is_empty())
+            .map(details);
+        let about_height = about.as_ref().map_or(0, |text| text.height() as u16 + 2);
+        let prompt_height = if self.prompt.is_some() { 3 } else { 0 };
+        let [list_area, about_area, prompt_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(about_height),
+            Constraint::Length(prompt_height),
+        ])
+        .areas(area);
+
+        let items: Vec<ListItem> = self
+            .recent
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let name = match self.meta.get(i) {
+                    Some(meta) => meta.title_or_name(path),
+                    None => DeckMeta::default().title_or_name(path),
+                };
+                let marker = if path == self.current {
+                    Span::styled("● ", Style::default().fg(Color::Green))
+                } else {
+                    Span::raw("  ")
+                };
+                ListItem::new(Line::from(vec![
+                    marker,
+                    Span::raw(name),
+                    Span::styled(
+                        format!("  {}", path.display()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ ");
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(list, list_area, buf, &mut state);
+
+        if let Some(about) = about {
+            Paragraph::new(about)
+                .block(Block::bordered().title(format!(" {} ", tr("About"))))
+                .render(about_area, buf);
+        }
+
+        if let Some((kind, input)) = self.prompt {
+            let title = match kind {
+                DeckPrompt::Open => "Open deck file",
+                DeckPrompt::New => "New deck file",
+                DeckPrompt::Fetch => "Deck URL",
+                DeckPrompt::Import => "Import org, Quizlet, Mochi or RemNote file, or notes folder",
+            };
+            Paragraph::new(Line::from(vec![
+                Span::raw("> "),
+                Span::styled(input, Style::default().fg(theme::palette().input)),
+                Span::styled("█", Style::default().fg(theme::palette().input)),
+            ]))
+            .block(Block::bordered().title(format!(" {} ", tr(title))))
+            .render(prompt_area, buf);
+        }
+    }
+}
+
+// Author, version and creation date on one line, the description below
+fn details(meta: &DeckMeta) -> Text<'static> {
+    let mut facts = Vec::new();
+    for (label, value) in [
+        ("by", &meta.author),
+        ("version", &meta.version),
+        ("created", &meta.created),
+    ] {
+        if let Some(value) = value {
+            facts.push(Span::styled(
+                format!("{} ", label),
+                Style::default().fg(Color::DarkGray),
+            ));
+            facts.push(Span::raw(format!("{}  ", value)));
+        }
+    }
+
+    let mut text = Text::default();
+    if !facts.is_empty() {
+        text.push_line(Line::from(facts));
+    }
+    if let Some(description) = &meta.description {
+        text.extend(description.lines().map(|line| Line::from(line.to_string())));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remember_moves_to_front() {
+        let mut recent: Vec<PathBuf> = (0..MAX_RECENT)
+            .map(|i| PathBuf::from(format!("/decks/{}.json", i)))
+            .collect();
+        remember(&mut recent, Path::new("/decks/3.json"));
+        assert_eq!(recent[0], Path::new("/decks/3.json"));
+        assert_eq!(recent.len(), MAX_RECENT);
+
+        remember(&mut recent, Path::new("/decks/new.json"));
+        assert_eq!(recent[0], Path::new("/decks/new.json"));
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert!(!recent.contains(&PathBuf::from("/decks/9.json")));
+    }
+}
@@ -0,0 +1,101 @@
+//! Modal dialogs drawn over the current screen.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Padding, Paragraph, Widget, Wrap},
+};
+
+use crate::l10n::hint;
+use crate::theme;
+
+const WIDTH: u16 = 60;
+
+#[derive(Debug)]
+pub struct ErrorDialog {
+    pub title: String,
+    pub message: String,
+    // Offer to quit without saving, for errors while saving on the way out
+    pub can_quit: bool,
+}
+
+impl ErrorDialog {
+    pub fn new(title: impl Into<String>, message: impl ToString) -> ErrorDialog {
+        ErrorDialog {
+            title: title.into(),
+            message: message.to_string(),
+            can_quit: false,
+        }
+    }
+}
+
+impl Widget for &ErrorDialog {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut hints = vec![hint("Dismiss"), "<Enter/Esc>".blue().bold()];
+        if self.can_quit {
+            hints.extend([hint("Quit without saving"), "<Q> ".red().bold()]);
+        } else {
+            hints.push(" ".into());
+        }
+
+        let paragraph = Paragraph::new(self.message.as_str())
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::bordered()
+                    .title(format!(" ⚠ {} ", self.title).bold())
+                    .title_bottom(Line::from(hints))
+                    .padding(Padding::uniform(1))
+                    .style(Style::default().fg(Color::Red)),
+            );
+        render_centered(paragraph, area, buf);
+    }
+}
+
+/// A question answered with yes or no, such as whether to reload a deck.
+#[derive(Debug)]
+pub struct ConfirmDialog {
+    pub title: String,
+    pub message: String,
+    // What answering yes and no does, shown next to their keys
+    pub yes: &'static str,
+    pub no: &'static str,
+}
+
+impl Widget for &ConfirmDialog {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let hints = Line::from(vec![
+            hint(self.yes),
+            "<Y>".blue().bold(),
+            hint(self.no),
+            "<N> ".blue().bold(),
+        ]);
+        let paragraph = Paragraph::new(self.message.as_str())
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::bordered()
+                    .title(format!(" {} ", self.title).bold())
+                    .title_bottom(hints)
+                    .padding(Padding::uniform(1))
+                    .style(Style::default().fg(theme::palette().input)),
+            );
+        render_centered(paragraph, area, buf);
+    }
+}
+
+// Draws `paragraph` in a box of the dialog width, as tall as its text
+pub fn render_centered(paragraph: Paragraph, area: Rect, buf: &mut Buffer) {
+    let width = WIDTH.min(area.width);
+    // Counted at the inner width, borders and padding are included
+    let height = (paragraph.line_count(width.saturating_sub(4)) as u16).min(area.height);
+    let [popup] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    Clear.render(popup, buf);
+    paragraph.render(popup, buf);
+}
@@ -0,0 +1,90 @@
+//! Word by word comparison of a typed answer with the card's answer.
+//!
+//! Words are compared ignoring case and the punctuation around them, so
+//! "Paris." matches "paris". The result keeps the words as they were written.
+
+/// A word of the comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Word<'a> {
+    /// In both answers
+    Same(&'a str),
+    /// In the card's answer, not in the typed one
+    Missing(&'a str),
+    /// Typed, but not in the card's answer
+    Extra(&'a str),
+}
+
+/// The words of `typed` and `expected` in order, each marked as in both or
+/// only one of them. Uses the longest run of words they share, so a missing
+/// or extra word doesn't throw off the rest.
+pub fn word_diff<'a>(typed: &'a str, expected: &'a str) -> Vec<Word<'a>> {
+    let typed: Vec<&str> = typed.split_whitespace().collect();
+    let expected: Vec<&str> = expected.split_whitespace().collect();
+    let same = |a: &str, b: &str| normalize(a) == normalize(b);
+
+    // Longest common subsequence of the suffixes, from the end back
+    let (n, m) = (typed.len(), expected.len());
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if same(typed[i], expected[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut words = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if same(typed[i], expected[j]) {
+            words.push(Word::Same(expected[j]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            words.push(Word::Extra(typed[i]));
+            i += 1;
+        } else {
+            words.push(Word::Missing(expected[j]));
+            j += 1;
+        }
+    }
+    words.extend(typed[i..].iter().map(|word| Word::Extra(word)));
+    words.extend(expected[j..].iter().map(|word| Word::Missing(word)));
+    words
+}
+
+/// Whether `typed` has all the words of `expected` and no others.
+pub fn matches(words: &[Word]) -> bool {
+    words.iter().all(|word| matches!(word, Word::Same(_)))
+}
+
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_diff() {
+        use Word::*;
+        assert_eq!(
+            word_diff("the big capital paris", "The capital of France: Paris."),
+            [
+                Same("The"),
+                Extra("big"),
+                Same("capital"),
+                Missing("of"),
+                Missing("France:"),
+                Same("Paris."),
+            ]
+        );
+        assert!(matches(&word_diff("PARIS", "Paris.")));
+        assert_eq!(word_diff("", "dog"), [Missing("dog")]);
+        assert_eq!(word_diff("cat", ""), [Extra("cat")]);
+    }
+}
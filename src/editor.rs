@@ -0,0 +1,357 @@
+//! Layout of the AddCard text fields.
+//!
+//! Rows are wrapped by display width rather than by character count, so
+//! double-width CJK characters and emoji don't push the cursor or the wrap
+//! point off by a column. Right-to-left paragraphs are reordered and
+//! right-aligned by `bidi`, and the arrow keys move the cursor through rows
+//! as they are shown rather than in the order the text is stored, so Left
+//! goes left in Hebrew too.
+//!
+//! Text taller than its area is scrolled so the cursor stays in view, with
+//! arrows in the right column marking lines hidden above or below.
+//!
+//! Misspelled words are underlined in red.
+//!
+//! `Counts` gives the live character, word and line counts shown under
+//! each field.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+use std::fmt;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::bidi;
+use crate::spell;
+
+const PROMPT: &str = "> ";
+const RTL_PROMPT: &str = " <";
+const INDENT: &str = "  ";
+const CURSOR: &str = "█";
+const MORE_ABOVE: &str = "▲";
+const MORE_BELOW: &str = "▼";
+
+/// Size of a field's text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub chars: usize,
+    pub words: usize,
+    pub lines: usize,
+}
+
+impl Counts {
+    pub fn of(text: &str) -> Counts {
+        Counts {
+            chars: text.chars().filter(|&c| c != '\n').count(),
+            words: text.split_whitespace().count(),
+            // As many as the editor shows, a trailing newline starts one
+            lines: text.split('\n').count(),
+        }
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} char{} · {} word{} · {} line{}",
+            self.chars,
+            plural(self.chars),
+            self.words,
+            plural(self.words),
+            self.lines,
+            plural(self.lines)
+        )
+    }
+}
+
+/// A text field laid out for a given width.
+#[derive(Debug)]
+pub struct EditorView {
+    lines: Vec<Line<'static>>,
+    // Cursor cell relative to the text area, only while editing
+    cursor: Option<Position>,
+    style: Style,
+}
+
+impl EditorView {
+    /// The field showing `text`, with the cursor before byte `cursor` of it
+    /// while editing.
+    pub fn new(text: &str, style: Style, cursor: Option<usize>, width: u16) -> EditorView {
+        let width = usize::from(width);
+        let text_width = width.saturating_sub(PROMPT.len());
+        let mut lines = vec![Line::from("")]; // Empty line at top
+        let mut cell = None;
+
+        let mut start = 0;
+        for line in text.split('\n') {
+            let visual = bidi::layout(line, text_width);
+            let last_row = visual.rows.len() - 1;
+            // Row of the line the cursor is on, and its column there
+            let on_line = cursor
+                .filter(|&c| (start..=start + line.len()).contains(&c))
+                .map(|c| visual.cursor(c - start));
+            start += line.len() + 1;
+
+            for (r, row) in visual.rows.into_iter().enumerate() {
+                let row_width = row.width();
+                let column = on_line
+                    .filter(|&(row, _)| row == r)
+                    .map(|(_, column)| column);
+
+                if visual.rtl {
+                    let spans = vec![Span::styled(row, style), Span::raw(RTL_PROMPT)];
+                    lines.push(Line::from(spans).right_aligned());
+                    let start = width.saturating_sub(RTL_PROMPT.len() + row_width);
+                    cell = match column {
+                        Some(Some(x)) => Some((start + x, lines.len() - 1)),
+                        // Left of the text, or the start of a new row when full
+                        Some(None) if start == 0 && r == last_row => {
+                            lines.push(Line::from(""));
+                            Some((width.saturating_sub(RTL_PROMPT.len() + 1), lines.len() - 1))
+                        }
+                        Some(None) => Some((start.saturating_sub(1), lines.len() - 1)),
+                        None => cell,
+                    };
+                } else {
+                    let prompt = if r == 0 { PROMPT } else { INDENT };
+                    lines.push(Line::from(vec![
+                        Span::raw(prompt),
+                        Span::styled(row, style),
+                    ]));
+                    let end = PROMPT.len() + row_width;
+                    cell = match column {
+                        Some(Some(x)) => Some((PROMPT.len() + x, lines.len() - 1)),
+                        // Right of the text, or the start of a new row when full
+                        Some(None) if end >= width && r == last_row => {
+                            lines.push(Line::from(INDENT));
+                            Some((PROMPT.len(), lines.len() - 1))
+                        }
+                        Some(None) => Some((end.min(width.saturating_sub(1)), lines.len() - 1)),
+                        None => cell,
+                    };
+                }
+            }
+        }
+
+        EditorView {
+            lines,
+            cursor: cell.map(|(x, y)| Position::new(x as u16, y as u16)),
+            style,
+        }
+    }
+
+    /// Underlines the `words` in the text.
+    pub fn misspelled(mut self, words: &[&str]) -> EditorView {
+        if words.is_empty() {
+            return self;
+        }
+        let marked = self.style.fg(Color::Red).add_modifier(Modifier::UNDERLINED);
+        for line in &mut self.lines {
+            let spans = std::mem::take(&mut line.spans);
+            for span in spans {
+                if span.style != self.style {
+                    line.spans.push(span);
+                    continue;
+                }
+                // Alternating runs of word and other characters
+                let text = span.content.as_ref();
+                let mut start = 0;
+                while start < text.len() {
+                    let in_word = text[start..].starts_with(spell::is_word_char);
+                    let end = text[start..]
+                        .find(|c| spell::is_word_char(c) != in_word)
+                        .map_or(text.len(), |len| start + len);
+                    let run = &text[start..end];
+                    let style = if in_word && words.contains(&run) {
+                        marked
+                    } else {
+                        self.style
+                    };
+                    line.spans.push(Span::styled(run.to_string(), style));
+                    start = end;
+                }
+            }
+        }
+        self
+    }
+
+    // First line shown in a viewport `height` lines tall: just enough to
+    // keep the cursor on the bottom row, or the top when not editing
+    fn scroll(&self, height: u16) -> u16 {
+        match self.cursor {
+            Some(cursor) => (cursor.y + 1).saturating_sub(height),
+            None => 0,
+        }
+    }
+}
+
+/// Moves `cursor`, a byte offset into `text`, one cell left of where it is
+/// shown in a field `width` columns wide, or right unless `left`. Off the
+/// side of a line it goes on to the line before or after, as the text reads.
+pub fn move_cursor(text: &str, cursor: usize, left: bool, width: u16) -> usize {
+    let text_width = usize::from(width).saturating_sub(PROMPT.len());
+    let mut start = 0;
+    for line in text.split('\n') {
+        let end = start + line.len();
+        if cursor <= end {
+            let visual = bidi::layout(line, text_width);
+            return match visual.step(cursor - start, left) {
+                Some(offset) => start + offset,
+                // To the start of the next line
+                None if left == visual.rtl => {
+                    if end < text.len() {
+                        end + 1
+                    } else {
+                        cursor
+                    }
+                }
+                // To the end of the previous one
+                None => start.checked_sub(1).unwrap_or(cursor),
+            };
+        }
+        start = end + 1;
+    }
+    text.len()
+}
+
+impl Widget for EditorView {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let scroll = self.scroll(area.height);
+        let hidden_below = self.lines.len() > usize::from(scroll + area.height);
+        Paragraph::new(self.lines)
+            .scroll((scroll, 0))
+            .render(area, buf);
+
+        if let Some(cursor) = self.cursor {
+            let position = Position::new(area.x + cursor.x, area.y + cursor.y - scroll);
+            if area.contains(position) {
+                let cell = &mut buf[position];
+                // Shown through the cursor when it is on a character
+                if cell.symbol().trim().is_empty() {
+                    cell.set_symbol(CURSOR).set_style(self.style);
+                } else {
+                    cell.set_style(self.style.add_modifier(Modifier::REVERSED));
+                }
+            }
+        }
+
+        if area.is_empty() {
+            return;
+        }
+        let right = area.right() - 1;
+        if scroll > 0 {
+            buf[(right, area.y)].set_symbol(MORE_ABOVE);
+        }
+        if hidden_below {
+            buf[(right, area.bottom() - 1)].set_symbol(MORE_BELOW);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_counts_display_columns() {
+        // Two double-width characters take four columns after the prompt
+        let view = EditorView::new("日本", Style::default(), Some(6), 20);
+        assert_eq!(view.cursor, Some(Position::new(6, 1)));
+
+        let view = EditorView::new("ab", Style::default(), Some(2), 20);
+        assert_eq!(view.cursor, Some(Position::new(4, 1)));
+    }
+
+    #[test]
+    fn test_wide_characters_wrap_by_width() {
+        // 8 columns of text fit a 10 column field, the 5th character wraps
+        let view = EditorView::new("日本語です", Style::default(), Some(15), 10);
+        assert_eq!(view.lines.len(), 3);
+        assert_eq!(view.lines[1].to_string(), "> 日本語で");
+        assert_eq!(view.lines[2].to_string(), "  す");
+        assert_eq!(view.cursor, Some(Position::new(4, 2)));
+    }
+
+    #[test]
+    fn test_cursor_inside_rtl_text() {
+        // The first letter is the rightmost, just left of the prompt
+        let view = EditorView::new("שלום", Style::default(), Some(0), 20);
+        assert_eq!(view.cursor, Some(Position::new(17, 1)));
+        let view = EditorView::new("שלום", Style::default(), Some(8), 20);
+        assert_eq!(view.cursor, Some(Position::new(13, 1)));
+
+        // Drawn over the letter rather than in place of it
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 2));
+        let view = EditorView::new("שלום", Style::default(), Some(0), 20);
+        view.render(buf.area, &mut buf);
+        assert_eq!(buf[(17, 1)].symbol(), "ש");
+        assert!(buf[(17, 1)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_move_cursor() {
+        let text = "ab\nשלום";
+        assert_eq!(move_cursor(text, 1, false, 20), 2);
+        // Off the end of a line onto the start of the next, which for
+        // Hebrew is on the right
+        assert_eq!(move_cursor(text, 2, false, 20), 3);
+        assert_eq!(move_cursor(text, 3, true, 20), 5);
+        assert_eq!(move_cursor(text, 3, false, 20), 2);
+        // Nowhere to go at either end of the text
+        assert_eq!(move_cursor(text, text.len(), true, 20), text.len());
+        assert_eq!(move_cursor(text, 0, true, 20), 0);
+    }
+
+    #[test]
+    fn test_viewport_follows_cursor() {
+        let text = "one\ntwo\nthree\nfour\nfive";
+        let view = EditorView::new(text, Style::default(), Some(text.len()), 20);
+        // Blank top line plus five lines, the cursor is on the sixth
+        assert_eq!(view.scroll(10), 0);
+        assert_eq!(view.scroll(4), 2);
+
+        let view = EditorView::new(text, Style::default(), None, 20);
+        assert_eq!(view.scroll(4), 0);
+    }
+
+    #[test]
+    fn test_misspelled_words_are_marked() {
+        let view =
+            EditorView::new("teh cat's teh", Style::default(), None, 20).misspelled(&["teh"]);
+        let marked: Vec<&str> = view.lines[1]
+            .spans
+            .iter()
+            .filter(|span| span.style.add_modifier.contains(Modifier::UNDERLINED))
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(marked, ["teh", "teh"]);
+        assert_eq!(view.lines[1].to_string(), "> teh cat's teh");
+    }
+
+    #[test]
+    fn test_counts() {
+        let counts = Counts::of("Paris is\nthe capital\n");
+        assert_eq!(
+            counts,
+            Counts {
+                chars: 19,
+                words: 4,
+                lines: 3,
+            }
+        );
+        assert_eq!(Counts::of("").to_string(), "0 chars · 0 words · 1 line");
+    }
+
+    #[test]
+    fn test_no_cursor_when_not_editing() {
+        let view = EditorView::new("text", Style::default(), None, 20);
+        assert_eq!(view.cursor, None);
+    }
+}
@@ -0,0 +1,304 @@
+//! Writing one topic to a file of its own, to share it without the rest of
+//! the deck.
+//!
+//! JSON gives a deck of just that topic, which opens or imports like any
+//! other. Markdown uses the `Q::`/`A::` lines the Obsidian importer reads.
+//! The printable formats are typst source, made into a PDF with
+//! `typst compile`: cards to cut out, answers on the back when printed
+//! double-sided, or a sheet of questions and answers in two columns.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use mem_flip::deck::Topics;
+use mem_flip::stats::csv_field;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Markdown,
+    PrintCards,
+    StudySheet,
+}
+
+const FORMATS: [ExportFormat; 5] = [
+    ExportFormat::Json,
+    ExportFormat::Csv,
+    ExportFormat::Markdown,
+    ExportFormat::PrintCards,
+    ExportFormat::StudySheet,
+];
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::PrintCards => "Printable cards (typst)",
+            ExportFormat::StudySheet => "Study sheet (typst)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+            ExportFormat::PrintCards => "cards.typ",
+            ExportFormat::StudySheet => "sheet.typ",
+        }
+    }
+
+    pub fn next(self) -> ExportFormat {
+        FORMATS[(self.position() + 1) % FORMATS.len()]
+    }
+
+    pub fn previous(self) -> ExportFormat {
+        FORMATS[(self.position() + FORMATS.len() - 1) % FORMATS.len()]
+    }
+
+    fn position(self) -> usize {
+        FORMATS.iter().position(|f| *f == self).unwrap_or(0)
+    }
+}
+
+/// File a topic is exported to, in the current directory: `Networking.md`
+/// for the topic Networking.
+pub fn export_path(topic: &str, format: ExportFormat) -> PathBuf {
+    PathBuf::from(format!("{}.{}", file_name(topic), format.extension()))
+}
+
+/// `name` made fit for a file name: characters that can't be in one become
+/// `_`.
+pub fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    name.trim().to_string()
+}
+
+/// Writes the topic `name` of `topics` to a new file at `path`. Returns the
+/// number of cards written.
+pub fn export_topic(
+    topics: &Topics,
+    name: &str,
+    format: ExportFormat,
+    path: &Path,
+) -> io::Result<usize> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let count = write_topic(topics, name, format, &mut out)?;
+    out.flush()?;
+    Ok(count)
+}
+
+/// Writes the topic `name` of `topics` as `format`. Returns the number of
+/// cards written.
+pub fn write_topic(
+    topics: &Topics,
+    name: &str,
+    format: ExportFormat,
+    mut out: impl Write,
+) -> io::Result<usize> {
+    let cards = topics
+        .topics_map
+        .get(name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no topic {}", name)))?;
+    match format {
+        ExportFormat::Json => {
+            // The deck's own details describe the whole deck, not the topic
+            let mut single = Topics::default();
+            single.topics_map.insert(name.to_string(), cards.clone());
+            single.set_meta(name, topics.meta_of(name));
+            serde_json::to_writer_pretty(&mut out, &single)?;
+            writeln!(out)?;
+        }
+        ExportFormat::Csv => {
+            writeln!(out, "question,answer")?;
+            for card in cards {
+                writeln!(
+                    out,
+                    "{},{}",
+                    csv_field(&card.question),
+                    csv_field(&card.answer)
+                )?;
+            }
+        }
+        ExportFormat::Markdown => {
+            writeln!(out, "# {}", name)?;
+            for card in cards {
+                // A blank line would end the card when read back
+                writeln!(out)?;
+                writeln!(out, "Q:: {}", without_blank_lines(&card.question))?;
+                writeln!(out, "A:: {}", without_blank_lines(&card.answer))?;
+            }
+        }
+        ExportFormat::PrintCards | ExportFormat::StudySheet => {
+            // The cards are typst strings, so nothing in them is markup
+            writeln!(out, "#let title = {}", typst_string(name))?;
+            writeln!(out, "#let cards = (")?;
+            for card in cards {
+                writeln!(
+                    out,
+                    "  ({}, {}),",
+                    typst_string(&card.question),
+                    typst_string(&card.answer)
+                )?;
+            }
+            writeln!(out, ")")?;
+            out.write_all(if format == ExportFormat::PrintCards {
+                PRINT_CARDS.as_bytes()
+            } else {
+                STUDY_SHEET.as_bytes()
+            })?;
+        }
+    }
+    Ok(cards.len())
+}
+
+// Ten cards to a page, each page of questions followed by their answers
+// with the columns swapped, so they line up when printed on both sides
+static PRINT_CARDS: &str = r#"
+#set page(paper: "a4", margin: 1cm)
+#set text(size: 12pt)
+#let card(body) = box(
+  width: 100%,
+  height: 5.2cm,
+  stroke: (paint: gray, dash: "dashed", thickness: 0.5pt),
+  inset: 10pt,
+  align(center + horizon, body),
+)
+#for (i, side) in cards.chunks(10).enumerate() {
+  if i > 0 { pagebreak() }
+  grid(columns: (1fr, 1fr), ..side.map(c => card(strong(c.at(0)))))
+  pagebreak()
+  let backs = side.map(c => card(c.at(1))).chunks(2)
+  grid(columns: (1fr, 1fr), ..backs.map(row => if row.len() == 1 {
+    (box(), row.at(0))
+  } else {
+    row.rev()
+  }).flatten())
+}
+"#;
+
+static STUDY_SHEET: &str = r#"
+#set page(paper: "a4", margin: 1.5cm, columns: 2)
+#set text(size: 10pt)
+#heading(title)
+#for (question, answer) in cards {
+  block(breakable: false, below: 1.2em)[#strong(question) \ #answer]
+}
+"#;
+
+// `text` as a typst string literal
+fn typst_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn without_blank_lines(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use mem_flip::deck::{Flashcard, TopicMeta};
+
+    use super::*;
+
+    fn networking() -> Topics {
+        let mut topics = Topics::default();
+        let card = |question: &str, answer: &str| Flashcard::new(question, answer);
+        topics.topics_map.insert(
+            "Networking".to_string(),
+            vec![
+                card("Port of HTTPS?", "443"),
+                card("Layers, top down", "application,\n\ntransport"),
+            ],
+        );
+        topics
+            .topics_map
+            .insert("Private".to_string(), vec![card("pin", "1234")]);
+        topics.set_meta(
+            "Networking",
+            TopicMeta {
+                reversed: true,
+                ..TopicMeta::default()
+            },
+        );
+        topics
+    }
+
+    fn export(format: ExportFormat) -> String {
+        let mut out = Vec::new();
+        write_topic(&networking(), "Networking", format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_json_has_only_the_topic() {
+        let single: Topics = serde_json::from_str(&export(ExportFormat::Json)).unwrap();
+        assert_eq!(single.topics_map.len(), 1);
+        assert_eq!(single.topics_map["Networking"].len(), 2);
+        assert!(single.meta_of("Networking").reversed);
+    }
+
+    #[test]
+    fn test_csv_and_markdown() {
+        assert_eq!(
+            export(ExportFormat::Csv),
+            "question,answer\nPort of HTTPS?,443\n\"Layers, top down\",\"application,\n\ntransport\"\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Markdown),
+            "# Networking\n\nQ:: Port of HTTPS?\nA:: 443\n\nQ:: Layers, top down\nA:: application,\ntransport\n"
+        );
+    }
+
+    #[test]
+    fn test_typst() {
+        let cards = export(ExportFormat::PrintCards);
+        assert!(cards.starts_with("#let title = \"Networking\"\n#let cards = (\n"));
+        assert!(cards.contains("  (\"Layers, top down\", \"application,\\n\\ntransport\"),\n"));
+        assert!(cards.contains("cards.chunks(10)"));
+        assert!(export(ExportFormat::StudySheet).contains("columns: 2"));
+        assert_eq!(
+            typst_string("say \"hi\" \\ #bye"),
+            r#""say \"hi\" \\ #bye""#
+        );
+    }
+
+    #[test]
+    fn test_export_path() {
+        assert_eq!(
+            export_path("TCP/IP", ExportFormat::Markdown),
+            PathBuf::from("TCP_IP.md")
+        );
+        assert_eq!(
+            export_path("TCP/IP", ExportFormat::StudySheet),
+            PathBuf::from("TCP_IP.sheet.typ")
+        );
+    }
+}
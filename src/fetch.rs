@@ -0,0 +1,71 @@
+//! Downloading decks that others have shared.
+
+use std::io;
+use std::path::Path;
+
+use mem_flip::deck::Topics;
+
+// Far beyond any real deck, but keeps a wrong URL from filling memory
+const MAX_DECK_BYTES: u64 = 50 * 1024 * 1024;
+static DEFAULT_NAME: &str = "shared.json";
+
+/// Downloads the deck at `url`, which must be HTTPS, and checks it.
+pub fn download(url: &str) -> io::Result<Topics> {
+    if !url.starts_with("https://") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only https:// URLs are supported",
+        ));
+    }
+    let mut response = ureq::get(url).call().map_err(ureq::Error::into_io)?;
+    let text = response
+        .body_mut()
+        .with_config()
+        .limit(MAX_DECK_BYTES)
+        .read_to_string()
+        .map_err(ureq::Error::into_io)?;
+    Topics::from_shared_json(&text)
+}
+
+/// Downloads the deck at `url` and saves it as a new deck at `path`, which
+/// must not exist yet.
+pub fn save_new(url: &str, path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    download(url)?.save(path)
+}
+
+/// Name to save a deck from `url` as: the end of its path if that is a JSON
+/// file, `shared.json` otherwise.
+pub fn file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    match path.rsplit('/').next() {
+        Some(name) if name.len() > ".json".len() && name.ends_with(".json") => name.to_string(),
+        _ => DEFAULT_NAME.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(
+            file_name("https://example.com/decks/spanish.json?raw=1"),
+            "spanish.json"
+        );
+        assert_eq!(file_name("https://example.com/decks/"), DEFAULT_NAME);
+        assert_eq!(file_name("https://example.com/.json"), DEFAULT_NAME);
+    }
+
+    #[test]
+    fn test_plain_http_is_refused() {
+        let error = download("http://example.com/deck.json").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
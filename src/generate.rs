@@ -0,0 +1,106 @@
+//! Drafting cards with a language model behind an OpenAI-compatible chat
+//! completions endpoint, such as OpenAI itself, Ollama or llama.cpp.
+//!
+//! The model is asked for a JSON array of question and answer pairs. The
+//! drafts are only proposals: the app shows them one at a time and adds
+//! the ones the user keeps.
+
+use std::io;
+
+use mem_flip::deck::Flashcard;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::config::Config;
+
+static PROMPT: &str = "You write flashcards for spaced repetition. From the text the user \
+gives, or about the subject it describes, write short, self-contained question and answer \
+pairs, one fact each. Reply with only a JSON array of objects with \"question\" and \
+\"answer\" string fields.";
+
+/// An endpoint to draft cards with.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    // Base URL, `/chat/completions` is appended to it
+    url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Draft {
+    question: String,
+    answer: String,
+}
+
+impl Generator {
+    /// The endpoint set up in `config`.
+    pub fn from_config(config: &Config) -> io::Result<Generator> {
+        let (Some(url), Some(model)) = (&config.llm_url, &config.llm_model) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "set llm_url and llm_model in config.toml to generate cards",
+            ));
+        };
+        Ok(Generator {
+            url: url.trim_end_matches('/').to_string(),
+            model: model.clone(),
+            api_key: config.llm_api_key.clone(),
+        })
+    }
+
+    /// Drafts cards from `source`, pasted text or a description of a
+    /// subject.
+    pub fn generate(&self, source: &str) -> io::Result<Vec<Flashcard>> {
+        let request = json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": PROMPT },
+                { "role": "user", "content": source },
+            ],
+        });
+        let mut call = ureq::post(format!("{}/chat/completions", self.url))
+            .header("Content-Type", "application/json");
+        if let Some(key) = &self.api_key {
+            call = call.header("Authorization", format!("Bearer {}", key));
+        }
+        let body = call
+            .send(request.to_string())
+            .and_then(|mut response| response.body_mut().read_to_string())
+            .map_err(ureq::Error::into_io)?;
+        let body: Value = serde_json::from_str(&body)?;
+        let reply = body["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the reply has no text"))?;
+        parse_reply(reply)
+    }
+}
+
+// The cards in the model's reply, which may wrap the JSON array in prose
+// or a code fence
+fn parse_reply(reply: &str) -> io::Result<Vec<Flashcard>> {
+    let not_cards = || io::Error::new(io::ErrorKind::InvalidData, "the reply has no cards");
+    let start = reply.find('[').ok_or_else(not_cards)?;
+    let end = reply.rfind(']').ok_or_else(not_cards)?;
+    let drafts: Vec<Draft> = serde_json::from_str(reply.get(start..=end).ok_or_else(not_cards)?)?;
+    Ok(drafts
+        .into_iter()
+        .filter(|draft| !draft.question.trim().is_empty() && !draft.answer.trim().is_empty())
+        .map(|draft| Flashcard::new(draft.question.trim(), draft.answer.trim()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        let reply = "Here you go:\n```json\n[{\"question\": \"Capital of Peru?\", \
+                     \"answer\": \"Lima\"}, {\"question\": \" \", \"answer\": \"x\"}]\n```";
+        let cards = parse_reply(reply).unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].answer, "Lima");
+        assert!(parse_reply("Sorry, I can't do that.").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! Committing the deck to git after each save, for history and syncing
+//! through push and pull.
+//!
+//! The deck's directory must already be a git repository; mem-flip only
+//! stages and commits its own files there and never pushes.
+
+use std::io;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use mem_flip::deck::{Flashcard, Topics};
+
+// More changes than this are counted rather than listed
+const MAX_LISTED: usize = 3;
+
+/// A commit message describing what changed from `old` to `new`, such as
+/// "add 3 cards to Rust", or `None` when the cards are the same.
+pub fn change_summary(old: &Topics, new: &Topics) -> Option<String> {
+    let mut names: Vec<&String> = old.topics_map.keys().chain(new.topics_map.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut changes = Vec::new();
+    for name in names {
+        match (old.topics_map.get(name), new.topics_map.get(name)) {
+            (None, Some(cards)) if cards.is_empty() => {
+                changes.push(format!("create topic {}", name))
+            }
+            (None, Some(cards)) => changes.push(format!(
+                "create topic {} with {}",
+                name,
+                cards_label(cards.len())
+            )),
+            (Some(_), None) => changes.push(format!("delete topic {}", name)),
+            (Some(before), Some(after)) if before != after => {
+                let added = missing_from(after, before);
+                let removed = missing_from(before, after);
+                // A changed card shows up as one added and one removed
+                let edited = added.min(removed);
+                if edited > 0 {
+                    changes.push(format!("edit {} in {}", cards_label(edited), name));
+                }
+                if added > edited {
+                    changes.push(format!("add {} to {}", cards_label(added - edited), name));
+                }
+                if removed > edited {
+                    changes.push(format!(
+                        "remove {} from {}",
+                        cards_label(removed - edited),
+                        name
+                    ));
+                }
+                if added == 0 && removed == 0 {
+                    changes.push(format!("reorder {}", name));
+                }
+            }
+            _ => {}
+        }
+    }
+    if old.meta != new.meta {
+        changes.push("update deck details".to_string());
+    }
+
+    match changes.len() {
+        0 => None,
+        n if n > MAX_LISTED => Some(format!(
+            "{} and {} more changes",
+            changes[..MAX_LISTED - 1].join(", "),
+            n - (MAX_LISTED - 1)
+        )),
+        _ => Some(changes.join(", ")),
+    }
+}
+
+/// A commit message for an encrypted deck, which names no topic or card:
+/// "update deck, 3 topics and 42 cards", or `None` when nothing changed.
+pub fn private_summary(old: &Topics, new: &Topics) -> Option<String> {
+    if old == new {
+        return None;
+    }
+    let topics = match new.topics_map.len() {
+        1 => "1 topic".to_string(),
+        n => format!("{} topics", n),
+    };
+    Some(format!(
+        "update deck, {} and {}",
+        topics,
+        cards_label(new.card_count())
+    ))
+}
+
+/// Stages `files` in the repository holding `dir` and commits them with
+/// `message`. Returns whether there was anything to commit.
+pub fn commit(dir: &Path, files: &[&Path], message: &str) -> io::Result<bool> {
+    run(dir, &["rev-parse", "--is-inside-work-tree"]).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not in a git repository", dir.display()),
+        )
+    })?;
+
+    let mut add = Command::new("git");
+    add.current_dir(dir).args(["add", "--"]).args(files);
+    check(add.output()?)?;
+
+    // Exits with 1 when the files are staged as they are in HEAD
+    let mut diff = Command::new("git");
+    diff.current_dir(dir)
+        .args(["diff", "--cached", "--quiet", "--"])
+        .args(files);
+    if diff.status()?.success() {
+        return Ok(false);
+    }
+
+    let mut commit = Command::new("git");
+    commit
+        .current_dir(dir)
+        .args(["commit", "--quiet", "-m", message, "--"])
+        .args(files);
+    check(commit.output()?)?;
+    Ok(true)
+}
+
+fn run(dir: &Path, args: &[&str]) -> io::Result<()> {
+    check(Command::new("git").current_dir(dir).args(args).output()?)
+}
+
+// Turns a failed git command into an error carrying the first line it printed
+fn check(output: Output) -> io::Result<()> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("git failed");
+    Err(io::Error::other(format!("git: {}", message.trim())))
+}
+
+// How many of `cards` aren't in `other`
+fn missing_from(cards: &[Flashcard], other: &[Flashcard]) -> usize {
+    cards.iter().filter(|card| !other.contains(card)).count()
+}
+
+/// `count` cards, as `1 card` or `3 cards`.
+pub fn cards_label(count: usize) -> String {
+    if count == 1 {
+        "1 card".to_string()
+    } else {
+        format!("{} cards", count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mem_flip::test_deck::deck;
+
+    #[test]
+    fn test_change_summary() {
+        let old = deck(&[("Rust", &[("a", "")]), ("Go", &[("x", ""), ("y", "")])]);
+        assert_eq!(change_summary(&old, &old), None);
+
+        let new = deck(&[
+            ("Rust", &[("a", ""), ("b", ""), ("c", ""), ("d", "")]),
+            ("Go", &[("x", ""), ("y", "")]),
+        ]);
+        assert_eq!(
+            change_summary(&old, &new).as_deref(),
+            Some("add 3 cards to Rust")
+        );
+
+        let new = deck(&[
+            ("Rust", &[("a", "")]),
+            ("Go", &[("x", ""), ("z", "")]),
+            ("C", &[]),
+        ]);
+        assert_eq!(
+            change_summary(&old, &new).as_deref(),
+            Some("create topic C, edit 1 card in Go")
+        );
+
+        let new = deck(&[("Go", &[("y", ""), ("x", "")]), ("Zig", &[("q", "")])]);
+        assert_eq!(
+            change_summary(&old, &new).as_deref(),
+            Some("reorder Go, delete topic Rust, create topic Zig with 1 card")
+        );
+
+        let mut new = deck(&[("Go", &[("y", ""), ("x", "")]), ("Zig", &[("q", "")])]);
+        new.meta.title = Some("Languages".to_string());
+        assert_eq!(
+            change_summary(&old, &new).as_deref(),
+            Some("reorder Go, delete topic Rust and 2 more changes")
+        );
+    }
+
+    #[test]
+    fn test_private_summary() {
+        let old = deck(&[("Rust", &[("a", "")])]);
+        assert_eq!(private_summary(&old, &old), None);
+        let new = deck(&[("Rust", &[("a", "")]), ("Secret plans", &[("b", "")])]);
+        assert_eq!(
+            private_summary(&old, &new).as_deref(),
+            Some("update deck, 2 topics and 2 cards")
+        );
+    }
+}
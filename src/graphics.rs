@@ -0,0 +1,362 @@
+//! Inline images in the card panes.
+//!
+//! Images are drawn with the kitty graphics protocol (PNG data sent
+//! directly, so it also works over SSH) or as sixels produced by
+//! `img2sixel`. Both bypass ratatui's cell buffer, so the app records where
+//! an image should go while rendering and `Graphics::update` emits or clears
+//! it after each frame. The size of each image is read from its header once
+//! and kept until the file changes, as it is needed on every frame.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use base64::Engine;
+use crossterm::{cursor::MoveTo, queue};
+use ratatui::layout::Rect;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::paths::in_path;
+
+// Fixed kitty image id so the previous image can be deleted by id
+const KITTY_IMAGE_ID: u32 = 4242;
+// Kitty accepts at most 4096 bytes of base64 payload per escape sequence
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsProtocol {
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    None,
+}
+
+// An image the current frame wants drawn at `area`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePlacement {
+    pub path: PathBuf,
+    pub area: Rect,
+}
+
+#[derive(Debug)]
+pub struct Graphics {
+    protocol: GraphicsProtocol,
+    shown: Option<ImagePlacement>,
+    // Size of each image asked about
+    sizes: RefCell<HashMap<PathBuf, CachedSize>>,
+}
+
+// The size of an image, as of when its file was last changed
+type CachedSize = (Option<SystemTime>, Option<ImageSize>);
+
+impl Graphics {
+    pub fn new(setting: GraphicsProtocol) -> Graphics {
+        let protocol = match setting {
+            GraphicsProtocol::Auto => detect_protocol(),
+            other => other,
+        };
+        debug!("drawing images with {:?}", protocol);
+
+        Graphics {
+            protocol,
+            shown: None,
+            sizes: RefCell::default(),
+        }
+    }
+
+    // Whether `path` can be drawn as an image rather than its text fallback
+    pub fn can_display(&self, path: &Path) -> bool {
+        match self.protocol {
+            // Kitty only decodes PNG natively
+            GraphicsProtocol::Kitty => self.size(path).is_some_and(|s| s.format == Format::Png),
+            GraphicsProtocol::Sixel => path.is_file(),
+            GraphicsProtocol::Auto | GraphicsProtocol::None => false,
+        }
+    }
+
+    /// Brings the terminal in line with the image wanted by the last frame.
+    ///
+    /// Returns `true` when a previously drawn sixel must be wiped; the caller
+    /// then clears the terminal, redraws the frame and calls this again.
+    pub fn update<W: Write>(
+        &mut self,
+        out: &mut W,
+        wanted: Option<ImagePlacement>,
+    ) -> io::Result<bool> {
+        if wanted == self.shown {
+            return Ok(false);
+        }
+
+        if self.shown.is_some() {
+            match self.protocol {
+                GraphicsProtocol::Kitty => {
+                    write!(out, "\x1b_Ga=d,d=I,i={},q=2\x1b\\", KITTY_IMAGE_ID)?;
+                }
+                GraphicsProtocol::Sixel => {
+                    // Sixel pixels live in the cells themselves, only a full redraw removes them
+                    self.shown = None;
+                    return Ok(true);
+                }
+                GraphicsProtocol::Auto | GraphicsProtocol::None => {}
+            }
+        }
+
+        if let Some(placement) = &wanted {
+            // A broken image file shouldn't take the whole UI down
+            let _ = self.draw(out, placement);
+        }
+        out.flush()?;
+        self.shown = wanted;
+        Ok(false)
+    }
+
+    // Size of the image at `path`, read again only once the file changes
+    fn size(&self, path: &Path) -> Option<ImageSize> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut sizes = self.sizes.borrow_mut();
+        if let Some((read, size)) = sizes.get(path)
+            && *read == modified
+        {
+            return *size;
+        }
+        let size = File::open(path).ok().and_then(image_size);
+        sizes.insert(path.to_path_buf(), (modified, size));
+        size
+    }
+
+    fn draw<W: Write>(&self, out: &mut W, placement: &ImagePlacement) -> io::Result<()> {
+        let size = self.size(&placement.path);
+        let (cols, rows) = fit_to_cells(size, placement.area, cell_size());
+        if cols == 0 || rows == 0 {
+            return Ok(());
+        }
+
+        queue!(out, MoveTo(placement.area.x, placement.area.y))?;
+        match self.protocol {
+            GraphicsProtocol::Kitty => {
+                let data = fs::read(&placement.path)?;
+                let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+                let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+
+                for (i, chunk) in chunks.iter().enumerate() {
+                    let more = u8::from(i + 1 < chunks.len());
+                    let payload = std::str::from_utf8(chunk).unwrap_or_default();
+                    if i == 0 {
+                        write!(
+                            out,
+                            "\x1b_Ga=T,f=100,i={},c={},r={},C=1,q=2,m={};{}\x1b\\",
+                            KITTY_IMAGE_ID, cols, rows, more, payload
+                        )?;
+                    } else {
+                        write!(out, "\x1b_Gm={};{}\x1b\\", more, payload)?;
+                    }
+                }
+            }
+            GraphicsProtocol::Sixel => {
+                let (cell_w, cell_h) = cell_size();
+                let output = Command::new("img2sixel")
+                    .arg("-w")
+                    .arg((cols * cell_w).to_string())
+                    .arg("-h")
+                    .arg((rows * cell_h).to_string())
+                    .arg(&placement.path)
+                    .output()?;
+                if output.status.success() {
+                    out.write_all(&output.stdout)?;
+                }
+            }
+            GraphicsProtocol::Auto | GraphicsProtocol::None => {}
+        }
+        Ok(())
+    }
+}
+
+fn detect_protocol() -> GraphicsProtocol {
+    let term = std::env::var("TERM").unwrap_or_default();
+    let term_program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+
+    if term.contains("kitty")
+        || std::env::var_os("KITTY_WINDOW_ID").is_some()
+        || term_program == "ghostty"
+        || term_program == "WezTerm"
+    {
+        return GraphicsProtocol::Kitty;
+    }
+
+    let sixel_terminal = term.contains("foot")
+        || term.contains("mlterm")
+        || term_program == "iTerm.app"
+        || std::env::var_os("KONSOLE_VERSION").is_some();
+    if sixel_terminal && in_path("img2sixel") {
+        return GraphicsProtocol::Sixel;
+    }
+
+    GraphicsProtocol::None
+}
+
+// Pixel size of one terminal cell, guessed when the terminal doesn't report it
+fn cell_size() -> (u16, u16) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+            (size.width / size.columns, size.height / size.rows)
+        }
+        _ => (8, 16),
+    }
+}
+
+// Largest cell box inside `area` that keeps the image's aspect ratio,
+// never scaling it above its natural size, with cells of `cell` pixels
+fn fit_to_cells(size: Option<ImageSize>, area: Rect, cell: (u16, u16)) -> (u16, u16) {
+    let Some(size) = size else {
+        return (area.width, area.height);
+    };
+    let (cell_w, cell_h) = cell;
+
+    let box_w = f64::from(area.width) * f64::from(cell_w);
+    let box_h = f64::from(area.height) * f64::from(cell_h);
+    let scale = (box_w / f64::from(size.width))
+        .min(box_h / f64::from(size.height))
+        .min(1.0);
+
+    let cols = (f64::from(size.width) * scale / f64::from(cell_w)).ceil() as u16;
+    let rows = (f64::from(size.height) * scale / f64::from(cell_h)).ceil() as u16;
+    (cols.clamp(1, area.width), rows.clamp(1, area.height))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Png,
+    Jpeg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ImageSize {
+    format: Format,
+    width: u32,
+    height: u32,
+}
+
+// Reads the pixel dimensions from a PNG or JPEG header, skipping over the
+// rest of the file
+fn image_size(mut file: impl Read + Seek) -> Option<ImageSize> {
+    let mut header = [0u8; 24];
+    file.read_exact(&mut header[..2]).ok()?;
+
+    if header[..2] == [0x89, b'P'] {
+        file.read_exact(&mut header[2..]).ok()?;
+        if !header.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return None;
+        }
+        return Some(ImageSize {
+            format: Format::Png,
+            width: u32::from_be_bytes(header[16..20].try_into().ok()?),
+            height: u32::from_be_bytes(header[20..24].try_into().ok()?),
+        });
+    }
+
+    if header[..2] == [0xFF, 0xD8] {
+        // Walk the JPEG segments until a start-of-frame marker
+        loop {
+            let mut segment = [0u8; 4];
+            file.read_exact(&mut segment).ok()?;
+            if segment[0] != 0xFF {
+                return None;
+            }
+            let len = u16::from_be_bytes([segment[2], segment[3]]);
+            if matches!(segment[1], 0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF) {
+                // Precision, then height and width
+                let mut frame = [0u8; 5];
+                file.read_exact(&mut frame).ok()?;
+                return Some(ImageSize {
+                    format: Format::Jpeg,
+                    width: u16::from_be_bytes([frame[3], frame[4]]).into(),
+                    height: u16::from_be_bytes([frame[1], frame[2]]).into(),
+                });
+            }
+            // The length counts its own two bytes
+            file.seek(SeekFrom::Current(i64::from(len) - 2)).ok()?;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        data.extend(width.to_be_bytes());
+        data.extend(height.to_be_bytes());
+        data.extend([8, 6, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_png_size() {
+        let size = image_size(Cursor::new(png(640, 480)));
+        assert_eq!(
+            size,
+            Some(ImageSize {
+                format: Format::Png,
+                width: 640,
+                height: 480,
+            })
+        );
+        // Cut short
+        assert_eq!(image_size(Cursor::new(&png(640, 480)[..20])), None);
+        assert_eq!(
+            image_size(Cursor::new(b"\x89PNX\r\n\x1a\n".repeat(3))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_jpeg_size() {
+        // Start of image, an APP0 segment to skip, then a baseline frame
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x06, b'J', b'F', b'I', b'F'];
+        data.extend([0xFF, 0xC0, 0x00, 0x11, 0x08, 0x01, 0xE0, 0x02, 0x80]);
+        data.extend([0u8; 12]);
+        assert_eq!(
+            image_size(Cursor::new(&data)),
+            Some(ImageSize {
+                format: Format::Jpeg,
+                width: 640,
+                height: 480,
+            })
+        );
+        // No frame before the end of the file
+        assert_eq!(image_size(Cursor::new(&data[..10])), None);
+        assert_eq!(image_size(Cursor::new(b"GIF89a")), None);
+    }
+
+    #[test]
+    fn test_fit_to_cells() {
+        let area = Rect::new(0, 0, 40, 10);
+        let size = |width, height| {
+            Some(ImageSize {
+                format: Format::Png,
+                width,
+                height,
+            })
+        };
+        // Shrunk to the height of the area, keeping its shape
+        assert_eq!(fit_to_cells(size(640, 480), area, (8, 16)), (27, 10));
+        // Shrunk to the width
+        assert_eq!(fit_to_cells(size(1280, 160), area, (8, 16)), (40, 3));
+        // Never grown past its own size
+        assert_eq!(fit_to_cells(size(16, 16), area, (8, 16)), (2, 1));
+        // The whole area when the size is unknown
+        assert_eq!(fit_to_cells(None, area, (8, 16)), (40, 10));
+    }
+}
@@ -0,0 +1,1199 @@
+//! Drives the app without a real terminal, for tests of whole screens.
+//!
+//! A `Harness` owns an `App` over a scratch deck, feeds it key events and
+//! renders it to ratatui's `TestBackend`, whose text is compared against
+//! insta snapshots.
+
+use std::path::Path;
+use std::time::Instant;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use mem_flip::deck::Topics;
+use mem_flip::history::{self, History};
+use ratatui::{Terminal, backend::TestBackend};
+use tempfile::TempDir;
+
+use crate::config::Config;
+use crate::graphics::GraphicsProtocol;
+use crate::{App, AppState};
+
+pub struct Harness {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+    // Holds the deck and history files until the harness is dropped
+    dir: TempDir,
+}
+
+impl Harness {
+    /// An app on `topics` in a terminal of the given size.
+    pub fn new(topics: Topics, width: u16, height: u16) -> Harness {
+        let dir = TempDir::new().expect("creating a scratch directory");
+        let deck_path = dir.path().join("flashcards.json");
+        // As if the app had loaded the deck from its file
+        topics.save(&deck_path).expect("writing the scratch deck");
+        let history = History::empty(History::path_for(&deck_path));
+        // Image support depends on the terminal running the tests otherwise,
+        // and screens are compared as they are once cards stop moving
+        let config = Config {
+            graphics: GraphicsProtocol::None,
+            reduce_motion: true,
+            ..Config::default()
+        };
+        let terminal = Terminal::new(TestBackend::new(width, height)).expect("test backend");
+        let mut app = App::new(deck_path, topics, history, config);
+        // Never the user's own config file
+        app.config_path = Some(dir.path().join("config.toml"));
+        app.resume_path = Some(dir.path().join("resume.json"));
+        // Most tests start from the topic list rather than the home screen
+        app.state = AppState::TopicSelection;
+        Harness { app, terminal, dir }
+    }
+
+    /// Scratch directory the deck is in, for tests that create more files.
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn press(&mut self, code: KeyCode) -> &mut Harness {
+        self.press_with(code, KeyModifiers::NONE)
+    }
+
+    pub fn press_with(&mut self, code: KeyCode, modifiers: KeyModifiers) -> &mut Harness {
+        self.app
+            .handle_event(Event::Key(KeyEvent::new(code, modifiers)));
+        self
+    }
+
+    pub fn type_text(&mut self, text: &str) -> &mut Harness {
+        for c in text.chars() {
+            self.press(KeyCode::Char(c));
+        }
+        self
+    }
+
+    /// Lets `count` ticks of time-driven updates happen.
+    pub fn tick(&mut self, count: usize) -> &mut Harness {
+        for _ in 0..count {
+            self.app.on_tick(Instant::now());
+        }
+        self
+    }
+
+    /// Draws the app and returns the screen, one quoted line per row.
+    pub fn screen(&mut self) -> String {
+        self.terminal
+            .draw(|frame| self.app.draw(frame))
+            .expect("drawing to the test backend");
+        self.terminal.backend().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    use insta::assert_snapshot;
+    use mem_flip::deck::TopicMeta;
+    use mem_flip::schedule::ReviewOrder;
+
+    use super::*;
+    use crate::animation::Transition;
+    use crate::l10n::{self, Language};
+    use crate::resume::ResumeState;
+    use crate::settings::SETTINGS;
+    use crate::status::SaveState;
+    use crate::tasks::Outcome;
+    use mem_flip::test_deck::deck;
+
+    fn spanish() -> Topics {
+        deck(&[
+            ("Spanish", &[("perro", "dog"), ("gato", "cat")]),
+            ("French", &[("chien", "dog")]),
+        ])
+    }
+
+    #[test]
+    fn test_empty_deck() {
+        let mut harness = Harness::new(Topics::default(), 60, 14);
+        assert_snapshot!(harness.screen());
+    }
+
+    #[test]
+    fn test_topic_list() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.press(KeyCode::Down);
+        assert_snapshot!(harness.screen());
+    }
+
+    #[test]
+    fn test_review() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.press(KeyCode::Down).press(KeyCode::Enter);
+        assert_snapshot!("review_question", harness.screen());
+        harness.press(KeyCode::Char(' '));
+        assert_snapshot!("review_answer", harness.screen());
+    }
+
+    #[test]
+    fn test_progress_saved_after_grading() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('y'));
+
+        // As if the terminal went away here, without quitting
+        let history = History::load(History::path_for(&harness.app.deck_path)).unwrap();
+        assert_eq!(history.reviews().len(), 1);
+        // The topic list's accuracy takes the grade in without a reload
+        let accuracy = &harness.app.accuracy["Spanish"];
+        assert_eq!(accuracy.last(), Some(&Some(100)));
+        let resume = std::fs::read_to_string(harness.dir().join("resume.json")).unwrap();
+        let resume: ResumeState = serde_json::from_str(&resume).unwrap();
+        assert!(resume.reviewing);
+        assert_eq!(resume.positions["Spanish"].card_index, 1);
+    }
+
+    #[test]
+    fn test_grades_reach_the_stats() {
+        let mut harness = Harness::new(spanish(), 100, 30);
+        // Missed perro, knew gato
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('m'))
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('y'));
+        let grades: Vec<(&str, bool)> = harness
+            .app
+            .history
+            .reviews()
+            .iter()
+            .map(|review| (review.question.as_str(), review.correct))
+            .collect();
+        assert_eq!(grades, [("perro", false), ("gato", true)]);
+
+        harness.app.state = AppState::Stats;
+        let screen = harness.screen();
+        assert!(screen.contains("✗ 1/1 Spanish: perro"));
+        assert!(!screen.contains("Spanish: gato"));
+    }
+
+    #[test]
+    fn test_missed_card_comes_back() {
+        let cards = [
+            ("uno", "1"),
+            ("dos", "2"),
+            ("tres", "3"),
+            ("cuatro", "4"),
+            ("cinco", "5"),
+        ];
+        let mut harness = Harness::new(deck(&[("Numbers", &cards)]), 60, 14);
+        harness
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('m'));
+        assert!(harness.screen().contains("1 to relearn"));
+        for _ in 0..3 {
+            harness.press(KeyCode::Char(' ')).press(KeyCode::Char('y'));
+        }
+        assert!(harness.screen().contains("Q: uno"));
+
+        harness.press(KeyCode::Char(' ')).press(KeyCode::Char('y'));
+        let screen = harness.screen();
+        assert!(screen.contains("Q: cinco"));
+        assert!(!screen.contains("to relearn"));
+    }
+
+    #[test]
+    fn test_review_order_kept_while_grading() {
+        let cards = [("a", "1"), ("b", "2"), ("c", "3"), ("d", "4")];
+        let mut harness = Harness::new(deck(&[("Letters", &cards)]), 60, 14);
+        harness.app.config.review_order = Some(ReviewOrder::LowestAccuracy);
+        // Grading the first card right puts it behind the second
+        let past = [
+            ("a", false),
+            ("b", true),
+            ("b", false),
+            ("b", false),
+            ("c", true),
+        ];
+        for (question, correct) in past {
+            let review = history::Review {
+                time: history::now() - history::DAY,
+                topic: "Letters".to_string(),
+                question: question.to_string(),
+                correct,
+                duration_ms: 0,
+                count: 1,
+            };
+            harness.app.history.append(review).unwrap();
+        }
+
+        let mut seen = Vec::new();
+        harness.press(KeyCode::Enter);
+        for _ in 0..cards.len() {
+            if let AppState::FlashcardReview { card_index, .. } = harness.app.state {
+                seen.push(card_index);
+            }
+            harness.press(KeyCode::Char(' ')).press(KeyCode::Char('y'));
+        }
+        // Every card once, in the order the session opened with
+        assert_eq!(seen, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_configured_new_per_day() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.app.config.new_per_day = Some(1);
+        harness.press(KeyCode::Down);
+        assert!(harness.screen().contains("Spanish  (1 due, 2 new"));
+
+        // A topic's own limit wins over the configured one
+        let meta = TopicMeta {
+            new_per_day: Some(5),
+            ..TopicMeta::default()
+        };
+        harness.app.topics.set_meta("Spanish", meta);
+        // As saving the topic's settings does
+        harness.app.topic_order.clear();
+        assert!(harness.screen().contains("Spanish  (2 due, 2 new"));
+    }
+
+    #[test]
+    fn test_study_ahead() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        let meta = TopicMeta {
+            new_per_day: Some(1),
+            ..TopicMeta::default()
+        };
+        harness.app.topics.set_meta("Spanish", meta);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('y'))
+            .press(KeyCode::Esc)
+            .press(KeyCode::Enter);
+        assert!(harness.screen().contains("Study ahead"));
+
+        harness.press(KeyCode::Char('y'));
+        assert!(harness.screen().contains("Card 1/2"));
+    }
+
+    #[test]
+    fn test_recently_added() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.press(KeyCode::Char('r'));
+        assert!(
+            harness
+                .screen()
+                .contains("No cards added in the last 7 days")
+        );
+
+        let cards = harness.app.topics.topics_map.get_mut("Spanish").unwrap();
+        cards[1].added = Some(history::now());
+        harness.press(KeyCode::Char('r'));
+        let screen = harness.screen();
+        assert!(screen.contains("Q: gato"));
+        assert!(screen.contains("Card 1/1"));
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('b'));
+        assert_snapshot!(harness.screen());
+
+        // The key that goes on does nothing else, the answer still shows
+        harness.press(KeyCode::Char('y'));
+        let screen = harness.screen();
+        assert!(screen.contains("A: dog"));
+        assert!(screen.contains("Known 0/0"));
+    }
+
+    #[test]
+    fn test_typed_answer() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char('t'))
+            .type_text("big dog")
+            .press(KeyCode::Enter);
+        assert_snapshot!(harness.screen());
+    }
+
+    #[test]
+    fn test_exam_plan() {
+        let mut harness = Harness::new(spanish(), 80, 16);
+        harness.press(KeyCode::Down).press(KeyCode::Char('t'));
+        for _ in 0..5 {
+            harness.press(KeyCode::Down);
+        }
+        harness
+            .press(KeyCode::Right)
+            .press_with(KeyCode::Right, KeyModifiers::SHIFT);
+        let exam = harness.app.topics.meta_of("Spanish").exam;
+        assert_eq!(exam, None);
+        harness.press(KeyCode::Enter).press(KeyCode::Esc);
+        let exam = harness.app.topics.meta_of("Spanish").exam.unwrap();
+        assert_eq!(exam / history::DAY, history::now() / history::DAY + 21);
+
+        // Two cards to get right three times in a row, over three weeks
+        assert!(
+            harness
+                .screen()
+                .contains("📅 Spanish exam in 21 days: 1 card a day, 0 done today")
+        );
+    }
+
+    #[test]
+    fn test_typing_practice() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('p'))
+            .type_text("dig");
+        assert_snapshot!(harness.screen());
+        assert!(matches!(harness.app.state, AppState::TypingPractice { .. }));
+
+        // Taken back and typed right, then on to the next card
+        harness
+            .press(KeyCode::Backspace)
+            .press(KeyCode::Backspace)
+            .type_text("og");
+        assert!(harness.screen().contains("card 2 of 2"));
+        harness.press(KeyCode::Tab);
+        assert!(matches!(harness.app.state, AppState::TopicSelection));
+        assert!(harness.screen().contains("Typed 1 card"));
+        assert!(harness.app.history.reviews().is_empty());
+    }
+
+    #[test]
+    fn test_study_timer() {
+        let mut harness = Harness::new(spanish(), 80, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char('w'))
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('y'));
+        let screen = harness.screen();
+        assert!(screen.contains("🍅"));
+        assert!(screen.contains("0 min, 1 card"));
+
+        harness.press(KeyCode::Char('w'));
+        assert!(!harness.screen().contains("🍅"));
+    }
+
+    #[test]
+    fn test_create_topic() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.press(KeyCode::Char('n')).type_text("German");
+        assert_snapshot!(harness.screen());
+    }
+
+    #[test]
+    fn test_add_card() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Char('a'))
+            .type_text("caballo")
+            .press(KeyCode::Tab)
+            .type_text("horse");
+        assert_snapshot!(harness.screen());
+    }
+
+    #[test]
+    fn test_edit_inside_the_text() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.press(KeyCode::Char('a')).type_text("שם");
+        harness.screen();
+        // Right goes back toward the start of Hebrew text
+        harness
+            .press(KeyCode::Right)
+            .type_text("ל")
+            .press(KeyCode::Tab)
+            .type_text("hrse")
+            .press(KeyCode::Left)
+            .press(KeyCode::Left)
+            .press(KeyCode::Left)
+            .type_text("o")
+            .press(KeyCode::End)
+            .press(KeyCode::Backspace);
+        let AppState::AddCard {
+            question_input,
+            answer_input,
+            ..
+        } = &harness.app.state
+        else {
+            panic!("left the editor");
+        };
+        assert_eq!(question_input, "שלם");
+        assert_eq!(answer_input, "hors");
+    }
+
+    #[test]
+    fn test_add_card_from_review() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char('n'))
+            .press_with(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        let AppState::AddCard { topic, .. } = &harness.app.state else {
+            panic!("not adding a card");
+        };
+        assert_eq!(topic, "Spanish");
+
+        harness
+            .type_text("caballo")
+            .press(KeyCode::Tab)
+            .type_text("horse")
+            .press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(harness.app.topics.topics_map["Spanish"].len(), 3);
+        assert!(matches!(
+            harness.app.state,
+            AppState::FlashcardReview { card_index: 1, .. }
+        ));
+
+        // Given up, the editor goes back too
+        harness
+            .press_with(KeyCode::Char('a'), KeyModifiers::CONTROL)
+            .press(KeyCode::Esc);
+        assert!(matches!(
+            harness.app.state,
+            AppState::FlashcardReview { .. }
+        ));
+    }
+
+    #[test]
+    fn test_card_template() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        // French is first in the list
+        harness
+            .press(KeyCode::Char('a'))
+            .type_text("Word: ")
+            .press(KeyCode::Tab)
+            .type_text("Gender: ")
+            .press_with(KeyCode::Char('t'), KeyModifiers::CONTROL)
+            .press(KeyCode::Esc)
+            .press(KeyCode::Char('a'));
+        let AppState::AddCard { answer_input, .. } = &harness.app.state else {
+            panic!("not adding a card");
+        };
+        assert_eq!(answer_input, "Gender: ");
+
+        // The bare template isn't a card
+        harness.press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(harness.app.topics.topics_map["French"].len(), 1);
+        harness
+            .type_text("chat")
+            .press(KeyCode::Tab)
+            .type_text("m")
+            .press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        let french = &harness.app.topics.topics_map["French"];
+        assert_eq!(french[1].question, "Word: chat");
+        assert_eq!(french[1].answer, "Gender: m");
+    }
+
+    #[test]
+    fn test_quick_add() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        for (question, answer) in [("chat", "cat"), ("oiseau", "bird")] {
+            if !matches!(harness.app.state, AppState::AddCard { .. }) {
+                harness.press(KeyCode::Char('a'));
+            }
+            harness
+                .type_text(question)
+                .press(KeyCode::Tab)
+                .type_text(answer)
+                .press_with(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        }
+        let french = &harness.app.topics.topics_map["French"];
+        assert_eq!(french.len(), 3);
+        assert_eq!(french[2].question, "oiseau");
+        // Still in the editor, with the fields cleared
+        let AppState::AddCard {
+            question_input,
+            editing_question,
+            ..
+        } = &harness.app.state
+        else {
+            panic!("not adding a card");
+        };
+        assert!(question_input.is_empty());
+        assert!(editing_question);
+    }
+
+    #[test]
+    fn test_browse_cards() {
+        let mut harness = Harness::new(spanish(), 60, 12);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('b'))
+            .press(KeyCode::Char('o'));
+        assert_snapshot!(harness.screen());
+
+        // Newest first, so the top card is the last one added
+        harness.press(KeyCode::Home).press(KeyCode::Enter);
+        assert!(matches!(
+            harness.app.state,
+            AppState::FlashcardReview { card_index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_edit_and_restore_card() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('b'))
+            .press(KeyCode::Char('e'))
+            .press(KeyCode::Tab)
+            .type_text(", hound")
+            .press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        let saved = Topics::load(&harness.app.deck_path).unwrap();
+        let card = &saved.topics_map["Spanish"][0];
+        assert_eq!(card.answer, "dog, hound");
+        assert_eq!(card.revisions[0].answer, "dog");
+        assert!(matches!(
+            harness.app.state,
+            AppState::BrowseCards { selected: 0, .. }
+        ));
+
+        harness.press(KeyCode::Char('h'));
+        assert!(harness.screen().contains("perro → dog"));
+        harness.press(KeyCode::Enter);
+        let card = &harness.app.topics.topics_map["Spanish"][0];
+        assert_eq!(card.answer, "dog");
+        assert_eq!(card.revisions[0].answer, "dog, hound");
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('b'))
+            .press(KeyCode::Char('x'))
+            .press(KeyCode::Esc)
+            .press(KeyCode::Up)
+            .press(KeyCode::Delete);
+        let saved = Topics::load(&harness.app.deck_path).unwrap();
+        assert_eq!(saved.topics_map["Spanish"].len(), 1);
+        assert!(!saved.topics_map.contains_key("French"));
+        assert_eq!(saved.trash.len(), 2);
+
+        harness.press(KeyCode::Char('g')).press(KeyCode::Char('x'));
+        assert!(harness.screen().contains("Topic French"));
+        assert!(harness.screen().contains("perro  in"));
+        harness.press(KeyCode::Down).press(KeyCode::Enter);
+        assert_eq!(harness.app.topics.topics_map["Spanish"].len(), 2);
+        assert_eq!(harness.app.topics.trash.len(), 1);
+    }
+
+    #[test]
+    fn test_topic_stats() {
+        let mut harness = Harness::new(spanish(), 60, 20);
+        harness.press(KeyCode::Down).press(KeyCode::Char('i'));
+        let screen = harness.screen();
+        assert!(screen.contains("📊 Spanish"));
+        assert!(screen.contains("Last studied  never"));
+        harness.press(KeyCode::Esc);
+        assert!(matches!(harness.app.state, AppState::TopicSelection));
+    }
+
+    #[test]
+    fn test_share_topic() {
+        let mut harness = Harness::new(spanish(), 60, 30);
+        harness.press(KeyCode::Down).press(KeyCode::Char('y'));
+        let screen = harness.screen();
+        assert!(screen.contains("Share 'Spanish'"));
+        assert!(screen.contains("▀"));
+        let AppState::ShareTopic { code, .. } = &harness.app.state else {
+            panic!("not sharing");
+        };
+        let shared = crate::share::decode(code).unwrap();
+        assert_eq!(shared.topics_map["Spanish"].len(), 2);
+    }
+
+    #[test]
+    fn test_attach_file() {
+        let mut harness = Harness::new(spanish(), 60, 16);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('b'))
+            .press(KeyCode::Char('f'))
+            .type_text("https://example.com/perro.mp3")
+            .press(KeyCode::Enter);
+        let saved = Topics::load(&harness.app.deck_path).unwrap();
+        assert_eq!(
+            saved.topics_map["Spanish"][0].attachments,
+            ["https://example.com/perro.mp3"]
+        );
+
+        harness.press(KeyCode::Enter).press(KeyCode::Char(' '));
+        assert!(
+            harness
+                .screen()
+                .contains("📎 https://example.com/perro.mp3")
+        );
+    }
+
+    #[test]
+    fn test_move_cards() {
+        let mut harness = Harness::new(spanish(), 60, 12);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('b'))
+            .press(KeyCode::Char('J'));
+        let saved = Topics::load(&harness.app.deck_path).unwrap();
+        assert_eq!(saved.topics_map["Spanish"][0].question, "gato");
+        assert!(matches!(
+            harness.app.state,
+            AppState::BrowseCards { selected: 1, .. }
+        ));
+
+        // Only in the topic's own order
+        harness.press(KeyCode::Char('o')).press(KeyCode::Char('K'));
+        assert_eq!(harness.app.topics.topics_map["Spanish"][0].question, "gato");
+    }
+
+    #[test]
+    fn test_mixed_review() {
+        let mut harness = Harness::new(spanish(), 60, 12);
+        harness
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Down)
+            .press(KeyCode::Char(' '));
+        assert_snapshot!(harness.screen());
+
+        // One card of each topic in turn
+        let mut seen = Vec::new();
+        harness.press(KeyCode::Char('m'));
+        for _ in 0..3 {
+            if let AppState::FlashcardReview {
+                topic, card_index, ..
+            } = &harness.app.state
+            {
+                seen.push((topic.clone(), *card_index));
+            }
+            harness.press(KeyCode::Char('n'));
+        }
+        let expected = [("French", 0), ("Spanish", 0), ("Spanish", 1)]
+            .map(|(topic, i)| (topic.to_string(), i));
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn test_rate_difficulty() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char('4'));
+        let saved = Topics::load(&harness.app.deck_path).unwrap();
+        assert_eq!(saved.topics_map["Spanish"][0].rating, Some(4));
+        assert!(harness.screen().contains("Spanish ◆4"));
+
+        harness.press(KeyCode::Char('0'));
+        assert_eq!(harness.app.topics.topics_map["Spanish"][0].rating, None);
+    }
+
+    #[test]
+    fn test_follow_link() {
+        let mut harness = Harness::new(
+            deck(&[
+                ("French", &[("chien", "dog, see [[Spanish/perro]]")]),
+                ("Spanish", &[("gato", "cat"), ("perro", "dog")]),
+            ]),
+            60,
+            14,
+        );
+        harness
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('l'));
+        assert!(harness.screen().contains("Q: perro"));
+        assert_eq!(harness.app.link_back, [("French".to_string(), 0)]);
+
+        harness.press(KeyCode::Backspace);
+        assert!(harness.screen().contains("Q: chien"));
+    }
+
+    #[test]
+    fn test_duplicate_card() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('b'))
+            .press(KeyCode::Char('c'));
+        assert!(harness.screen().contains("perro"));
+
+        // An unchanged copy isn't saved
+        harness.press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(harness.app.topics.topics_map["Spanish"].len(), 2);
+
+        harness
+            .press(KeyCode::Char('s'))
+            .press_with(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        let cards = &harness.app.topics.topics_map["Spanish"];
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[2].question, "perros");
+        assert_eq!(cards[2].answer, "dog");
+    }
+
+    #[test]
+    fn test_duplicate_topic_name() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.press(KeyCode::Char('n'));
+        for c in "spanish".chars() {
+            harness.press(KeyCode::Char(c));
+        }
+        harness.press(KeyCode::Enter);
+        assert!(harness.screen().contains("already a topic"));
+        assert_eq!(harness.app.topics.topics_map["Spanish"].len(), 2);
+        assert_eq!(harness.app.topics.topics_map.len(), 2);
+    }
+
+    #[test]
+    fn test_archive_topic() {
+        // Wide enough for the toasts to leave the list rows in sight
+        let mut harness = Harness::new(spanish(), 100, 14);
+        harness.press(KeyCode::Char('z'));
+        assert!(harness.app.topics.is_archived("French"));
+        assert!(!harness.screen().contains("French  ("));
+        assert_eq!(harness.app.due_count(), 2);
+        let saved = Topics::load(&harness.app.deck_path).unwrap();
+        assert!(saved.is_archived("French"));
+
+        harness.press(KeyCode::Char('Z'));
+        assert!(
+            harness
+                .screen()
+                .contains("French  (1 due, 1 new, 1 total, archived)")
+        );
+        harness.press(KeyCode::Up).press(KeyCode::Char('z'));
+        assert!(!harness.app.topics.is_archived("French"));
+        assert!(harness.app.topics.topic_meta.is_empty());
+    }
+
+    #[test]
+    fn test_card_animations() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.app.config.reduce_motion = false;
+        harness.press(KeyCode::Down).press(KeyCode::Enter);
+        assert_eq!(harness.app.animation, None);
+
+        harness.press(KeyCode::Char(' '));
+        let animation = harness.app.animation.unwrap();
+        assert_eq!(animation.transition, Transition::Flip);
+        harness.press(KeyCode::Char('n'));
+        let animation = harness.app.animation.unwrap();
+        assert_eq!(animation.transition, Transition::Slide { forward: true });
+        // Mid-animation frames still draw
+        assert!(harness.screen().contains("gato"));
+    }
+
+    #[test]
+    fn test_home() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.app.state = AppState::Home;
+        assert_snapshot!(harness.screen());
+
+        harness.press(KeyCode::Char('c'));
+        assert!(harness.screen().contains("Q: chien"));
+        harness.press(KeyCode::Esc).press(KeyCode::Esc);
+        assert!(matches!(harness.app.state, AppState::Home));
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut harness = Harness::new(spanish(), 60, 20);
+        harness.app.state = AppState::Home;
+        harness
+            .press(KeyCode::Char(','))
+            .press(KeyCode::Down)
+            .press(KeyCode::Down)
+            .press(KeyCode::Right);
+        assert_snapshot!(harness.screen());
+        assert_eq!(harness.app.config.review_order, Some(ReviewOrder::Shuffled));
+
+        let path = harness.app.config_path.clone().unwrap();
+        let saved: Config = toml::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(saved.review_order, Some(ReviewOrder::Shuffled));
+        assert!(saved.reduce_motion);
+
+        // The list scrolls to the last setting
+        for _ in 0..SETTINGS.len() {
+            harness.press(KeyCode::Down);
+        }
+        assert!(harness.screen().contains("Commit to git"));
+        assert!(!harness.screen().contains("Theme "));
+
+        harness.press(KeyCode::Esc);
+        assert!(matches!(harness.app.state, AppState::Home));
+    }
+
+    #[test]
+    fn test_unreadable_config_kept() {
+        let mut harness = Harness::new(spanish(), 60, 20);
+        let path = harness.app.config_path.clone().unwrap();
+        fs::write(&path, "sync_password = \"secret\"\nbroken =").unwrap();
+        harness.app.config_unreadable = true;
+        harness.app.state = AppState::Settings { selected: 0 };
+        harness.press(KeyCode::Right);
+        assert!(harness.screen().contains("Not saved"));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "sync_password = \"secret\"\nbroken ="
+        );
+    }
+
+    #[test]
+    fn test_topic_settings() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('t'))
+            .press(KeyCode::Down)
+            .press(KeyCode::Right)
+            .press(KeyCode::Down)
+            .press(KeyCode::Right)
+            .press(KeyCode::Right)
+            .press(KeyCode::Down)
+            .press(KeyCode::Left)
+            .press(KeyCode::Down)
+            .press(KeyCode::Right);
+        assert_snapshot!(harness.screen());
+
+        harness.press(KeyCode::Enter);
+        let meta = harness.app.topics.meta_of("Spanish");
+        assert!(meta.reversed);
+        assert_eq!(meta.new_per_day, Some(10));
+        assert_eq!(meta.color.as_deref(), Some("cyan"));
+        assert_eq!(meta.icon.as_deref(), Some("📘"));
+        assert!(harness.screen().contains("📘 Spanish"));
+        let saved = Topics::load(&harness.app.deck_path).unwrap();
+        assert_eq!(saved.meta_of("Spanish"), meta);
+
+        // Answers come first in review
+        harness.press(KeyCode::Enter);
+        assert!(harness.screen().contains("A: dog"));
+    }
+
+    #[test]
+    fn test_translated_screens() {
+        let mut harness = Harness::new(spanish(), 60, 20);
+        l10n::set_language(Language::German);
+        let screen = harness.screen();
+        assert!(screen.contains("Memory-Flip-Karteikarten"));
+        assert!(screen.contains("(2 fällig, 2 neu, 2 gesamt)"));
+        assert!(screen.contains("2 Themen │ 3 Karten"));
+        assert!(screen.contains("gespeichert"));
+
+        harness.press_with(KeyCode::Char('Z'), KeyModifiers::SHIFT);
+        assert!(harness.screen().contains("Archivierte Themen eingeblendet"));
+
+        harness.press(KeyCode::Char('n')).type_text("spanish");
+        assert!(
+            harness
+                .screen()
+                .contains("Es gibt schon ein Thema 'Spanish'")
+        );
+        harness.press(KeyCode::Esc);
+
+        harness.press(KeyCode::Down).press(KeyCode::Char('t'));
+        let screen = harness.screen();
+        assert!(screen.contains("Einstellungen von 'Spanish'"));
+        assert!(screen.contains("Neue Karten pro Tag  ◀ keine Grenze ▶"));
+        harness.press(KeyCode::Esc);
+
+        harness.app.state = AppState::Settings { selected: 0 };
+        let screen = harness.screen();
+        assert!(screen.contains("Einstellungen"));
+        assert!(screen.contains("Ruby-Lesungen        ◀ darüber ▶"));
+        assert!(screen.contains("Weniger Bewegung     ◀ ja ▶"));
+
+        harness.app.state = AppState::TopicSelection;
+        harness.app.tasks.spawn("Waiting", |progress| {
+            while !progress.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Outcome::Failed("Stopped".to_string())
+        });
+        harness.press(KeyCode::Char('q'));
+        let screen = harness.screen();
+        assert!(screen.contains("Noch beschäftigt"));
+        assert!(screen.contains("Beenden <Y> Weiterarbeiten <N>"));
+        harness.app.tasks.stop();
+    }
+
+    #[test]
+    fn test_read_only() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.app.read_only = true;
+        harness.press(KeyCode::Char('n'));
+        assert_snapshot!(harness.screen());
+
+        // Grading still works, it just isn't written down
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char('y'));
+        let history = History::path_for(&harness.app.deck_path);
+        assert!(!history.exists());
+    }
+
+    #[test]
+    fn test_unreadable_deck_kept() {
+        let mut harness = Harness::new(Topics::default(), 60, 14);
+        let deck_path = harness.app.deck_path.clone();
+        fs::write(&deck_path, "{ not json").unwrap();
+        let e = std::io::Error::other("expected value");
+        harness.app.dialog = Some(harness.app.load_error_dialog(&e));
+        harness.press(KeyCode::Esc);
+        harness.press(KeyCode::Char('n')).type_text("Spanish");
+        harness.press(KeyCode::Enter);
+        assert!(harness.app.save_to_disk().is_ok());
+
+        let kept = |harness: &Harness| -> Vec<String> {
+            fs::read_dir(harness.dir())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with("flashcards.json.broken-"))
+                .collect()
+        };
+        let broken = kept(&harness);
+        assert_eq!(broken.len(), 1);
+        let text = fs::read_to_string(harness.dir().join(&broken[0])).unwrap();
+        assert_eq!(text, "{ not json");
+        assert!(Topics::load(&deck_path).is_ok());
+
+        // Only the first save copies it
+        harness.app.save_to_disk().unwrap();
+        assert_eq!(kept(&harness), broken);
+    }
+
+    #[test]
+    fn test_quit_while_busy() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.app.tasks.spawn("Waiting", |progress| {
+            while !progress.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Outcome::Failed("Stopped".to_string())
+        });
+        harness.press(KeyCode::Char('q'));
+        assert!(harness.screen().contains("Still working"));
+        harness.press(KeyCode::Char('n'));
+        assert!(!harness.app.exit);
+        assert!(!harness.screen().contains("Still working"));
+
+        harness.press(KeyCode::Char('q')).press(KeyCode::Char('y'));
+        assert!(harness.app.exit);
+        harness.app.tasks.stop();
+        assert!(!harness.app.tasks.is_busy());
+    }
+
+    #[test]
+    fn test_keep_drafted_cards() {
+        let mut harness = Harness::new(spanish(), 60, 16);
+        harness
+            .press(KeyCode::Down)
+            .press(KeyCode::Char('g'))
+            .press(KeyCode::Char('c'))
+            .type_text("animals");
+        // As if the model had been asked and is answering
+        harness.app.state = AppState::GenerateCards {
+            topic: "Spanish".to_string(),
+            input: "animals".to_string(),
+            waiting: true,
+        };
+        let drafts = deck(&[("Spanish", &[("pez", "fish"), ("vaca", "cow")])]);
+        harness
+            .app
+            .show_drafts("Spanish".to_string(), drafts.topics_map["Spanish"].clone());
+        assert_snapshot!(harness.screen());
+
+        harness.press(KeyCode::Char('n')).press(KeyCode::Char('y'));
+        let spanish = &harness.app.topics.topics_map["Spanish"];
+        assert_eq!(spanish.len(), 3);
+        assert_eq!(spanish[2].question, "vaca");
+        assert!(matches!(harness.app.state, AppState::TopicSelection));
+    }
+
+    #[test]
+    fn test_leader_sequences() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.press(KeyCode::Char('g'));
+        assert!(harness.screen().contains("g… h home"));
+        harness.press(KeyCode::Char('s'));
+        assert!(matches!(harness.app.state, AppState::Stats));
+
+        // A key that finishes no sequence is dropped
+        harness.press(KeyCode::Char('g')).press(KeyCode::Char('z'));
+        assert!(matches!(harness.app.state, AppState::Stats));
+        harness.press(KeyCode::Char('g')).press(KeyCode::Char('h'));
+        assert!(matches!(harness.app.state, AppState::Home));
+    }
+
+    #[test]
+    fn test_stats_without_reviews() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        harness.press(KeyCode::Char('s'));
+        assert_snapshot!(harness.screen());
+    }
+
+    #[test]
+    fn test_switch_decks() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        let first = harness.app.deck_path.clone();
+        let work = harness.dir().join("work.json");
+
+        harness
+            .press(KeyCode::Char('d'))
+            .press(KeyCode::Char('n'))
+            .type_text(&work.to_string_lossy())
+            .press(KeyCode::Enter);
+        assert_eq!(harness.app.deck_path, work);
+        assert!(work.is_file());
+        assert!(harness.app.topics.topics_map.is_empty());
+        assert!(harness.app.topics.meta.created.is_some());
+
+        // The first deck is second in the recent list now
+        harness
+            .press(KeyCode::Char('d'))
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter);
+        assert_eq!(harness.app.deck_path, first);
+        assert_eq!(harness.app.topics.names(), vec!["French", "Spanish"]);
+    }
+
+    #[test]
+    fn test_import_file() {
+        let mut harness = Harness::new(spanish(), 60, 14);
+        let file = harness.dir().join("Italian.txt");
+        fs::write(&file, "cane\tdog\ngatto\tcat\n").unwrap();
+
+        harness
+            .press(KeyCode::Char('d'))
+            .press(KeyCode::Char('i'))
+            .type_text(&file.to_string_lossy())
+            .press(KeyCode::Enter);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while harness.app.importing.is_some() && Instant::now() < deadline {
+            harness.tick(1);
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(harness.app.importing, None);
+        assert_eq!(harness.app.topics.topics_map["Italian"].len(), 2);
+        let saved = Topics::load(&harness.app.deck_path).unwrap();
+        assert_eq!(saved.topics_map["Italian"][0].question, "cane");
+    }
+
+    // Writes `topics` to the deck at `path` as another program would, with
+    // a later time than the app saw
+    fn change_on_disk(path: &Path, topics: &Topics) {
+        topics.save(path).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reload_changed_deck() {
+        let mut harness = Harness::new(spanish(), 60, 16);
+        let path = harness.app.deck_path.clone();
+        // As if a text editor had saved the deck, with nothing changed here
+        change_on_disk(&path, &deck(&[("German", &[("Hund", "dog")])]));
+
+        harness.tick(4);
+        assert_snapshot!(harness.screen());
+        assert_eq!(harness.app.topics.names(), vec!["German"]);
+
+        // Nothing more to do until the file changes again
+        harness.tick(4);
+        assert!(harness.app.reload_prompt.is_none());
+    }
+
+    #[test]
+    fn test_merge_changed_deck() {
+        let mut harness = Harness::new(spanish(), 60, 16);
+        let path = harness.app.deck_path.clone();
+        // Reworded here and, differently, on another computer, which also
+        // added a card
+        harness.app.topics.topics_map.get_mut("Spanish").unwrap()[0].answer = "doggy".into();
+        harness.app.topics.topics_map.get_mut("Spanish").unwrap()[1].answer = "kitty".into();
+        change_on_disk(
+            &path,
+            &deck(&[
+                ("Spanish", &[("perro", "hound"), ("gato", "cat")]),
+                ("French", &[("chien", "dog"), ("chat", "cat")]),
+            ]),
+        );
+
+        harness.tick(4);
+        assert_snapshot!(harness.screen());
+        harness.press(KeyCode::Char('n'));
+        assert!(harness.app.reload_prompt.is_none());
+
+        let saved = Topics::load(&path).unwrap();
+        assert_eq!(saved, harness.app.topics);
+        let answers: Vec<&str> = saved.topics_map["Spanish"]
+            .iter()
+            .map(|card| card.answer.as_str())
+            .collect();
+        assert_eq!(answers, ["hound", "kitty"]);
+        assert_eq!(saved.topics_map["French"].len(), 2);
+    }
+
+    #[test]
+    fn test_save_merges_changed_deck() {
+        let mut harness = Harness::new(spanish(), 60, 16);
+        let path = harness.app.deck_path.clone();
+        change_on_disk(
+            &path,
+            &deck(&[
+                ("Spanish", &[("perro", "dog"), ("gato", "cat")]),
+                ("French", &[("chien", "dog"), ("chat", "cat")]),
+            ]),
+        );
+
+        // Saved before the next check of the file
+        harness.app.topics.topics_map.get_mut("Spanish").unwrap()[1].answer = "kitty".into();
+        harness.app.save_state = SaveState::Unsaved;
+        harness.app.quit_now();
+        assert!(harness.app.exit);
+        let saved = Topics::load(&path).unwrap();
+        assert_eq!(saved.topics_map["Spanish"][1].answer, "kitty");
+        assert_eq!(saved.topics_map["French"].len(), 2);
+
+        // A conflict is asked about rather than quitting over it
+        let mut harness = Harness::new(spanish(), 60, 16);
+        change_on_disk(
+            &harness.app.deck_path.clone(),
+            &deck(&[("Spanish", &[("perro", "hound"), ("gato", "cat")])]),
+        );
+        harness.app.topics.topics_map.get_mut("Spanish").unwrap()[0].answer = "doggy".into();
+        harness.app.save_state = SaveState::Unsaved;
+        harness.app.quit_now();
+        assert!(!harness.app.exit);
+        assert!(harness.app.reload_prompt.is_some());
+    }
+}
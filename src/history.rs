@@ -0,0 +1,520 @@
+//! Log of graded reviews, kept next to the deck as JSON lines.
+//!
+//! Every grade is appended as it happens, so the log survives crashes and
+//! never has to be rewritten. Lines that can't be parsed are skipped. The
+//! history of an encrypted deck has each line sealed with the deck's key.
+//!
+//! Old reviews can be compacted into one line for many, so the file of a
+//! deck reviewed every day for years stays small. A line then records up to
+//! when the history was compacted, so syncing with an older copy doesn't
+//! bring the reviews back.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::crypt::Key;
+
+/// Seconds in a day.
+pub const DAY: u64 = 24 * 60 * 60;
+/// Seconds in a month, taken as 30 days.
+pub const MONTH: u64 = 30 * DAY;
+
+/// One grade given to a card.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Review {
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    /// Topic of the card
+    pub topic: String,
+    /// Question of the card. Cards have no ids, the question stands in for
+    /// one.
+    pub question: String,
+    /// Whether the answer was known
+    pub correct: bool,
+    /// Time spent on the card before grading it
+    pub duration_ms: u64,
+    /// Reviews the line stands for, more than one once compacted: `time` is
+    /// then the last of them and `duration_ms` their total
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    pub count: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn is_one(count: &u32) -> bool {
+    *count == 1
+}
+
+/// What old reviews are compacted into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compaction {
+    /// One line for each card, day and grade, which keeps the charts by day
+    #[default]
+    Daily,
+    /// One line for each card and grade, which keeps only the totals
+    Totals,
+}
+
+// A line of the history file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Review(Review),
+    Compacted { compacted_before: u64 },
+}
+
+/// All reviews of a deck, backed by its history file.
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    // Oldest first
+    reviews: Vec<Review>,
+    // Seals each line of the file of an encrypted deck
+    key: Option<Key>,
+    // Reviews before then have been compacted, 0 if never
+    compacted_before: u64,
+}
+
+impl History {
+    /// Where the history of a deck is kept: `deck.history.jsonl` for
+    /// `deck.json`.
+    pub fn path_for(deck: &Path) -> PathBuf {
+        deck.with_extension("history.jsonl")
+    }
+
+    /// A history with no reviews yet, appending to `path`.
+    pub fn empty(path: PathBuf) -> History {
+        History {
+            path,
+            reviews: Vec::new(),
+            key: None,
+            compacted_before: 0,
+        }
+    }
+
+    /// Reads the history at `path`, empty if the file doesn't exist yet.
+    pub fn load(path: PathBuf) -> io::Result<History> {
+        History::load_with(path, None)
+    }
+
+    /// Reads the history of an encrypted deck with its `key`, or a plain
+    /// one without. Lines that weren't sealed with the key are skipped.
+    pub fn load_with(path: PathBuf, key: Option<Key>) -> io::Result<History> {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let (reviews, compacted_before) = parse_with(&text, key.as_ref());
+        Ok(History {
+            path,
+            reviews,
+            key,
+            compacted_before,
+        })
+    }
+
+    /// The same reviews, written to the file sealed with `key` from now on,
+    /// or in plain text without one. Rewrites the file.
+    pub fn set_key(&mut self, key: Option<Key>) -> io::Result<()> {
+        self.key = key;
+        atomic::write(&self.path, self.to_jsonl())
+    }
+
+    /// Records a review, appending it to the file. The file is flushed to
+    /// disk, so the review is kept however the session ends.
+    pub fn append(&mut self, review: Review) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", self.line(&review)?)?;
+        file.sync_data()?;
+        self.reviews.push(review);
+        Ok(())
+    }
+
+    /// All reviews, oldest first.
+    pub fn reviews(&self) -> &[Review] {
+        &self.reviews
+    }
+
+    /// Adds the reviews from `other` that aren't here yet, such as those
+    /// made on another machine, and rewrites the file in time order.
+    /// `other_compacted` is when `other` was compacted up to, 0 if never.
+    /// Before that time the side compacted furthest is kept, as both stand
+    /// for the same reviews, except for the reviews here from
+    /// `unsynced_after` on, which `other` has never had: those are folded
+    /// into its compacted lines. Returns how many were added.
+    pub fn merge(
+        &mut self,
+        other: Vec<Review>,
+        other_compacted: u64,
+        unsynced_after: u64,
+    ) -> io::Result<usize> {
+        let compacted_before = self.compacted_before;
+        let added = self.absorb(other, other_compacted, unsynced_after);
+        if added > 0 || self.compacted_before != compacted_before {
+            atomic::write(&self.path, self.to_jsonl())?;
+        }
+        Ok(added)
+    }
+
+    /// Merges like [`History::merge`], leaving the file as it is, for a
+    /// copy of the history that is only read.
+    pub fn absorb(
+        &mut self,
+        mut other: Vec<Review>,
+        other_compacted: u64,
+        unsynced_after: u64,
+    ) -> usize {
+        let mut unsynced = Vec::new();
+        if other_compacted > self.compacted_before {
+            let old;
+            (old, self.reviews) = std::mem::take(&mut self.reviews)
+                .into_iter()
+                .partition(|review| review.time < other_compacted);
+            unsynced.extend(
+                old.into_iter()
+                    .filter(|review| review.time >= unsynced_after),
+            );
+            self.compacted_before = other_compacted;
+        } else {
+            other.retain(|review| review.time >= self.compacted_before);
+        }
+        let before = self.reviews.len();
+        let mut known: HashSet<Review> = self.reviews.iter().cloned().collect();
+        for review in other {
+            if known.insert(review.clone()) {
+                self.reviews.push(review);
+            }
+        }
+        let added = self.reviews.len() - before;
+
+        // Into the line of the same card, day and grade, as compacting by
+        // day makes, or a line of their own
+        let mut index: HashMap<_, usize> = self
+            .reviews
+            .iter()
+            .enumerate()
+            .filter(|(_, review)| review.time < self.compacted_before)
+            .map(|(i, review)| (group(review, Compaction::Daily), i))
+            .collect();
+        for review in unsynced {
+            match index.get(&group(&review, Compaction::Daily)) {
+                Some(&i) => {
+                    let line = &mut self.reviews[i];
+                    line.time = line.time.max(review.time);
+                    line.duration_ms += review.duration_ms;
+                    line.count += review.count;
+                }
+                None => {
+                    index.insert(group(&review, Compaction::Daily), self.reviews.len());
+                    self.reviews.push(review);
+                }
+            }
+        }
+        self.reviews.sort_by_key(|review| review.time);
+        added
+    }
+
+    /// Merges the reviews from before `before` into one line for each card
+    /// and grade, by day or in all, and rewrites the file. Returns how many
+    /// lines that saved.
+    pub fn compact(&mut self, before: u64, how: Compaction) -> io::Result<usize> {
+        let (old, recent): (Vec<Review>, Vec<Review>) = std::mem::take(&mut self.reviews)
+            .into_iter()
+            .partition(|review| review.time < before);
+        let lines = old.len();
+        let mut compacted: Vec<Review> = Vec::new();
+        let mut index: HashMap<(String, String, bool, u64), usize> = HashMap::new();
+        for review in old {
+            let key = group(&review, how);
+            match index.get(&key) {
+                Some(&i) => {
+                    let line = &mut compacted[i];
+                    line.time = line.time.max(review.time);
+                    line.duration_ms += review.duration_ms;
+                    line.count += review.count;
+                }
+                None => {
+                    index.insert(key, compacted.len());
+                    compacted.push(review);
+                }
+            }
+        }
+        let saved = lines - compacted.len();
+        compacted.sort_by_key(|review| review.time);
+        compacted.extend(recent);
+        self.reviews = compacted;
+        if saved > 0 {
+            self.compacted_before = self.compacted_before.max(before);
+            atomic::write(&self.path, self.to_jsonl())?;
+        }
+        Ok(saved)
+    }
+
+    /// The reviews as the history file holds them, one JSON object a line
+    /// (sealed for an encrypted deck).
+    pub fn to_jsonl(&self) -> String {
+        let mut jsonl = String::new();
+        if self.compacted_before > 0 {
+            let marker = serde_json::json!({ "compacted_before": self.compacted_before });
+            if let Ok(line) = self.seal(marker.to_string()) {
+                jsonl.push_str(&line);
+                jsonl.push('\n');
+            }
+        }
+        for review in &self.reviews {
+            if let Ok(line) = self.line(review) {
+                jsonl.push_str(&line);
+                jsonl.push('\n');
+            }
+        }
+        jsonl
+    }
+
+    fn line(&self, review: &Review) -> io::Result<String> {
+        self.seal(serde_json::to_string(review)?)
+    }
+
+    fn seal(&self, json: String) -> io::Result<String> {
+        match &self.key {
+            Some(key) => key.seal_line(&json),
+            None => Ok(json),
+        }
+    }
+
+    /// Percentage of reviews of `topic` answered correctly on each of the
+    /// last `days` days (UTC) up to and including the day of `now`, oldest
+    /// first. Days without reviews are `None`.
+    pub fn daily_accuracy(&self, topic: &str, days: usize, now: u64) -> Vec<Option<u64>> {
+        self.accuracy_by_topic(days, now)
+            .remove(topic)
+            .unwrap_or_else(|| vec![None; days])
+    }
+
+    /// [`History::daily_accuracy`] of every topic reviewed in the last
+    /// `days` days, worked out in one pass over the reviews.
+    pub fn accuracy_by_topic(&self, days: usize, now: u64) -> HashMap<String, Vec<Option<u64>>> {
+        let today = now / DAY;
+        let mut totals: HashMap<&str, Vec<(u64, u64)>> = HashMap::new();
+        for review in &self.reviews {
+            let age = today.saturating_sub(review.time / DAY) as usize;
+            if age < days {
+                let days_totals = totals
+                    .entry(review.topic.as_str())
+                    .or_insert_with(|| vec![(0, 0); days]);
+                let (correct, total) = &mut days_totals[days - 1 - age];
+                if review.correct {
+                    *correct += u64::from(review.count);
+                }
+                *total += u64::from(review.count);
+            }
+        }
+        totals
+            .into_iter()
+            .map(|(topic, totals)| {
+                let accuracy = totals
+                    .into_iter()
+                    .map(|(correct, total)| (total > 0).then(|| correct * 100 / total))
+                    .collect();
+                (topic.to_string(), accuracy)
+            })
+            .collect()
+    }
+}
+
+// The line `review` is compacted into: its card, grade and, by day, day
+fn group(review: &Review, how: Compaction) -> (String, String, bool, u64) {
+    let day = match how {
+        Compaction::Daily => review.time / DAY,
+        Compaction::Totals => 0,
+    };
+    (
+        review.topic.clone(),
+        review.question.clone(),
+        review.correct,
+        day,
+    )
+}
+
+/// Reviews in the history file format and when they were compacted up to,
+/// 0 if never, opening the lines with `key` for an encrypted deck. Lines
+/// that can't be opened or parsed are skipped.
+pub fn parse_with(text: &str, key: Option<&Key>) -> (Vec<Review>, u64) {
+    match key {
+        Some(key) => read_entries(text.lines().filter_map(|line| key.open_line(line).ok())),
+        None => read_entries(text.lines().map(str::to_string)),
+    }
+}
+
+// The reviews in `lines` and when they were compacted up to
+fn read_entries(lines: impl Iterator<Item = String>) -> (Vec<Review>, u64) {
+    let mut reviews = Vec::new();
+    let mut compacted_before = 0;
+    for entry in lines.filter_map(|line| serde_json::from_str(&line).ok()) {
+        match entry {
+            Entry::Review(review) => reviews.push(review),
+            Entry::Compacted {
+                compacted_before: time,
+            } => compacted_before = compacted_before.max(time),
+        }
+    }
+    (reviews, compacted_before)
+}
+
+/// Seconds since the Unix epoch, the clock reviews are recorded with.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(topic: &str, time: u64, correct: bool) -> Review {
+        Review {
+            time,
+            topic: topic.to_string(),
+            question: "q".to_string(),
+            correct,
+            duration_ms: 1000,
+            count: 1,
+        }
+    }
+
+    #[test]
+    fn test_daily_accuracy() {
+        let now = 100 * DAY + 10;
+        let history = History {
+            path: PathBuf::new(),
+            key: None,
+            compacted_before: 0,
+            reviews: vec![
+                review("es", 97 * DAY, true),
+                review("es", 99 * DAY + 5, true),
+                review("es", 99 * DAY + 6, false),
+                review("es", 100 * DAY, true),
+                review("fr", 100 * DAY, false),
+                // Too old for the window
+                review("es", 90 * DAY, false),
+            ],
+        };
+        assert_eq!(
+            history.daily_accuracy("es", 4, now),
+            vec![Some(100), None, Some(50), Some(100)]
+        );
+        assert_eq!(history.daily_accuracy("de", 2, now), vec![None, None]);
+
+        let by_topic = history.accuracy_by_topic(2, now);
+        assert_eq!(by_topic.len(), 2);
+        assert_eq!(by_topic["es"], vec![Some(50), Some(100)]);
+        assert_eq!(by_topic["fr"], vec![None, Some(0)]);
+    }
+
+    #[test]
+    fn test_merge_keeps_time_order() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("deck.history.jsonl");
+        let mut history = History::empty(path.clone());
+        history.append(review("es", 10, true)).unwrap();
+        history.append(review("es", 30, true)).unwrap();
+
+        let elsewhere = vec![review("es", 20, false), review("es", 30, true)];
+        assert_eq!(history.merge(elsewhere, 0, 0).unwrap(), 1);
+        let times: Vec<u64> = history.reviews().iter().map(|r| r.time).collect();
+        assert_eq!(times, vec![10, 20, 30]);
+        assert_eq!(History::load(path).unwrap().reviews(), history.reviews());
+    }
+
+    #[test]
+    fn test_compact() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("deck.history.jsonl");
+        let mut history = History::empty(path.clone());
+        for time in [DAY + 1, DAY + 2, 2 * DAY, 3 * DAY] {
+            history.append(review("es", time, true)).unwrap();
+        }
+        history.append(review("es", DAY + 3, false)).unwrap();
+        history.append(review("es", 5 * DAY, true)).unwrap();
+
+        assert_eq!(history.compact(4 * DAY, Compaction::Daily).unwrap(), 1);
+        let lines: Vec<(u64, u32)> = history
+            .reviews()
+            .iter()
+            .map(|r| (r.time, r.count))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (DAY + 2, 2),
+                (DAY + 3, 1),
+                (2 * DAY, 1),
+                (3 * DAY, 1),
+                (5 * DAY, 1)
+            ]
+        );
+        assert_eq!(history.reviews()[0].duration_ms, 2000);
+        assert_eq!(history.daily_accuracy("es", 5, 5 * DAY)[0], Some(66));
+
+        assert_eq!(history.compact(4 * DAY, Compaction::Totals).unwrap(), 2);
+        let loaded = History::load(path).unwrap();
+        assert_eq!(loaded.reviews(), history.reviews());
+        assert_eq!(loaded.reviews()[1].count, 4);
+        assert_eq!(parse_with(&loaded.to_jsonl(), None).1, 4 * DAY);
+
+        // Syncing with an uncompacted copy doesn't bring the old reviews back
+        let mut elsewhere = History::empty(dir.path().join("other.history.jsonl"));
+        elsewhere.append(review("es", DAY + 1, true)).unwrap();
+        elsewhere.append(review("es", 6 * DAY, true)).unwrap();
+        assert_eq!(
+            history.merge(elsewhere.reviews().to_vec(), 0, 0).unwrap(),
+            1
+        );
+        assert_eq!(
+            elsewhere
+                .merge(history.reviews().to_vec(), 4 * DAY, 6 * DAY)
+                .unwrap(),
+            3
+        );
+        assert_eq!(elsewhere.reviews(), history.reviews());
+    }
+
+    #[test]
+    fn test_merge_folds_unsynced() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut compacted = History::empty(dir.path().join("deck.history.jsonl"));
+        compacted.append(review("es", DAY + 1, true)).unwrap();
+        compacted.append(review("es", DAY + 2, true)).unwrap();
+        compacted.compact(2 * DAY, Compaction::Daily).unwrap();
+
+        // Synced the first review at DAY + 2, then made one more offline
+        let mut here = History::empty(dir.path().join("other.history.jsonl"));
+        here.append(review("es", DAY + 1, true)).unwrap();
+        here.append(review("es", DAY + 3, true)).unwrap();
+        here.merge(compacted.reviews().to_vec(), 2 * DAY, DAY + 2)
+            .unwrap();
+        let lines: Vec<(u64, u32)> = here.reviews().iter().map(|r| (r.time, r.count)).collect();
+        assert_eq!(lines, vec![(DAY + 3, 3)]);
+    }
+
+    #[test]
+    fn test_path_next_to_deck() {
+        assert_eq!(
+            History::path_for(Path::new("decks/flashcards.json")),
+            PathBuf::from("decks/flashcards.history.jsonl")
+        );
+    }
+}
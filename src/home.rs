@@ -0,0 +1,155 @@
+//! The home screen shown at launch: how the deck stands, how the last
+//! session went, and shortcuts to what is usually done next.
+
+use mem_flip::history::{History, Review};
+use mem_flip::stats::{self, StudyPlan};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Padding, Paragraph, Widget, Wrap},
+};
+
+use crate::l10n::{hint, tr, trf};
+use crate::stats_view::format_duration;
+use crate::theme;
+
+pub struct Dashboard<'a> {
+    // Title of the deck, or its file name
+    pub deck: &'a str,
+    pub topics: usize,
+    pub cards: usize,
+    pub due: usize,
+    pub history: &'a History,
+    // Topic review would continue with
+    pub continue_topic: Option<&'a str>,
+    // Topics with an exam coming up and what it takes to be ready, the
+    // soonest first
+    pub plans: &'a [(String, StudyPlan)],
+    pub now: u64,
+}
+
+impl Widget for Dashboard<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut instructions = Vec::new();
+        if self.continue_topic.is_some() {
+            instructions.extend([hint("Continue"), "<C>".green().bold()]);
+        }
+        instructions.extend([
+            hint("Topics"),
+            "<Enter>".blue().bold(),
+            hint("Add Card"),
+            "<A>".blue().bold(),
+            hint("Import"),
+            "<I>".blue().bold(),
+            hint("Stats"),
+            "<S>".blue().bold(),
+            hint("Decks"),
+            "<D>".blue().bold(),
+            hint("Settings"),
+            "<,>".blue().bold(),
+            hint("Quit"),
+            "<Q> ".blue().bold(),
+        ]);
+        let block = Block::bordered()
+            .title(format!(" 🏠 {} ", self.deck).bold())
+            .title_bottom(Line::from(instructions))
+            .padding(Padding::new(2, 2, 1, 1));
+
+        let dim = Style::default().fg(Color::DarkGray);
+        let accent = Style::default().fg(theme::palette().accent).bold();
+        let reviews = self.history.reviews();
+        let streak = stats::streak(reviews, self.now);
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled(self.due.to_string(), accent),
+                Span::raw(format!(" {}", tr("due"))),
+                Span::styled(" · ", dim),
+                Span::raw(trf("{} cards in {} topics", &[&self.cards, &self.topics])),
+            ]),
+            Line::from(""),
+            match streak {
+                0 => Line::from(Span::styled(
+                    tr("No streak yet, review today to start one"),
+                    dim,
+                )),
+                1 => Line::from(format!("🔥 {}", tr("1 day in a row"))),
+                days => Line::from(format!("🔥 {}", trf("{} days in a row", &[&days]))),
+            },
+            Line::from(""),
+        ];
+        if !self.plans.is_empty() {
+            lines.extend(
+                self.plans
+                    .iter()
+                    .map(|(topic, plan)| plan_line(topic, plan, dim)),
+            );
+            lines.push(Line::from(""));
+        }
+        lines.extend(session_lines(stats::last_session(reviews), dim));
+        if let Some(topic) = self.continue_topic {
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::raw("Continue with "),
+                Span::styled(topic.to_string(), accent),
+            ]));
+        }
+
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(block)
+            .render(area, buf);
+    }
+}
+
+// When the exam on `topic` is and the cards a day to be ready for it
+fn plan_line(topic: &str, plan: &StudyPlan, dim: Style) -> Line<'static> {
+    let when = match plan.days_left {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        days => format!("in {} days", days),
+    };
+    let mut spans = vec![Span::raw(format!("📅 {} exam {}: ", topic, when))];
+    if plan.needed == 0 {
+        spans.push(Span::raw("every card learned"));
+    } else {
+        let cards = if plan.per_day == 1 { "card" } else { "cards" };
+        spans.push(format!("{} {} a day", plan.per_day, cards).bold());
+        spans.push(Span::styled(
+            format!(", {} done today", plan.done_today),
+            dim,
+        ));
+    }
+    Line::from(spans)
+}
+
+// What the last session was: when, how many cards, how well and how long
+fn session_lines(session: &[Review], dim: Style) -> Vec<Line<'static>> {
+    let (Some(first), Some(last)) = (session.first(), session.last()) else {
+        return vec![Line::from(Span::styled("No sessions yet", dim))];
+    };
+    let known = session.iter().filter(|review| review.correct).count();
+    let time: u64 = session.iter().map(|review| review.duration_ms).sum();
+    let mut topics: Vec<&str> = Vec::new();
+    for review in session {
+        if !topics.contains(&review.topic.as_str()) {
+            topics.push(&review.topic);
+        }
+    }
+    vec![
+        Line::from(vec![
+            "Last session ".bold(),
+            Span::styled(stats::format_date(first.time.min(last.time)), dim),
+        ]),
+        Line::from(format!(
+            "{} cards, {} known ({}%), {}",
+            session.len(),
+            known,
+            known * 100 / session.len(),
+            format_duration(time)
+        )),
+        Line::from(Span::styled(topics.join(", "), dim)),
+    ]
+}
@@ -0,0 +1,168 @@
+//! The deck as one web page, for people who won't install a terminal app.
+//!
+//! The page needs nothing but a browser: the cards are in it as JSON and a
+//! few lines of script show them one at a time, flipping on a click or the
+//! space bar. Card text is shown as plain text, images and ruby as written.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use mem_flip::deck::Topics;
+use serde::Serialize;
+
+// What the page's script reads, topics sorted by name
+#[derive(Serialize)]
+struct PageTopic<'a> {
+    name: &'a str,
+    cards: Vec<[&'a str; 2]>,
+}
+
+/// Default page name for the deck at `deck_path`: `deck.html` next to
+/// `deck.json`.
+pub fn page_path(deck_path: &Path) -> PathBuf {
+    deck_path.with_extension("html")
+}
+
+/// Writes the page to a new file at `path`.
+pub fn export_page(title: &str, topics: &Topics, path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_page(title, topics, &mut out)?;
+    out.flush()
+}
+
+/// Writes the deck as a page titled `title`.
+pub fn write_page(title: &str, topics: &Topics, mut out: impl Write) -> io::Result<()> {
+    let mut names: Vec<&String> = topics.topics_map.keys().collect();
+    names.sort();
+    let page_topics: Vec<PageTopic> = names
+        .into_iter()
+        .map(|name| PageTopic {
+            name,
+            cards: topics.topics_map[name]
+                .iter()
+                .map(|card| [card.question.as_str(), card.answer.as_str()])
+                .collect(),
+        })
+        .collect();
+    // `<` only appears inside strings, where the escape reads the same, so
+    // no card can close the script element
+    let data = serde_json::to_string(&page_topics)?.replace('<', "\\u003c");
+    let page = PAGE
+        .replace("{title}", &escape_html(title))
+        .replace("{data}", &data);
+    out.write_all(page.as_bytes())
+}
+
+// Braces too, so the title can't look like the place the cards go
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('{', "&#123;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+static PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+  body { font-family: system-ui, sans-serif; max-width: 40rem; margin: 2rem auto; padding: 0 1rem; color: #222; background: #fafafa; }
+  select, button { font-size: 1rem; padding: 0.4rem 0.8rem; }
+  #card { min-height: 12rem; margin: 1.5rem 0; padding: 1.5rem; border: 1px solid #ccc; border-radius: 0.5rem; background: #fff; display: flex; align-items: center; justify-content: center; text-align: center; white-space: pre-wrap; font-size: 1.3rem; cursor: pointer; user-select: none; }
+  #card.answer { background: #eef6ee; }
+  #controls { display: flex; gap: 0.5rem; align-items: center; }
+  #position { margin-left: auto; color: #777; }
+  @media (prefers-color-scheme: dark) {
+    body { color: #ddd; background: #1e1e1e; }
+    #card { background: #2a2a2a; border-color: #444; }
+    #card.answer { background: #24352a; }
+  }
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<select id="topic"></select>
+<div id="card" title="Click or press space to flip"></div>
+<div id="controls">
+  <button id="previous">← Previous</button>
+  <button id="flip">Flip</button>
+  <button id="next">Next →</button>
+  <span id="position"></span>
+</div>
+<script type="application/json" id="deck">{data}</script>
+<script>
+  const topics = JSON.parse(document.getElementById("deck").textContent);
+  const select = document.getElementById("topic");
+  const card = document.getElementById("card");
+  const position = document.getElementById("position");
+  let topic = 0, index = 0, answer = false;
+
+  topics.forEach((t, i) => select.add(new Option(`${t.name} (${t.cards.length})`, i)));
+
+  function show() {
+    const cards = topics.length ? topics[topic].cards : [];
+    if (!cards.length) {
+      card.textContent = "No cards";
+      position.textContent = "";
+      return;
+    }
+    card.textContent = cards[index][answer ? 1 : 0];
+    card.classList.toggle("answer", answer);
+    position.textContent = `${index + 1} / ${cards.length}`;
+  }
+  function move(by) {
+    const count = topics[topic].cards.length;
+    if (!count) return;
+    index = (index + by + count) % count;
+    answer = false;
+    show();
+  }
+  function flip() { answer = !answer; show(); }
+
+  select.onchange = () => { topic = Number(select.value); index = 0; answer = false; show(); };
+  card.onclick = flip;
+  document.getElementById("flip").onclick = flip;
+  document.getElementById("previous").onclick = () => move(-1);
+  document.getElementById("next").onclick = () => move(1);
+  document.onkeydown = (e) => {
+    if (e.target === select) return;
+    if (e.key === " " || e.key === "Enter") { e.preventDefault(); flip(); }
+    else if (e.key === "ArrowRight") move(1);
+    else if (e.key === "ArrowLeft") move(-1);
+  };
+  show();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use mem_flip::deck::Flashcard;
+
+    use super::*;
+
+    #[test]
+    fn test_page_keeps_cards_inert() {
+        let mut topics = Topics::default();
+        topics.topics_map.insert(
+            "Web".to_string(),
+            vec![Flashcard::new("Ends a script?", "</script><b>")],
+        );
+        let mut out = Vec::new();
+        write_page("Tags & <more>", &topics, &mut out).unwrap();
+        let page = String::from_utf8(out).unwrap();
+
+        assert!(page.contains("<title>Tags &amp; &lt;more&gt;</title>"));
+        assert!(
+            page.contains(
+                r#"[{"name":"Web","cards":[["Ends a script?","\u003c/script>\u003cb>"]]}]"#
+            )
+        );
+        assert_eq!(page.matches("</script>").count(), 2);
+    }
+}
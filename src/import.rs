@@ -0,0 +1,239 @@
+//! Bringing cards made in other tools into a deck.
+//!
+//! Each importer turns its source into `Topics`, which `apply` adds to the
+//! open deck. By default a card whose question is already in its topic has
+//! its answer updated instead of being added twice, so importing the same
+//! source again picks up edits made there.
+
+use std::io;
+use std::path::Path;
+
+use clap::ValueEnum;
+use mem_flip::deck::{Flashcard, Topics};
+use mem_flip::history;
+
+pub mod mochi;
+pub mod obsidian;
+pub mod org;
+pub mod quizlet;
+pub mod remnote;
+
+/// What to do with an imported card whose question is already in its topic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum MergeStrategy {
+    /// Keep the card in the deck as it is
+    Skip,
+    /// Take the imported answer
+    #[default]
+    Overwrite,
+    /// Add the imported card next to it
+    Append,
+}
+
+/// What an import changed in the deck.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Imported {
+    pub added: usize,
+    pub updated: usize,
+    // Cards already in the deck, left as they were
+    pub skipped: usize,
+    // Questions already in their topic with another answer, whichever of
+    // the two was kept
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A question that is in the deck and the import with different answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub topic: String,
+    pub question: String,
+    // Answer in the deck before the import
+    pub ours: String,
+    pub theirs: String,
+}
+
+/// Reads the cards at `path` with the importer its kind calls for: a folder
+/// is a vault of Markdown notes, an `.org` file org-drill headings, a
+/// `.mochi` or `.json` file a Mochi export, an `.md` file RemNote's export,
+/// and any other file a Quizlet export, whose cards go in a topic named
+/// after it.
+pub fn read_path(path: &Path) -> io::Result<Topics> {
+    if path.is_dir() {
+        return obsidian::read_vault(path);
+    }
+    let extension = path.extension().unwrap_or_default();
+    if extension == "org" {
+        return org::read_file(path);
+    }
+    if extension == "mochi" || extension == "json" {
+        return mochi::read_file(path);
+    }
+    if extension == "md" {
+        return remnote::read_path(path);
+    }
+    let (cards, _) = quizlet::read_file(path)?;
+    let mut topics = Topics::default();
+    if !cards.is_empty() {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        topics.topics_map.insert(name.into_owned(), cards);
+    }
+    Ok(topics)
+}
+
+/// Adds the cards of `imported` to `topics`, creating topics as needed, and
+/// deals with cards that are already there as `strategy` says.
+pub fn apply(topics: &mut Topics, imported: Topics, strategy: MergeStrategy) -> Imported {
+    apply_with(topics, imported, strategy, |_, _| true).unwrap_or_default()
+}
+
+/// `apply`, telling `progress` how many of the cards have been gone through
+/// and how many there are before each one. Stops when it returns false,
+/// returning `None` with `topics` partly changed.
+pub fn apply_with(
+    topics: &mut Topics,
+    imported: Topics,
+    strategy: MergeStrategy,
+    mut progress: impl FnMut(usize, usize) -> bool,
+) -> Option<Imported> {
+    let mut result = Imported::default();
+    let total = imported.card_count();
+    let mut done = 0;
+    for (name, cards) in imported.topics_map {
+        let topic = topics.topics_map.entry(name.clone()).or_default();
+        for card in cards {
+            if !progress(done, total) {
+                return None;
+            }
+            done += 1;
+            let existing = match strategy {
+                MergeStrategy::Append => None,
+                _ => topic.iter_mut().find(|c| c.question == card.question),
+            };
+            match existing {
+                Some(existing) if existing.answer == card.answer => result.skipped += 1,
+                Some(existing) => {
+                    result.conflicts.push(Conflict {
+                        topic: name.clone(),
+                        question: card.question.clone(),
+                        ours: existing.answer.clone(),
+                        theirs: card.answer.clone(),
+                    });
+                    if strategy == MergeStrategy::Skip {
+                        result.skipped += 1;
+                        continue;
+                    }
+                    // The old answer is kept as a revision
+                    let question = existing.question.clone();
+                    existing.revise(question, card.answer, history::now());
+                    result.updated += 1;
+                }
+                None => {
+                    // Added now, unless the source says when
+                    let added = card.added.or(Some(history::now()));
+                    topic.push(Flashcard { added, ..card });
+                    result.added += 1;
+                }
+            }
+        }
+    }
+    Some(result)
+}
+
+// A card from question and answer text, `None` if either is blank
+fn card(question: &str, answer: &str) -> Option<Flashcard> {
+    let (question, answer) = (question.trim(), answer.trim());
+    if question.is_empty() || answer.is_empty() {
+        return None;
+    }
+    Some(Flashcard::new(question, answer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Applies an import of three cards, one known, one with a new answer
+    // and one new, to a deck of two
+    fn apply_sample(strategy: MergeStrategy) -> (Topics, Imported) {
+        let mut topics = Topics::default();
+        topics.topics_map.insert(
+            "Spanish".to_string(),
+            vec![card("perro", "dog").unwrap(), card("gato", "cat").unwrap()],
+        );
+        let mut imported = Topics::default();
+        imported.topics_map.insert(
+            "Spanish".to_string(),
+            vec![
+                card("perro", "dog").unwrap(),
+                card("gato", "cat, tomcat").unwrap(),
+                card("pez", "fish").unwrap(),
+            ],
+        );
+        let result = apply(&mut topics, imported, strategy);
+        (topics, result)
+    }
+
+    #[test]
+    fn test_apply_updates_answers() {
+        let (topics, result) = apply_sample(MergeStrategy::Overwrite);
+        assert_eq!(
+            result,
+            Imported {
+                added: 1,
+                updated: 1,
+                skipped: 1,
+                conflicts: vec![Conflict {
+                    topic: "Spanish".to_string(),
+                    question: "gato".to_string(),
+                    ours: "cat".to_string(),
+                    theirs: "cat, tomcat".to_string(),
+                }],
+            }
+        );
+        let spanish = &topics.topics_map["Spanish"];
+        assert_eq!(spanish[1].answer, "cat, tomcat");
+        assert_eq!(spanish[2].question, "pez");
+    }
+
+    #[test]
+    fn test_apply_strategies() {
+        let (topics, result) = apply_sample(MergeStrategy::Skip);
+        assert_eq!((result.added, result.updated, result.skipped), (1, 0, 2));
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(topics.topics_map["Spanish"][1].answer, "cat");
+
+        let (topics, result) = apply_sample(MergeStrategy::Append);
+        assert_eq!((result.added, result.updated, result.skipped), (3, 0, 0));
+        assert_eq!(topics.topics_map["Spanish"].len(), 5);
+    }
+
+    #[test]
+    fn test_apply_with_progress() {
+        let mut imported = Topics::default();
+        imported.topics_map.insert(
+            "Spanish".to_string(),
+            vec![card("perro", "dog").unwrap(), card("gato", "cat").unwrap()],
+        );
+        let mut seen = Vec::new();
+        let mut topics = Topics::default();
+        let result = apply_with(
+            &mut topics,
+            imported.clone(),
+            MergeStrategy::Skip,
+            |done, total| {
+                seen.push((done, total));
+                true
+            },
+        );
+        assert_eq!(result.map(|result| result.added), Some(2));
+        assert_eq!(seen, [(0, 2), (1, 2)]);
+
+        let stopped = apply_with(
+            &mut Topics::default(),
+            imported,
+            MergeStrategy::Skip,
+            |done, _| done < 1,
+        );
+        assert_eq!(stopped, None);
+    }
+}
@@ -0,0 +1,336 @@
+//! Cards from a Mochi export: the `.mochi` file Mochi saves, which is a zip
+//! archive, or the `data.json` inside it. Each Mochi deck is a topic, named
+//! after the decks it sits in too, such as `Languages / Spanish`.
+//!
+//! The sides of a card are separated by `---` lines: the first is the
+//! question and the rest the answer. A card made from a template has its
+//! fields put into the template's `<< Field >>` placeholders first.
+//! Trashed decks and cards are left out.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+use mem_flip::deck::{Flashcard, Topics};
+use serde::Deserialize;
+use serde_json::Value;
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+use super::card;
+
+static DATA_FILE: &str = "data.json";
+static SIDE_BREAK: &str = "---";
+// Most `data.json` may unpack to, against archives made to fill the memory
+const MAX_DATA: u64 = 256 << 20;
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    decks: Vec<Deck>,
+    #[serde(default)]
+    templates: Vec<Template>,
+}
+
+#[derive(Deserialize)]
+struct Deck {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "parent-id")]
+    parent_id: Option<String>,
+    #[serde(default)]
+    cards: Vec<Card>,
+    #[serde(rename = "trashed?")]
+    trashed: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Card {
+    #[serde(default)]
+    content: String,
+    #[serde(rename = "template-id")]
+    template_id: Option<String>,
+    // Values by field id
+    #[serde(default)]
+    fields: HashMap<String, Field>,
+    #[serde(rename = "trashed?")]
+    trashed: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    #[serde(default)]
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Template {
+    id: String,
+    #[serde(default)]
+    content: String,
+    // Names by field id
+    #[serde(default)]
+    fields: HashMap<String, TemplateField>,
+}
+
+#[derive(Deserialize)]
+struct TemplateField {
+    name: String,
+}
+
+/// Cards in the Mochi export at `path`, or in the folder it was unzipped
+/// into.
+pub fn read_file(path: &Path) -> io::Result<Topics> {
+    let bytes = if path.is_dir() {
+        fs::read(path.join(DATA_FILE))?
+    } else {
+        fs::read(path)?
+    };
+    let json = if bytes.starts_with(b"PK") {
+        unzip(&bytes, DATA_FILE, MAX_DATA)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?
+    } else {
+        bytes
+    };
+    let export: Export = serde_json::from_slice(&json).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} isn't a Mochi export: {}", path.display(), e),
+        )
+    })?;
+    Ok(parse(export))
+}
+
+fn parse(export: Export) -> Topics {
+    let templates: HashMap<&str, &Template> = export
+        .templates
+        .iter()
+        .map(|template| (template.id.as_str(), template))
+        .collect();
+    let decks: HashMap<&str, &Deck> = export
+        .decks
+        .iter()
+        .map(|deck| (deck.id.as_str(), deck))
+        .collect();
+
+    let mut topics = Topics::default();
+    for deck in export.decks.iter().filter(|deck| !is_set(&deck.trashed)) {
+        let cards: Vec<Flashcard> = deck
+            .cards
+            .iter()
+            .filter(|card| !is_set(&card.trashed))
+            .filter_map(|card| {
+                let template = card.template_id.as_deref().and_then(|id| templates.get(id));
+                let content = match template {
+                    Some(template) => fill(template, card),
+                    None => card.content.clone(),
+                };
+                sides(&content)
+            })
+            .collect();
+        if !cards.is_empty() {
+            topics
+                .topics_map
+                .entry(topic_name(deck, &decks))
+                .or_default()
+                .extend(cards);
+        }
+    }
+    topics
+}
+
+// Whether a flag such as `trashed?` is on: Mochi writes `false`, nothing,
+// or the date it was set
+fn is_set(flag: &Option<Value>) -> bool {
+    matches!(flag, Some(value) if !value.is_null() && *value != Value::Bool(false))
+}
+
+// `deck`'s name after those of the decks it is in
+fn topic_name(deck: &Deck, decks: &HashMap<&str, &Deck>) -> String {
+    let mut names = vec![deck.name.trim()];
+    let mut parent = deck.parent_id.as_deref();
+    // Bounded, in case of a loop in a hand-edited export
+    while let Some(id) = parent.filter(|_| names.len() <= decks.len()) {
+        let Some(deck) = decks.get(id) else {
+            break;
+        };
+        names.push(deck.name.trim());
+        parent = deck.parent_id.as_deref();
+    }
+    names.reverse();
+    names.join(" / ")
+}
+
+// The template's content with the card's fields in its placeholders
+fn fill(template: &Template, card: &Card) -> String {
+    let mut content = template.content.clone();
+    for (id, field) in &template.fields {
+        let value = card.fields.get(id).map_or("", |field| field.value.as_str());
+        for placeholder in [
+            format!("<< {} >>", field.name),
+            format!("<<{}>>", field.name),
+        ] {
+            content = content.replace(&placeholder, value);
+        }
+    }
+    content
+}
+
+// A card from its first side and the others, `None` with only one
+fn sides(content: &str) -> Option<Flashcard> {
+    let mut sides: Vec<String> = vec![String::new()];
+    for line in content.lines() {
+        if line.trim() == SIDE_BREAK {
+            sides.push(String::new());
+        } else {
+            let side = sides.last_mut().unwrap();
+            side.push_str(line);
+            side.push('\n');
+        }
+    }
+    let answer: Vec<&str> = sides[1..]
+        .iter()
+        .map(|side| side.trim())
+        .filter(|side| !side.is_empty())
+        .collect();
+    card(&sides[0], &answer.join("\n\n"))
+}
+
+// The file `name` in the zip archive `bytes`, refused when it would unpack
+// to more than `limit` bytes
+fn unzip(bytes: &[u8], name: &str, limit: u64) -> io::Result<Vec<u8>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?;
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no {} in the export, export from Mochi as JSON", name),
+            ));
+        }
+        Err(e) => return Err(zip_error(e)),
+    };
+    let too_big = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} unpacks to more than {} MB", name, limit >> 20),
+        )
+    };
+    if file.size() > limit {
+        return Err(too_big());
+    }
+    // The size in the archive may lie, so it is checked while unpacking too
+    let mut data = Vec::new();
+    (&mut file).take(limit + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > limit {
+        return Err(too_big());
+    }
+    Ok(data)
+}
+
+fn zip_error(e: ZipError) -> io::Error {
+    match e {
+        ZipError::Io(e) => e,
+        ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the export is encrypted, export from Mochi without a password",
+        ),
+        ZipError::UnsupportedArchive(what) => io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("the export can't be read: {}", what),
+        ),
+        e => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("not a readable zip archive: {}", e),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    static EXPORT: &str = r##"{
+        "version": 2,
+        "decks": [
+            {"id": "lang", "name": "Languages", "cards": []},
+            {"id": "es", "name": "Spanish", "parent-id": "lang", "cards": [
+                {"content": "perro\n---\ndog", "trashed?": false},
+                {"content": "gato\n---\ncat", "trashed?": {"date": "2024-01-01"}},
+                {"content": "", "template-id": "word", "fields": {
+                    "w": {"id": "w", "value": "pez"},
+                    "m": {"id": "m", "value": "fish"},
+                    "e": {"id": "e", "value": "El pez nada."}
+                }}
+            ]},
+            {"id": "old", "name": "Old", "trashed?": {"date": "2023-01-01"},
+             "cards": [{"content": "q\n---\na"}]}
+        ],
+        "templates": [
+            {"id": "word", "name": "Word",
+             "content": "# << Word >>\n---\n<<Meaning>>\n---\n<< Example >>",
+             "fields": {
+                "w": {"id": "w", "name": "Word", "pos": "a"},
+                "m": {"id": "m", "name": "Meaning", "pos": "b"},
+                "e": {"id": "e", "name": "Example", "pos": "c"}
+             }}
+        ]
+    }"##;
+
+    // A zip archive holding `data` as `name`
+    fn zip(name: &str, data: &[u8], options: SimpleFileOptions) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file(name, options).unwrap();
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_read_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("export.mochi");
+        let options = SimpleFileOptions::default();
+        fs::write(&path, zip(DATA_FILE, EXPORT.as_bytes(), options)).unwrap();
+
+        let topics = read_file(&path).unwrap();
+        assert_eq!(topics.names(), vec!["Languages / Spanish"]);
+        let spanish = &topics.topics_map["Languages / Spanish"];
+        assert_eq!(spanish.len(), 2);
+        assert_eq!(spanish[0].question, "perro");
+        assert_eq!(spanish[0].answer, "dog");
+        assert_eq!(spanish[1].question, "# pez");
+        assert_eq!(spanish[1].answer, "fish\n\nEl pez nada.");
+
+        fs::write(dir.path().join(DATA_FILE), EXPORT).unwrap();
+        assert_eq!(read_file(dir.path()).unwrap(), topics);
+        fs::write(&path, zip("data.edn", b"{}", options)).unwrap();
+        assert_eq!(
+            read_file(&path).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_unzip_limits() {
+        let options = SimpleFileOptions::default();
+        // Deflates to a few bytes, unpacks past the limit
+        let bomb = zip(DATA_FILE, &[b' '; 4096], options);
+        assert_eq!(unzip(&bomb, DATA_FILE, 4096).unwrap().len(), 4096);
+        let error = unzip(&bomb, DATA_FILE, 1024).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let large = zip(DATA_FILE, b"{}", options.large_file(true));
+        assert_eq!(unzip(&large, DATA_FILE, 1024).unwrap(), b"{}");
+
+        let corrupt = b"PK\x03\x04 not really a zip".to_vec();
+        let error = unzip(&corrupt, DATA_FILE, 1024).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
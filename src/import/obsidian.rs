@@ -0,0 +1,168 @@
+//! Cards from an Obsidian vault or any folder of Markdown notes.
+//!
+//! Two conventions are recognised:
+//!
+//! - `Q:: question` followed by `A:: answer` lines. The answer goes on until
+//!   a blank line or the next `Q::`. These cards go in a topic named after
+//!   the note.
+//! - A note tagged `#flashcard`, which is one card: the note's name is the
+//!   question and the rest of the note the answer. These go in a topic
+//!   named after the note's folder.
+//!
+//! Hidden folders such as `.obsidian` and `.trash` are skipped.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use mem_flip::deck::{Flashcard, Topics};
+
+use super::card;
+
+static TAG: &str = "#flashcard";
+
+/// Cards in the notes under `vault`.
+pub fn read_vault(vault: &Path) -> io::Result<Topics> {
+    let mut topics = Topics::default();
+    let vault_name = name_of(vault);
+    read_dir(vault, &vault_name, &mut topics)?;
+    Ok(topics)
+}
+
+fn read_dir(dir: &Path, folder: &str, topics: &mut Topics) -> io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<io::Result<_>>()?;
+    // Cards come out in the same order on every import
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = name_of(&path);
+        if name.starts_with('.') {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            read_dir(&path, &name, topics)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            let text = fs::read_to_string(&path)?;
+            let (cards, tagged) = parse_note(&text);
+            if !cards.is_empty() {
+                topics
+                    .topics_map
+                    .entry(name.clone())
+                    .or_default()
+                    .extend(cards);
+            }
+            if let Some(answer) = tagged.and_then(|answer| card(&name, &answer)) {
+                topics
+                    .topics_map
+                    .entry(folder.to_string())
+                    .or_default()
+                    .push(answer);
+            }
+        }
+    }
+    Ok(())
+}
+
+// The `Q::`/`A::` cards in a note, and the note's text without the tag if
+// it is tagged as a card itself
+fn parse_note(text: &str) -> (Vec<Flashcard>, Option<String>) {
+    let body = strip_front_matter(text);
+    let mut cards = Vec::new();
+    let mut question: Option<String> = None;
+    let mut answer: Option<String> = None;
+    let mut finish = |question: &mut Option<String>, answer: &mut Option<String>| {
+        if let (Some(q), Some(a)) = (question.take(), answer.take())
+            && let Some(card) = card(&q, &a)
+        {
+            cards.push(card);
+        }
+    };
+
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if let Some(q) = trimmed.strip_prefix("Q::") {
+            finish(&mut question, &mut answer);
+            question = Some(q.to_string());
+        } else if let Some(a) = trimmed.strip_prefix("A::") {
+            if question.is_some() {
+                answer = Some(a.to_string());
+            }
+        } else if trimmed.is_empty() {
+            finish(&mut question, &mut answer);
+            question = None;
+        } else if let Some(answer) = answer.as_mut() {
+            answer.push('\n');
+            answer.push_str(line);
+        } else if let Some(question) = question.as_mut() {
+            question.push('\n');
+            question.push_str(line);
+        }
+    }
+    finish(&mut question, &mut answer);
+
+    let tagged = has_tag(body).then(|| body.lines().map(remove_tag).collect::<Vec<_>>().join("\n"));
+    (cards, tagged)
+}
+
+fn strip_front_matter(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("---\n") else {
+        return text;
+    };
+    match rest.find("\n---\n") {
+        Some(end) => &rest[end + "\n---\n".len()..],
+        None => text,
+    }
+}
+
+fn has_tag(text: &str) -> bool {
+    text.split_whitespace().any(|word| word == TAG)
+}
+
+fn remove_tag(line: &str) -> String {
+    line.split(' ')
+        .filter(|word| *word != TAG)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn name_of(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_read_vault() {
+        let vault = TempDir::new().unwrap();
+        fs::create_dir(vault.path().join("Biology")).unwrap();
+        fs::create_dir(vault.path().join(".trash")).unwrap();
+        fs::write(
+            vault.path().join("Spanish.md"),
+            "---\ntags: [language]\n---\n# Animals\n\nQ:: perro\nA:: dog\n\n\
+             Q:: gato\nA:: cat\nalso a tomcat\n\nSome notes.\n",
+        )
+        .unwrap();
+        fs::write(
+            vault.path().join("Biology/Mitochondria.md"),
+            "#flashcard\nThe powerhouse of the cell.\n",
+        )
+        .unwrap();
+        fs::write(vault.path().join(".trash/Old.md"), "Q:: old\nA:: gone\n").unwrap();
+
+        let topics = read_vault(vault.path()).unwrap();
+        assert_eq!(topics.names(), vec!["Biology", "Spanish"]);
+        let spanish = &topics.topics_map["Spanish"];
+        assert_eq!(spanish.len(), 2);
+        assert_eq!(spanish[1].answer, "cat\nalso a tomcat");
+        let biology = &topics.topics_map["Biology"][0];
+        assert_eq!(biology.question, "Mitochondria");
+        assert_eq!(biology.answer, "The powerhouse of the cell.");
+    }
+}
@@ -0,0 +1,142 @@
+//! Cards from Emacs org files in the org-drill style: every heading tagged
+//! `:drill:` is a card, the heading the question and everything under it,
+//! subheadings included, the answer. The cards of a file go in a topic
+//! named after it.
+//!
+//! Drawers such as `:PROPERTIES:` and planning lines such as `SCHEDULED:`
+//! hold org-drill's own review data and are left out of the answer.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use mem_flip::deck::{Flashcard, Topics};
+
+use super::card;
+
+static TAG: &str = "drill";
+static PLANNING: [&str; 3] = ["SCHEDULED:", "DEADLINE:", "CLOSED:"];
+static KEYWORDS: [&str; 2] = ["TODO", "DONE"];
+
+/// Cards in the org file at `path`.
+pub fn read_file(path: &Path) -> io::Result<Topics> {
+    let cards = parse(&fs::read_to_string(path)?);
+    let mut topics = Topics::default();
+    if !cards.is_empty() {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        topics.topics_map.insert(name.into_owned(), cards);
+    }
+    Ok(topics)
+}
+
+// A drill heading being read: its level, question and answer lines so far
+struct Entry {
+    level: usize,
+    question: String,
+    answer: Vec<String>,
+}
+
+fn parse(text: &str) -> Vec<Flashcard> {
+    let mut cards = Vec::new();
+    let mut entry: Option<Entry> = None;
+    let mut in_drawer = false;
+
+    for line in text.lines() {
+        if let Some((level, title, tags)) = heading(line) {
+            // A subheading is part of the answer, a sibling ends it
+            if let Some(current) = entry.as_mut()
+                && level > current.level
+            {
+                current.answer.push(title.to_string());
+                continue;
+            }
+            if let Some(done) = entry.take() {
+                cards.extend(card(&done.question, &done.answer.join("\n")));
+            }
+            if tags.contains(&TAG) {
+                entry = Some(Entry {
+                    level,
+                    question: title.to_string(),
+                    answer: Vec::new(),
+                });
+            }
+            continue;
+        }
+        let Some(current) = entry.as_mut() else {
+            continue;
+        };
+        let trimmed = line.trim();
+        if in_drawer {
+            in_drawer = !trimmed.eq_ignore_ascii_case(":END:");
+        } else if trimmed.len() > 2 && trimmed.starts_with(':') && trimmed.ends_with(':') {
+            in_drawer = true;
+        } else if !PLANNING.iter().any(|word| trimmed.starts_with(word)) {
+            current.answer.push(line.to_string());
+        }
+    }
+    if let Some(done) = entry {
+        cards.extend(card(&done.question, &done.answer.join("\n")));
+    }
+    cards
+}
+
+// The level, title and tags of a heading line such as
+// `** TODO Capital of France? :drill:geo:`
+fn heading(line: &str) -> Option<(usize, &str, Vec<&str>)> {
+    let level = line.chars().take_while(|&c| c == '*').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    if level == 0 {
+        return None;
+    }
+    let mut title = rest.trim();
+    for keyword in KEYWORDS {
+        if let Some(after) = title
+            .strip_prefix(keyword)
+            .and_then(|t| t.strip_prefix(' '))
+        {
+            title = after.trim_start();
+        }
+    }
+    let mut tags = Vec::new();
+    if let Some(start) = title.rfind(" :")
+        && title.ends_with(':')
+    {
+        let candidate = &title[start + 2..title.len() - 1];
+        if !candidate.is_empty() && !candidate.contains(' ') {
+            tags = candidate.split(':').collect();
+            title = title[..start].trim_end();
+        }
+    }
+    Some((level, title, tags))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "\
+#+TITLE: Geography
+* Capitals
+** What is the capital of France? :drill:
+SCHEDULED: <2024-05-01 Wed>
+:PROPERTIES:
+:DRILL_LAST_INTERVAL: 4.0
+:END:
+Paris
+*** Notes
+On the Seine.
+** Not a card
+Skipped.
+** TODO Capital of Peru :geo:drill:
+Lima
+";
+        let cards = parse(text);
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].question, "What is the capital of France?");
+        assert_eq!(cards[0].answer, "Paris\nNotes\nOn the Seine.");
+        assert_eq!(cards[1].question, "Capital of Peru");
+        assert_eq!(cards[1].answer, "Lima");
+    }
+}
@@ -0,0 +1,92 @@
+//! Cards from a Quizlet set exported as text, one `term<Tab>definition`
+//! line per card. Quizlet sets have no topics, so the cards all go into
+//! one, chosen on the command line or asked for.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use mem_flip::deck::{Flashcard, Topics};
+
+use super::card;
+
+/// The cards in the export at `path`, and how many lines weren't cards.
+pub fn read_file(path: &Path) -> io::Result<(Vec<Flashcard>, usize)> {
+    Ok(parse(&fs::read_to_string(path)?))
+}
+
+fn parse(text: &str) -> (Vec<Flashcard>, usize) {
+    let mut cards = Vec::new();
+    let mut skipped = 0;
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        match line
+            .split_once('\t')
+            .and_then(|(term, def)| card(term, def))
+        {
+            Some(card) => cards.push(card),
+            None => skipped += 1,
+        }
+    }
+    (cards, skipped)
+}
+
+/// Asks which topic of `topics` the cards go into, by number or a new
+/// name, with `default` for an empty answer.
+pub fn choose_topic(
+    topics: &Topics,
+    default: &str,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<String> {
+    let names = topics.names();
+    for (i, name) in names.iter().enumerate() {
+        writeln!(output, "{:>3}. {}", i + 1, name)?;
+    }
+    loop {
+        write!(output, "Topic number or new topic name [{}]: ", default)?;
+        output.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Ok(default.to_string());
+        }
+        match answer.parse::<usize>() {
+            Ok(number) => match names.get(number.wrapping_sub(1)) {
+                Some(name) => return Ok(name.clone()),
+                None => writeln!(output, "There is no topic {}", number)?,
+            },
+            Err(_) => return Ok(answer.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let (cards, skipped) = parse("perro\tdog\ngato\tcat\n\nno tab here\n\tno term\n");
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[1].answer, "cat");
+        assert_eq!(skipped, 2);
+    }
+
+    #[test]
+    fn test_choose_topic() {
+        let mut topics = Topics::default();
+        topics.topics_map.insert("French".to_string(), Vec::new());
+        topics.topics_map.insert("Spanish".to_string(), Vec::new());
+
+        let choose = |answers: &str| {
+            choose_topic(&topics, "Biology 101", answers.as_bytes(), io::sink()).unwrap()
+        };
+        assert_eq!(choose("2\n"), "Spanish");
+        assert_eq!(choose("9\n1\n"), "French");
+        assert_eq!(choose("\n"), "Biology 101");
+        assert_eq!(choose("German\n"), "German");
+    }
+}
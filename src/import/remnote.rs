@@ -0,0 +1,152 @@
+//! Cards from RemNote's Markdown export, in which every bullet with a card
+//! delimiter is a card:
+//!
+//! - `question >> answer`, and concepts and descriptors written with `::`
+//!   and `;;`, asked forward
+//! - `answer << question`, asked backward
+//! - `front <> back`, asked both ways as two cards
+//! - `question >>>` (or `:::`, `;;;`) with the answer in the bullets under it
+//! - text with `{{cloze}}` parts, asked with the parts hidden
+//!
+//! The cards of a file go in a topic named after it. A folder is read file
+//! by file.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use mem_flip::deck::{Flashcard, Topics};
+
+use super::card;
+
+static FORWARD: [&str; 3] = [">>", "::", ";;"];
+static MULTILINE: [&str; 3] = [">>>", ":::", ";;;"];
+static CLOZE_GAP: &str = "[...]";
+
+/// Cards in the RemNote export at `path`, a Markdown file or a folder of
+/// them.
+pub fn read_path(path: &Path) -> io::Result<Topics> {
+    let mut files = vec![path.to_path_buf()];
+    if path.is_dir() {
+        files = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()?;
+        files.retain(|file| file.extension().is_some_and(|ext| ext == "md"));
+        // Topics come out in the same order on every import
+        files.sort();
+    }
+    let mut topics = Topics::default();
+    for file in files {
+        let cards = parse(&fs::read_to_string(&file)?);
+        if !cards.is_empty() {
+            let name = file.file_stem().unwrap_or_default().to_string_lossy();
+            topics
+                .topics_map
+                .entry(name.into_owned())
+                .or_default()
+                .extend(cards);
+        }
+    }
+    Ok(topics)
+}
+
+fn parse(text: &str) -> Vec<Flashcard> {
+    let bullets: Vec<(usize, &str)> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(bullet)
+        .collect();
+    let mut cards = Vec::new();
+    for (i, &(indent, text)) in bullets.iter().enumerate() {
+        if let Some(question) = MULTILINE.iter().find_map(|end| text.strip_suffix(end)) {
+            let answer: Vec<&str> = bullets[i + 1..]
+                .iter()
+                .take_while(|(child, _)| *child > indent)
+                .map(|(_, text)| *text)
+                .collect();
+            cards.extend(card(question, &answer.join("\n")));
+        } else if let Some((front, back)) = text.split_once("<>") {
+            cards.extend(card(front, back));
+            cards.extend(card(back, front));
+        } else if let Some((answer, question)) = text.split_once("<<") {
+            cards.extend(card(question, answer));
+        } else if let Some((question, answer)) = FORWARD
+            .iter()
+            .find_map(|delimiter| text.split_once(delimiter))
+        {
+            cards.extend(card(question, answer));
+        } else if let Some(question) = cloze(text) {
+            cards.extend(card(&question, &text.replace("{{", "").replace("}}", "")));
+        }
+    }
+    cards
+}
+
+// The indent of a line, counting a tab as four spaces, and its text
+// without the bullet
+fn bullet(line: &str) -> (usize, &str) {
+    let text = line.trim_start();
+    let indent = line[..line.len() - text.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let text = text
+        .strip_prefix("- ")
+        .or_else(|| text.strip_prefix("* "))
+        .unwrap_or(text);
+    (indent, text.trim_end())
+}
+
+// `text` with its `{{cloze}}` parts hidden, `None` without any
+fn cloze(text: &str) -> Option<String> {
+    let mut question = String::new();
+    let mut rest = text;
+    while let Some((before, after)) = rest.split_once("{{") {
+        let (_, after) = after.split_once("}}")?;
+        question.push_str(before);
+        question.push_str(CLOZE_GAP);
+        rest = after;
+    }
+    (!question.is_empty()).then(|| question + rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "\
+- Spanish
+    - perro >> dog
+    - cat << gato
+    - pez <> fish
+    - ser :: to be
+    - Uses of ser >>>
+        - identity
+        - origin
+    - Madrid is the {{capital}} of {{Spain}}
+- A note without a card
+";
+        let cards = parse(text);
+        let sides: Vec<(&str, &str)> = cards
+            .iter()
+            .map(|card| (card.question.as_str(), card.answer.as_str()))
+            .collect();
+        assert_eq!(
+            sides,
+            [
+                ("perro", "dog"),
+                ("gato", "cat"),
+                ("pez", "fish"),
+                ("fish", "pez"),
+                ("ser", "to be"),
+                ("Uses of ser", "identity\norigin"),
+                (
+                    "Madrid is the [...] of [...]",
+                    "Madrid is the capital of Spain"
+                ),
+            ]
+        );
+    }
+}
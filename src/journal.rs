@@ -0,0 +1,243 @@
+//! Saving big decks a topic at a time.
+//!
+//! Rewriting a deck of thousands of cards after every change is slow, so a
+//! [`Journal`] appends the topics changed since the last save to a file
+//! next to the deck instead, one JSON line per topic with all its cards.
+//! Loading a deck replays its journal over it, and writing the deck in full
+//! leaves the journal out, so everything that only reads and writes whole
+//! decks keeps working. The journal of an encrypted deck has each line
+//! sealed with the deck's key, like its history.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::crypt::Key;
+use crate::deck::{Flashcard, TopicMeta, Topics};
+
+/// Decks with fewer cards than this are always written in full.
+pub const LARGE_DECK: usize = 1000;
+// Journal lines after which the deck is written in full again
+const COMPACT_AFTER: usize = 100;
+
+// A topic as of a save, or its removal
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    topic: String,
+    // `None` when the topic was removed
+    cards: Option<Vec<Flashcard>>,
+    #[serde(default, skip_serializing_if = "TopicMeta::is_empty")]
+    meta: TopicMeta,
+}
+
+/// Where the journal of a deck is kept: `deck.journal.jsonl` for
+/// `deck.json`.
+pub fn path_for(deck: &Path) -> PathBuf {
+    deck.with_extension("journal.jsonl")
+}
+
+/// Applies the journal of the deck at `path` to `topics`, read from that
+/// deck. Lines that can't be read are skipped.
+pub fn replay(topics: &mut Topics, path: &Path, key: Option<&Key>) -> io::Result<()> {
+    let text = match fs::read_to_string(path_for(path)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for line in text.lines() {
+        let line = match key {
+            Some(key) => match key.open_line(line) {
+                Ok(line) => line,
+                Err(_) => continue,
+            },
+            None => line.to_string(),
+        };
+        let Ok(entry) = serde_json::from_str::<Entry>(&line) else {
+            continue;
+        };
+        match entry.cards {
+            Some(cards) => {
+                topics.topics_map.insert(entry.topic.clone(), cards);
+            }
+            None => {
+                topics.topics_map.remove(&entry.topic);
+            }
+        }
+        topics.set_meta(&entry.topic, entry.meta);
+    }
+    Ok(())
+}
+
+/// Keeps track of what changed in a deck since it was last saved, to save
+/// only that.
+#[derive(Debug)]
+pub struct Journal {
+    // Fingerprint of each topic's cards and settings as saved
+    saved: HashMap<String, u64>,
+    // Fingerprint of the deck's own details and trash as saved
+    saved_meta: u64,
+    // Lines in the journal file
+    entries: usize,
+}
+
+impl Journal {
+    /// Starts from `topics` as they were just loaded from, or saved to,
+    /// the deck at `path`.
+    pub fn open(topics: &Topics, path: &Path) -> Journal {
+        let entries = fs::read_to_string(path_for(path)).map_or(0, |text| text.lines().count());
+        Journal {
+            saved: fingerprints(topics),
+            saved_meta: fingerprint(&(&topics.meta, &topics.trash)),
+            entries,
+        }
+    }
+
+    /// Saves `topics` to the deck at `path`. Only the topics changed since
+    /// the last save are written, unless the deck is small, its own details
+    /// or trash changed or the journal has grown long, when the whole deck
+    /// is.
+    pub fn save(&mut self, topics: &Topics, path: &Path, key: Option<&Key>) -> io::Result<()> {
+        if topics.card_count() < LARGE_DECK
+            || fingerprint(&(&topics.meta, &topics.trash)) != self.saved_meta
+            || self.entries >= COMPACT_AFTER
+        {
+            return self.compact(topics, path, key);
+        }
+
+        let current = fingerprints(topics);
+        let mut names: Vec<&String> = current
+            .iter()
+            .filter(|(name, print)| self.saved.get(*name) != Some(print))
+            .map(|(name, _)| name)
+            .chain(
+                self.saved
+                    .keys()
+                    .filter(|name| !current.contains_key(*name)),
+            )
+            .collect();
+        names.sort();
+        if names.is_empty() {
+            return Ok(());
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path_for(path))?;
+        let mut writer = BufWriter::new(file);
+        for name in &names {
+            let entry = Entry {
+                topic: name.to_string(),
+                cards: topics.topics_map.get(*name).cloned(),
+                meta: topics.meta_of(name),
+            };
+            let json = serde_json::to_string(&entry)?;
+            match key {
+                Some(key) => writeln!(writer, "{}", key.seal_line(&json)?)?,
+                None => writeln!(writer, "{}", json)?,
+            }
+        }
+        writer.flush()?;
+        self.entries += names.len();
+        self.saved = current;
+        Ok(())
+    }
+
+    /// Writes the whole deck to `path`, which drops the journal.
+    pub fn compact(&mut self, topics: &Topics, path: &Path, key: Option<&Key>) -> io::Result<()> {
+        topics.save_with(path, key)?;
+        *self = Journal {
+            saved: fingerprints(topics),
+            saved_meta: fingerprint(&(&topics.meta, &topics.trash)),
+            entries: 0,
+        };
+        Ok(())
+    }
+
+    /// Whether changes are kept in the journal rather than the deck file.
+    pub fn has_entries(&self) -> bool {
+        self.entries > 0
+    }
+}
+
+fn fingerprints(topics: &Topics) -> HashMap<String, u64> {
+    topics
+        .topics_map
+        .iter()
+        .map(|(name, cards)| {
+            let meta = topics.topic_meta.get(name);
+            (name.clone(), fingerprint(&(cards, meta)))
+        })
+        .collect()
+}
+
+fn fingerprint(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big_deck() -> Topics {
+        let cards = |prefix: &str| {
+            (0..LARGE_DECK)
+                .map(|i| Flashcard::new(format!("{} {}", prefix, i), i.to_string()))
+                .collect()
+        };
+        let mut topics = Topics::default();
+        topics.topics_map.insert("Numbers".to_string(), cards("n"));
+        topics.topics_map.insert("More".to_string(), cards("m"));
+        topics
+    }
+
+    #[test]
+    fn test_saves_changed_topics() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("big.json");
+        let mut topics = big_deck();
+        let mut journal = Journal::open(&topics, &path);
+        journal.compact(&topics, &path, None).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+
+        topics.topics_map.get_mut("Numbers").unwrap()[0].answer = "zero".to_string();
+        topics.topics_map.remove("More");
+        journal.save(&topics, &path, None).unwrap();
+        // Nothing changed since
+        journal.save(&topics, &path, None).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+        let lines = fs::read_to_string(path_for(&path)).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        assert_eq!(Topics::load(&path).unwrap(), topics);
+
+        // Writing the deck in full leaves the journal out
+        journal.compact(&topics, &path, None).unwrap();
+        assert!(!path_for(&path).exists());
+        assert!(!journal.has_entries());
+        assert_eq!(Topics::load(&path).unwrap(), topics);
+    }
+
+    #[test]
+    fn test_encrypted_journal() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("big.json");
+        let key = Key::new("secret").unwrap();
+        let mut topics = big_deck();
+        let mut journal = Journal::open(&topics, &path);
+        journal.compact(&topics, &path, Some(&key)).unwrap();
+
+        topics.topics_map.get_mut("More").unwrap().truncate(3);
+        journal.save(&topics, &path, Some(&key)).unwrap();
+        let lines = fs::read_to_string(path_for(&path)).unwrap();
+        assert!(!lines.contains("m 1"));
+        assert_eq!(Topics::load_with(&path, Some(&key)).unwrap(), topics);
+    }
+}
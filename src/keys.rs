@@ -0,0 +1,65 @@
+//! Key sequences started with a leader key, such as `g s` for stats, for
+//! more bindings than single letters leave room for.
+//!
+//! Sequences work on the screens that don't take text: home, the topic
+//! list, stats, the card browser and the trash. Any key that doesn't finish
+//! a sequence drops it.
+
+use crate::l10n::tr;
+
+/// Starts a sequence.
+pub const LEADER: char = 'g';
+
+/// Where a sequence goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sequence {
+    Home,
+    Topics,
+    Stats,
+    Decks,
+    /// Drafting cards for the selected topic
+    Generate,
+    /// Browsing the cards of the selected topic
+    Browse,
+    Trash,
+}
+
+// Second key of each sequence, with what it does for the status bar
+const SEQUENCES: [(char, Sequence, &str); 7] = [
+    ('h', Sequence::Home, "home"),
+    ('t', Sequence::Topics, "topics"),
+    ('s', Sequence::Stats, "stats"),
+    ('d', Sequence::Decks, "decks"),
+    ('c', Sequence::Generate, "generate cards"),
+    ('b', Sequence::Browse, "browse"),
+    ('x', Sequence::Trash, "trash"),
+];
+
+/// The sequence the leader and then `key` make, if any.
+pub fn sequence(key: char) -> Option<Sequence> {
+    SEQUENCES
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|(_, sequence, _)| *sequence)
+}
+
+/// What can follow the leader, as `g… h home  t topics …`.
+pub fn pending_hint() -> String {
+    let keys: Vec<String> = SEQUENCES
+        .iter()
+        .map(|(key, _, label)| format!("{} {}", key, tr(label)))
+        .collect();
+    format!("{}… {}", LEADER, keys.join("  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(sequence('s'), Some(Sequence::Stats));
+        assert_eq!(sequence('z'), None);
+        assert!(pending_hint().starts_with("g… h home  t topics"));
+    }
+}
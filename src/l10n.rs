@@ -0,0 +1,1697 @@
+//! Translations of the interface.
+//!
+//! Text is looked up by its English wording in a table per language, so
+//! anything without a translation yet simply stays in English. The language
+//! is picked at startup, from the config or else the environment, and again
+//! when it is changed in the settings. Text with values in it is looked up
+//! with `{}` where the values go.
+
+use std::env;
+use std::fmt::Display;
+#[cfg(not(test))]
+use std::sync::RwLock;
+
+use ratatui::text::Span;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "es")]
+    Spanish,
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "fr")]
+    French,
+}
+
+impl Language {
+    /// The language of a POSIX locale such as `de_DE.UTF-8`, English for
+    /// ones without translations.
+    pub fn from_locale(locale: &str) -> Language {
+        match locale.get(..2) {
+            Some("es") => Language::Spanish,
+            Some("de") => Language::German,
+            Some("fr") => Language::French,
+            _ => Language::English,
+        }
+    }
+
+    /// The language of the environment's locale.
+    pub fn from_env() -> Language {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map_or(Language::English, |locale| Language::from_locale(&locale))
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => &[],
+            Language::Spanish => SPANISH,
+            Language::German => GERMAN,
+            Language::French => FRENCH,
+        }
+    }
+}
+
+#[cfg(not(test))]
+static LANGUAGE: RwLock<Language> = RwLock::new(Language::English);
+
+// Tests run side by side, so each has a language of its own
+#[cfg(test)]
+thread_local! {
+    static LANGUAGE: std::cell::Cell<Language> = const { std::cell::Cell::new(Language::English) };
+}
+
+/// Sets the language of the interface.
+#[cfg(not(test))]
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap_or_else(|e| e.into_inner()) = language;
+}
+
+#[cfg(test)]
+pub fn set_language(language: Language) {
+    LANGUAGE.set(language);
+}
+
+#[cfg(not(test))]
+fn language() -> Language {
+    *LANGUAGE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+fn language() -> Language {
+    LANGUAGE.get()
+}
+
+/// `text` in `language`, or as it is when there is no translation.
+pub fn translate(language: Language, text: &'static str) -> &'static str {
+    language
+        .table()
+        .iter()
+        .find(|(english, _)| *english == text)
+        .map_or(text, |(_, translated)| translated)
+}
+
+/// `text` in the interface language.
+pub fn tr(text: &'static str) -> &'static str {
+    translate(language(), text)
+}
+
+/// `text` in the interface language, with each `{}` in it filled in with
+/// the next of `args`.
+pub fn trf(text: &'static str, args: &[&dyn Display]) -> String {
+    let mut parts = tr(text).split("{}");
+    let mut filled = parts.next().unwrap_or_default().to_string();
+    for (i, part) in parts.enumerate() {
+        if let Some(arg) = args.get(i) {
+            filled.push_str(&arg.to_string());
+        }
+        filled.push_str(part);
+    }
+    filled
+}
+
+/// The label before a key in a list of key hints, translated.
+pub fn hint(label: &'static str) -> Span<'static> {
+    Span::raw(format!(" {} ", tr(label)))
+}
+
+static SPANISH: &[(&str, &str)] = &[
+    ("Add Card", "Añadir tarjeta"),
+    ("Archive", "Archivar"),
+    ("As template", "Como plantilla"),
+    ("Attach", "Adjuntar"),
+    ("Back", "Volver"),
+    ("Browse", "Explorar"),
+    ("Bundle", "Paquete"),
+    ("Cancel", "Cancelar"),
+    ("Change", "Cambiar"),
+    ("Check", "Comprobar"),
+    ("Continue", "Continuar"),
+    ("Decks", "Mazos"),
+    ("Delete", "Borrar"),
+    ("Dismiss", "Cerrar"),
+    ("Duplicate", "Duplicar"),
+    ("Enter topic name:", "Nombre del tema:"),
+    ("Export", "Exportar"),
+    ("Export CSV", "Exportar CSV"),
+    ("Fetch", "Descargar"),
+    ("Fix spelling", "Corregir"),
+    ("Flip", "Voltear"),
+    ("Follow link", "Seguir enlace"),
+    ("Furigana", "Furigana"),
+    ("Generate", "Generar"),
+    ("Generating cards… Cancel", "Generando tarjetas… Cancelar"),
+    ("Go", "Ir"),
+    ("Home", "Inicio"),
+    ("Import", "Importar"),
+    ("Info", "Info"),
+    ("Jump", "Saltar"),
+    ("Jump to card :", "Ir a la tarjeta :"),
+    ("Keep", "Conservar"),
+    ("Knew it", "La sabía"),
+    ("Link back", "Volver del enlace"),
+    ("Mark", "Marcar"),
+    ("Mark to mix", "Marcar para mezclar"),
+    ("Missed it", "Fallé"),
+    ("Mix in turn", "Mezclar por turnos"),
+    ("Mix shuffled", "Mezclar al azar"),
+    ("Move", "Mover"),
+    ("Navigate", "Navegar"),
+    ("New Deck", "Nuevo mazo"),
+    ("New Topic", "Nuevo tema"),
+    ("Next", "Siguiente"),
+    ("No cards available", "No hay tarjetas"),
+    ("Open", "Abrir"),
+    ("Open File", "Abrir archivo"),
+    ("Open attachments", "Abrir adjuntos"),
+    (
+        "Press Enter to create | Esc to cancel",
+        "Enter para crear | Esc para cancelar",
+    ),
+    (
+        "[Press Space to reveal answer]",
+        "[Pulsa Espacio para ver la respuesta]",
+    ),
+    ("Previous", "Anterior"),
+    ("Quit", "Salir"),
+    ("Quit without saving", "Salir sin guardar"),
+    ("Rate difficulty", "Valorar dificultad"),
+    ("Recently added", "Añadidas hace poco"),
+    ("Review", "Repasar"),
+    ("Save", "Guardar"),
+    ("Save & next", "Guardar y seguir"),
+    ("Scroll", "Desplazar"),
+    ("Select", "Elegir"),
+    ("Settings", "Ajustes"),
+    ("Share", "Compartir"),
+    ("Show archived", "Mostrar archivados"),
+    ("Snapshot", "Instantánea"),
+    ("Skip", "Omitir"),
+    ("Sort", "Ordenar"),
+    ("Speak", "Leer en voz alta"),
+    ("Spelling:", "Ortografía:"),
+    ("Stats", "Estadísticas"),
+    ("Stop", "Detener"),
+    ("Switch field", "Cambiar campo"),
+    ("Sync", "Sincronizar"),
+    ("Terminal too small", "Terminal demasiado pequeña"),
+    ("Topics", "Temas"),
+    ("Type answer", "Escribir respuesta"),
+    ("Typing practice", "Práctica de mecanografía"),
+    ("Timer", "Temporizador"),
+    ("Pause", "Pausa"),
+    (
+        "Paused, press any key to go on",
+        "En pausa, pulsa una tecla para seguir",
+    ),
+    ("Web page", "Página web"),
+    ("Your answer:", "Tu respuesta:"),
+    ("1 day in a row", "1 día seguido"),
+    ("ANSI text", "texto ANSI"),
+    ("About", "Acerca de"),
+    ("Accuracy", "Acierto"),
+    ("Add Card to '{}' topic", "Añadir tarjeta al tema '{}'"),
+    (
+        "Added {} of {} cards to '{}'",
+        "Añadidas {} de {} tarjetas a '{}'",
+    ),
+    (
+        "An import is already running",
+        "Ya hay una importación en curso",
+    ),
+    ("Answer", "Respuesta"),
+    ("Answer first", "Respuesta primero"),
+    ("Archived '{}'", "'{}' archivado"),
+    ("Attached {}", "{} adjuntado"),
+    ("Attachments", "Adjuntos"),
+    ("Break minutes", "Minutos de descanso"),
+    (
+        "Break's over, back to the cards",
+        "Se acabó el descanso, a las tarjetas",
+    ),
+    (
+        "Brought '{}' back from the archive",
+        "'{}' sacado del archivo",
+    ),
+    ("Bundle failed: {}", "Falló el paquete: {}"),
+    (
+        "Bundled {} images into {}",
+        "{} imágenes empaquetadas en {}",
+    ),
+    (
+        "Bundled, but {} images weren't found, such as {}",
+        "Empaquetado, pero no se encontraron {} imágenes, como {}",
+    ),
+    ("Bundling deck", "Empaquetando el mazo"),
+    (
+        "Can't generate cards: {}",
+        "No se pueden generar tarjetas: {}",
+    ),
+    ("Can't sync: {}", "No se puede sincronizar: {}"),
+    ("Card history", "Historial de la tarjeta"),
+    ("Card saved to '{}'", "Tarjeta guardada en '{}'"),
+    ("Card snapshots", "Capturas de tarjetas"),
+    (
+        "Card updated, the old wording is in its history",
+        "Tarjeta actualizada, la redacción anterior está en su historial",
+    ),
+    ("Card {} of {} for '{}'", "Tarjeta {} de {} para '{}'"),
+    ("Cards", "Tarjetas"),
+    (
+        "Change the copy before saving it",
+        "Cambia la copia antes de guardarla",
+    ),
+    (
+        "Changed elsewhere too ({} left)",
+        "Cambiada también en otro sitio (quedan {})",
+    ),
+    ("Color", "Color"),
+    ("Commit failed: {}", "Falló el commit: {}"),
+    ("Commit to git", "Commit en git"),
+    ("Compact history", "Compactar historial"),
+    ("Couldn't attach: {}", "No se pudo adjuntar: {}"),
+    ("Couldn't create {}: {}", "No se pudo crear {}: {}"),
+    ("Couldn't load cards", "No se pudieron cargar las tarjetas"),
+    ("Couldn't load config", "No se pudo cargar la configuración"),
+    ("Couldn't open {}: {}", "No se pudo abrir {}: {}"),
+    ("Couldn't open: {}", "No se pudo abrir: {}"),
+    (
+        "Couldn't record review: {}",
+        "No se pudo registrar el repaso: {}",
+    ),
+    (
+        "Couldn't reload the deck: {}",
+        "No se pudo recargar el mazo: {}",
+    ),
+    (
+        "Couldn't save the settings: {}",
+        "No se pudieron guardar los ajustes: {}",
+    ),
+    ("Couldn't share: {}", "No se pudo compartir: {}"),
+    ("Couldn't unpack bundle: {}", "No se pudo desempaquetar: {}"),
+    ("Couldn't write {}: {}", "No se pudo escribir {}: {}"),
+    (
+        "Create a topic to add cards to first",
+        "Crea antes un tema al que añadir tarjetas",
+    ),
+    ("Days in trash", "Días en la papelera"),
+    ("Debug", "Depuración"),
+    ("Deck URL", "URL del mazo"),
+    ("Download failed: {}", "Falló la descarga: {}"),
+    ("Downloading deck", "Descargando el mazo"),
+    ("Earlier wording restored", "Redacción anterior restaurada"),
+    ("Edit", "Editar"),
+    ("Edit Card in '{}' topic", "Editar tarjeta del tema '{}'"),
+    (
+        "Every topic is archived.\n\nPress 'Shift + Z' to show them.",
+        "Todos los temas están archivados.\n\nPulsa 'Shift + Z' para verlos.",
+    ),
+    ("Exam date", "Fecha del examen"),
+    ("Export '{}'", "Exportar '{}'"),
+    ("Export failed: {}", "Falló la exportación: {}"),
+    ("Exported {} reviews to {}", "{} repasos exportados a {}"),
+    ("Exported {} to {}", "{} exportadas a {}"),
+    ("Exporting stats", "Exportando estadísticas"),
+    ("Exporting topic", "Exportando el tema"),
+    ("File or link to attach", "Archivo o enlace para adjuntar"),
+    ("Fill in the template first", "Rellena antes la plantilla"),
+    ("Focus minutes", "Minutos de concentración"),
+    ("Format", "Formato"),
+    ("Generate Cards for '{}'", "Generar tarjetas para '{}'"),
+    ("Generating cards", "Generando tarjetas"),
+    (
+        "Generating cards failed: {}",
+        "Falló la generación de tarjetas: {}",
+    ),
+    ("Hardest cards", "Tarjetas más difíciles"),
+    ("Hiding archived topics", "Ocultando temas archivados"),
+    ("History", "Historial"),
+    ("Icon", "Icono"),
+    (
+        "Import cancelled, the deck is unchanged",
+        "Importación cancelada, el mazo no ha cambiado",
+    ),
+    ("Import failed: {}", "Falló la importación: {}"),
+    (
+        "Import org, Quizlet, Mochi or RemNote file, or notes folder",
+        "Importar archivo de org, Quizlet, Mochi o RemNote, o carpeta de notas",
+    ),
+    (
+        "Imported {}, updated {} and skipped {} already there",
+        "Importadas {}, actualizadas {} y omitidas {} que ya estaban",
+    ),
+    ("Importing", "Importando"),
+    ("Keep mine", "Quedarme la mía"),
+    ("Keep working", "Seguir trabajando"),
+    ("Language", "Idioma"),
+    ("Last studied", "Último estudio"),
+    ("Learning", "Aprendiendo"),
+    ("Loading cards", "Cargando tarjetas"),
+    ("Longest answer", "Respuesta más larga"),
+    (
+        "Mark two or more topics with Space to review them together",
+        "Marca dos o más temas con Espacio para repasarlos juntos",
+    ),
+    ("Mature", "Maduras"),
+    ("Memory Flip Flashcards", "Tarjetas Memory Flip"),
+    (
+        "Merged in changes made elsewhere",
+        "Cambios hechos en otro sitio incorporados",
+    ),
+    ("Moved '{}' to the trash", "'{}' movido a la papelera"),
+    ("New", "Nuevas"),
+    ("New cards a day", "Tarjetas nuevas al día"),
+    (
+        "New cards in '{}' start from this template",
+        "Las tarjetas nuevas de '{}' parten de esta plantilla",
+    ),
+    ("New deck file", "Archivo de mazo nuevo"),
+    (
+        "No answers to type in {}",
+        "No hay respuestas que escribir en {}",
+    ),
+    (
+        "No card for link [[{}]]",
+        "No hay tarjeta para el enlace [[{}]]",
+    ),
+    (
+        "No card {}, pick 1 to {}",
+        "No existe la tarjeta {}, elige de 1 a {}",
+    ),
+    (
+        "No cards added in the last {} days",
+        "No se añadieron tarjetas en los últimos {} días",
+    ),
+    (
+        "No cards came back, try more text",
+        "No llegaron tarjetas, prueba con más texto",
+    ),
+    ("No cards found in {}", "No se encontraron tarjetas en {}"),
+    (
+        "No cards to review in the marked topics",
+        "No hay tarjetas que repasar en los temas marcados",
+    ),
+    (
+        "No config directory to save settings to",
+        "No hay carpeta de configuración donde guardar los ajustes",
+    ),
+    ("No deck at {}", "No hay mazo en {}"),
+    (
+        "No more new cards in '{}' today",
+        "No hay más tarjetas nuevas en '{}' hoy",
+    ),
+    (
+        "No reviews yet.\n\nGrade cards with Y or M after flipping them in review.",
+        "Aún no hay repasos.\n\nCalifica las tarjetas con Y o M tras voltearlas en el repaso.",
+    ),
+    (
+        "No streak yet, review today to start one",
+        "Aún no hay racha, repasa hoy para empezar una",
+    ),
+    (
+        "No topics yet!\n\nPress 'N' to create your first topic.",
+        "¡Aún no hay temas!\n\nPulsa 'N' para crear tu primer tema.",
+    ),
+    ("Not now", "Ahora no"),
+    (
+        "Not saved, the config file couldn't be read",
+        "No guardado, no se pudo leer el archivo de configuración",
+    ),
+    ("Nothing due", "Nada pendiente"),
+    (
+        "Nothing is attached to this card",
+        "Esta tarjeta no tiene adjuntos",
+    ),
+    ("Now:", "Ahora:"),
+    ("Open deck file", "Abrir archivo de mazo"),
+    ("Opened {}", "{} abierto"),
+    (
+        "Paste notes to make cards from, or describe a subject:",
+        "Pega notas de las que sacar tarjetas, o describe un tema:",
+    ),
+    ("Preview", "Vista previa"),
+    ("Printable cards (typst)", "Tarjetas para imprimir (typst)"),
+    ("Question", "Pregunta"),
+    (
+        "Rated {} of 5 for difficulty",
+        "Dificultad valorada en {} de 5",
+    ),
+    ("Rating cleared", "Valoración borrada"),
+    ("Reading the cards…", "Leyendo las tarjetas…"),
+    ("Recent days", "Días recientes"),
+    ("Reduce motion", "Reducir movimiento"),
+    ("Reloaded the deck", "Mazo recargado"),
+    ("Restore", "Restaurar"),
+    ("Restored a card to '{}'", "Tarjeta restaurada en '{}'"),
+    ("Restored topic '{}'", "Tema '{}' restaurado"),
+    ("Review order", "Orden de repaso"),
+    ("Reviews", "Repasos"),
+    ("Reviews per day", "Repasos por día"),
+    ("Ruby readings", "Lecturas ruby"),
+    ("SVG image", "imagen SVG"),
+    ("Save failed", "Falló el guardado"),
+    ("Saved {}", "{} guardado"),
+    ("Select a topic first", "Elige antes un tema"),
+    ("Settings of '{}'", "Ajustes de '{}'"),
+    ("Settings of '{}' saved", "Ajustes de '{}' guardados"),
+    ("Share '{}'", "Compartir '{}'"),
+    ("Showing archived topics", "Mostrando temas archivados"),
+    ("Snapshot failed: {}", "Falló la captura: {}"),
+    ("Sort {} to move cards", "Ordena por {} para mover tarjetas"),
+    ("Speech failed: {}", "Falló la voz: {}"),
+    (
+        "Spell checking is off: {}",
+        "La corrección ortográfica está desactivada: {}",
+    ),
+    ("Statistics", "Estadísticas"),
+    ("Still working", "Aún trabajando"),
+    ("Studied {} min, {}", "Estudiado {} min, {}"),
+    ("Study ahead", "Adelantar estudio"),
+    ("Study ahead days", "Días de estudio adelantado"),
+    ("Study sheet (typst)", "Hoja de estudio (typst)"),
+    ("Study timer started", "Temporizador de estudio en marcha"),
+    ("Sync failed: {}", "Falló la sincronización: {}"),
+    (
+        "Synced, but couldn't merge reviews: {}",
+        "Sincronizado, pero no se pudieron combinar los repasos: {}",
+    ),
+    ("Synced, {}", "Sincronizado, {}"),
+    ("Syncing", "Sincronizando"),
+    ("Take theirs", "Tomar la otra"),
+    ("Template of '{}' removed", "Plantilla de '{}' quitada"),
+    (
+        "The deck is open read-only",
+        "El mazo está abierto en solo lectura",
+    ),
+    (
+        "The deck that couldn't be read is kept in {}",
+        "El mazo que no se pudo leer se guarda en {}",
+    ),
+    (
+        "The import was for a deck that is no longer open",
+        "La importación era para un mazo que ya no está abierto",
+    ),
+    (
+        "The synced deck was closed, sync it again to take in the changes",
+        "El mazo sincronizado se cerró, vuelve a sincronizarlo para traer los cambios",
+    ),
+    ("The trash is empty", "La papelera está vacía"),
+    ("Theme", "Tema de color"),
+    (
+        "This card links to no other cards",
+        "Esta tarjeta no enlaza a otras",
+    ),
+    (
+        "Time for a {} minute break",
+        "Hora de un descanso de {} minutos",
+    ),
+    ("Time per topic", "Tiempo por tema"),
+    ("Timer at start", "Temporizador al empezar"),
+    ("To:", "A:"),
+    ("Topic '{}' created", "Tema '{}' creado"),
+    ("Topic {} ({})", "Tema {} ({})"),
+    ("Topics sorted by {}", "Temas ordenados por {}"),
+    ("Trash, kept {} days", "Papelera, se guarda {} días"),
+    ("Type the answer", "Escribe la respuesta"),
+    ("Typed {}: {}", "Escritas {}: {}"),
+    (
+        "Wait for the import to finish, or cancel it",
+        "Espera a que termine la importación, o cancélala",
+    ),
+    ("Web page failed: {}", "Falló la página web: {}"),
+    ("Writing snapshot", "Escribiendo captura"),
+    ("Writing web page", "Escribiendo página web"),
+    ("Wrote the card to {}", "Tarjeta escrita en {}"),
+    ("Wrote the deck to {}", "Mazo escrito en {}"),
+    (
+        "You have reviewed all of today's cards in {}. Study ahead, with the new cards of the next {} days?",
+        "Has repasado todas las tarjetas de hoy en {}. ¿Adelantar estudio con las tarjetas nuevas de los próximos {} días?",
+    ),
+    ("Your answer", "Tu respuesta"),
+    ("above", "encima"),
+    ("after {} months", "tras {} meses"),
+    ("archived", "archivado"),
+    ("auto", "automático"),
+    ("beside", "al lado"),
+    ("browse", "explorar"),
+    ("dark", "oscuro"),
+    ("decks", "mazos"),
+    ("due", "pendientes"),
+    ("from the locale", "según el sistema"),
+    ("generate cards", "generar tarjetas"),
+    ("hardest first", "las más difíciles primero"),
+    ("hidden", "oculto"),
+    ("home", "inicio"),
+    ("in order", "en orden"),
+    ("least recently seen first", "las menos vistas primero"),
+    ("light", "claro"),
+    ("lowest accuracy first", "menor acierto primero"),
+    ("most cards", "más tarjetas"),
+    ("most due", "más pendientes"),
+    ("name", "nombre"),
+    ("need at least {}x{}", "se necesita al menos {}x{}"),
+    ("never", "nunca"),
+    ("newest first", "las más nuevas primero"),
+    ("no", "no"),
+    ("no limit", "sin límite"),
+    ("none", "ninguno"),
+    ("oldest first", "las más antiguas primero"),
+    ("question", "pregunta"),
+    ("read-only", "solo lectura"),
+    ("recently created", "creados recientemente"),
+    ("recently studied", "estudiados recientemente"),
+    ("removed", "quitada"),
+    ("save failed", "falló el guardado"),
+    ("saved", "guardado"),
+    ("shuffled", "al azar"),
+    ("stats", "estadísticas"),
+    ("text", "texto"),
+    ("today", "hoy"),
+    ("topics", "temas"),
+    ("trash", "papelera"),
+    ("unsaved", "sin guardar"),
+    ("yes", "sí"),
+    ("{}  in {}", "{}  en {}"),
+    ("{} already exists", "{} ya existe"),
+    ("{} cards in {} topics", "{} tarjetas en {} temas"),
+    ("{} days in a row", "{} días seguidos"),
+    (
+        "{} due, {} new, {} total",
+        "{} pendientes, {} nuevas, {} en total",
+    ),
+    (
+        "{} is encrypted, open it with mem-flip --deck {}",
+        "{} está cifrado, ábrelo con mem-flip --deck {}",
+    ),
+    ("{} marked", "{} marcados"),
+    ("{} of {}", "{} de {}"),
+    ("{} tasks", "{} tareas"),
+    (
+        "{} tasks are still running. Quitting stops them and waits for them to finish.",
+        "Aún hay {} tareas en marcha. Salir las detiene y espera a que terminen.",
+    ),
+    (
+        "{} topics │ {} cards │ {} due",
+        "{} temas │ {} tarjetas │ {} pendientes",
+    ),
+    ("{} · card {}", "{} · tarjeta {}"),
+    ("{} · card {} of {}", "{} · tarjeta {} de {}"),
+    (
+        "{} · {}\nHere: {}\nThere: {}",
+        "{} · {}\nAquí: {}\nAllí: {}",
+    ),
+    ("{} · {} typed", "{} · {} escritas"),
+    ("{}d ago", "hace {}d"),
+    (
+        "{}\n\nUsing the default settings. Changed settings aren't saved until the file is fixed.",
+        "{}\n\nSe usan los ajustes predeterminados. Los cambios no se guardan hasta que se corrija el archivo.",
+    ),
+    (
+        "{}: {}\n\nStarting with an empty deck. The file is copied to {} before anything is saved over it.",
+        "{}: {}\n\nSe empieza con un mazo vacío. El archivo se copia a {} antes de guardar nada encima.",
+    ),
+    ("The name can't be empty", "El nombre no puede estar vacío"),
+    (
+        "The name can't hold tabs or line breaks",
+        "El nombre no puede tener tabuladores ni saltos de línea",
+    ),
+    ("There is already a topic '{}'", "Ya hay un tema '{}'"),
+];
+
+static GERMAN: &[(&str, &str)] = &[
+    ("Add Card", "Karte hinzufügen"),
+    ("Archive", "Archivieren"),
+    ("As template", "Als Vorlage"),
+    ("Attach", "Anhängen"),
+    ("Back", "Zurück"),
+    ("Browse", "Durchsehen"),
+    ("Bundle", "Paket"),
+    ("Cancel", "Abbrechen"),
+    ("Change", "Ändern"),
+    ("Check", "Prüfen"),
+    ("Continue", "Weiterlernen"),
+    ("Decks", "Stapel"),
+    ("Delete", "Löschen"),
+    ("Dismiss", "Schließen"),
+    ("Duplicate", "Duplizieren"),
+    ("Enter topic name:", "Name des Themas:"),
+    ("Export", "Exportieren"),
+    ("Export CSV", "CSV exportieren"),
+    ("Fetch", "Herunterladen"),
+    ("Fix spelling", "Korrigieren"),
+    ("Flip", "Umdrehen"),
+    ("Follow link", "Link folgen"),
+    ("Furigana", "Furigana"),
+    ("Generate", "Erzeugen"),
+    (
+        "Generating cards… Cancel",
+        "Karten werden erzeugt… Abbrechen",
+    ),
+    ("Go", "Los"),
+    ("Home", "Start"),
+    ("Import", "Importieren"),
+    ("Info", "Info"),
+    ("Jump", "Springen"),
+    ("Jump to card :", "Zu Karte springen :"),
+    ("Keep", "Behalten"),
+    ("Knew it", "Gewusst"),
+    ("Link back", "Zurück vom Link"),
+    ("Mark", "Markieren"),
+    ("Mark to mix", "Zum Mischen markieren"),
+    ("Missed it", "Nicht gewusst"),
+    ("Mix in turn", "Abwechselnd mischen"),
+    ("Mix shuffled", "Zufällig mischen"),
+    ("Move", "Verschieben"),
+    ("Navigate", "Navigieren"),
+    ("New Deck", "Neuer Stapel"),
+    ("New Topic", "Neues Thema"),
+    ("Next", "Weiter"),
+    ("No cards available", "Keine Karten vorhanden"),
+    ("Open", "Öffnen"),
+    ("Open File", "Datei öffnen"),
+    ("Open attachments", "Anhänge öffnen"),
+    (
+        "Press Enter to create | Esc to cancel",
+        "Enter zum Anlegen | Esc zum Abbrechen",
+    ),
+    (
+        "[Press Space to reveal answer]",
+        "[Leertaste zeigt die Antwort]",
+    ),
+    ("Previous", "Zurück"),
+    ("Quit", "Beenden"),
+    ("Quit without saving", "Beenden ohne Speichern"),
+    ("Rate difficulty", "Schwierigkeit bewerten"),
+    ("Recently added", "Kürzlich hinzugefügt"),
+    ("Review", "Lernen"),
+    ("Save", "Speichern"),
+    ("Save & next", "Speichern & weiter"),
+    ("Scroll", "Blättern"),
+    ("Select", "Auswählen"),
+    ("Settings", "Einstellungen"),
+    ("Share", "Teilen"),
+    ("Show archived", "Archiv zeigen"),
+    ("Snapshot", "Schnappschuss"),
+    ("Skip", "Überspringen"),
+    ("Sort", "Sortieren"),
+    ("Speak", "Vorlesen"),
+    ("Spelling:", "Rechtschreibung:"),
+    ("Stats", "Statistik"),
+    ("Stop", "Anhalten"),
+    ("Switch field", "Feld wechseln"),
+    ("Sync", "Synchronisieren"),
+    ("Terminal too small", "Terminal zu klein"),
+    ("Topics", "Themen"),
+    ("Type answer", "Antwort tippen"),
+    ("Typing practice", "Tippübung"),
+    ("Timer", "Timer"),
+    ("Pause", "Pause"),
+    (
+        "Paused, press any key to go on",
+        "Pausiert, beliebige Taste zum Fortfahren",
+    ),
+    ("Web page", "Webseite"),
+    ("Your answer:", "Deine Antwort:"),
+    ("1 day in a row", "1 Tag in Folge"),
+    ("ANSI text", "ANSI-Text"),
+    ("About", "Über"),
+    ("Accuracy", "Trefferquote"),
+    ("Add Card to '{}' topic", "Karte zum Thema '{}' hinzufügen"),
+    (
+        "Added {} of {} cards to '{}'",
+        "{} von {} Karten zu '{}' hinzugefügt",
+    ),
+    ("An import is already running", "Es läuft schon ein Import"),
+    ("Answer", "Antwort"),
+    ("Answer first", "Antwort zuerst"),
+    ("Archived '{}'", "'{}' archiviert"),
+    ("Attached {}", "{} angehängt"),
+    ("Attachments", "Anhänge"),
+    ("Break minutes", "Pausenminuten"),
+    (
+        "Break's over, back to the cards",
+        "Pause vorbei, zurück zu den Karten",
+    ),
+    (
+        "Brought '{}' back from the archive",
+        "'{}' aus dem Archiv geholt",
+    ),
+    ("Bundle failed: {}", "Paket fehlgeschlagen: {}"),
+    ("Bundled {} images into {}", "{} Bilder in {} gepackt"),
+    (
+        "Bundled, but {} images weren't found, such as {}",
+        "Gepackt, aber {} Bilder wurden nicht gefunden, etwa {}",
+    ),
+    ("Bundling deck", "Stapel wird gepackt"),
+    (
+        "Can't generate cards: {}",
+        "Karten können nicht erstellt werden: {}",
+    ),
+    ("Can't sync: {}", "Synchronisieren nicht möglich: {}"),
+    ("Card history", "Kartenverlauf"),
+    ("Card saved to '{}'", "Karte in '{}' gespeichert"),
+    ("Card snapshots", "Kartenbilder"),
+    (
+        "Card updated, the old wording is in its history",
+        "Karte geändert, die alte Fassung steht im Verlauf",
+    ),
+    ("Card {} of {} for '{}'", "Karte {} von {} für '{}'"),
+    ("Cards", "Karten"),
+    (
+        "Change the copy before saving it",
+        "Ändere die Kopie vor dem Speichern",
+    ),
+    (
+        "Changed elsewhere too ({} left)",
+        "Auch anderswo geändert (noch {})",
+    ),
+    ("Color", "Farbe"),
+    ("Commit failed: {}", "Commit fehlgeschlagen: {}"),
+    ("Commit to git", "In git committen"),
+    ("Compact history", "Verlauf verdichten"),
+    ("Couldn't attach: {}", "Anhängen nicht möglich: {}"),
+    (
+        "Couldn't create {}: {}",
+        "{} konnte nicht erstellt werden: {}",
+    ),
+    ("Couldn't load cards", "Karten konnten nicht geladen werden"),
+    (
+        "Couldn't load config",
+        "Einstellungen konnten nicht geladen werden",
+    ),
+    (
+        "Couldn't open {}: {}",
+        "{} konnte nicht geöffnet werden: {}",
+    ),
+    ("Couldn't open: {}", "Öffnen nicht möglich: {}"),
+    (
+        "Couldn't record review: {}",
+        "Wiederholung konnte nicht gespeichert werden: {}",
+    ),
+    (
+        "Couldn't reload the deck: {}",
+        "Stapel konnte nicht neu geladen werden: {}",
+    ),
+    (
+        "Couldn't save the settings: {}",
+        "Einstellungen konnten nicht gespeichert werden: {}",
+    ),
+    ("Couldn't share: {}", "Teilen nicht möglich: {}"),
+    (
+        "Couldn't unpack bundle: {}",
+        "Paket konnte nicht entpackt werden: {}",
+    ),
+    (
+        "Couldn't write {}: {}",
+        "{} konnte nicht geschrieben werden: {}",
+    ),
+    (
+        "Create a topic to add cards to first",
+        "Lege zuerst ein Thema für die Karten an",
+    ),
+    ("Days in trash", "Tage im Papierkorb"),
+    ("Debug", "Debug"),
+    ("Deck URL", "URL des Stapels"),
+    ("Download failed: {}", "Download fehlgeschlagen: {}"),
+    ("Downloading deck", "Stapel wird heruntergeladen"),
+    (
+        "Earlier wording restored",
+        "Frühere Fassung wiederhergestellt",
+    ),
+    ("Edit", "Bearbeiten"),
+    ("Edit Card in '{}' topic", "Karte im Thema '{}' bearbeiten"),
+    (
+        "Every topic is archived.\n\nPress 'Shift + Z' to show them.",
+        "Alle Themen sind archiviert.\n\nMit 'Shift + Z' werden sie angezeigt.",
+    ),
+    ("Exam date", "Prüfungsdatum"),
+    ("Export '{}'", "'{}' exportieren"),
+    ("Export failed: {}", "Export fehlgeschlagen: {}"),
+    (
+        "Exported {} reviews to {}",
+        "{} Wiederholungen nach {} exportiert",
+    ),
+    ("Exported {} to {}", "{} nach {} exportiert"),
+    ("Exporting stats", "Statistik wird exportiert"),
+    ("Exporting topic", "Thema wird exportiert"),
+    ("File or link to attach", "Datei oder Link zum Anhängen"),
+    ("Fill in the template first", "Fülle zuerst die Vorlage aus"),
+    ("Focus minutes", "Fokusminuten"),
+    ("Format", "Format"),
+    ("Generate Cards for '{}'", "Karten für '{}' erstellen"),
+    ("Generating cards", "Karten werden erstellt"),
+    (
+        "Generating cards failed: {}",
+        "Erstellen der Karten fehlgeschlagen: {}",
+    ),
+    ("Hardest cards", "Schwierigste Karten"),
+    ("Hiding archived topics", "Archivierte Themen ausgeblendet"),
+    ("History", "Verlauf"),
+    ("Icon", "Symbol"),
+    (
+        "Import cancelled, the deck is unchanged",
+        "Import abgebrochen, der Stapel ist unverändert",
+    ),
+    ("Import failed: {}", "Import fehlgeschlagen: {}"),
+    (
+        "Import org, Quizlet, Mochi or RemNote file, or notes folder",
+        "org-, Quizlet-, Mochi- oder RemNote-Datei oder Notizordner importieren",
+    ),
+    (
+        "Imported {}, updated {} and skipped {} already there",
+        "{} importiert, {} aktualisiert und {} vorhandene übersprungen",
+    ),
+    ("Importing", "Import läuft"),
+    ("Keep mine", "Meine behalten"),
+    ("Keep working", "Weiterarbeiten"),
+    ("Language", "Sprache"),
+    ("Last studied", "Zuletzt gelernt"),
+    ("Learning", "In Arbeit"),
+    ("Loading cards", "Karten werden geladen"),
+    ("Longest answer", "Längste Antwort"),
+    (
+        "Mark two or more topics with Space to review them together",
+        "Markiere zwei oder mehr Themen mit Leertaste, um sie zusammen zu lernen",
+    ),
+    ("Mature", "Gefestigt"),
+    ("Memory Flip Flashcards", "Memory-Flip-Karteikarten"),
+    (
+        "Merged in changes made elsewhere",
+        "Änderungen von anderswo übernommen",
+    ),
+    (
+        "Moved '{}' to the trash",
+        "'{}' in den Papierkorb verschoben",
+    ),
+    ("New", "Neu"),
+    ("New cards a day", "Neue Karten pro Tag"),
+    (
+        "New cards in '{}' start from this template",
+        "Neue Karten in '{}' beginnen mit dieser Vorlage",
+    ),
+    ("New deck file", "Neue Stapeldatei"),
+    (
+        "No answers to type in {}",
+        "Keine Antworten zum Tippen in {}",
+    ),
+    ("No card for link [[{}]]", "Keine Karte für den Link [[{}]]"),
+    ("No card {}, pick 1 to {}", "Keine Karte {}, wähle 1 bis {}"),
+    (
+        "No cards added in the last {} days",
+        "Keine Karten in den letzten {} Tagen hinzugefügt",
+    ),
+    (
+        "No cards came back, try more text",
+        "Keine Karten erhalten, versuche mehr Text",
+    ),
+    ("No cards found in {}", "Keine Karten in {} gefunden"),
+    (
+        "No cards to review in the marked topics",
+        "Keine Karten zu lernen in den markierten Themen",
+    ),
+    (
+        "No config directory to save settings to",
+        "Kein Konfigurationsordner zum Speichern der Einstellungen",
+    ),
+    ("No deck at {}", "Kein Stapel unter {}"),
+    (
+        "No more new cards in '{}' today",
+        "Heute keine neuen Karten mehr in '{}'",
+    ),
+    (
+        "No reviews yet.\n\nGrade cards with Y or M after flipping them in review.",
+        "Noch keine Wiederholungen.\n\nBewerte Karten mit Y oder M, nachdem du sie umgedreht hast.",
+    ),
+    (
+        "No streak yet, review today to start one",
+        "Noch keine Serie, lerne heute, um eine zu beginnen",
+    ),
+    (
+        "No topics yet!\n\nPress 'N' to create your first topic.",
+        "Noch keine Themen!\n\nDrücke 'N', um dein erstes Thema anzulegen.",
+    ),
+    ("Not now", "Nicht jetzt"),
+    (
+        "Not saved, the config file couldn't be read",
+        "Nicht gespeichert, die Konfigurationsdatei war nicht lesbar",
+    ),
+    ("Nothing due", "Nichts fällig"),
+    (
+        "Nothing is attached to this card",
+        "An dieser Karte hängt nichts",
+    ),
+    ("Now:", "Jetzt:"),
+    ("Open deck file", "Stapeldatei öffnen"),
+    ("Opened {}", "{} geöffnet"),
+    (
+        "Paste notes to make cards from, or describe a subject:",
+        "Füge Notizen für Karten ein oder beschreibe ein Thema:",
+    ),
+    ("Preview", "Vorschau"),
+    ("Printable cards (typst)", "Druckbare Karten (typst)"),
+    ("Question", "Frage"),
+    (
+        "Rated {} of 5 for difficulty",
+        "Schwierigkeit mit {} von 5 bewertet",
+    ),
+    ("Rating cleared", "Bewertung entfernt"),
+    ("Reading the cards…", "Karten werden gelesen…"),
+    ("Recent days", "Letzte Tage"),
+    ("Reduce motion", "Weniger Bewegung"),
+    ("Reloaded the deck", "Stapel neu geladen"),
+    ("Restore", "Wiederherstellen"),
+    ("Restored a card to '{}'", "Karte in '{}' wiederhergestellt"),
+    ("Restored topic '{}'", "Thema '{}' wiederhergestellt"),
+    ("Review order", "Lernreihenfolge"),
+    ("Reviews", "Wiederholungen"),
+    ("Reviews per day", "Wiederholungen pro Tag"),
+    ("Ruby readings", "Ruby-Lesungen"),
+    ("SVG image", "SVG-Bild"),
+    ("Save failed", "Speichern fehlgeschlagen"),
+    ("Saved {}", "{} gespeichert"),
+    ("Select a topic first", "Wähle zuerst ein Thema"),
+    ("Settings of '{}'", "Einstellungen von '{}'"),
+    (
+        "Settings of '{}' saved",
+        "Einstellungen von '{}' gespeichert",
+    ),
+    ("Share '{}'", "'{}' teilen"),
+    ("Showing archived topics", "Archivierte Themen eingeblendet"),
+    ("Snapshot failed: {}", "Kartenbild fehlgeschlagen: {}"),
+    (
+        "Sort {} to move cards",
+        "Sortiere nach {}, um Karten zu verschieben",
+    ),
+    ("Speech failed: {}", "Sprachausgabe fehlgeschlagen: {}"),
+    (
+        "Spell checking is off: {}",
+        "Rechtschreibprüfung ist aus: {}",
+    ),
+    ("Statistics", "Statistik"),
+    ("Still working", "Noch beschäftigt"),
+    ("Studied {} min, {}", "{} Min. gelernt, {}"),
+    ("Study ahead", "Vorauslernen"),
+    ("Study ahead days", "Tage vorauslernen"),
+    ("Study sheet (typst)", "Lernblatt (typst)"),
+    ("Study timer started", "Lerntimer gestartet"),
+    ("Sync failed: {}", "Synchronisieren fehlgeschlagen: {}"),
+    (
+        "Synced, but couldn't merge reviews: {}",
+        "Synchronisiert, aber Wiederholungen nicht zusammengeführt: {}",
+    ),
+    ("Synced, {}", "Synchronisiert, {}"),
+    ("Syncing", "Synchronisiere"),
+    ("Take theirs", "Andere nehmen"),
+    ("Template of '{}' removed", "Vorlage von '{}' entfernt"),
+    (
+        "The deck is open read-only",
+        "Der Stapel ist schreibgeschützt geöffnet",
+    ),
+    (
+        "The deck that couldn't be read is kept in {}",
+        "Der unlesbare Stapel liegt in {}",
+    ),
+    (
+        "The import was for a deck that is no longer open",
+        "Der Import war für einen Stapel, der nicht mehr offen ist",
+    ),
+    (
+        "The synced deck was closed, sync it again to take in the changes",
+        "Der synchronisierte Stapel wurde geschlossen, synchronisiere erneut für die Änderungen",
+    ),
+    ("The trash is empty", "Der Papierkorb ist leer"),
+    ("Theme", "Farbschema"),
+    (
+        "This card links to no other cards",
+        "Diese Karte verlinkt keine anderen",
+    ),
+    ("Time for a {} minute break", "Zeit für {} Minuten Pause"),
+    ("Time per topic", "Zeit pro Thema"),
+    ("Timer at start", "Timer beim Start"),
+    ("To:", "Nach:"),
+    ("Topic '{}' created", "Thema '{}' angelegt"),
+    ("Topic {} ({})", "Thema {} ({})"),
+    ("Topics sorted by {}", "Themen sortiert nach {}"),
+    ("Trash, kept {} days", "Papierkorb, {} Tage aufbewahrt"),
+    ("Type the answer", "Tippe die Antwort"),
+    ("Typed {}: {}", "{} getippt: {}"),
+    (
+        "Wait for the import to finish, or cancel it",
+        "Warte, bis der Import fertig ist, oder brich ihn ab",
+    ),
+    ("Web page failed: {}", "Webseite fehlgeschlagen: {}"),
+    ("Writing snapshot", "Kartenbild wird geschrieben"),
+    ("Writing web page", "Webseite wird geschrieben"),
+    ("Wrote the card to {}", "Karte nach {} geschrieben"),
+    ("Wrote the deck to {}", "Stapel nach {} geschrieben"),
+    (
+        "You have reviewed all of today's cards in {}. Study ahead, with the new cards of the next {} days?",
+        "Du hast alle heutigen Karten in {} gelernt. Vorauslernen, mit den neuen Karten der nächsten {} Tage?",
+    ),
+    ("Your answer", "Deine Antwort"),
+    ("above", "darüber"),
+    ("after {} months", "nach {} Monaten"),
+    ("archived", "archiviert"),
+    ("auto", "automatisch"),
+    ("beside", "daneben"),
+    ("browse", "durchsuchen"),
+    ("dark", "dunkel"),
+    ("decks", "Stapel"),
+    ("due", "fällig"),
+    ("from the locale", "aus dem System"),
+    ("generate cards", "Karten erstellen"),
+    ("hardest first", "schwierigste zuerst"),
+    ("hidden", "ausgeblendet"),
+    ("home", "Start"),
+    ("in order", "der Reihe nach"),
+    (
+        "least recently seen first",
+        "am längsten nicht gesehen zuerst",
+    ),
+    ("light", "hell"),
+    ("lowest accuracy first", "niedrigste Trefferquote zuerst"),
+    ("most cards", "meiste Karten"),
+    ("most due", "meiste fällig"),
+    ("name", "Name"),
+    ("need at least {}x{}", "mindestens {}x{} nötig"),
+    ("never", "nie"),
+    ("newest first", "neueste zuerst"),
+    ("no", "nein"),
+    ("no limit", "keine Grenze"),
+    ("none", "keins"),
+    ("oldest first", "älteste zuerst"),
+    ("question", "Frage"),
+    ("read-only", "schreibgeschützt"),
+    ("recently created", "zuletzt angelegt"),
+    ("recently studied", "zuletzt gelernt"),
+    ("removed", "entfernt"),
+    ("save failed", "Speichern fehlgeschlagen"),
+    ("saved", "gespeichert"),
+    ("shuffled", "gemischt"),
+    ("stats", "Statistik"),
+    ("text", "Text"),
+    ("today", "heute"),
+    ("topics", "Themen"),
+    ("trash", "Papierkorb"),
+    ("unsaved", "ungespeichert"),
+    ("yes", "ja"),
+    ("{}  in {}", "{}  in {}"),
+    ("{} already exists", "{} existiert bereits"),
+    ("{} cards in {} topics", "{} Karten in {} Themen"),
+    ("{} days in a row", "{} Tage in Folge"),
+    ("{} due, {} new, {} total", "{} fällig, {} neu, {} gesamt"),
+    (
+        "{} is encrypted, open it with mem-flip --deck {}",
+        "{} ist verschlüsselt, öffne ihn mit mem-flip --deck {}",
+    ),
+    ("{} marked", "{} markiert"),
+    ("{} of {}", "{} von {}"),
+    ("{} tasks", "{} Aufgaben"),
+    (
+        "{} tasks are still running. Quitting stops them and waits for them to finish.",
+        "Es laufen noch {} Aufgaben. Beim Beenden werden sie gestoppt und abgewartet.",
+    ),
+    (
+        "{} topics │ {} cards │ {} due",
+        "{} Themen │ {} Karten │ {} fällig",
+    ),
+    ("{} · card {}", "{} · Karte {}"),
+    ("{} · card {} of {}", "{} · Karte {} von {}"),
+    (
+        "{} · {}\nHere: {}\nThere: {}",
+        "{} · {}\nHier: {}\nDort: {}",
+    ),
+    ("{} · {} typed", "{} · {} getippt"),
+    ("{}d ago", "vor {} T."),
+    (
+        "{}\n\nUsing the default settings. Changed settings aren't saved until the file is fixed.",
+        "{}\n\nEs gelten die Standardeinstellungen. Änderungen werden erst gespeichert, wenn die Datei repariert ist.",
+    ),
+    (
+        "{}: {}\n\nStarting with an empty deck. The file is copied to {} before anything is saved over it.",
+        "{}: {}\n\nEs geht mit einem leeren Stapel los. Die Datei wird nach {} kopiert, bevor etwas darüber gespeichert wird.",
+    ),
+    ("The name can't be empty", "Der Name darf nicht leer sein"),
+    (
+        "The name can't hold tabs or line breaks",
+        "Der Name darf keine Tabs oder Zeilenumbrüche enthalten",
+    ),
+    (
+        "There is already a topic '{}'",
+        "Es gibt schon ein Thema '{}'",
+    ),
+];
+
+static FRENCH: &[(&str, &str)] = &[
+    ("Add Card", "Ajouter une carte"),
+    ("Archive", "Archiver"),
+    ("As template", "Comme modèle"),
+    ("Attach", "Joindre"),
+    ("Back", "Retour"),
+    ("Browse", "Parcourir"),
+    ("Bundle", "Paquet"),
+    ("Cancel", "Annuler"),
+    ("Change", "Modifier"),
+    ("Check", "Vérifier"),
+    ("Continue", "Continuer"),
+    ("Decks", "Paquets"),
+    ("Delete", "Supprimer"),
+    ("Dismiss", "Fermer"),
+    ("Duplicate", "Dupliquer"),
+    ("Enter topic name:", "Nom du thème :"),
+    ("Export", "Exporter"),
+    ("Export CSV", "Exporter en CSV"),
+    ("Fetch", "Télécharger"),
+    ("Fix spelling", "Corriger"),
+    ("Flip", "Retourner"),
+    ("Follow link", "Suivre le lien"),
+    ("Furigana", "Furigana"),
+    ("Generate", "Générer"),
+    ("Generating cards… Cancel", "Génération des cartes… Annuler"),
+    ("Go", "Aller"),
+    ("Home", "Accueil"),
+    ("Import", "Importer"),
+    ("Info", "Infos"),
+    ("Jump", "Aller à"),
+    ("Jump to card :", "Aller à la carte :"),
+    ("Keep", "Garder"),
+    ("Knew it", "Je savais"),
+    ("Link back", "Revenir du lien"),
+    ("Mark", "Marquer"),
+    ("Mark to mix", "Marquer pour mélanger"),
+    ("Missed it", "Raté"),
+    ("Mix in turn", "Mélanger à tour de rôle"),
+    ("Mix shuffled", "Mélanger au hasard"),
+    ("Move", "Déplacer"),
+    ("Navigate", "Naviguer"),
+    ("New Deck", "Nouveau paquet"),
+    ("New Topic", "Nouveau thème"),
+    ("Next", "Suivante"),
+    ("No cards available", "Aucune carte"),
+    ("Open", "Ouvrir"),
+    ("Open File", "Ouvrir un fichier"),
+    ("Open attachments", "Ouvrir les pièces jointes"),
+    (
+        "Press Enter to create | Esc to cancel",
+        "Entrée pour créer | Échap pour annuler",
+    ),
+    (
+        "[Press Space to reveal answer]",
+        "[Espace pour voir la réponse]",
+    ),
+    ("Previous", "Précédente"),
+    ("Quit", "Quitter"),
+    ("Quit without saving", "Quitter sans enregistrer"),
+    ("Rate difficulty", "Noter la difficulté"),
+    ("Recently added", "Ajoutées récemment"),
+    ("Review", "Réviser"),
+    ("Save", "Enregistrer"),
+    ("Save & next", "Enregistrer et continuer"),
+    ("Scroll", "Défiler"),
+    ("Select", "Choisir"),
+    ("Settings", "Réglages"),
+    ("Share", "Partager"),
+    ("Show archived", "Voir les archivés"),
+    ("Snapshot", "Instantané"),
+    ("Skip", "Passer"),
+    ("Sort", "Trier"),
+    ("Speak", "Lire à voix haute"),
+    ("Spelling:", "Orthographe :"),
+    ("Stats", "Statistiques"),
+    ("Stop", "Arrêter"),
+    ("Switch field", "Changer de champ"),
+    ("Sync", "Synchroniser"),
+    ("Terminal too small", "Terminal trop petit"),
+    ("Topics", "Thèmes"),
+    ("Type answer", "Taper la réponse"),
+    ("Typing practice", "Entraînement à la frappe"),
+    ("Timer", "Minuteur"),
+    ("Pause", "Pause"),
+    (
+        "Paused, press any key to go on",
+        "En pause, appuyez sur une touche pour continuer",
+    ),
+    ("Web page", "Page web"),
+    ("Your answer:", "Ta réponse :"),
+    ("1 day in a row", "1 jour d'affilée"),
+    ("ANSI text", "texte ANSI"),
+    ("About", "À propos"),
+    ("Accuracy", "Réussite"),
+    ("Add Card to '{}' topic", "Ajouter une carte au thème '{}'"),
+    (
+        "Added {} of {} cards to '{}'",
+        "{} cartes sur {} ajoutées à '{}'",
+    ),
+    (
+        "An import is already running",
+        "Une importation est déjà en cours",
+    ),
+    ("Answer", "Réponse"),
+    ("Answer first", "Réponse d'abord"),
+    ("Archived '{}'", "'{}' archivé"),
+    ("Attached {}", "{} joint"),
+    ("Attachments", "Pièces jointes"),
+    ("Break minutes", "Minutes de pause"),
+    (
+        "Break's over, back to the cards",
+        "Fin de la pause, retour aux cartes",
+    ),
+    (
+        "Brought '{}' back from the archive",
+        "'{}' sorti des archives",
+    ),
+    ("Bundle failed: {}", "Échec du paquet : {}"),
+    ("Bundled {} images into {}", "{} images empaquetées dans {}"),
+    (
+        "Bundled, but {} images weren't found, such as {}",
+        "Empaqueté, mais {} images sont introuvables, comme {}",
+    ),
+    ("Bundling deck", "Empaquetage du paquet"),
+    (
+        "Can't generate cards: {}",
+        "Impossible de générer des cartes : {}",
+    ),
+    ("Can't sync: {}", "Synchronisation impossible : {}"),
+    ("Card history", "Historique de la carte"),
+    ("Card saved to '{}'", "Carte enregistrée dans '{}'"),
+    ("Card snapshots", "Captures de cartes"),
+    (
+        "Card updated, the old wording is in its history",
+        "Carte modifiée, l'ancienne formulation est dans son historique",
+    ),
+    ("Card {} of {} for '{}'", "Carte {} sur {} pour '{}'"),
+    ("Cards", "Cartes"),
+    (
+        "Change the copy before saving it",
+        "Modifiez la copie avant de l'enregistrer",
+    ),
+    (
+        "Changed elsewhere too ({} left)",
+        "Modifiée ailleurs aussi ({} restantes)",
+    ),
+    ("Color", "Couleur"),
+    ("Commit failed: {}", "Échec du commit : {}"),
+    ("Commit to git", "Commit dans git"),
+    ("Compact history", "Compacter l'historique"),
+    ("Couldn't attach: {}", "Impossible de joindre : {}"),
+    ("Couldn't create {}: {}", "Impossible de créer {} : {}"),
+    ("Couldn't load cards", "Impossible de charger les cartes"),
+    (
+        "Couldn't load config",
+        "Impossible de charger la configuration",
+    ),
+    ("Couldn't open {}: {}", "Impossible d'ouvrir {} : {}"),
+    ("Couldn't open: {}", "Impossible d'ouvrir : {}"),
+    (
+        "Couldn't record review: {}",
+        "Impossible d'enregistrer la révision : {}",
+    ),
+    (
+        "Couldn't reload the deck: {}",
+        "Impossible de recharger le paquet : {}",
+    ),
+    (
+        "Couldn't save the settings: {}",
+        "Impossible d'enregistrer les réglages : {}",
+    ),
+    ("Couldn't share: {}", "Impossible de partager : {}"),
+    (
+        "Couldn't unpack bundle: {}",
+        "Impossible de dépaqueter : {}",
+    ),
+    ("Couldn't write {}: {}", "Impossible d'écrire {} : {}"),
+    (
+        "Create a topic to add cards to first",
+        "Créez d'abord un thème où ajouter des cartes",
+    ),
+    ("Days in trash", "Jours dans la corbeille"),
+    ("Debug", "Débogage"),
+    ("Deck URL", "URL du paquet"),
+    ("Download failed: {}", "Échec du téléchargement : {}"),
+    ("Downloading deck", "Téléchargement du paquet"),
+    ("Earlier wording restored", "Ancienne formulation restaurée"),
+    ("Edit", "Modifier"),
+    (
+        "Edit Card in '{}' topic",
+        "Modifier une carte du thème '{}'",
+    ),
+    (
+        "Every topic is archived.\n\nPress 'Shift + Z' to show them.",
+        "Tous les thèmes sont archivés.\n\nAppuyez sur 'Shift + Z' pour les voir.",
+    ),
+    ("Exam date", "Date d'examen"),
+    ("Export '{}'", "Exporter '{}'"),
+    ("Export failed: {}", "Échec de l'export : {}"),
+    (
+        "Exported {} reviews to {}",
+        "{} révisions exportées vers {}",
+    ),
+    ("Exported {} to {}", "{} exportées vers {}"),
+    ("Exporting stats", "Export des statistiques"),
+    ("Exporting topic", "Export du thème"),
+    ("File or link to attach", "Fichier ou lien à joindre"),
+    ("Fill in the template first", "Remplissez d'abord le modèle"),
+    ("Focus minutes", "Minutes de concentration"),
+    ("Format", "Format"),
+    ("Generate Cards for '{}'", "Générer des cartes pour '{}'"),
+    ("Generating cards", "Génération des cartes"),
+    (
+        "Generating cards failed: {}",
+        "Échec de la génération des cartes : {}",
+    ),
+    ("Hardest cards", "Cartes les plus difficiles"),
+    ("Hiding archived topics", "Thèmes archivés masqués"),
+    ("History", "Historique"),
+    ("Icon", "Icône"),
+    (
+        "Import cancelled, the deck is unchanged",
+        "Importation annulée, le paquet est inchangé",
+    ),
+    ("Import failed: {}", "Échec de l'importation : {}"),
+    (
+        "Import org, Quizlet, Mochi or RemNote file, or notes folder",
+        "Importer un fichier org, Quizlet, Mochi ou RemNote, ou un dossier de notes",
+    ),
+    (
+        "Imported {}, updated {} and skipped {} already there",
+        "{} importées, {} mises à jour et {} déjà présentes ignorées",
+    ),
+    ("Importing", "Importation"),
+    ("Keep mine", "Garder la mienne"),
+    ("Keep working", "Continuer"),
+    ("Language", "Langue"),
+    ("Last studied", "Dernière étude"),
+    ("Learning", "En apprentissage"),
+    ("Loading cards", "Chargement des cartes"),
+    ("Longest answer", "Réponse la plus longue"),
+    (
+        "Mark two or more topics with Space to review them together",
+        "Marquez deux thèmes ou plus avec Espace pour les réviser ensemble",
+    ),
+    ("Mature", "Acquises"),
+    ("Memory Flip Flashcards", "Cartes Memory Flip"),
+    (
+        "Merged in changes made elsewhere",
+        "Modifications faites ailleurs intégrées",
+    ),
+    ("Moved '{}' to the trash", "'{}' mis à la corbeille"),
+    ("New", "Nouvelles"),
+    ("New cards a day", "Nouvelles cartes par jour"),
+    (
+        "New cards in '{}' start from this template",
+        "Les nouvelles cartes de '{}' partent de ce modèle",
+    ),
+    ("New deck file", "Nouveau fichier de paquet"),
+    ("No answers to type in {}", "Aucune réponse à taper dans {}"),
+    (
+        "No card for link [[{}]]",
+        "Aucune carte pour le lien [[{}]]",
+    ),
+    (
+        "No card {}, pick 1 to {}",
+        "Pas de carte {}, choisissez de 1 à {}",
+    ),
+    (
+        "No cards added in the last {} days",
+        "Aucune carte ajoutée ces {} derniers jours",
+    ),
+    (
+        "No cards came back, try more text",
+        "Aucune carte reçue, essayez avec plus de texte",
+    ),
+    ("No cards found in {}", "Aucune carte trouvée dans {}"),
+    (
+        "No cards to review in the marked topics",
+        "Aucune carte à réviser dans les thèmes marqués",
+    ),
+    (
+        "No config directory to save settings to",
+        "Aucun dossier de configuration où enregistrer les réglages",
+    ),
+    ("No deck at {}", "Aucun paquet à {}"),
+    (
+        "No more new cards in '{}' today",
+        "Plus de nouvelles cartes dans '{}' aujourd'hui",
+    ),
+    (
+        "No reviews yet.\n\nGrade cards with Y or M after flipping them in review.",
+        "Pas encore de révisions.\n\nNotez les cartes avec Y ou M après les avoir retournées.",
+    ),
+    (
+        "No streak yet, review today to start one",
+        "Pas encore de série, révisez aujourd'hui pour en commencer une",
+    ),
+    (
+        "No topics yet!\n\nPress 'N' to create your first topic.",
+        "Pas encore de thèmes !\n\nAppuyez sur 'N' pour créer votre premier thème.",
+    ),
+    ("Not now", "Pas maintenant"),
+    (
+        "Not saved, the config file couldn't be read",
+        "Non enregistré, le fichier de configuration est illisible",
+    ),
+    ("Nothing due", "Rien à réviser"),
+    (
+        "Nothing is attached to this card",
+        "Rien n'est joint à cette carte",
+    ),
+    ("Now:", "Maintenant :"),
+    ("Open deck file", "Ouvrir un fichier de paquet"),
+    ("Opened {}", "{} ouvert"),
+    (
+        "Paste notes to make cards from, or describe a subject:",
+        "Collez des notes à transformer en cartes, ou décrivez un sujet :",
+    ),
+    ("Preview", "Aperçu"),
+    ("Printable cards (typst)", "Cartes à imprimer (typst)"),
+    ("Question", "Question"),
+    ("Rated {} of 5 for difficulty", "Difficulté notée {} sur 5"),
+    ("Rating cleared", "Note effacée"),
+    ("Reading the cards…", "Lecture des cartes…"),
+    ("Recent days", "Jours récents"),
+    ("Reduce motion", "Réduire les animations"),
+    ("Reloaded the deck", "Paquet rechargé"),
+    ("Restore", "Restaurer"),
+    ("Restored a card to '{}'", "Carte restaurée dans '{}'"),
+    ("Restored topic '{}'", "Thème '{}' restauré"),
+    ("Review order", "Ordre de révision"),
+    ("Reviews", "Révisions"),
+    ("Reviews per day", "Révisions par jour"),
+    ("Ruby readings", "Lectures ruby"),
+    ("SVG image", "image SVG"),
+    ("Save failed", "Échec de l'enregistrement"),
+    ("Saved {}", "{} enregistré"),
+    ("Select a topic first", "Choisissez d'abord un thème"),
+    ("Settings of '{}'", "Réglages de '{}'"),
+    ("Settings of '{}' saved", "Réglages de '{}' enregistrés"),
+    ("Share '{}'", "Partager '{}'"),
+    ("Showing archived topics", "Thèmes archivés affichés"),
+    ("Snapshot failed: {}", "Échec de la capture : {}"),
+    (
+        "Sort {} to move cards",
+        "Triez par {} pour déplacer des cartes",
+    ),
+    ("Speech failed: {}", "Échec de la lecture : {}"),
+    (
+        "Spell checking is off: {}",
+        "La correction orthographique est désactivée : {}",
+    ),
+    ("Statistics", "Statistiques"),
+    ("Still working", "Encore en cours"),
+    ("Studied {} min, {}", "Étudié {} min, {}"),
+    ("Study ahead", "Étudier en avance"),
+    ("Study ahead days", "Jours d'avance"),
+    ("Study sheet (typst)", "Fiche d'étude (typst)"),
+    ("Study timer started", "Minuteur d'étude lancé"),
+    ("Sync failed: {}", "Échec de la synchronisation : {}"),
+    (
+        "Synced, but couldn't merge reviews: {}",
+        "Synchronisé, mais impossible de fusionner les révisions : {}",
+    ),
+    ("Synced, {}", "Synchronisé, {}"),
+    ("Syncing", "Synchronisation"),
+    ("Take theirs", "Prendre l'autre"),
+    ("Template of '{}' removed", "Modèle de '{}' supprimé"),
+    (
+        "The deck is open read-only",
+        "Le paquet est ouvert en lecture seule",
+    ),
+    (
+        "The deck that couldn't be read is kept in {}",
+        "Le paquet illisible est conservé dans {}",
+    ),
+    (
+        "The import was for a deck that is no longer open",
+        "L'importation visait un paquet qui n'est plus ouvert",
+    ),
+    (
+        "The synced deck was closed, sync it again to take in the changes",
+        "Le paquet synchronisé a été fermé, resynchronisez-le pour récupérer les modifications",
+    ),
+    ("The trash is empty", "La corbeille est vide"),
+    ("Theme", "Thème de couleurs"),
+    (
+        "This card links to no other cards",
+        "Cette carte ne renvoie à aucune autre",
+    ),
+    (
+        "Time for a {} minute break",
+        "C'est l'heure d'une pause de {} minutes",
+    ),
+    ("Time per topic", "Temps par thème"),
+    ("Timer at start", "Minuteur au démarrage"),
+    ("To:", "Vers :"),
+    ("Topic '{}' created", "Thème '{}' créé"),
+    ("Topic {} ({})", "Thème {} ({})"),
+    ("Topics sorted by {}", "Thèmes triés par {}"),
+    ("Trash, kept {} days", "Corbeille, conservée {} jours"),
+    ("Type the answer", "Tapez la réponse"),
+    ("Typed {}: {}", "{} tapées : {}"),
+    (
+        "Wait for the import to finish, or cancel it",
+        "Attendez la fin de l'importation, ou annulez-la",
+    ),
+    ("Web page failed: {}", "Échec de la page web : {}"),
+    ("Writing snapshot", "Écriture de la capture"),
+    ("Writing web page", "Écriture de la page web"),
+    ("Wrote the card to {}", "Carte écrite dans {}"),
+    ("Wrote the deck to {}", "Paquet écrit dans {}"),
+    (
+        "You have reviewed all of today's cards in {}. Study ahead, with the new cards of the next {} days?",
+        "Vous avez révisé toutes les cartes du jour dans {}. Étudier en avance, avec les nouvelles cartes des {} prochains jours ?",
+    ),
+    ("Your answer", "Votre réponse"),
+    ("above", "au-dessus"),
+    ("after {} months", "après {} mois"),
+    ("archived", "archivé"),
+    ("auto", "automatique"),
+    ("beside", "à côté"),
+    ("browse", "parcourir"),
+    ("dark", "sombre"),
+    ("decks", "paquets"),
+    ("due", "à réviser"),
+    ("from the locale", "selon le système"),
+    ("generate cards", "générer des cartes"),
+    ("hardest first", "les plus difficiles d'abord"),
+    ("hidden", "masqué"),
+    ("home", "accueil"),
+    ("in order", "dans l'ordre"),
+    (
+        "least recently seen first",
+        "les moins vues récemment d'abord",
+    ),
+    ("light", "clair"),
+    ("lowest accuracy first", "moins bonne réussite d'abord"),
+    ("most cards", "le plus de cartes"),
+    ("most due", "le plus à réviser"),
+    ("name", "nom"),
+    ("need at least {}x{}", "il faut au moins {}x{}"),
+    ("never", "jamais"),
+    ("newest first", "les plus récentes d'abord"),
+    ("no", "non"),
+    ("no limit", "sans limite"),
+    ("none", "aucun"),
+    ("oldest first", "les plus anciennes d'abord"),
+    ("question", "question"),
+    ("read-only", "lecture seule"),
+    ("recently created", "créés récemment"),
+    ("recently studied", "étudiés récemment"),
+    ("removed", "retirée"),
+    ("save failed", "échec de l'enregistrement"),
+    ("saved", "enregistré"),
+    ("shuffled", "mélangées"),
+    ("stats", "statistiques"),
+    ("text", "texte"),
+    ("today", "aujourd'hui"),
+    ("topics", "thèmes"),
+    ("trash", "corbeille"),
+    ("unsaved", "non enregistré"),
+    ("yes", "oui"),
+    ("{}  in {}", "{}  dans {}"),
+    ("{} already exists", "{} existe déjà"),
+    ("{} cards in {} topics", "{} cartes dans {} thèmes"),
+    ("{} days in a row", "{} jours d'affilée"),
+    (
+        "{} due, {} new, {} total",
+        "{} à réviser, {} nouvelles, {} au total",
+    ),
+    (
+        "{} is encrypted, open it with mem-flip --deck {}",
+        "{} est chiffré, ouvrez-le avec mem-flip --deck {}",
+    ),
+    ("{} marked", "{} marqués"),
+    ("{} of {}", "{} sur {}"),
+    ("{} tasks", "{} tâches"),
+    (
+        "{} tasks are still running. Quitting stops them and waits for them to finish.",
+        "{} tâches sont encore en cours. Quitter les arrête et attend qu'elles se terminent.",
+    ),
+    (
+        "{} topics │ {} cards │ {} due",
+        "{} thèmes │ {} cartes │ {} à réviser",
+    ),
+    ("{} · card {}", "{} · carte {}"),
+    ("{} · card {} of {}", "{} · carte {} sur {}"),
+    (
+        "{} · {}\nHere: {}\nThere: {}",
+        "{} · {}\nIci : {}\nLà-bas : {}",
+    ),
+    ("{} · {} typed", "{} · {} tapées"),
+    ("{}d ago", "il y a {} j"),
+    (
+        "{}\n\nUsing the default settings. Changed settings aren't saved until the file is fixed.",
+        "{}\n\nLes réglages par défaut sont utilisés. Les modifications ne sont pas enregistrées tant que le fichier n'est pas corrigé.",
+    ),
+    (
+        "{}: {}\n\nStarting with an empty deck. The file is copied to {} before anything is saved over it.",
+        "{} : {}\n\nDémarrage avec un paquet vide. Le fichier est copié vers {} avant tout enregistrement par-dessus.",
+    ),
+    ("The name can't be empty", "Le nom ne peut pas être vide"),
+    (
+        "The name can't hold tabs or line breaks",
+        "Le nom ne peut pas contenir de tabulations ni de sauts de ligne",
+    ),
+    (
+        "There is already a topic '{}'",
+        "Il existe déjà un thème '{}'",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(translate(Language::German, "Quit"), "Beenden");
+        assert_eq!(translate(Language::English, "Quit"), "Quit");
+        // Untranslated text stays in English
+        assert_eq!(
+            translate(Language::French, "Nothing like this"),
+            "Nothing like this"
+        );
+    }
+
+    #[test]
+    fn test_tables_are_complete() {
+        // Every language translates the same texts
+        let english: Vec<&str> = SPANISH.iter().map(|(text, _)| *text).collect();
+        for table in [GERMAN, FRENCH] {
+            let texts: Vec<&str> = table.iter().map(|(text, _)| *text).collect();
+            assert_eq!(texts, english);
+        }
+    }
+
+    #[test]
+    fn test_from_locale() {
+        assert_eq!(Language::from_locale("de_DE.UTF-8"), Language::German);
+        assert_eq!(Language::from_locale("C"), Language::English);
+        assert_eq!(Language::from_locale("pt_BR"), Language::English);
+    }
+}
@@ -0,0 +1,20 @@
+//! The core of mem-flip: flashcard decks, the review history and what is
+//! computed from them, without any terminal UI.
+//!
+//! The `mem-flip` binary is one frontend on top of this crate. Others can
+//! load a deck with [`deck::Topics::load`], record grades in a
+//! [`history::History`] and use the rest to decide what to show.
+
+#![warn(missing_docs)]
+
+pub mod atomic;
+pub mod crypt;
+pub mod deck;
+pub mod history;
+pub mod journal;
+pub mod merge;
+pub mod schedule;
+pub mod session;
+pub mod stats;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_deck;
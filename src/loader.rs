@@ -0,0 +1,76 @@
+//! Reading big decks on a background thread.
+//!
+//! Parsing a deck of many thousands of cards takes long enough to notice, so
+//! the app starts with an empty deck and takes in the cards once they have
+//! been read. Decks small enough to read at once are read before the UI
+//! starts, as always.
+//!
+//! The whole deck is still read in one go, not a topic at a time: it is one
+//! JSON document, and its journal is replayed over all of it.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+
+use mem_flip::crypt::Key;
+use mem_flip::deck::Topics;
+use mem_flip::journal;
+
+/// Deck files this big or bigger, with their journal, are read in the
+/// background.
+const LARGE_FILE: u64 = 1 << 20;
+
+/// Whether the deck at `path` is big enough to read in the background.
+pub fn is_large(path: &Path) -> bool {
+    let size = |path: &Path| fs::metadata(path).map_or(0, |meta| meta.len());
+    size(path) + size(&journal::path_for(path)) >= LARGE_FILE
+}
+
+/// A deck being read.
+#[derive(Debug)]
+pub struct DeckLoader {
+    handle: JoinHandle<io::Result<Topics>>,
+}
+
+impl DeckLoader {
+    /// Starts reading the deck at `path`, opening it with `key`.
+    pub fn start(path: PathBuf, key: Option<Key>) -> DeckLoader {
+        let handle = thread::spawn(move || Topics::load_with(&path, key.as_ref()));
+        DeckLoader { handle }
+    }
+
+    /// Whether the deck has been read, so `wait` returns at once.
+    pub fn is_done(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// The deck, once it has been read.
+    pub fn wait(self) -> io::Result<Topics> {
+        self.handle
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("reading the deck panicked")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mem_flip::deck::Flashcard;
+
+    use super::*;
+
+    #[test]
+    fn test_loads_in_background() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("deck.json");
+        let mut topics = Topics::default();
+        topics
+            .topics_map
+            .insert("es".to_string(), vec![Flashcard::new("perro", "dog")]);
+        topics.save(&path).unwrap();
+
+        assert!(!is_large(&path));
+        let loader = DeckLoader::start(path, None);
+        assert_eq!(loader.wait().unwrap(), topics);
+    }
+}
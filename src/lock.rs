@@ -0,0 +1,94 @@
+//! Keeping two mem-flips from having the same deck open, where the one
+//! saving last would silently undo the other's changes.
+//!
+//! The lock is an OS file lock on `deck.lock` next to `deck.json`, so it is
+//! released even when mem-flip is killed. The file holds the process id of
+//! the owner for the error message. It is left in place when the lock is
+//! released: removing it would let a process that had just opened it lock a
+//! file nobody else can see anymore, while a third locks a new one.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, Write};
+use std::path::Path;
+
+/// The lock on one deck, held until dropped.
+#[derive(Debug)]
+pub struct DeckLock {
+    file: File,
+}
+
+impl DeckLock {
+    /// Takes the lock on the deck at `deck_path`, failing with
+    /// `ResourceBusy` when another process has it.
+    pub fn acquire(deck_path: &Path) -> io::Result<DeckLock> {
+        let path = deck_path.with_extension("lock");
+        // Not truncated: the owner's process id is still needed for the error
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut owner = String::new();
+                file.read_to_string(&mut owner)?;
+                let owner = match owner.trim() {
+                    "" => String::new(),
+                    pid => format!(" (process {})", pid),
+                };
+                return Err(io::Error::new(
+                    io::ErrorKind::ResourceBusy,
+                    format!(
+                        "{} is open in another mem-flip{}",
+                        deck_path.display(),
+                        owner
+                    ),
+                ));
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        Ok(DeckLock { file })
+    }
+}
+
+impl Drop for DeckLock {
+    fn drop(&mut self) {
+        // Emptied while still locked, so nobody reads a process id that no
+        // longer owns it
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_second_lock_is_refused() {
+        let dir = TempDir::new().unwrap();
+        let deck = dir.path().join("deck.json");
+        let lock = DeckLock::acquire(&deck).unwrap();
+
+        let error = DeckLock::acquire(&deck).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ResourceBusy);
+        assert!(error.to_string().contains(&std::process::id().to_string()));
+
+        drop(lock);
+        let path = dir.path().join("deck.lock");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+        let _again = DeckLock::acquire(&deck).unwrap();
+        // The same file as before, so a process that opened it in between
+        // still competes for it
+        let error = DeckLock::acquire(&deck).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ResourceBusy);
+    }
+}
@@ -0,0 +1,47 @@
+//! Logging to `<state dir>/mem-flip/log`.
+//!
+//! The terminal belongs to the UI, so everything goes to a file instead.
+//! Warnings and errors are always logged, `--verbose` adds more detail.
+
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use tracing::level_filters::LevelFilter;
+
+use crate::paths;
+
+static LOG_FILE: &str = "log";
+
+pub fn path() -> Option<PathBuf> {
+    paths::state_dir().map(|dir| dir.join(LOG_FILE))
+}
+
+/// Starts logging at the level picked by the number of `-v` flags. Logging
+/// stays off if the log file can't be opened.
+pub fn init(verbose: u8) {
+    let level = match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+
+    let Some(path) = path() else {
+        return;
+    };
+    if let Some(dir) = path.parent()
+        && fs::create_dir_all(dir).is_err()
+    {
+        return;
+    }
+    let Ok(file) = OpenOptions::new().create(true).append(true).open(&path) else {
+        return;
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_ansi(false)
+        .with_writer(Mutex::new(file))
+        .init();
+}
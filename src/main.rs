@@ -1,69 +1,1286 @@
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::fs;
 use std::io;
+use std::mem;
+use std::net::ToSocketAddrs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
 
+use clap::Parser;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use mem_flip::crypt::{self, Key};
+use mem_flip::deck::{DeckMeta, Flashcard, TopicMeta, TopicNameError, Topics};
+use mem_flip::history::{self, Compaction, History, Review};
+use mem_flip::journal::Journal;
+use mem_flip::merge::{self, Conflict};
+use mem_flip::schedule::{self, Interleave, ReviewOrder};
+use mem_flip::session::{Next, Session};
+use mem_flip::stats;
 use ratatui::{
     DefaultTerminal, Frame,
     buffer::Buffer,
-    layout::{Constraint, Layout, Rect},
+    layout::{Constraint, Flex, Layout, Margin, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, List, ListItem, ListState, Paragraph, Widget, Wrap},
+    widgets::{
+        Block, Clear, LineGauge, List, ListItem, ListState, Padding, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Sparkline, StatefulWidget, Widget, Wrap,
+    },
 };
-use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
 
-static CARDS_FILE: &str = "flashcards.json";
+mod animation;
+mod anki;
+mod bidi;
+mod browser;
+mod bundle;
+mod check;
+mod cli;
+mod config;
+mod decks;
+mod dialog;
+mod diff;
+mod editor;
+mod export;
+mod fetch;
+mod generate;
+mod git;
+mod graphics;
+#[cfg(test)]
+mod harness;
+mod home;
+mod html;
+mod import;
+mod keys;
+mod l10n;
+mod loader;
+mod lock;
+mod logging;
+mod markup;
+mod media;
+mod notify;
+mod passphrase;
+mod paths;
+mod plain;
+mod resume;
+mod serve;
+mod settings;
+mod share;
+mod snapshot;
+mod spell;
+mod stats_view;
+mod status;
+mod summary;
+mod sync;
+mod tasks;
+mod theme;
+mod timer;
+mod toast;
+mod topic_sort;
+mod trash;
+mod tts;
+mod typing;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Flashcard {
-    pub question: String,
-    pub answer: String,
-}
+use animation::{Animation, FRAME_TIME, Transition};
+use anki::Anki;
+use browser::{CardBrowser, CardSort};
+use cli::{Cli, Command, ImportSource};
+use config::Config;
+use decks::{DeckPrompt, DeckSwitcher};
+use dialog::{ConfirmDialog, ErrorDialog};
+use editor::{Counts, EditorView};
+use export::ExportFormat;
+use generate::Generator;
+use graphics::{Graphics, ImagePlacement};
+use home::Dashboard;
+use import::{Imported, MergeStrategy};
+use keys::Sequence;
+use l10n::{Language, hint, tr, trf};
+use loader::DeckLoader;
+use lock::DeckLock;
+use resume::{ResumeState, ReviewPosition};
+use settings::{SETTINGS, SettingsView};
+use spell::{Misspelling, Speller};
+use stats_view::StatsView;
+use status::{SaveState, StatusBar};
+use summary::Summary;
+use tasks::{Outcome, Tasks};
+use timer::{Phase, StudyTimer};
+use toast::Toasts;
+use topic_sort::{TopicOrder, TopicSort};
+use trash::TrashView;
+use tts::Speaker;
+use typing::{Typing, TypingView};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Topics {
-    pub topics_map: HashMap<String, Vec<Flashcard>>,
-}
+// Deck opened when none is given and none was open last time
+static CARDS_FILE: &str = "flashcards.json";
+// Written by the export action of the stats screen
+static STATS_FILE: &str = "stats.csv";
+
+// Smallest terminal the screens can be drawn in
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 12;
+// Width from which review shows question and answer side by side
+const SIDE_BY_SIDE_WIDTH: u16 = 100;
+// Days of accuracy shown next to each topic
+const ACCURACY_DAYS: usize = 14;
+// Answer length the editor warns beyond, unless configured
+const MAX_ANSWER_LENGTH: usize = 250;
+// Days of new cards studying ahead brings in, unless configured
+const STUDY_AHEAD_DAYS: u64 = 3;
+// Days back the review of recently added cards goes, unless configured
+const RECENT_DAYS: u64 = 7;
+// Cards an import goes through between reports of its progress
+const IMPORT_REPORT_EVERY: usize = 50;
+// How often timers and other time-driven state are updated
+const TICK_RATE: Duration = Duration::from_millis(250);
+// Ticks between looks at whether the deck file was changed by something else
+const WATCH_TICKS: u64 = 4;
 
 fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    // Before anything looks up where its files are
+    let portable = paths::init_portable(cli.portable)?;
+    if let Some(profile) = &cli.profile {
+        paths::set_profile(profile)?;
+    }
+    logging::init(cli.verbose);
+    info!("starting mem-flip {}", env!("CARGO_PKG_VERSION"));
+    if portable {
+        info!("portable, keeping files next to the executable");
+    }
+
+    let mut resume = ResumeState::load();
+    // The deck named on the command line, else the one open last time
+    let deck_path = match cli.deck.or_else(|| resume.deck.clone()) {
+        Some(path) => path,
+        None => default_deck()?,
+    };
+    let deck_path = decks::absolute(&deck_path);
+
+    match cli.command {
+        Some(Command::Stats { export }) => {
+            let key = unlock(&deck_path)?;
+            return export_stats(&deck_path, key, export.as_deref());
+        }
+        Some(Command::Fetch { url, merge, output }) => {
+            return fetch_deck(&deck_path, &url, merge, output);
+        }
+        Some(Command::Bundle { output }) => return bundle_deck(&deck_path, output),
+        Some(Command::Share { topic }) => return share_topic(&deck_path, &topic),
+        Some(Command::Html { output }) => return html_deck(&deck_path, output),
+        Some(Command::Unbundle { bundle, output }) => return unbundle_deck(&bundle, output),
+        Some(Command::Sync) => return sync_deck(&deck_path),
+        Some(Command::Encrypt) => return encrypt_deck(&deck_path),
+        Some(Command::Decrypt) => return decrypt_deck(&deck_path),
+        Some(Command::Status { format, json }) => {
+            return print_status(&deck_path, &format, json);
+        }
+        Some(Command::Notify) => return notify_due(&deck_path),
+        Some(Command::Check { files }) => return check_decks(&deck_path, files),
+        Some(Command::Random { topic, overdue }) => {
+            return print_card(&deck_path, topic.as_deref(), overdue);
+        }
+        Some(Command::Import { duplicates, source }) => {
+            return import_cards(&deck_path, source, duplicates);
+        }
+        Some(Command::Merge { other, conflicts }) => {
+            return merge_deck(&deck_path, &other, conflicts);
+        }
+        Some(Command::Doctor { dry_run }) => return tidy_deck(&deck_path, dry_run),
+        Some(Command::Anki { topics }) => return sync_anki(&deck_path, &topics),
+        Some(Command::CompactHistory { months, totals }) => {
+            return compact_history(&deck_path, months, totals);
+        }
+        Some(Command::Serve { port, host }) => {
+            return serve_deck(&deck_path, &host, port, cli.read_only);
+        }
+        None => {}
+    }
+
+    // Refused before anything else, so a second instance can't save over
+    // the first one's changes. Others can still browse it read-only.
+    let lock = if cli.read_only {
+        None
+    } else {
+        Some(DeckLock::acquire(&deck_path)?)
+    };
+    // An encrypted deck can't be read until the passphrase is given
+    let key = unlock(&deck_path)?;
+    resume.open(key.as_ref());
+    if cli.plain {
+        return plain_mode(deck_path, key, cli.read_only);
+    }
+
+    // Also installs a panic hook that disables raw mode and leaves the
+    // alternate screen before the panic message is printed
     let mut terminal = ratatui::init();
     terminal.clear()?;
 
-    // Load topics from file, or create empty if file doesn't exist
-    let topics = match std::fs::File::open(CARDS_FILE) {
-        Ok(file) => {
-            let reader = std::io::BufReader::new(file);
-            // Return new empty map if file has bad data
-            serde_json::from_reader(reader).unwrap_or_else(|_| Topics {
-                topics_map: HashMap::new(),
-            })
+    // Errors loading either file are shown once the app is up, with defaults
+    // in the meantime. A big deck is read while the app starts.
+    let (topics, topics_error, deck_loader) = if loader::is_large(&deck_path) {
+        let loader = DeckLoader::start(deck_path.clone(), key.clone());
+        (Topics::default(), None, Some(loader))
+    } else {
+        match Topics::load_with(&deck_path, key.as_ref()) {
+            Ok(topics) => (topics, None, None),
+            Err(e) => (Topics::default(), Some(e), None),
         }
-        Err(_) => Topics {
-            topics_map: HashMap::new(),
-        },
     };
+    let (config, config_error) = match Config::load() {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
+    // Needs the raw mode the terminal was just put in
+    theme::init(config.theme);
+    let history_path = History::path_for(&deck_path);
+    // Reviews are still appended to the file if earlier ones can't be read
+    let mut history = History::load_with(history_path.clone(), key.clone()).unwrap_or_else(|e| {
+        error!("loading {}: {}", history_path.display(), e);
+        History::empty(history_path)
+    });
+    if let Some(months) = config.history_months.filter(|_| !cli.read_only) {
+        let before = history::now().saturating_sub(months * history::MONTH);
+        match history.compact(before, config.history_compaction) {
+            Ok(0) => {}
+            Ok(saved) => info!("compacted the review history by {} lines", saved),
+            Err(e) => error!("compacting the review history: {}", e),
+        }
+    }
+
+    l10n::set_language(config.language.unwrap_or_else(Language::from_env));
+    let mut app = App::new(deck_path, topics, history, config);
+    app.key = key;
+    app.lock = lock;
+    app.read_only = cli.read_only;
+    app.loader = deck_loader;
+    if let Some(language) = &app.config.spell_language {
+        match Speller::start(language) {
+            Ok(speller) => app.speller = Some(speller),
+            Err(e) => {
+                warn!("spell checking is off: {}", e);
+                app.toasts.error(trf("Spell checking is off: {}", &[&e]));
+            }
+        }
+    }
+    if let Some(e) = &topics_error {
+        error!("loading {}: {}", app.deck_path.display(), e);
+    } else if app.loader.is_some() {
+        info!("loading {} in the background", app.deck_path.display());
+    } else {
+        let count = app.topics.topics_map.len();
+        info!("loaded {} topics from {}", count, app.deck_path.display());
+    }
+    if let Some(e) = topics_error {
+        app.dialog = Some(app.load_error_dialog(&e));
+    }
+    if let Some(e) = config_error {
+        error!("loading config: {}", e);
+        app.config_unreadable = true;
+        if app.dialog.is_none() {
+            app.dialog = Some(ErrorDialog::new(
+                tr("Couldn't load config"),
+                trf(
+                    "{}\n\nUsing the default settings. Changed settings aren't saved \
+                     until the file is fixed.",
+                    &[&e],
+                ),
+            ));
+        }
+    }
+    app.resume(resume);
 
-    let mut app = App::new(topics);
-    let app_result = app.run(&mut terminal);
+    let app_result = match panic::catch_unwind(AssertUnwindSafe(|| app.run(&mut terminal))) {
+        Ok(result) => result,
+        Err(payload) if app.read_only => panic::resume_unwind(payload),
+        Err(payload) => {
+            // The hook has restored the terminal and printed the panic by now
+            let recovery = app.recovery_path();
+            error!("panicked, saving cards to {}", recovery.display());
+            match app.topics.save_with(&recovery, app.key.as_ref()) {
+                Ok(()) => eprintln!("Cards saved to {}", recovery.display()),
+                Err(e) => {
+                    error!("saving {}: {}", recovery.display(), e);
+                    eprintln!("Error saving cards to {}: {}", recovery.display(), e);
+                }
+            }
+            panic::resume_unwind(payload);
+        }
+    };
 
+    // Lets a sync or an export finish writing before the app is gone
+    app.tasks.stop();
     ratatui::restore();
 
-    // Save topics to disk before exiting
-    if let Err(e) = app.save_to_disk() {
+    app.save_progress();
+
+    // Quitting saves, so this is only for changes left when the app failed
+    if app_result.is_err()
+        && app.save_state != SaveState::Saved
+        && let Err(e) = app.save_to_disk()
+    {
+        error!("saving {}: {}", app.deck_path.display(), e);
         eprintln!("Error saving topics: {}", e);
     }
 
+    if let Err(e) = &app_result {
+        error!("exiting on error: {}", e);
+    }
     app_result
 }
 
+// Where a deck that couldn't be read is kept before it is saved over:
+// `deck.json.broken-1700000000` for `deck.json`
+fn broken_path(path: &Path, time: u64) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.broken-{}", name, time))
+}
+
+// The deck opened when none is given and none was open last time. Each
+// profile has its own, so profiles never share review history, and a
+// portable mem-flip keeps it next to the executable.
+fn default_deck() -> io::Result<PathBuf> {
+    match paths::data_dir() {
+        Some(dir) => {
+            fs::create_dir_all(&dir)?;
+            Ok(dir.join(CARDS_FILE))
+        }
+        None => Ok(PathBuf::from(CARDS_FILE)),
+    }
+}
+
+// Asks for the passphrase of the deck at `deck_path` if it is encrypted,
+// until it opens the deck, and returns the key
+fn unlock(deck_path: &Path) -> io::Result<Option<Key>> {
+    const ATTEMPTS: usize = 3;
+    let Some(salt) = crypt::deck_salt(deck_path)? else {
+        return Ok(None);
+    };
+    let label = format!("Passphrase for {}", deck_path.display());
+    for _ in 0..ATTEMPTS {
+        let key = Key::derive(&passphrase::ask(&label)?, &salt)?;
+        match Topics::load_with(deck_path, Some(&key)) {
+            Ok(_) => return Ok(Some(key)),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => eprintln!("{}", e),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "wrong passphrase",
+    ))
+}
+
+// `mem-flip encrypt`: seals the deck and its history with a new passphrase
+fn encrypt_deck(deck_path: &Path) -> io::Result<()> {
+    let _lock = DeckLock::acquire(deck_path)?;
+    if crypt::deck_salt(deck_path)?.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is already encrypted", deck_path.display()),
+        ));
+    }
+    let topics = Topics::load(deck_path)?;
+    let mut history = History::load(History::path_for(deck_path))?;
+    let key = Key::new(&passphrase::ask_new()?)?;
+    topics.save_with(deck_path, Some(&key))?;
+    history.set_key(Some(key))?;
+    info!("encrypted {}", deck_path.display());
+    eprintln!("Encrypted {} and its history", deck_path.display());
+    Ok(())
+}
+
+// `mem-flip decrypt`: stores the deck and its history in plain text again
+fn decrypt_deck(deck_path: &Path) -> io::Result<()> {
+    let _lock = DeckLock::acquire(deck_path)?;
+    let Some(key) = unlock(deck_path)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} isn't encrypted", deck_path.display()),
+        ));
+    };
+    let topics = Topics::load_with(deck_path, Some(&key))?;
+    let mut history = History::load_with(History::path_for(deck_path), Some(key))?;
+    topics.save(deck_path)?;
+    history.set_key(None)?;
+    info!("decrypted {}", deck_path.display());
+    eprintln!("Decrypted {} and its history", deck_path.display());
+    Ok(())
+}
+
+// `mem-flip stats`, which runs without the terminal UI
+fn export_stats(deck_path: &Path, key: Option<Key>, path: Option<&Path>) -> io::Result<()> {
+    let history = History::load_with(History::path_for(deck_path), key)?;
+    // A deck that is missing or can't be read still has a file name
+    let deck = DeckMeta::read(deck_path)
+        .unwrap_or_default()
+        .title_or_name(deck_path);
+    match path {
+        Some(path) => {
+            stats::export_csv(&deck, history.reviews(), path, |_| {})?;
+            let count = history.reviews().len();
+            info!("exported {} reviews to {}", count, path.display());
+            eprintln!("Exported {} reviews to {}", count, path.display());
+            Ok(())
+        }
+        None => stats::write_csv(&deck, history.reviews(), io::stdout().lock()),
+    }
+}
+
+// `mem-flip fetch`, which saves the deck as a new file or merges it into the
+// open one
+fn fetch_deck(deck_path: &Path, url: &str, merge: bool, output: Option<PathBuf>) -> io::Result<()> {
+    if merge {
+        let _lock = DeckLock::acquire(deck_path)?;
+        let key = unlock(deck_path)?;
+        let shared = fetch::download(url)?;
+        let mut topics = Topics::load_with(deck_path, key.as_ref())?;
+        let added = topics.merge(shared);
+        topics.save_with(deck_path, key.as_ref())?;
+        info!(
+            "merged {} cards from {} into {}",
+            added,
+            url,
+            deck_path.display()
+        );
+        eprintln!("Added {} cards to {}", added, deck_path.display());
+        return Ok(());
+    }
+
+    let path =
+        decks::absolute(&output.unwrap_or_else(|| deck_path.with_file_name(fetch::file_name(url))));
+    fetch::save_new(url, &path)?;
+    info!("saved {} to {}", url, path.display());
+    eprintln!("Saved to {}", path.display());
+    eprintln!("Open it with: mem-flip --deck {}", path.display());
+    Ok(())
+}
+
+// `mem-flip bundle`
+fn bundle_deck(deck_path: &Path, output: Option<PathBuf>) -> io::Result<()> {
+    let key = unlock(deck_path)?;
+    let topics = Topics::load_with(deck_path, key.as_ref())?;
+    let out = output.unwrap_or_else(|| bundle::bundle_path(deck_path));
+    let deck_dir = deck_path.parent().unwrap_or(Path::new(""));
+    let packed = bundle::pack(&topics, deck_dir, &out)?;
+    info!(
+        "bundled {} with {} images",
+        deck_path.display(),
+        packed.media
+    );
+    for missing in &packed.missing {
+        warn!("image not found: {}", missing);
+        eprintln!("Image not found, left out: {}", missing);
+    }
+    eprintln!(
+        "Bundled {} cards and {} images into {}",
+        topics.card_count(),
+        packed.media,
+        out.display()
+    );
+    if key.is_some() {
+        // A bundle is for handing to someone else, who needs to read it
+        eprintln!("The bundle isn't encrypted, unlike the deck");
+    }
+    Ok(())
+}
+
+// `mem-flip check`
+fn check_decks(deck_path: &Path, mut files: Vec<PathBuf>) -> io::Result<()> {
+    if files.is_empty() {
+        files.push(deck_path.to_path_buf());
+    }
+    let mut failed = 0;
+    for file in &files {
+        let text = fs::read_to_string(file)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file.display(), e)))?;
+        match check::check(&text) {
+            Ok(checked) if checked.encrypted => {
+                println!(
+                    "{}: encrypted, only its envelope was checked",
+                    file.display()
+                );
+            }
+            Ok(checked) => println!(
+                "{}: fine, {} topics and {}",
+                file.display(),
+                checked.topics,
+                git::cards_label(checked.cards)
+            ),
+            Err(problems) => {
+                failed += 1;
+                for problem in problems {
+                    println!("{}:{}", file.display(), problem);
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} of {} decks have problems", failed, files.len()),
+        ));
+    }
+    Ok(())
+}
+
+// `mem-flip random`
+fn print_card(deck_path: &Path, topic: Option<&str>, overdue: bool) -> io::Result<()> {
+    let key = unlock(deck_path)?;
+    let topics = Topics::load_with(deck_path, key.as_ref())?;
+    let history = History::load_with(History::path_for(deck_path), key)?;
+    // Nanoseconds, so greetings a second apart show different cards
+    let seed = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+    let Some((topic_name, card)) =
+        schedule::pick_card(&topics, topic, history.reviews(), overdue, seed)
+    else {
+        let place = topic.map_or(String::new(), |topic| format!(" in {}", topic));
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No cards{} in {}", place, deck_path.display()),
+        ));
+    };
+    println!("{} · {}", topic_name, card.question);
+    println!("→ {}", card.answer);
+    Ok(())
+}
+
+// `mem-flip --plain`
+fn plain_mode(deck_path: PathBuf, key: Option<Key>, read_only: bool) -> io::Result<()> {
+    let topics = Topics::load_with(&deck_path, key.as_ref())?;
+    let history = History::load_with(History::path_for(&deck_path), key.clone())?;
+    // The defaults will do, the prompt has nowhere to show the error
+    let config = Config::load().unwrap_or_else(|e| {
+        error!("loading config: {}", e);
+        Config::default()
+    });
+    let mut plain = plain::Plain {
+        deck_path,
+        key,
+        read_only,
+        topics,
+        history,
+        review_order: config.review_order,
+        new_per_day: config.new_per_day,
+        input: io::stdin().lock(),
+        out: io::stdout(),
+    };
+    plain.run()
+}
+
+// `mem-flip share`
+fn share_topic(deck_path: &Path, topic: &str) -> io::Result<()> {
+    let key = unlock(deck_path)?;
+    let topics = Topics::load_with(deck_path, key.as_ref())?;
+    let Some(cards) = topics.topics_map.get(topic) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No topic {} in {}", topic, deck_path.display()),
+        ));
+    };
+    let code = share::encode(topic, cards)?;
+    info!(
+        "shared {} as a code of {} characters",
+        logged(topic, key.as_ref()),
+        code.len()
+    );
+    share::print(&code, io::stdout().lock())
+}
+
+// `mem-flip html`
+fn html_deck(deck_path: &Path, output: Option<PathBuf>) -> io::Result<()> {
+    let key = unlock(deck_path)?;
+    let topics = Topics::load_with(deck_path, key.as_ref())?;
+    let out = output.unwrap_or_else(|| html::page_path(deck_path));
+    html::export_page(&topics.meta.title_or_name(deck_path), &topics, &out)?;
+    info!(
+        "wrote {} as a page to {}",
+        deck_path.display(),
+        out.display()
+    );
+    eprintln!("Wrote {} cards to {}", topics.card_count(), out.display());
+    Ok(())
+}
+
+// `mem-flip unbundle`
+fn unbundle_deck(path: &Path, output: Option<PathBuf>) -> io::Result<()> {
+    let deck_path = decks::absolute(&output.unwrap_or_else(|| bundle::deck_path(path)));
+    let topics = bundle::unpack(path, &deck_path)?;
+    info!("unbundled {} to {}", path.display(), deck_path.display());
+    eprintln!(
+        "Unpacked {} cards to {}",
+        topics.card_count(),
+        deck_path.display()
+    );
+    eprintln!("Open it with: mem-flip --deck {}", deck_path.display());
+    Ok(())
+}
+
+// `mem-flip compact-history`
+fn compact_history(deck_path: &Path, months: Option<u64>, totals: bool) -> io::Result<()> {
+    let config = Config::load()?;
+    let Some(months) = months.or(config.history_months) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "give --months, or set history_months in config.toml",
+        ));
+    };
+    let how = if totals {
+        Compaction::Totals
+    } else {
+        config.history_compaction
+    };
+    let _lock = DeckLock::acquire(deck_path)?;
+    let key = unlock(deck_path)?;
+    let mut history = History::load_with(History::path_for(deck_path), key)?;
+    let before = history::now().saturating_sub(months * history::MONTH);
+    let saved = history.compact(before, how)?;
+    info!(
+        "compacted the history of {} by {} lines",
+        deck_path.display(),
+        saved
+    );
+    eprintln!(
+        "Compacted the history of {}: {} lines fewer, {} left",
+        deck_path.display(),
+        saved,
+        history.reviews().len()
+    );
+    Ok(())
+}
+
+// `mem-flip sync`
+fn sync_deck(deck_path: &Path) -> io::Result<()> {
+    let _lock = DeckLock::acquire(deck_path)?;
+    let remote = sync::Remote::from_config(&Config::load()?)?;
+    let key = unlock(deck_path)?;
+    let mut topics = Topics::load_with(deck_path, key.as_ref())?;
+    let mut pulled = sync::sync(&remote, deck_path, topics.clone(), key.as_ref())?;
+    // Nothing changed here in the meantime, so nothing to conflict
+    pulled.merge_deck(&mut topics);
+    topics.save_with(deck_path, key.as_ref())?;
+    pulled.save_base(deck_path, key.as_ref())?;
+    let mut history = History::load_with(History::path_for(deck_path), key)?;
+    pulled.merge_history(&mut history)?;
+    info!("synced {}: {:?}", deck_path.display(), pulled);
+    eprintln!(
+        "Synced {}: {} from the server",
+        deck_path.display(),
+        pulled.describe()
+    );
+    Ok(())
+}
+
+// `mem-flip import`
+fn import_cards(
+    deck_path: &Path,
+    source: ImportSource,
+    duplicates: MergeStrategy,
+) -> io::Result<()> {
+    let _lock = DeckLock::acquire(deck_path)?;
+    let key = unlock(deck_path)?;
+    let mut topics = Topics::load_with(deck_path, key.as_ref())?;
+    let imported = match source {
+        ImportSource::Obsidian { vault } => import::obsidian::read_vault(&vault)?,
+        ImportSource::Org { file } => import::org::read_file(&file)?,
+        ImportSource::Mochi { file } => import::mochi::read_file(&file)?,
+        ImportSource::Remnote { path } => import::remnote::read_path(&path)?,
+        ImportSource::Code { code } => share::decode(&code)?,
+        ImportSource::Quizlet { file, topic } => {
+            let (cards, skipped) = import::quizlet::read_file(&file)?;
+            if skipped > 0 {
+                eprintln!("Skipped {} lines without a term and definition", skipped);
+            }
+            let topic = match topic {
+                Some(topic) => topic,
+                None => {
+                    let name = file.file_stem().unwrap_or_default().to_string_lossy();
+                    import::quizlet::choose_topic(&topics, &name, io::stdin().lock(), io::stderr())?
+                }
+            };
+            let mut imported = Topics::default();
+            imported.topics_map.insert(topic, cards);
+            imported
+        }
+    };
+    let result = import::apply(&mut topics, imported, duplicates);
+    topics.save_with(deck_path, key.as_ref())?;
+    info!(
+        "imported into {}: {} added, {} updated, {} skipped",
+        deck_path.display(),
+        result.added,
+        result.updated,
+        result.skipped
+    );
+    eprintln!(
+        "Added {} cards to {}, updated {} and skipped {} already there",
+        result.added,
+        deck_path.display(),
+        result.updated,
+        result.skipped
+    );
+    Ok(())
+}
+
+// `mem-flip merge`
+fn merge_deck(deck_path: &Path, other_path: &Path, conflicts: MergeStrategy) -> io::Result<()> {
+    let _lock = DeckLock::acquire(deck_path)?;
+    let key = unlock(deck_path)?;
+    let mut topics = Topics::load_with(deck_path, key.as_ref())?;
+    let other_key = unlock(other_path)?;
+    let other = Topics::load_with(other_path, other_key.as_ref())?;
+    // Settings only for topics that have none
+    for (name, meta) in other.topic_meta {
+        topics.topic_meta.entry(name).or_insert(meta);
+    }
+    let other = Topics {
+        topics_map: other.topics_map,
+        ..Topics::default()
+    };
+    let result = import::apply(&mut topics, other, conflicts);
+    topics.save_with(deck_path, key.as_ref())?;
+    info!(
+        "merged {} into {}: {} added, {} updated, {} skipped, {} conflicts",
+        other_path.display(),
+        deck_path.display(),
+        result.added,
+        result.updated,
+        result.skipped,
+        result.conflicts.len()
+    );
+    eprintln!(
+        "Added {} cards to {}, updated {} and skipped {} already there",
+        result.added,
+        deck_path.display(),
+        result.updated,
+        result.skipped
+    );
+    if !result.conflicts.is_empty() {
+        let kept = match conflicts {
+            MergeStrategy::Skip => "kept this deck's answers",
+            _ => "took the other deck's answers",
+        };
+        eprintln!(
+            "{} questions have different answers, {}:",
+            result.conflicts.len(),
+            kept
+        );
+        for conflict in &result.conflicts {
+            eprintln!("  {}: {}", conflict.topic, conflict.question);
+            eprintln!("    this deck:  {}", conflict.ours);
+            eprintln!("    other deck: {}", conflict.theirs);
+        }
+    }
+    Ok(())
+}
+
+// `mem-flip doctor`
+fn tidy_deck(deck_path: &Path, dry_run: bool) -> io::Result<()> {
+    let _lock = DeckLock::acquire(deck_path)?;
+    let key = unlock(deck_path)?;
+    let mut topics = Topics::load_with(deck_path, key.as_ref())?;
+    let tidied = topics.tidy();
+    if tidied.is_empty() {
+        eprintln!("{} is tidy, nothing to fix", deck_path.display());
+        return Ok(());
+    }
+
+    for name in &tidied.empty_topics {
+        eprintln!("Empty topic '{}'", name);
+    }
+    eprintln!(
+        "{} empty topics, {} cards with stray whitespace or line endings, {} duplicate cards",
+        tidied.empty_topics.len(),
+        tidied.cleaned_cards,
+        tidied.duplicates
+    );
+    if dry_run {
+        eprintln!("Nothing changed, run without --dry-run to fix these");
+        return Ok(());
+    }
+    topics.save_with(deck_path, key.as_ref())?;
+    info!("tidied {}: {:?}", deck_path.display(), tidied);
+    eprintln!("Fixed {}", deck_path.display());
+    Ok(())
+}
+
+// `mem-flip anki`
+fn sync_anki(deck_path: &Path, names: &[String]) -> io::Result<()> {
+    let config = Config::load()?;
+    let anki = Anki::new(config.anki_url.as_deref().unwrap_or(anki::DEFAULT_URL));
+    let _lock = DeckLock::acquire(deck_path)?;
+    let key = unlock(deck_path)?;
+    let mut topics = Topics::load_with(deck_path, key.as_ref())?;
+    let synced = anki::sync(&anki, &mut topics, names)?;
+    if synced.pulled != Imported::default() {
+        topics.save_with(deck_path, key.as_ref())?;
+    }
+    info!("synced {} with Anki: {:?}", deck_path.display(), synced);
+    eprintln!(
+        "From Anki: {} new cards, {} updated. To Anki: {} new notes.",
+        synced.pulled.added, synced.pulled.updated, synced.pushed
+    );
+    Ok(())
+}
+
+// `mem-flip status`
+fn print_status(deck_path: &Path, format: &str, json: bool) -> io::Result<()> {
+    let key = unlock(deck_path)?;
+    let topics = Topics::load_with(deck_path, key.as_ref())?;
+    let history = History::load_with(History::path_for(deck_path), key)?;
+    let deck = topics.meta.title_or_name(deck_path);
+    let new_per_day = Config::load()?.new_per_day;
+    let summary = Summary::new(deck, &topics, &history, new_per_day);
+    if json {
+        println!("{}", serde_json::to_string(&summary)?);
+    } else {
+        println!("{}", summary.format(format)?);
+    }
+    Ok(())
+}
+
+// `mem-flip notify`, which stays quiet when nothing is due
+fn notify_due(deck_path: &Path) -> io::Result<()> {
+    let key = unlock(deck_path)?;
+    let topics = Topics::load_with(deck_path, key.as_ref())?;
+    let history = History::load_with(History::path_for(deck_path), key)?;
+    let config = Config::load()?;
+    let message = notify::due_message(&topics, history.reviews(), config.new_per_day);
+    let Some(message) = message else {
+        info!("nothing due in {}", deck_path.display());
+        return Ok(());
+    };
+    let title = topics.meta.title_or_name(deck_path);
+    notify::send(config.notify_command.as_deref(), &title, &message)?;
+    info!("notified about {}: {:?}", deck_path.display(), message);
+    Ok(())
+}
+
+// `mem-flip serve`, holding the deck's lock while it runs unless read-only
+fn serve_deck(deck_path: &Path, host: &str, port: u16, read_only: bool) -> io::Result<()> {
+    let _lock = if read_only {
+        None
+    } else {
+        Some(DeckLock::acquire(deck_path)?)
+    };
+    let key = unlock(deck_path)?;
+    let topics = Topics::load_with(deck_path, key.as_ref())?;
+    let history = History::load_with(History::path_for(deck_path), key.clone())?;
+    let config = Config::load()?;
+    let store = serve::Store::new(
+        deck_path.to_path_buf(),
+        topics,
+        history,
+        key,
+        read_only,
+        config.new_per_day,
+    );
+    let access = serve::Access::new(config.serve_origins)?;
+    // Taken apart from the port, so IPv6 hosts such as `::1` work
+    let addr = (host, port).to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} has no address", host),
+        )
+    })?;
+    serve::serve(store, access, addr)
+}
+
+// Rows of the topic settings popup
+const TOPIC_SETTINGS: usize = 6;
+// Days ahead an exam date starts at when one is first set
+const EXAM_DAYS: u64 = 14;
+// Colors and icons topics can be given from the settings popup
+const TOPIC_COLORS: [&str; 6] = ["red", "green", "yellow", "blue", "magenta", "cyan"];
+const TOPIC_ICONS: [&str; 10] = ["📘", "📗", "📕", "📙", "🧪", "🌍", "🎵", "💻", "🧠", "⭐"];
+
+// The next (or previous) of `choices` after `current`, wrapping through
+// none at either end
+fn cycle(choices: &[&str], current: Option<&str>, forward: bool) -> Option<String> {
+    let position = current.and_then(|c| choices.iter().position(|&choice| choice == c));
+    let next = match (position, forward) {
+        (None, true) => Some(0),
+        (None, false) => choices.len().checked_sub(1),
+        (Some(i), true) => (i + 1 < choices.len()).then_some(i + 1),
+        (Some(i), false) => i.checked_sub(1),
+    };
+    next.map(|i| choices[i].to_string())
+}
+
+// A study timer with the configured lengths, starting now
+fn start_study_timer(config: &Config, now: Instant) -> StudyTimer {
+    let minutes = |set: Option<u64>, default| Duration::from_secs(set.unwrap_or(default) * 60);
+    StudyTimer::new(
+        minutes(config.focus_minutes, timer::DEFAULT_FOCUS_MINUTES),
+        minutes(config.break_minutes, timer::DEFAULT_BREAK_MINUTES),
+        now,
+    )
+}
+
+// Style of a topic's name, in the topic's color if it has one
+fn topic_style(meta: &TopicMeta) -> Style {
+    let color = meta.color.as_deref().and_then(|c| c.parse::<Color>().ok());
+    color.map_or(Style::default(), |color| Style::default().fg(color))
+}
+
+fn order_label(order: ReviewOrder) -> &'static str {
+    match order {
+        ReviewOrder::Sequential => "in order",
+        ReviewOrder::Shuffled => "shuffled",
+        ReviewOrder::Hardest => "hardest first",
+        ReviewOrder::LeastRecent => "least recently seen first",
+        ReviewOrder::LowestAccuracy => "lowest accuracy first",
+    }
+}
+
+// The settings of `topic` as a popup over the topic list, the `selected`
+// row highlighted. `order` and `new_per_day` are the review order and
+// limit the settings come to.
+fn render_topic_settings(
+    area: Rect,
+    buf: &mut Buffer,
+    topic: &str,
+    meta: &TopicMeta,
+    order: ReviewOrder,
+    new_per_day: Option<usize>,
+    selected: usize,
+) {
+    let order = tr(order_label(order));
+    let new_per_day = new_per_day.map_or(tr("no limit").to_string(), |n| n.to_string());
+    let none = || tr("none").to_string();
+    let rows = [
+        ("Review order", order.to_string()),
+        (
+            "Answer first",
+            tr(if meta.reversed { "yes" } else { "no" }).to_string(),
+        ),
+        ("New cards a day", new_per_day),
+        ("Color", meta.color.clone().unwrap_or_else(none)),
+        ("Icon", meta.icon.clone().unwrap_or_else(none)),
+        ("Exam date", meta.exam.map_or_else(none, stats::format_date)),
+    ];
+    // Values line up after the longest name
+    let width = rows
+        .iter()
+        .map(|(name, _)| tr(name).chars().count() + 1)
+        .max()
+        .unwrap_or_default()
+        .max(16);
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let mut line = Line::from(format!("{:<width$} ◀ {} ▶", tr(name), value));
+            if name == "Color" {
+                line = line.patch_style(topic_style(meta));
+            }
+            if i == selected { line.reversed() } else { line }
+        })
+        .collect();
+    let hints = Line::from(vec![
+        hint("Change"),
+        "<←→>".blue().bold(),
+        hint("Save"),
+        "<Enter>".green().bold(),
+        hint("Cancel"),
+        "<Esc> ".red().bold(),
+    ]);
+    let paragraph = Paragraph::new(lines).block(
+        Block::bordered()
+            .title(format!(" ⚙ {} ", trf("Settings of '{}'", &[&topic])).bold())
+            .title_bottom(hints)
+            .padding(Padding::uniform(1))
+            .style(Style::default().fg(theme::palette().accent)),
+    );
+    dialog::render_centered(paragraph, area, buf);
+}
+
+// The typed answer compared word by word with `expected`, in a box at the
+// top of `area`. Returns the rest of `area`.
+fn render_typed_answer(area: Rect, buf: &mut Buffer, typed: &str, expected: &str) -> Rect {
+    let words = diff::word_diff(typed, expected);
+    let mut spans = Vec::new();
+    for word in &words {
+        let span = match word {
+            diff::Word::Same(word) => word.green(),
+            diff::Word::Missing(word) => word.red().crossed_out(),
+            diff::Word::Extra(word) => word.yellow(),
+        };
+        spans.extend([span, " ".into()]);
+    }
+    let title = if diff::matches(&words) {
+        format!(" ✓ {} ", tr("Your answer")).green().bold()
+    } else {
+        format!(" {} ", tr("Your answer")).bold()
+    };
+    let paragraph = Paragraph::new(Line::from(spans))
+        .wrap(Wrap { trim: true })
+        .block(Block::bordered().title(title));
+    let height =
+        (paragraph.line_count(area.width.saturating_sub(2)) as u16 + 2).min(area.height / 2);
+    let [typed_area, rest] =
+        Layout::vertical([Constraint::Length(height), Constraint::Min(0)]).areas(area);
+    paragraph.render(typed_area, buf);
+    rest
+}
+
+// The attachments of a card, in a box at the bottom of `area`. Returns the
+// rest of `area`.
+fn render_attachments(area: Rect, buf: &mut Buffer, attachments: &[String]) -> Rect {
+    let lines: Vec<Line> = attachments
+        .iter()
+        .map(|attachment| Line::from(format!("📎 {}", media::label(attachment))))
+        .collect();
+    let height = (lines.len() as u16 + 2).min(area.height / 2);
+    let [rest, list_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(height)]).areas(area);
+    Paragraph::new(lines)
+        .block(Block::bordered().title(format!(" {} ", tr("Attachments"))))
+        .render(list_area, buf);
+    rest
+}
+
+// The path or URL being attached to a card, as a popup over the browser
+fn render_attach_prompt(area: Rect, buf: &mut Buffer, input: &str) {
+    let hints = Line::from(vec![
+        hint("Attach"),
+        "<Enter>".green().bold(),
+        hint("Cancel"),
+        "<Esc> ".red().bold(),
+    ]);
+    let paragraph = Paragraph::new(Line::from(vec![
+        Span::raw("> "),
+        Span::styled(input, Style::default().fg(theme::palette().input)),
+        Span::styled("█", Style::default().fg(theme::palette().input)),
+    ]))
+    .wrap(Wrap { trim: false })
+    .block(
+        Block::bordered()
+            .title(format!(" 📎 {} ", tr("File or link to attach")).bold())
+            .title_bottom(hints)
+            .padding(Padding::uniform(1))
+            .style(Style::default().fg(theme::palette().accent)),
+    );
+    dialog::render_centered(paragraph, area, buf);
+}
+
+// How far along `topic` is, as a popup over the topic list
+fn render_topic_stats(
+    area: Rect,
+    buf: &mut Buffer,
+    topic: &str,
+    cards: usize,
+    stats: &stats::TopicStats,
+) {
+    let accuracy = stats
+        .accuracy
+        .map_or("–".to_string(), |accuracy| format!("{:.0}%", accuracy));
+    let last_studied = stats
+        .last_studied
+        .map_or(tr("never").to_string(), stats::format_date);
+    let rows = [
+        ("Cards", cards.to_string()),
+        ("  New", stats.new.to_string()),
+        ("  Learning", stats.learning.to_string()),
+        ("  Mature", stats.mature.to_string()),
+        ("Reviews", stats.reviews.to_string()),
+        ("Accuracy", accuracy),
+        ("Last studied", last_studied),
+    ];
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(name, value)| {
+            // Kinds of cards are indented under their total
+            let name = match name.strip_prefix("  ") {
+                Some(kind) => format!("  {}", tr(kind)),
+                None => tr(name).to_string(),
+            };
+            Line::from(vec![format!("{:<14}", name).into(), value.bold()])
+        })
+        .collect();
+    let hints = Line::from(vec![hint("Back"), "<Esc> ".blue().bold()]);
+    let paragraph = Paragraph::new(lines).block(
+        Block::bordered()
+            .title(format!(" 📊 {} ", topic).bold())
+            .title_bottom(hints)
+            .padding(Padding::uniform(1))
+            .style(Style::default().fg(theme::palette().accent)),
+    );
+    dialog::render_centered(paragraph, area, buf);
+}
+
+// How far an import is, as cards gone through out of all, with a bar
+fn render_import_progress(area: Rect, buf: &mut Buffer, progress: Option<(usize, usize)>) {
+    const BAR_WIDTH: usize = 30;
+    let (done, total) = progress.unwrap_or_default();
+    let filled = (done * BAR_WIDTH).checked_div(total).unwrap_or(0);
+    let counts = match progress {
+        Some(_) => trf("{} of {}", &[&done, &git::cards_label(total)]),
+        None => tr("Reading the cards…").to_string(),
+    };
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(
+                "█".repeat(filled),
+                Style::default().fg(theme::palette().accent),
+            ),
+            Span::styled(
+                "░".repeat(BAR_WIDTH - filled),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]),
+        Line::from(counts),
+    ];
+    let hints = Line::from(vec![hint("Cancel"), "<C> ".red().bold()]);
+    let paragraph = Paragraph::new(lines).centered().block(
+        Block::bordered()
+            .title(format!(" 📥 {} ", tr("Importing")).bold())
+            .title_bottom(hints)
+            .padding(Padding::uniform(1)),
+    );
+    dialog::render_centered(paragraph, area, buf);
+}
+
+// The share code of `topic` as a QR code, with the code itself below it
+fn render_share(area: Rect, buf: &mut Buffer, topic: &str, code: &str) {
+    let hints = Line::from(vec![hint("Back"), "<Esc> ".blue().bold()]);
+    let block = Block::bordered()
+        .title(format!(" 📲 {} ", trf("Share '{}'", &[&topic])).bold())
+        .title_bottom(hints);
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let code_text = Paragraph::new(code).wrap(Wrap { trim: false });
+    let code_height = code_text.line_count(inner.width) as u16;
+    let qr = share::qr_lines(code).unwrap_or_default();
+    let qr_width = qr.first().map_or(0, |line| line.chars().count()) as u16;
+    let [qr_area, code_area] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(code_height.min(inner.height)),
+    ])
+    .areas(inner);
+    if qr.is_empty() || qr.len() as u16 > qr_area.height || qr_width > qr_area.width {
+        Paragraph::new("Too big for a QR code here, scan or copy the code below")
+            .centered()
+            .dark_gray()
+            .render(qr_area, buf);
+    } else {
+        // Dark modules on a light background, whatever the theme
+        let lines: Vec<Line> = qr.into_iter().map(Line::from).collect();
+        let [qr_area] = Layout::horizontal([Constraint::Length(qr_width)])
+            .flex(Flex::Center)
+            .areas(qr_area);
+        Paragraph::new(lines)
+            .style(Style::default().fg(Color::Black).bg(Color::White))
+            .render(qr_area, buf);
+    }
+    code_text.render(code_area, buf);
+}
+
+// Where `topic` is about to be exported, as a popup over the topic list
+fn render_export_topic(area: Rect, buf: &mut Buffer, topic: &str, format: ExportFormat) {
+    let lines = vec![
+        Line::from(format!("{}  ◀ {} ▶", tr("Format"), tr(format.label()))),
+        Line::from(""),
+        Line::from(vec![
+            hint("To:"),
+            export::export_path(topic, format)
+                .display()
+                .to_string()
+                .bold(),
+        ]),
+    ];
+    let hints = Line::from(vec![
+        hint("Change"),
+        "<←→>".blue().bold(),
+        hint("Export"),
+        "<Enter>".green().bold(),
+        hint("Cancel"),
+        "<Esc> ".red().bold(),
+    ]);
+    let paragraph = Paragraph::new(lines).block(
+        Block::bordered()
+            .title(format!(" 📤 {} ", trf("Export '{}'", &[&topic])).bold())
+            .title_bottom(hints)
+            .padding(Padding::uniform(1))
+            .style(Style::default().fg(theme::palette().accent)),
+    );
+    dialog::render_centered(paragraph, area, buf);
+}
+
+// Earlier wordings of `card` as a popup, newest first, the `selected` one
+// shown in full
+fn render_card_history(area: Rect, buf: &mut Buffer, card: &Flashcard, selected: usize) {
+    let mut lines = vec![
+        Line::from(vec![
+            hint("Now:"),
+            browser::first_line(&card.question).bold(),
+        ]),
+        Line::from(""),
+    ];
+    if card.revisions.is_empty() {
+        lines.push(Line::from("This card hasn't been edited").dark_gray());
+    }
+    for (i, revision) in card.revisions.iter().rev().enumerate() {
+        let date = stats::format_date(revision.replaced);
+        let line = Line::from(format!(
+            "{}  {} → {}",
+            date,
+            browser::first_line(&revision.question),
+            browser::first_line(&revision.answer)
+        ));
+        lines.push(if i == selected { line.reversed() } else { line });
+    }
+    let hints = Line::from(vec![
+        hint("Navigate"),
+        "<↑↓>".blue().bold(),
+        hint("Restore"),
+        "<Enter>".green().bold(),
+        hint("Back"),
+        "<Esc> ".red().bold(),
+    ]);
+    let paragraph = Paragraph::new(lines).block(
+        Block::bordered()
+            .title(format!(" 🕘 {} ", tr("Card history")).bold())
+            .title_bottom(hints)
+            .padding(Padding::uniform(1))
+            .style(Style::default().fg(theme::palette().accent)),
+    );
+    dialog::render_centered(paragraph, area, buf);
+}
+
+// `topic` as written to the log, which leaves out the topics of an
+// encrypted deck
+fn logged(topic: &str, key: Option<&Key>) -> String {
+    match key {
+        Some(_) => "a topic".to_string(),
+        None => format!("{:?}", topic),
+    }
+}
+
+// When the file at `path` was last changed, `None` if it doesn't exist
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
 // Represents different screens in the app
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 enum AppState {
+    Home,
     TopicSelection,
     FlashcardReview {
         topic: String,
         card_index: usize,
         show_answer: bool,
+        scroll: u16, // lines scrolled in the visible side
     },
     CreateTopic {
         input: String,
@@ -73,387 +1290,3622 @@ enum AppState {
         question_input: String,
         answer_input: String,
         editing_question: bool, // true = editing question, false = editing answer
+        cursor: usize,          // byte offset into the field being edited
+        // Card being reworded and the browser sort to go back to, `None`
+        // for a new card
+        editing: Option<(usize, CardSort)>,
+    },
+    Stats,
+    Decks {
+        selected: usize, // index into the recent decks
+        prompt: Option<(DeckPrompt, String)>,
+    },
+    GenerateCards {
+        topic: String,
+        input: String, // text or subject to draft cards from
+        waiting: bool, // sent, the drafts haven't come back yet
+    },
+    DraftedCards {
+        topic: String,
+        cards: Vec<Flashcard>,
+        index: usize, // card being shown
+        kept: usize,
+    },
+    BrowseCards {
+        topic: String,
+        selected: usize, // position in the sorted cards
+        sort: CardSort,
+    },
+    TopicSettings {
+        topic: String,
+        meta: TopicMeta, // as edited, saved on Enter
+        selected: usize, // setting row
+    },
+    CardHistory {
+        topic: String,
+        card_index: usize,
+        selected: usize, // revision, newest first
+        sort: CardSort,  // of the browser to go back to
+    },
+    Trash {
+        selected: usize, // newest first
+    },
+    ExportTopic {
+        topic: String,
+        format: ExportFormat,
+    },
+    TopicStats {
+        topic: String,
+    },
+    AttachFile {
+        topic: String,
+        card_index: usize,
+        sort: CardSort, // of the browser to go back to
+        input: String,  // path or URL being typed
+    },
+    ShareTopic {
+        topic: String,
+        code: String, // share code of the topic's cards
+    },
+    StudyAhead {
+        topic: String, // with nothing left to review today
+    },
+    Settings {
+        selected: usize, // setting row
+    },
+    TypingPractice {
+        typing: Typing,
     },
 }
 
 #[derive(Debug)]
 pub struct App {
+    // File the deck is saved to
+    deck_path: PathBuf,
+    // Key of the deck when it is encrypted
+    key: Option<Key>,
+    // Held while the deck is open, so no other instance opens it
+    lock: Option<DeckLock>,
+    // Nothing is ever written to the deck or its history
+    read_only: bool,
     topics: Topics,
+    // What of the deck changed since it was saved, to save only that
+    journal: Journal,
+    // Reads a big deck, whose cards are taken in once it is done
+    loader: Option<DeckLoader>,
+    // Where the deck file is copied before it is first saved over, when it
+    // couldn't be read
+    unreadable: Option<PathBuf>,
+    // Where the user left off, picked up once the deck has loaded
+    pending_resume: Option<ResumeState>,
     state: AppState,
     list_state: ListState,
+    graphics: Graphics,
+    // Image requested by the frame being rendered, drawn after it by `run`
+    image_slot: RefCell<Option<ImagePlacement>>,
+    // Largest scroll offset and page height of the scrolled review pane, as
+    // of the last frame
+    review_scroll: Cell<(u16, u16)>,
+    // Rows visible in the topic list, for paging
+    topic_page: Cell<u16>,
+    // Width of the AddCard fields as of the last frame, for moving the
+    // cursor through rows as they are shown
+    editor_width: Cell<u16>,
+    topic_sort: TopicSort,
+    // Archived topics are in the topic list too
+    show_archived: bool,
+    // Topic list in the order of `topic_sort`
+    topic_order: TopicOrder,
+    // Topics marked in the list to review together
+    marked_topics: Vec<String>,
+    speaker: Speaker,
+    // Checks the AddCard fields when a dictionary is configured
+    speller: Option<Speller>,
+    // Misspelled words in the AddCard fields, as of the last key press
+    misspelled: Vec<Misspelling>,
+    config: Config,
+    // File the settings screen saves the config to
+    config_path: Option<PathBuf>,
+    // The config file couldn't be read, so it isn't saved over with the
+    // defaults used instead: it may hold passwords and keys
+    config_unreadable: bool,
+    // File where the user left off is kept, written after every graded card
+    // as well as at exit
+    resume_path: Option<PathBuf>,
+    // Screen the add card hotkey was pressed on, to go back to once the
+    // card is saved or given up
+    add_return: Option<AppState>,
+    // Task importing cards into the deck, which waits for it before
+    // anything else changes the deck
+    importing: Option<u64>,
+    // Ruby readings can be hidden during review to test yourself
+    show_ruby: bool,
+    // Card number being typed after `:` in review
+    jump_input: Option<String>,
+    // Answer being typed in review, before the card is flipped
+    answer_input: Option<String>,
+    // Topic, index and typed answer of the card last answered by typing,
+    // compared with its answer once flipped
+    typed_answer: Option<(String, usize, String)>,
+    // The leader key was pressed, the rest of its sequence is awaited
+    leader: bool,
+    // Where review of each topic was left, to pick up there
+    review_positions: HashMap<String, ReviewPosition>,
+    // Most recent first, the open deck included
+    recent_decks: Vec<PathBuf>,
+    // Metadata of each recent deck, read when the deck switcher is opened
+    recent_meta: Vec<DeckMeta>,
+    session: Session,
+    // Pomodoro timer, while it is on
+    study_timer: Option<StudyTimer>,
+    // Shuffles the cards of topics reviewed in random order, picked anew for
+    // each session
+    shuffle_seed: u64,
+    // Days ahead the review is studying, bringing in their new cards today
+    ahead_days: u64,
+    // Cards of a review of several topics together, as topic and card
+    // index in the order they come up
+    mix: Option<Vec<(String, usize)>>,
+    // The reviewed topic's queue as it was when the topic opened, stepped
+    // through until it closes so grading doesn't reorder it
+    queue: Option<(String, Vec<usize>)>,
+    // Cards left by following `[[...]]` links, to go back to
+    link_back: Vec<(String, usize)>,
+    history: History,
+    // Daily accuracy of each topic for the topic list, worked out again
+    // when the history changes or the day does
+    accuracy: HashMap<String, Vec<Option<u64>>>,
+    accuracy_day: u64,
+    save_state: SaveState,
+    toasts: Toasts,
+    tasks: Tasks,
+    // Ticks since launch, for animations
+    ticks: u64,
+    // Card flip or slide in progress in review
+    animation: Option<Animation>,
+    // Error shown over the screen until dismissed
+    dialog: Option<ErrorDialog>,
+    // When the deck file was last changed, as of the last time it was read
+    // or written here
+    disk_modified: Option<SystemTime>,
+    // The deck as last read from or written to its file, that changes made
+    // here and by other programs are merged from
+    base: Topics,
+    // Cards changed both here and by another program, settled one at a
+    // time through `reload_prompt`
+    conflicts: Vec<Conflict>,
+    // Asks which side of the first of `conflicts` to keep
+    reload_prompt: Option<ConfirmDialog>,
+    // Asks whether to quit while tasks are still running
+    quit_prompt: Option<ConfirmDialog>,
+    // Internal state shown over the screen with F12, for bug reports
+    show_debug: bool,
+    last_event: Option<Event>,
     exit: bool,
 }
 
 impl App {
-    pub fn new(topics: Topics) -> App {
+    pub fn new(deck_path: PathBuf, topics: Topics, history: History, config: Config) -> App {
         let mut list_state = ListState::default();
         // Select first item by default if topics exist
         if !topics.topics_map.is_empty() {
             list_state.select(Some(0));
         }
+        let study_timer = config
+            .study_timer
+            .then(|| start_study_timer(&config, Instant::now()));
+        let recent_decks = vec![deck_path.clone()];
+        let now = history::now();
+        let accuracy = history.accuracy_by_topic(ACCURACY_DAYS, now);
 
         App {
+            disk_modified: modified_time(&deck_path),
+            journal: Journal::open(&topics, &deck_path),
+            base: topics.clone(),
+            loader: None,
+            unreadable: None,
+            pending_resume: None,
+            deck_path,
+            key: None,
+            lock: None,
+            read_only: false,
             topics,
-            state: AppState::TopicSelection,
+            state: AppState::Home,
             list_state,
+            graphics: Graphics::new(config.graphics),
+            image_slot: RefCell::new(None),
+            review_scroll: Cell::new((0, 0)),
+            topic_page: Cell::new(0),
+            editor_width: Cell::new(0),
+            topic_sort: TopicSort::default(),
+            show_archived: false,
+            topic_order: TopicOrder::default(),
+            marked_topics: Vec::new(),
+            speaker: Speaker::new(config.tts_command.clone()),
+            speller: None,
+            misspelled: Vec::new(),
+            config,
+            config_path: Config::path(),
+            config_unreadable: false,
+            resume_path: ResumeState::path(),
+            add_return: None,
+            importing: None,
+            show_ruby: true,
+            jump_input: None,
+            answer_input: None,
+            typed_answer: None,
+            leader: false,
+            review_positions: HashMap::new(),
+            recent_decks,
+            recent_meta: Vec::new(),
+            session: Session::default(),
+            study_timer,
+            shuffle_seed: 0,
+            ahead_days: 0,
+            mix: None,
+            queue: None,
+            link_back: Vec::new(),
+            accuracy,
+            accuracy_day: now / history::DAY,
+            history,
+            save_state: SaveState::Saved,
+            toasts: Toasts::default(),
+            tasks: Tasks::default(),
+            ticks: 0,
+            animation: None,
+            dialog: None,
+            conflicts: Vec::new(),
+            reload_prompt: None,
+            quit_prompt: None,
+            show_debug: false,
+            last_event: None,
             exit: false,
         }
     }
 
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        let mut last_tick = Instant::now();
         while !self.exit {
             terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
+            let image = self.image_slot.take();
+            if self.graphics.update(terminal.backend_mut(), image)? {
+                // Wipe a stale sixel and draw the frame again
+                terminal.clear()?;
+                terminal.draw(|frame| self.draw(frame))?;
+                let image = self.image_slot.take();
+                self.graphics.update(terminal.backend_mut(), image)?;
+            }
+
+            // Wait for input no longer than the rest of the tick, so the
+            // screen keeps updating without keypresses, or the next frame
+            // of an animation
+            let now = Instant::now();
+            self.animation = self.animation.filter(|a| a.progress(now).is_some());
+            let mut timeout = TICK_RATE.saturating_sub(last_tick.elapsed());
+            if self.animation.is_some() {
+                timeout = timeout.min(FRAME_TIME);
+            }
+            if event::poll(timeout)? {
+                self.handle_event(event::read()?);
+            }
+            if last_tick.elapsed() >= TICK_RATE {
+                self.on_tick(Instant::now());
+                last_tick = Instant::now();
+            }
         }
         Ok(())
     }
 
+    // `topic` as written to the log
+    fn logged(&self, topic: &str) -> String {
+        logged(topic, self.key.as_ref())
+    }
+
+    // Works out the accuracy shown in the topic list again, once per change
+    // to the history rather than on every frame
+    fn refresh_accuracy(&mut self) {
+        let now = history::now();
+        self.accuracy = self.history.accuracy_by_topic(ACCURACY_DAYS, now);
+        self.accuracy_day = now / history::DAY;
+    }
+
+    // Time-driven updates, run every tick whether or not there was input
+    fn on_tick(&mut self, now: Instant) {
+        self.ticks += 1;
+        if history::now() / history::DAY != self.accuracy_day {
+            self.refresh_accuracy();
+        }
+        self.toasts.prune(now);
+        // A paused session stops the study timer too
+        let timer = self
+            .study_timer
+            .as_mut()
+            .filter(|_| !self.session.is_paused());
+        match timer.and_then(|timer| timer.tick(now)) {
+            Some(Phase::Break) => {
+                let minutes = self.config.break_minutes;
+                self.toasts.info(trf(
+                    "Time for a {} minute break",
+                    &[&minutes.unwrap_or(timer::DEFAULT_BREAK_MINUTES)],
+                ));
+            }
+            Some(Phase::Focus) => self.toasts.info(tr("Break's over, back to the cards")),
+            None => {}
+        }
+        if self.ticks.is_multiple_of(WATCH_TICKS) {
+            self.check_deck_file();
+        }
+        if self.loader.as_ref().is_some_and(DeckLoader::is_done) {
+            self.finish_loading();
+        }
+        for task in self.tasks.poll() {
+            match task.outcome {
+                Outcome::Done(message) => {
+                    info!("{}: {}", task.label, message);
+                    self.toasts.info(message);
+                }
+                Outcome::Failed(message) => {
+                    error!("{}: {}", task.label, message);
+                    self.toasts.error(message);
+                }
+                Outcome::NewDeck(path) => {
+                    info!("{}: saved {}", task.label, path.display());
+                    self.toasts.info(trf(
+                        "Saved {}",
+                        &[&path.file_name().unwrap_or_default().to_string_lossy()],
+                    ));
+                    decks::remember(&mut self.recent_decks, &path);
+                    // Put the new deck in the list if the switcher is open
+                    if let AppState::Decks { prompt: None, .. } = self.state {
+                        self.show_decks();
+                    }
+                }
+                Outcome::Synced { deck, pulled } => self.finish_sync(&deck, *pulled),
+                Outcome::Drafted { topic, cards } => self.show_drafts(topic, cards),
+                Outcome::Imported {
+                    deck,
+                    topics,
+                    result,
+                } => self.finish_import(deck, *topics, result),
+            }
+        }
+        // Cancelled and failed imports end with a message like other tasks
+        if self
+            .importing
+            .is_some_and(|id| self.tasks.progress(id).is_none())
+        {
+            self.importing = None;
+        }
+    }
+
     fn draw(&self, frame: &mut Frame) {
         frame.render_widget(self, frame.area());
     }
 
-    fn handle_events(&mut self) -> io::Result<()> {
-        if let Event::Key(key_event) = event::read()? {
-            if key_event.kind == KeyEventKind::Press {
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                let before = self.reviewed_card();
                 self.handle_key_event(key_event);
+                self.animate(before);
             }
+            // The next draw picks up the new size and lays the screen out again
+            Event::Resize(_, _) => {}
+            _ => {}
         }
-        Ok(())
+        self.last_event = Some(event);
     }
 
-    fn handle_key_event(&mut self, key_event: KeyEvent) {
-        match &self.state.clone() {
-            AppState::TopicSelection => self.handle_topic_selection_keys(key_event),
+    // Topic, card index and whether the answer shows, during review
+    fn reviewed_card(&self) -> Option<(String, usize, bool)> {
+        match &self.state {
             AppState::FlashcardReview {
                 topic,
                 card_index,
                 show_answer,
-            } => self.handle_flashcard_keys(key_event, topic, *card_index, *show_answer),
-            AppState::CreateTopic { input } => self.handle_create_topic_keys(key_event, &input),
-            AppState::AddCard {
-                topic,
-                question_input,
-                answer_input,
-                editing_question,
-            } => self.handle_add_card_keys(
-                key_event,
-                topic,
-                question_input,
-                answer_input,
-                *editing_question,
-            ),
+                ..
+            } => Some((topic.clone(), *card_index, *show_answer)),
+            _ => None,
         }
     }
 
-    fn handle_topic_selection_keys(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Char('q') => self.exit = true,
-            KeyCode::Char('n') => {
-                self.state = AppState::CreateTopic {
-                    input: String::new(),
-                };
+    // Starts the animation for going from the card `before` to the one now
+    // reviewed, if any
+    fn animate(&mut self, before: Option<(String, usize, bool)>) {
+        if self.config.reduce_motion {
+            return;
+        }
+        let (Some(before), Some(after)) = (before, self.reviewed_card()) else {
+            return;
+        };
+        let transition = if (&before.0, before.1) != (&after.0, after.1) {
+            let from = self.review_position(&before.0, before.1).0;
+            let to = self.review_position(&after.0, after.1).0;
+            Transition::Slide {
+                forward: to >= from,
             }
-            KeyCode::Char('a') => {
-                // Add card to selected topic
-                if let Some(selected) = self.list_state.selected() {
-                    let topic_name = self.get_sorted_topics()[selected].clone();
-                    self.state = AppState::AddCard {
-                        topic: topic_name,
-                        question_input: String::new(),
-                        answer_input: String::new(),
-                        editing_question: true,
-                    };
+        } else if !before.2 && after.2 {
+            Transition::Flip
+        } else {
+            return;
+        };
+        self.animation = Some(Animation::start(transition, Instant::now()));
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if key_event.code == KeyCode::F(12) {
+            self.show_debug = !self.show_debug;
+            return;
+        }
+
+        // An open dialog takes every key until it is dismissed
+        if let Some(dialog) = &self.dialog {
+            match key_event.code {
+                KeyCode::Enter | KeyCode::Esc => self.dialog = None,
+                KeyCode::Char('q') if dialog.can_quit => self.exit = true,
+                _ => {}
+            }
+            return;
+        }
+        if self.reload_prompt.is_some() {
+            match key_event.code {
+                // The merged deck already has this side
+                KeyCode::Char('y') => {
+                    self.conflicts.remove(0);
                 }
+                KeyCode::Char('n') => {
+                    let conflict = self.conflicts.remove(0);
+                    merge::take_theirs(&mut self.topics, &conflict);
+                    self.leave_changed_cards(&[conflict.topic]);
+                }
+                // Mine for the rest
+                KeyCode::Esc => self.conflicts.clear(),
+                _ => return,
             }
-            KeyCode::Enter => {
-                // Enter topic for flashcard review
-                if let Some(selected) = self.list_state.selected() {
-                    let topic_name = self.get_sorted_topics()[selected].clone();
-
-                    // Only enter if topic has cards
-                    if let Some(cards) = self.topics.topics_map.get(&topic_name) {
-                        if !cards.is_empty() {
-                            self.state = AppState::FlashcardReview {
-                                topic: topic_name,
-                                card_index: 0,
-                                show_answer: false,
-                            };
-                        }
-                    }
+            self.reload_prompt = None;
+            self.settle_conflicts();
+            return;
+        }
+        if self.quit_prompt.is_some() {
+            match key_event.code {
+                KeyCode::Char('y') => {
+                    self.quit_prompt = None;
+                    self.quit_now();
                 }
+                KeyCode::Char('n') | KeyCode::Esc => self.quit_prompt = None,
+                _ => {}
             }
-            KeyCode::Down | KeyCode::Char('j') => self.select_next_topic(),
-            KeyCode::Up | KeyCode::Char('k') => self.select_previous_topic(),
-            _ => {}
+            return;
         }
-    }
 
-    fn handle_flashcard_keys(
-        &mut self,
-        key_event: KeyEvent,
-        topic: &str,
-        card_index: usize,
-        show_answer: bool,
-    ) {
-        match key_event.code {
-            KeyCode::Char('q') | KeyCode::Esc => {
-                self.state = AppState::TopicSelection;
+        // Ctrl+A adds a card from any screen but the editor itself
+        if key_event.code == KeyCode::Char('a')
+            && key_event.modifiers.contains(KeyModifiers::CONTROL)
+            && !matches!(self.state, AppState::AddCard { .. })
+        {
+            self.quick_add();
+            return;
+        }
+
+        if self.leader {
+            self.leader = false;
+            if let KeyCode::Char(c) = key_event.code
+                && let Some(sequence) = keys::sequence(c)
+            {
+                self.follow_sequence(sequence);
             }
-            KeyCode::Char(' ') | KeyCode::Enter => {
-                // Toggle answer visibility
-                self.state = AppState::FlashcardReview {
-                    topic: topic.to_string(),
-                    card_index,
-                    show_answer: !show_answer,
-                };
+            return;
+        }
+        let takes_sequences = matches!(
+            self.state,
+            AppState::Home
+                | AppState::TopicSelection
+                | AppState::Stats
+                | AppState::BrowseCards { .. }
+                | AppState::Trash { .. }
+        );
+        if takes_sequences && key_event.code == KeyCode::Char(keys::LEADER) {
+            self.leader = true;
+            return;
+        }
+
+        // Handlers edit the state in place, so only what they need to call
+        // other methods with is copied out of it
+        match &self.state {
+            AppState::Home => self.handle_home_keys(key_event),
+            AppState::TopicSelection => self.handle_topic_selection_keys(key_event),
+            AppState::FlashcardReview {
+                topic,
+                card_index,
+                show_answer,
+                scroll,
+            } => {
+                let (topic, card_index, show_answer, scroll) =
+                    (topic.clone(), *card_index, *show_answer, *scroll);
+                self.handle_flashcard_keys(key_event, &topic, card_index, show_answer, scroll)
             }
-            KeyCode::Char('n') | KeyCode::Right => {
-                // Next card
-                if let Some(cards) = self.topics.topics_map.get(topic) {
-                    let next_index = (card_index + 1) % cards.len();
-                    self.state = AppState::FlashcardReview {
-                        topic: topic.to_string(),
-                        card_index: next_index,
-                        show_answer: false,
-                    };
+            AppState::CreateTopic { .. } => self.handle_create_topic_keys(key_event),
+            AppState::AddCard { .. } => {
+                self.handle_add_card_keys(key_event);
+                self.check_spelling();
+            }
+            AppState::GenerateCards { .. } => self.handle_generate_keys(key_event),
+            AppState::DraftedCards { .. } => self.handle_drafted_keys(key_event),
+            AppState::BrowseCards {
+                topic,
+                selected,
+                sort,
+            } => {
+                let (topic, selected, sort) = (topic.clone(), *selected, *sort);
+                self.handle_browse_keys(key_event, &topic, selected, sort)
+            }
+            AppState::TopicSettings { .. } => self.handle_topic_settings_keys(key_event),
+            AppState::Stats => match key_event.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.state = AppState::TopicSelection,
+                KeyCode::Char('e') => self.export_stats(),
+                _ => {}
+            },
+            AppState::Decks { .. } => self.handle_deck_keys(key_event),
+            AppState::CardHistory { .. } => self.handle_card_history_keys(key_event),
+            AppState::Trash { .. } => self.handle_trash_keys(key_event),
+            AppState::Settings { .. } => self.handle_settings_keys(key_event),
+            AppState::TypingPractice { .. } => self.handle_typing_keys(key_event),
+            AppState::AttachFile { .. } => self.handle_attach_keys(key_event),
+            AppState::TopicStats { .. } => {
+                if matches!(
+                    key_event.code,
+                    KeyCode::Esc | KeyCode::Char('q' | 'i') | KeyCode::Enter
+                ) {
+                    self.state = AppState::TopicSelection;
                 }
             }
-            KeyCode::Char('p') | KeyCode::Left => {
-                // Previous card
-                if let Some(cards) = self.topics.topics_map.get(topic) {
-                    let prev_index = if card_index == 0 {
-                        cards.len() - 1
-                    } else {
-                        card_index - 1
-                    };
-                    self.state = AppState::FlashcardReview {
-                        topic: topic.to_string(),
-                        card_index: prev_index,
-                        show_answer: false,
-                    };
+            AppState::ShareTopic { .. } => {
+                if matches!(key_event.code, KeyCode::Esc | KeyCode::Char('q')) {
+                    self.state = AppState::TopicSelection;
                 }
             }
-            _ => {}
+            AppState::StudyAhead { topic } => match key_event.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    let topic = topic.clone();
+                    self.state = AppState::TopicSelection;
+                    self.open_review(topic, self.study_ahead_days());
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.state = AppState::TopicSelection,
+                _ => {}
+            },
+            AppState::ExportTopic { topic, format } => match key_event.code {
+                KeyCode::Left | KeyCode::Char('h') => {
+                    let (topic, format) = (topic.clone(), format.previous());
+                    self.state = AppState::ExportTopic { topic, format };
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    let (topic, format) = (topic.clone(), format.next());
+                    self.state = AppState::ExportTopic { topic, format };
+                }
+                KeyCode::Enter => {
+                    let (topic, format) = (topic.clone(), *format);
+                    self.export_topic(topic, format);
+                    self.state = AppState::TopicSelection;
+                }
+                KeyCode::Esc | KeyCode::Char('q') => self.state = AppState::TopicSelection,
+                _ => {}
+            },
         }
     }
 
-    fn handle_create_topic_keys(&mut self, key_event: KeyEvent, current_input: &str) {
-        let mut input = current_input.to_string();
+    fn handle_deck_keys(&mut self, key_event: KeyEvent) {
+        let AppState::Decks { selected, prompt } = &mut self.state else {
+            return;
+        };
+        let selected = *selected;
+        if let Some((kind, input)) = prompt {
+            let kind = *kind;
+            match key_event.code {
+                KeyCode::Enter if kind == DeckPrompt::Fetch && !input.trim().is_empty() => {
+                    let url = input.trim().to_string();
+                    self.fetch_deck(url);
+                    self.state = AppState::Decks {
+                        selected,
+                        prompt: None,
+                    };
+                }
+                KeyCode::Enter if kind == DeckPrompt::Import && !input.trim().is_empty() => {
+                    let path = PathBuf::from(input.trim());
+                    self.import_path(path);
+                    self.state = AppState::Decks {
+                        selected,
+                        prompt: None,
+                    };
+                }
+                KeyCode::Enter if !input.trim().is_empty() => {
+                    // The prompt stays open if the deck can't be opened
+                    let path = PathBuf::from(input.trim());
+                    if kind == DeckPrompt::Open && bundle::is_bundle(&path) {
+                        self.open_bundle(&path);
+                    } else {
+                        self.open_deck(path, kind == DeckPrompt::New);
+                    }
+                }
+                KeyCode::Esc => *prompt = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return;
+        }
 
+        let last = self.recent_decks.len().saturating_sub(1);
         match key_event.code {
-            KeyCode::Esc => {
-                self.state = AppState::TopicSelection;
+            KeyCode::Char('q') | KeyCode::Esc => self.state = AppState::TopicSelection,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.state = AppState::Decks {
+                    selected: (selected + 1).min(last),
+                    prompt: None,
+                };
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.state = AppState::Decks {
+                    selected: selected.saturating_sub(1),
+                    prompt: None,
+                };
             }
             KeyCode::Enter => {
-                if !input.trim().is_empty() {
-                    // Create new topic
-                    self.topics
-                        .topics_map
-                        .insert(input.trim().to_string(), Vec::new());
-                    self.state = AppState::TopicSelection;
-                    // Select the newly created topic
-                    self.update_list_selection();
+                if let Some(path) = self.recent_decks.get(selected).cloned() {
+                    self.open_deck(path, false);
                 }
             }
-            KeyCode::Char(c) => {
-                input.push(c);
-                self.state = AppState::CreateTopic { input };
+            KeyCode::Char('o') => {
+                self.state = AppState::Decks {
+                    selected,
+                    prompt: Some((DeckPrompt::Open, String::new())),
+                };
             }
-            KeyCode::Backspace => {
-                input.pop();
-                self.state = AppState::CreateTopic { input };
+            KeyCode::Char('c') if let Some(id) = self.importing => self.tasks.cancel(id),
+            KeyCode::Char('n' | 'f' | 's' | 'i') if self.refuse_read_only() => {}
+            KeyCode::Char('n') => {
+                self.state = AppState::Decks {
+                    selected,
+                    prompt: Some((DeckPrompt::New, String::new())),
+                };
+            }
+            KeyCode::Char('b') => self.bundle_deck(),
+            KeyCode::Char('h') => self.export_page(),
+            KeyCode::Char('s') => self.sync_deck(),
+            KeyCode::Char('f') => {
+                self.state = AppState::Decks {
+                    selected,
+                    prompt: Some((DeckPrompt::Fetch, String::new())),
+                };
+            }
+            KeyCode::Char('i') => {
+                self.state = AppState::Decks {
+                    selected,
+                    prompt: Some((DeckPrompt::Import, String::new())),
+                };
             }
             _ => {}
         }
     }
 
-    fn handle_add_card_keys(
-        &mut self,
-        key_event: KeyEvent,
-        topic: &str,
-        question: &str,
-        answer: &str,
-        editing_question: bool,
-    ) {
+    fn handle_home_keys(&mut self, key_event: KeyEvent) {
         match key_event.code {
-            KeyCode::Esc => {
-                self.state = AppState::TopicSelection;
+            KeyCode::Char('q') => self.quit(),
+            KeyCode::Enter | KeyCode::Char('t') => self.state = AppState::TopicSelection,
+            KeyCode::Char('c') => {
+                if let Some(topic) = self.continue_topic() {
+                    self.start_review(topic);
+                }
             }
-
-            KeyCode::Tab => {
-                // Switch between question and answer input
-                self.state = AppState::AddCard {
-                    topic: topic.to_string(),
-                    question_input: question.to_string(),
-                    answer_input: answer.to_string(),
-                    editing_question: !editing_question,
+            KeyCode::Char('a' | 'i') if self.refuse_read_only() => {}
+            KeyCode::Char('a') => match self.continue_topic() {
+                Some(topic) => self.add_card(topic),
+                None => self
+                    .toasts
+                    .error(tr("Create a topic to add cards to first")),
+            },
+            KeyCode::Char('i') => {
+                // Decks are imported by downloading them into the switcher
+                self.show_decks();
+                self.state = AppState::Decks {
+                    selected: 0,
+                    prompt: Some((DeckPrompt::Fetch, String::new())),
                 };
             }
+            KeyCode::Char('s') => self.state = AppState::Stats,
+            KeyCode::Char('d') => self.show_decks(),
+            KeyCode::Char(',') => self.state = AppState::Settings { selected: 0 },
+            _ => {}
+        }
+    }
 
-            // KeyCode::Enter
-            // // this is on macos: SHIFT+OPTION+ENTER
-            //     if key_event
-            //         .modifiers
-            //         .contains(crossterm::event::KeyModifiers::ALT) =>
-            KeyCode::Enter => {
-                // Plain Enter: Add newline
-                if editing_question {
-                    let mut q = question.to_string();
-                    q.push('\n');
-                    self.state = AppState::AddCard {
-                        topic: topic.to_string(),
-                        question_input: q,
-                        answer_input: answer.to_string(),
-                        editing_question,
-                    };
-                } else {
-                    let mut a = answer.to_string();
-                    a.push('\n');
-                    self.state = AppState::AddCard {
-                        topic: topic.to_string(),
-                        question_input: question.to_string(),
-                        answer_input: a,
-                        editing_question,
-                    };
-                }
+    // Changes take effect and are saved to the config file right away
+    fn handle_settings_keys(&mut self, key_event: KeyEvent) {
+        let AppState::Settings { selected } = &mut self.state else {
+            return;
+        };
+        let up = match key_event.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                *selected = (*selected + 1).min(SETTINGS.len() - 1);
+                return;
             }
-
-            KeyCode::Char('s')
-            // CONTROL + S on macos
-                if key_event
-                    .modifiers
-                    .intersects(KeyModifiers::CONTROL | KeyModifiers::SUPER) =>
-            {
-                // Ctrl+S or Cmd+S: Save card
-                if !question.trim().is_empty() && !answer.trim().is_empty() {
-                    let flashcard = Flashcard {
-                        question: question.trim().to_string(),
-                        answer: answer.trim().to_string(),
-                    };
-
-                    if let Some(cards) = self.topics.topics_map.get_mut(topic) {
-                        cards.push(flashcard);
-                    }
-
-                    let _ = self.save_to_disk();
-                    self.state = AppState::TopicSelection;
-                }
+            KeyCode::Up | KeyCode::Char('k') => {
+                *selected = selected.saturating_sub(1);
+                return;
             }
-
-            KeyCode::Char(c) => {
-                if editing_question {
-                    let mut q = question.to_string();
-                    q.push(c);
-                    self.state = AppState::AddCard {
-                        topic: topic.to_string(),
-                        question_input: q,
-                        answer_input: answer.to_string(),
-                        editing_question,
-                    };
-                } else {
-                    let mut a = answer.to_string();
-                    a.push(c);
-                    self.state = AppState::AddCard {
-                        topic: topic.to_string(),
-                        question_input: question.to_string(),
-                        answer_input: a,
-                        editing_question,
-                    };
-                }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.state = AppState::Home;
+                return;
             }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter | KeyCode::Char(' ') => true,
+            KeyCode::Left | KeyCode::Char('h') => false,
+            _ => return,
+        };
+        let setting = SETTINGS[*selected];
+        setting.change(&mut self.config, up);
+        info!("changed the setting {:?}", setting);
+        if self.config_unreadable {
+            self.toasts
+                .error(tr("Not saved, the config file couldn't be read"));
+            return;
+        }
+        let Some(path) = &self.config_path else {
+            self.toasts
+                .error(tr("No config directory to save settings to"));
+            return;
+        };
+        if let Err(e) = self.config.save(path) {
+            error!("saving {}: {}", path.display(), e);
+            self.toasts
+                .error(trf("Couldn't save the settings: {}", &[&e]));
+        }
+    }
 
-            KeyCode::Backspace => {
-                if editing_question {
-                    let mut q = question.to_string();
-                    q.pop();
-                    self.state = AppState::AddCard {
-                        topic: topic.to_string(),
-                        question_input: q,
-                        answer_input: answer.to_string(),
-                        editing_question,
-                    };
+    // Goes where a sequence of keys after the leader leads. Cards are
+    // drafted for and browsed in the topic selected in the list.
+    fn follow_sequence(&mut self, sequence: Sequence) {
+        let selected = self
+            .list_state
+            .selected()
+            .and_then(|i| self.get_sorted_topics().get(i).cloned());
+        match sequence {
+            Sequence::Home => self.state = AppState::Home,
+            Sequence::Topics => self.state = AppState::TopicSelection,
+            Sequence::Stats => self.state = AppState::Stats,
+            Sequence::Decks => self.show_decks(),
+            Sequence::Trash => self.state = AppState::Trash { selected: 0 },
+            Sequence::Generate if self.refuse_read_only() => {}
+            Sequence::Generate | Sequence::Browse => {
+                let Some(topic) = selected else {
+                    self.toasts.error(tr("Select a topic first"));
+                    return;
+                };
+                self.state = if sequence == Sequence::Generate {
+                    AppState::GenerateCards {
+                        topic,
+                        input: String::new(),
+                        waiting: false,
+                    }
                 } else {
-                    let mut a = answer.to_string();
-                    a.pop();
-                    self.state = AppState::AddCard {
-                        topic: topic.to_string(),
-                        question_input: question.to_string(),
-                        answer_input: a,
-                        editing_question,
-                    };
-                }
+                    AppState::BrowseCards {
+                        topic,
+                        selected: 0,
+                        sort: CardSort::default(),
+                    }
+                };
             }
-            _ => {}
         }
     }
 
-    fn select_next_topic(&mut self) {
-        let topics_count = self.topics.topics_map.len();
-        if topics_count == 0 {
+    // Topic the home screen offers to go on with: the last one reviewed,
+    // else the one selected in the list
+    fn continue_topic(&self) -> Option<String> {
+        let has_cards = |topic: &str| {
+            !self.topics.is_archived(topic)
+                && self
+                    .topics
+                    .topics_map
+                    .get(topic)
+                    .is_some_and(|c| !c.is_empty())
+        };
+        let last = self.history.reviews().last().map(|review| &review.topic);
+        let selected = self
+            .list_state
+            .selected()
+            .and_then(|i| self.get_sorted_topics().get(i).cloned());
+        last.filter(|topic| has_cards(topic))
+            .cloned()
+            .or(selected.filter(|topic| has_cards(topic)))
+    }
+
+    // Saves and closes the app, or stays open to show the error if the deck
+    // can't be saved. Asks first while tasks are running.
+    fn quit(&mut self) {
+        if self.tasks.is_busy() {
+            self.quit_prompt = Some(ConfirmDialog {
+                title: tr("Still working").to_string(),
+                message: trf(
+                    "{} tasks are still running. Quitting stops them and waits for them to finish.",
+                    &[&self.tasks.count()],
+                ),
+                yes: "Quit",
+                no: "Keep working",
+            });
             return;
         }
+        self.quit_now();
+    }
 
-        let i = match self.list_state.selected() {
-            Some(i) => (i + 1) % topics_count,
-            None => 0,
+    fn quit_now(&mut self) {
+        if self.save_state == SaveState::Saved || self.save() {
+            self.exit = true;
+        } else if let Some(dialog) = &mut self.dialog {
+            dialog.can_quit = true;
+        }
+    }
+
+    // Opens the editor on a new card for `topic`, starting from its template
+    fn add_card(&mut self, topic: String) {
+        let template = self.topics.meta_of(&topic).template;
+        let (question_input, answer_input) = template
+            .map(|card| (card.question, card.answer))
+            .unwrap_or_default();
+        self.state = AppState::AddCard {
+            topic,
+            cursor: question_input.len(),
+            question_input,
+            answer_input,
+            editing_question: true,
+            editing: None,
         };
-        self.list_state.select(Some(i));
     }
 
-    fn select_previous_topic(&mut self) {
-        let topics_count = self.topics.topics_map.len();
-        if topics_count == 0 {
+    // Opens the editor on a new card for the topic on screen, coming back
+    // to the screen afterwards
+    fn quick_add(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let Some(topic) = self.current_topic() else {
+            self.toasts
+                .error(tr("Create a topic to add cards to first"));
             return;
+        };
+        // The editor replaces the screen, which is kept to go back to
+        let back = mem::replace(&mut self.state, AppState::TopicSelection);
+        self.add_card(topic);
+        self.add_return = Some(back);
+    }
+
+    // Topic the screen is about, or else the one selected in the list or
+    // last reviewed
+    fn current_topic(&self) -> Option<String> {
+        match &self.state {
+            AppState::FlashcardReview { topic, .. }
+            | AppState::GenerateCards { topic, .. }
+            | AppState::DraftedCards { topic, .. }
+            | AppState::BrowseCards { topic, .. }
+            | AppState::TopicSettings { topic, .. }
+            | AppState::CardHistory { topic, .. }
+            | AppState::ExportTopic { topic, .. }
+            | AppState::TopicStats { topic }
+            | AppState::AttachFile { topic, .. }
+            | AppState::ShareTopic { topic, .. } => Some(topic.clone()),
+            AppState::TopicSelection => self
+                .list_state
+                .selected()
+                .and_then(|i| self.get_sorted_topics().get(i).cloned()),
+            _ => self.continue_topic(),
         }
+    }
 
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    topics_count - 1
+    // Closes the editor on a new card, back to where it was opened from
+    fn leave_add_card(&mut self) {
+        self.state = self.add_return.take().unwrap_or(AppState::TopicSelection);
+    }
+
+    fn handle_topic_selection_keys(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('q') => self.quit(),
+            KeyCode::Esc => self.state = AppState::Home,
+            KeyCode::Char('n' | 'a' | 't' | 'x' | 'z') | KeyCode::Delete
+                if self.refuse_read_only() => {}
+            KeyCode::Char('n') => {
+                self.state = AppState::CreateTopic {
+                    input: String::new(),
+                };
+            }
+            KeyCode::Char('a') => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    self.add_card(topic);
+                }
+            }
+            KeyCode::Char('b') => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    self.state = AppState::BrowseCards {
+                        topic,
+                        selected: 0,
+                        sort: CardSort::default(),
+                    };
+                }
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    self.trash_topic(&topic);
+                }
+            }
+            KeyCode::Char('z') => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    self.toggle_archived(&topic);
+                }
+            }
+            KeyCode::Char('Z') => {
+                // Keeping the same topic selected, unless it is hidden now
+                let selected = self.list_state.selected();
+                let topic = selected.and_then(|i| self.get_sorted_topics().get(i).cloned());
+                self.show_archived = !self.show_archived;
+                self.update_list_selection();
+                if let Some(topic) = topic {
+                    self.select_topic(&topic);
+                }
+                self.toasts.info(tr(if self.show_archived {
+                    "Showing archived topics"
                 } else {
-                    i - 1
+                    "Hiding archived topics"
+                }));
+            }
+            KeyCode::Char('i') => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    self.state = AppState::TopicStats { topic };
                 }
             }
-            None => 0,
+            KeyCode::Char('y') => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    let cards = &self.topics.topics_map[&topic];
+                    match share::encode(&topic, cards) {
+                        Ok(code) => self.state = AppState::ShareTopic { topic, code },
+                        Err(e) => self.toasts.error(trf("Couldn't share: {}", &[&e])),
+                    }
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    self.state = AppState::ExportTopic {
+                        topic,
+                        format: ExportFormat::default(),
+                    };
+                }
+            }
+            KeyCode::Char('t') => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    self.state = AppState::TopicSettings {
+                        meta: self.topics.meta_of(&topic),
+                        topic,
+                        selected: 0,
+                    };
+                }
+            }
+            KeyCode::Enter => {
+                // Enter topic for flashcard review
+                if let Some(selected) = self.list_state.selected() {
+                    let topic_name = self.get_sorted_topics()[selected].clone();
+                    self.start_review(topic_name);
+                }
+            }
+            KeyCode::Char('s') => self.state = AppState::Stats,
+            KeyCode::Char('d') => self.show_decks(),
+            KeyCode::Char('p') => {
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    self.start_typing(topic);
+                }
+            }
+            KeyCode::Char(' ') => {
+                // Mark or unmark the topic for reviewing topics together
+                if let Some(selected) = self.list_state.selected() {
+                    let topic = self.get_sorted_topics()[selected].clone();
+                    match self.marked_topics.iter().position(|t| *t == topic) {
+                        Some(i) => {
+                            self.marked_topics.remove(i);
+                        }
+                        None => self.marked_topics.push(topic),
+                    }
+                }
+            }
+            KeyCode::Char('m') => self.start_mix(Interleave::RoundRobin),
+            KeyCode::Char('M') => self.start_mix(Interleave::Shuffled),
+            KeyCode::Char('r') => self.start_recent(),
+            KeyCode::Char('o') => {
+                // Next sort, keeping the same topic selected
+                let selected = self.list_state.selected();
+                let topic = selected.and_then(|i| self.get_sorted_topics().get(i).cloned());
+                self.topic_sort = self.topic_sort.next();
+                if let Some(topic) = topic {
+                    self.select_topic(&topic);
+                }
+                self.toasts
+                    .info(trf("Topics sorted by {}", &[&tr(self.topic_sort.label())]));
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.select_next_topic(),
+            KeyCode::Up | KeyCode::Char('k') => self.select_previous_topic(),
+            KeyCode::PageDown => self.select_topic_page(true),
+            KeyCode::PageUp => self.select_topic_page(false),
+            KeyCode::Home => self.update_list_selection(),
+            KeyCode::End => {
+                let topics_count = self.get_sorted_topics().len();
+                if topics_count > 0 {
+                    self.list_state.select(Some(topics_count - 1));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Typing practice through the cards of `topic` that have an answer to
+    // type, in their order in the deck
+    fn start_typing(&mut self, topic: String) {
+        let cards = self
+            .topics
+            .topics_map
+            .get(&topic)
+            .map_or(&[][..], Vec::as_slice);
+        match typing::next_to_type(cards, 0) {
+            Some(card_index) => {
+                self.state = AppState::TypingPractice {
+                    typing: Typing::new(topic, card_index),
+                };
+            }
+            None => self.toasts.info(trf("No answers to type in {}", &[&topic])),
+        }
+    }
+
+    fn handle_typing_keys(&mut self, key_event: KeyEvent) {
+        let AppState::TypingPractice { typing } = &mut self.state else {
+            return;
+        };
+        let cards = self
+            .topics
+            .topics_map
+            .get(&typing.topic)
+            .map_or(&[][..], Vec::as_slice);
+        let target = cards
+            .get(typing.card_index)
+            .map_or(String::new(), |card| typing::target(&card.answer));
+        let now = Instant::now();
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Tab => {}
+            KeyCode::Backspace => typing.backspace(),
+            KeyCode::Enter => typing.type_char('\n', &target, now),
+            KeyCode::Char(c) if !key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                typing.type_char(c, &target, now);
+            }
+            _ => return,
+        }
+        // On to the next card once this one is typed out or skipped. The run
+        // ends after the last card, or on Esc.
+        let next = match key_event.code {
+            KeyCode::Esc => None,
+            KeyCode::Tab => typing::next_to_type(cards, typing.card_index + 1),
+            _ if typing.is_complete(&target) => typing::next_to_type(cards, typing.card_index + 1),
+            _ => return,
+        };
+        let stats = typing.stats(&target, now);
+        typing.next_card(next.unwrap_or(typing.card_index), &target, now);
+        if next.is_none() {
+            let done = typing.done;
+            self.state = AppState::TopicSelection;
+            if done > 0 {
+                self.toasts
+                    .info(trf("Typed {}: {}", &[&git::cards_label(done), &stats]));
+            }
+        }
+    }
+
+    fn handle_flashcard_keys(
+        &mut self,
+        key_event: KeyEvent,
+        topic: &str,
+        card_index: usize,
+        show_answer: bool,
+        scroll: u16,
+    ) {
+        // Any key goes on from a pause
+        if self.session.is_paused() {
+            let away = self.session.resume(Instant::now());
+            if let Some(timer) = &mut self.study_timer {
+                timer.delay(away);
+            }
+            return;
+        }
+        if let Some(input) = self.jump_input.take() {
+            self.handle_jump_keys(key_event, topic, input);
+            return;
+        }
+        if let Some(input) = self.answer_input.take() {
+            self.handle_answer_keys(key_event, topic, card_index, input);
+            return;
+        }
+
+        let (max_scroll, page) = self.review_scroll.get();
+        let scroll_to = |scroll: u16| AppState::FlashcardReview {
+            topic: topic.to_string(),
+            card_index,
+            show_answer,
+            scroll: scroll.min(max_scroll),
+        };
+
+        match key_event.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.speaker.stop();
+                // Remember the place for the next time this topic is opened
+                let position = ReviewPosition { card_index, scroll };
+                self.review_positions.insert(topic.to_string(), position);
+                self.mix = None;
+                self.queue = None;
+                self.link_back.clear();
+                self.state = AppState::TopicSelection;
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                // Toggle answer visibility
+                self.state = AppState::FlashcardReview {
+                    topic: topic.to_string(),
+                    card_index,
+                    show_answer: !show_answer,
+                    scroll: 0,
+                };
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.state = scroll_to(scroll.saturating_add(1)),
+            KeyCode::Char('k') | KeyCode::Up => self.state = scroll_to(scroll.saturating_sub(1)),
+            KeyCode::PageDown => self.state = scroll_to(scroll.saturating_add(page.max(1))),
+            KeyCode::PageUp => self.state = scroll_to(scroll.saturating_sub(page.max(1))),
+            KeyCode::Char(c @ ('y' | 'm')) if show_answer => {
+                // Grade the card as known or missed and move on
+                let correct = c == 'y';
+                let spent = self
+                    .session
+                    .grade(self.session_key(topic, card_index), correct);
+                if let Some(timer) = &mut self.study_timer {
+                    timer.graded += 1;
+                }
+                if let Some(card) = self
+                    .topics
+                    .topics_map
+                    .get(topic)
+                    .and_then(|cards| cards.get(card_index))
+                {
+                    let review = Review {
+                        time: history::now(),
+                        topic: topic.to_string(),
+                        question: card.question.clone(),
+                        correct,
+                        duration_ms: spent.as_millis() as u64,
+                        count: 1,
+                    };
+                    debug!(
+                        "graded {} card {}: {}",
+                        self.logged(topic),
+                        card_index,
+                        correct
+                    );
+                    if self.read_only {
+                        // Grading still moves on, it just isn't recorded
+                    } else if let Err(e) = self.history.append(review) {
+                        error!("recording review: {}", e);
+                        self.toasts.error(trf("Couldn't record review: {}", &[&e]));
+                    } else {
+                        self.refresh_accuracy();
+                    }
+                }
+                self.next_after_grade(topic, card_index);
+                // The grade is in the history already, this keeps the place
+                // in the review should the terminal go away
+                self.save_progress();
+            }
+            KeyCode::Char('n') | KeyCode::Right => self.step_card(topic, card_index, true),
+            KeyCode::Char('p') | KeyCode::Left => self.step_card(topic, card_index, false),
+            KeyCode::Char('s') => {
+                // Speak the visible side of the card
+                if let Some(card) = self
+                    .topics
+                    .topics_map
+                    .get(topic)
+                    .and_then(|cards| cards.get(card_index))
+                {
+                    let reversed = self.topics.meta_of(topic).reversed;
+                    let text = if show_answer != reversed {
+                        &card.answer
+                    } else {
+                        &card.question
+                    };
+                    let (text, _) = markup::split_images(text);
+                    let text = markup::ruby_readings(&markup::render_math(&text));
+                    if let Err(e) = self.speaker.speak(&text) {
+                        warn!("speaking card: {}", e);
+                        self.toasts.error(trf("Speech failed: {}", &[&e]));
+                    }
+                }
+            }
+            KeyCode::Char('f') => self.show_ruby = !self.show_ruby,
+            KeyCode::Char('w') => self.toggle_study_timer(),
+            KeyCode::Char('b') => self.session.pause(Instant::now()),
+            KeyCode::Char(':') => self.jump_input = Some(String::new()),
+            KeyCode::Char('t') if !show_answer => self.answer_input = Some(String::new()),
+            KeyCode::Char('o') if show_answer => self.open_attachments(topic, card_index),
+            KeyCode::Char('l') => self.follow_link(topic, card_index, show_answer),
+            KeyCode::Char('c') if self.refuse_read_only() => {}
+            KeyCode::Char('c') => self.duplicate_card(topic, card_index),
+            KeyCode::Char('e') => self.snapshot_card(topic, card_index),
+            KeyCode::Backspace => {
+                if let Some((topic, card_index)) = self.link_back.pop() {
+                    self.state = AppState::FlashcardReview {
+                        topic,
+                        card_index,
+                        show_answer: false,
+                        scroll: 0,
+                    };
+                }
+            }
+            KeyCode::Char('0'..='5') if self.refuse_read_only() => {}
+            KeyCode::Char(c @ '0'..='5') => {
+                // Rate how hard the card is, 0 to go back to its grades
+                let rating = c.to_digit(10).filter(|&n| n > 0).map(|n| n as u8);
+                if let Some(card) = self
+                    .topics
+                    .topics_map
+                    .get_mut(topic)
+                    .and_then(|cards| cards.get_mut(card_index))
+                {
+                    card.rating = rating;
+                    debug!(
+                        "rated {} card {}: {:?}",
+                        self.logged(topic),
+                        card_index,
+                        rating
+                    );
+                    if self.save() {
+                        self.toasts.info(match rating {
+                            Some(n) => trf("Rated {} of 5 for difficulty", &[&n]),
+                            None => tr("Rating cleared").to_string(),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Starts the pomodoro timer, or stops it with a word on the time studied
+    fn toggle_study_timer(&mut self) {
+        let now = Instant::now();
+        match self.study_timer.take() {
+            Some(timer) => {
+                let minutes = timer.focused(now).as_secs() / 60;
+                info!("study timer stopped after {} minutes", minutes);
+                self.toasts.info(trf(
+                    "Studied {} min, {}",
+                    &[&minutes, &git::cards_label(timer.graded)],
+                ));
+            }
+            None => {
+                self.study_timer = Some(start_study_timer(&self.config, now));
+                self.toasts.info(tr("Study timer started"));
+            }
+        }
+    }
+
+    // Opens the files and links attached to a card
+    fn open_attachments(&mut self, topic: &str, card_index: usize) {
+        let Some(card) = self
+            .topics
+            .topics_map
+            .get(topic)
+            .and_then(|cards| cards.get(card_index))
+        else {
+            return;
+        };
+        if card.attachments.is_empty() {
+            self.toasts.error(tr("Nothing is attached to this card"));
+            return;
+        }
+        let deck_dir = self.deck_path.parent().unwrap_or(Path::new(""));
+        let mut failed = None;
+        for attachment in &card.attachments {
+            if let Err(e) = media::open(deck_dir, attachment) {
+                warn!("opening {}: {}", attachment, e);
+                failed = Some(e);
+            }
+        }
+        if let Some(e) = failed {
+            self.toasts.error(trf("Couldn't open: {}", &[&e]));
+        }
+    }
+
+    // Typing the answer to a card before flipping it
+    fn handle_answer_keys(
+        &mut self,
+        key_event: KeyEvent,
+        topic: &str,
+        card_index: usize,
+        mut input: String,
+    ) {
+        match key_event.code {
+            KeyCode::Esc => {}
+            KeyCode::Enter => {
+                self.typed_answer = Some((topic.to_string(), card_index, input));
+                self.state = AppState::FlashcardReview {
+                    topic: topic.to_string(),
+                    card_index,
+                    show_answer: true,
+                    scroll: 0,
+                };
+            }
+            KeyCode::Backspace => {
+                input.pop();
+                self.answer_input = Some(input);
+            }
+            KeyCode::Char(c) => {
+                input.push(c);
+                self.answer_input = Some(input);
+            }
+            _ => self.answer_input = Some(input),
+        }
+    }
+
+    // Typing a card number after `:` in review
+    fn handle_jump_keys(&mut self, key_event: KeyEvent, topic: &str, mut input: String) {
+        match key_event.code {
+            KeyCode::Esc => {}
+            KeyCode::Enter => {
+                let count = self.topics.topics_map.get(topic).map_or(0, Vec::len);
+                match input.parse::<usize>() {
+                    Ok(number) if (1..=count).contains(&number) => {
+                        self.state = AppState::FlashcardReview {
+                            topic: topic.to_string(),
+                            card_index: number - 1,
+                            show_answer: false,
+                            scroll: 0,
+                        };
+                    }
+                    _ => self
+                        .toasts
+                        .error(trf("No card {}, pick 1 to {}", &[&input, &count])),
+                }
+            }
+            KeyCode::Backspace => {
+                // Backspace on an empty prompt closes it
+                if input.pop().is_some() {
+                    self.jump_input = Some(input);
+                }
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                input.push(c);
+                self.jump_input = Some(input);
+            }
+            _ => self.jump_input = Some(input),
+        }
+    }
+
+    fn handle_create_topic_keys(&mut self, key_event: KeyEvent) {
+        let AppState::CreateTopic { input } = &mut self.state else {
+            return;
+        };
+
+        match key_event.code {
+            KeyCode::Esc => {
+                self.state = AppState::TopicSelection;
+            }
+            KeyCode::Enter => {
+                // Create new topic, unless the name is taken or unusable
+                if self.topics.new_topic_error(input).is_some() {
+                    return;
+                }
+                let name = input.trim().to_string();
+                self.topics.topics_map.insert(name.clone(), Vec::new());
+                self.topic_order.clear();
+                let meta = TopicMeta {
+                    created: Some(history::now()),
+                    ..self.topics.meta_of(&name)
+                };
+                self.topics.set_meta(&name, meta);
+                self.save_state = SaveState::Unsaved;
+                info!("created topic {}", self.logged(&name));
+                self.toasts.info(trf("Topic '{}' created", &[&name]));
+                self.state = AppState::TopicSelection;
+                // Select the newly created topic
+                self.select_topic(&name);
+            }
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_add_card_keys(&mut self, key_event: KeyEvent) {
+        let AppState::AddCard {
+            topic,
+            question_input,
+            answer_input,
+            editing_question,
+            cursor,
+            editing,
+        } = &mut self.state
+        else {
+            return;
+        };
+        let editing = *editing;
+        let field = if *editing_question {
+            &mut *question_input
+        } else {
+            &mut *answer_input
+        };
+
+        match key_event.code {
+            KeyCode::Esc => match editing {
+                Some((card_index, sort)) => {
+                    let topic = topic.clone();
+                    self.browse_card(topic, card_index, sort);
+                }
+                None => self.leave_add_card(),
+            },
+
+            // Switch between question and answer input, carrying on at the
+            // end of the other one
+            KeyCode::Tab => {
+                *editing_question = !*editing_question;
+                *cursor = if *editing_question {
+                    question_input.len()
+                } else {
+                    answer_input.len()
+                };
+            }
+
+            // KeyCode::Enter
+            // // this is on macos: SHIFT+OPTION+ENTER
+            //     if key_event
+            //         .modifiers
+            //         .contains(crossterm::event::KeyModifiers::ALT) =>
+            // Plain Enter: Add newline
+            KeyCode::Enter => {
+                field.insert(*cursor, '\n');
+                *cursor += 1;
+            }
+
+            KeyCode::Char(c @ ('s' | 'n'))
+            // CONTROL + S on macos
+                if key_event
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::SUPER) =>
+            {
+                // Ctrl+S or Cmd+S: Save card. Ctrl+N: Save it and start on
+                // the next one, for entering many cards in a row
+                let (topic, question, answer) =
+                    (topic.clone(), question_input.clone(), answer_input.clone());
+                match editing {
+                    Some((card_index, sort)) => {
+                        self.save_edited_card(&topic, card_index, &question, &answer);
+                        self.browse_card(topic, card_index, sort);
+                    }
+                    None => self.save_new_card(&topic, &question, &answer, c == 'n'),
+                }
+            }
+
+            KeyCode::Char('t') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                // Ctrl+T: Keep the fields as the topic's template
+                let (topic, question, answer) =
+                    (topic.clone(), question_input.clone(), answer_input.clone());
+                self.keep_template(&topic, &question, &answer);
+            }
+
+            KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                // Ctrl+R: Replace the last misspelled word with the first
+                // suggestion
+                if let Some((at, misspelling)) = spell::last_fixable(field, &self.misspelled)
+                    && let Some(fixed) = spell::fix_last(field, &self.misspelled)
+                {
+                    // Past the word the cursor keeps its place in the text,
+                    // inside it the cursor goes to its end
+                    if *cursor > at {
+                        let word_end = at + misspelling.word.len();
+                        *cursor = (*cursor).max(word_end) + fixed.len() - field.len();
+                    }
+                    *field = fixed;
+                }
+            }
+
+            KeyCode::Char(c) => {
+                field.insert(*cursor, c);
+                *cursor += c.len_utf8();
+            }
+
+            KeyCode::Backspace => {
+                if let Some(c) = field[..*cursor].chars().next_back() {
+                    *cursor -= c.len_utf8();
+                    field.remove(*cursor);
+                }
+            }
+            KeyCode::Delete if *cursor < field.len() => {
+                field.remove(*cursor);
+            }
+
+            // By where the text is shown, which for right-to-left text is
+            // the other way from where it is stored
+            KeyCode::Left | KeyCode::Right => {
+                let left = key_event.code == KeyCode::Left;
+                *cursor = editor::move_cursor(field, *cursor, left, self.editor_width.get());
+            }
+            KeyCode::Home => *cursor = field[..*cursor].rfind('\n').map_or(0, |i| i + 1),
+            KeyCode::End => {
+                *cursor += field[*cursor..].find('\n').unwrap_or(field.len() - *cursor);
+            }
+            _ => {}
+        }
+    }
+
+    // Adds the card in the editor to `topic` and saves the deck, then
+    // starts on the next card if `next`, or goes back to where the editor
+    // was opened from
+    fn save_new_card(&mut self, topic: &str, question: &str, answer: &str, next: bool) {
+        if question.trim().is_empty() || answer.trim().is_empty() {
+            return;
+        }
+
+        let flashcard = Flashcard {
+            added: Some(history::now()),
+            ..Flashcard::new(question.trim(), answer.trim())
+        };
+        let template = self.topics.meta_of(topic).template;
+        if template.is_some_and(|t| {
+            t.question.trim() == flashcard.question && t.answer.trim() == flashcard.answer
+        }) {
+            self.toasts.info(tr("Fill in the template first"));
+            return;
+        }
+        let cards = self
+            .topics
+            .topics_map
+            .get(topic)
+            .map_or(&[][..], Vec::as_slice);
+        if cards
+            .iter()
+            .any(|c| c.question == flashcard.question && c.answer == flashcard.answer)
+        {
+            self.toasts.info(tr("Change the copy before saving it"));
+            return;
+        }
+
+        if let Some(cards) = self.topics.topics_map.get_mut(topic) {
+            cards.push(flashcard);
+            self.topic_order.clear();
+            debug!("added card {} to {}", cards.len(), self.logged(topic));
+        }
+
+        if self.save() {
+            self.toasts.info(trf("Card saved to '{}'", &[&topic]));
+        }
+        if next {
+            self.add_card(topic.to_string());
+        } else {
+            self.leave_add_card();
+        }
+    }
+
+    // Rewords card `card_index` of `topic` with what is in the editor,
+    // keeping its earlier wording
+    fn save_edited_card(&mut self, topic: &str, card_index: usize, question: &str, answer: &str) {
+        let (question, answer) = (question.trim(), answer.trim());
+        if question.is_empty() || answer.is_empty() {
+            return;
+        }
+        let Some(card) = self
+            .topics
+            .topics_map
+            .get_mut(topic)
+            .and_then(|cards| cards.get_mut(card_index))
+        else {
+            return;
+        };
+        if !card.revise(question.to_string(), answer.to_string(), history::now()) {
+            return;
+        }
+        self.topic_order.clear();
+        debug!("edited card {} of {}", card_index, self.logged(topic));
+        if self.save() {
+            self.toasts
+                .info(tr("Card updated, the old wording is in its history"));
+        }
+    }
+
+    // Opens the browser on `topic` sorted by `sort`, with card `card_index`
+    // selected
+    fn browse_card(&mut self, topic: String, card_index: usize, sort: CardSort) {
+        let order = self.browse_order(&topic, sort);
+        let selected = order.iter().position(|&i| i == card_index);
+        self.state = AppState::BrowseCards {
+            topic,
+            selected: selected.unwrap_or(0),
+            sort,
+        };
+    }
+
+    fn handle_attach_keys(&mut self, key_event: KeyEvent) {
+        let AppState::AttachFile {
+            topic,
+            card_index,
+            sort,
+            input,
+        } = &mut self.state
+        else {
+            return;
+        };
+        match key_event.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => {
+                let (topic, card_index, sort) = (topic.clone(), *card_index, *sort);
+                self.browse_card(topic, card_index, sort);
+            }
+            KeyCode::Enter if !input.trim().is_empty() => {
+                let (topic, card_index, sort) = (topic.clone(), *card_index, *sort);
+                let deck_dir = self.deck_path.parent().unwrap_or(Path::new(""));
+                // The prompt stays open if the file can't be attached
+                let attachment = match media::attach(deck_dir, input) {
+                    Ok(attachment) => attachment,
+                    Err(e) => {
+                        self.toasts.error(trf("Couldn't attach: {}", &[&e]));
+                        return;
+                    }
+                };
+                let Some(card) = self
+                    .topics
+                    .topics_map
+                    .get_mut(&topic)
+                    .and_then(|cards| cards.get_mut(card_index))
+                else {
+                    return;
+                };
+                if !card.attachments.contains(&attachment) {
+                    card.attachments.push(attachment.clone());
+                }
+                info!(
+                    "attached {} to card {} of {:?}",
+                    attachment, card_index, topic
+                );
+                if self.save() {
+                    self.toasts
+                        .info(trf("Attached {}", &[&media::label(&attachment)]));
+                }
+                self.browse_card(topic, card_index, sort);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_trash_keys(&mut self, key_event: KeyEvent) {
+        let AppState::Trash { selected } = &mut self.state else {
+            return;
+        };
+        let count = self.topics.trash.len();
+        match key_event.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                *selected = (*selected + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => *selected = selected.saturating_sub(1),
+            KeyCode::Esc | KeyCode::Char('q') => self.state = AppState::TopicSelection,
+            KeyCode::Enter if count > 0 => {
+                let position = *selected;
+                if self.refuse_read_only() {
+                    return;
+                }
+                // The trash is listed newest first
+                let Some(trashed) = self.topics.restore(count - 1 - position) else {
+                    return;
+                };
+                self.topic_order.clear();
+                info!("restored {} from the trash", self.logged(&trashed.topic));
+                if self.save() {
+                    self.toasts.info(if trashed.whole_topic {
+                        trf("Restored topic '{}'", &[&trashed.topic])
+                    } else {
+                        trf("Restored a card to '{}'", &[&trashed.topic])
+                    });
+                }
+                self.state = AppState::Trash {
+                    selected: position.min(count.saturating_sub(2)),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    // Moves `topic` and its cards to the trash
+    fn trash_topic(&mut self, topic: &str) {
+        if !self.topics.trash_topic(topic, history::now()) {
+            return;
+        }
+        self.marked_topics.retain(|t| t != topic);
+        self.topic_order.clear();
+        info!("moved topic {} to the trash", self.logged(topic));
+        if self.save() {
+            self.toasts.info(trf("Moved '{}' to the trash", &[&topic]));
+        }
+        let count = self.get_sorted_topics().len();
+        let selected = self.list_state.selected().unwrap_or(0);
+        self.list_state
+            .select((count > 0).then(|| selected.min(count - 1)));
+    }
+
+    // Archives `topic`, or brings it back if it is archived
+    fn toggle_archived(&mut self, topic: &str) {
+        let mut meta = self.topics.meta_of(topic);
+        meta.archived = !meta.archived;
+        let archived = meta.archived;
+        self.topics.set_meta(topic, meta);
+        self.marked_topics.retain(|t| t != topic);
+        self.topic_order.clear();
+        info!(
+            "set archived of topic {} to {}",
+            self.logged(topic),
+            archived
+        );
+        if self.save() {
+            self.toasts.info(if archived {
+                trf("Archived '{}'", &[&topic])
+            } else {
+                trf("Brought '{}' back from the archive", &[&topic])
+            });
+        }
+        let count = self.get_sorted_topics().len();
+        let selected = self.list_state.selected().unwrap_or(0);
+        self.list_state
+            .select((count > 0).then(|| selected.min(count - 1)));
+    }
+
+    fn handle_card_history_keys(&mut self, key_event: KeyEvent) {
+        let AppState::CardHistory {
+            topic,
+            card_index,
+            selected,
+            sort,
+        } = &mut self.state
+        else {
+            return;
+        };
+        let (card_index, sort) = (*card_index, *sort);
+        let revisions = self
+            .topics
+            .topics_map
+            .get(topic.as_str())
+            .and_then(|cards| cards.get(card_index))
+            .map_or(0, |card| card.revisions.len());
+        match key_event.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                *selected = (*selected + 1).min(revisions.saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => *selected = selected.saturating_sub(1),
+            KeyCode::Esc | KeyCode::Char('q') => {
+                let topic = topic.clone();
+                self.browse_card(topic, card_index, sort);
+            }
+            KeyCode::Enter if revisions > 0 => {
+                let (topic, selected) = (topic.clone(), *selected);
+                if self.refuse_read_only() {
+                    return;
+                }
+                if let Some(card) = self
+                    .topics
+                    .topics_map
+                    .get_mut(&topic)
+                    .and_then(|cards| cards.get_mut(card_index))
+                {
+                    // Revisions are listed newest first
+                    card.restore(revisions - 1 - selected, history::now());
+                }
+                self.topic_order.clear();
+                info!(
+                    "restored a wording of card {} of {}",
+                    card_index,
+                    self.logged(&topic)
+                );
+                if self.save() {
+                    self.toasts.info(tr("Earlier wording restored"));
+                }
+                self.browse_card(topic, card_index, sort);
+            }
+            _ => {}
+        }
+    }
+
+    // Keeps the editor's fields as the template of `topic`, or drops the
+    // template when they're empty
+    fn keep_template(&mut self, topic: &str, question: &str, answer: &str) {
+        let mut meta = self.topics.meta_of(topic);
+        meta.template = if question.trim().is_empty() && answer.trim().is_empty() {
+            None
+        } else {
+            Some(Flashcard::new(question, answer))
+        };
+        let message = match meta.template {
+            Some(_) => trf("New cards in '{}' start from this template", &[&topic]),
+            None => trf("Template of '{}' removed", &[&topic]),
+        };
+        self.topics.set_meta(topic, meta);
+        self.topic_order.clear();
+        if self.save() {
+            self.toasts.info(message);
+        }
+    }
+
+    // Rechecks the AddCard fields, turning spell checking off if hunspell
+    // stops working
+    fn check_spelling(&mut self) {
+        let AppState::AddCard {
+            question_input,
+            answer_input,
+            ..
+        } = &self.state
+        else {
+            self.misspelled.clear();
+            return;
+        };
+        let Some(speller) = self.speller.as_mut() else {
+            return;
+        };
+        match speller.check(&format!("{}\n{}", question_input, answer_input)) {
+            Ok(misspelled) => self.misspelled = misspelled,
+            Err(e) => {
+                error!("spell check failed: {}", e);
+                self.toasts.error(trf("Spell checking is off: {}", &[&e]));
+                self.speller = None;
+                self.misspelled.clear();
+            }
+        }
+    }
+
+    fn handle_generate_keys(&mut self, key_event: KeyEvent) {
+        let AppState::GenerateCards {
+            topic,
+            input,
+            waiting,
+        } = &mut self.state
+        else {
+            return;
+        };
+        match key_event.code {
+            // Drafts that come back after this are dropped
+            KeyCode::Esc => self.state = AppState::TopicSelection,
+            _ if *waiting => {}
+            KeyCode::Char('s')
+                if key_event
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::SUPER)
+                    && !input.trim().is_empty() =>
+            {
+                let (topic, input) = (topic.clone(), input.clone());
+                self.generate_cards(topic, input);
+            }
+            KeyCode::Char(_) if key_event.modifiers.contains(KeyModifiers::CONTROL) => {}
+            KeyCode::Enter => input.push('\n'),
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_drafted_keys(&mut self, key_event: KeyEvent) {
+        let AppState::DraftedCards {
+            topic,
+            cards,
+            index,
+            kept,
+        } = &mut self.state
+        else {
+            return;
+        };
+        let keep = match key_event.code {
+            KeyCode::Char('y') | KeyCode::Enter => true,
+            KeyCode::Char('n') => false,
+            KeyCode::Esc => {
+                let (topic, kept, total) = (topic.clone(), *kept, cards.len());
+                self.finish_drafts(&topic, kept, total);
+                return;
+            }
+            _ => return,
+        };
+        if keep && let Some(existing) = self.topics.topics_map.get_mut(topic.as_str()) {
+            existing.push(Flashcard {
+                added: Some(history::now()),
+                ..cards[*index].clone()
+            });
+            self.topic_order.clear();
+            self.save_state = SaveState::Unsaved;
+            *kept += 1;
+        }
+        if *index + 1 < cards.len() {
+            *index += 1;
+        } else {
+            let (topic, kept, total) = (topic.clone(), *kept, cards.len());
+            self.finish_drafts(&topic, kept, total);
+        }
+    }
+
+    // Indexes of the cards of `topic` in the browser, sorted by `sort`
+    fn browse_order(&self, topic: &str, sort: CardSort) -> Vec<usize> {
+        let cards = self
+            .topics
+            .topics_map
+            .get(topic)
+            .map_or(&[][..], Vec::as_slice);
+        let difficulty = stats::difficulty(cards, topic, self.history.reviews());
+        browser::sorted(cards, &difficulty, sort)
+    }
+
+    fn handle_browse_keys(
+        &mut self,
+        key_event: KeyEvent,
+        topic: &str,
+        selected: usize,
+        sort: CardSort,
+    ) {
+        let order = self.browse_order(topic, sort);
+        let last = order.len().saturating_sub(1);
+        let (selected, sort) = match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.state = AppState::TopicSelection;
+                return;
+            }
+            KeyCode::Enter => {
+                // Review from the selected card on
+                if let Some(&card_index) = order.get(selected) {
+                    let position = ReviewPosition {
+                        card_index,
+                        scroll: 0,
+                    };
+                    self.review_positions.insert(topic.to_string(), position);
+                    self.start_review(topic.to_string());
+                }
+                return;
+            }
+            KeyCode::Down | KeyCode::Char('j') => ((selected + 1).min(last), sort),
+            KeyCode::Up | KeyCode::Char('k') => (selected.saturating_sub(1), sort),
+            KeyCode::Home => (0, sort),
+            KeyCode::End => (last, sort),
+            KeyCode::Char('c' | 'e' | 'f' | 'x' | 'J' | 'K') | KeyCode::Delete
+                if self.refuse_read_only() =>
+            {
+                return;
+            }
+            KeyCode::Char('c') => {
+                if let Some(&card_index) = order.get(selected) {
+                    self.duplicate_card(topic, card_index);
+                }
+                return;
+            }
+            KeyCode::Char('e') => {
+                // Reword the selected card in the editor
+                if let Some(card) = order
+                    .get(selected)
+                    .and_then(|&i| Some((i, self.topics.topics_map.get(topic)?.get(i)?)))
+                {
+                    let (card_index, card) = card;
+                    self.state = AppState::AddCard {
+                        topic: topic.to_string(),
+                        question_input: card.question.clone(),
+                        answer_input: card.answer.clone(),
+                        editing_question: true,
+                        cursor: card.question.len(),
+                        editing: Some((card_index, sort)),
+                    };
+                }
+                return;
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                let Some(&card_index) = order.get(selected) else {
+                    return;
+                };
+                let question = self.topics.topics_map[topic][card_index].question.clone();
+                self.topics.trash_card(topic, card_index, history::now());
+                self.topic_order.clear();
+                info!(
+                    "moved card {} of {} to the trash",
+                    card_index,
+                    self.logged(topic)
+                );
+                if self.save() {
+                    self.toasts.info(trf(
+                        "Moved '{}' to the trash",
+                        &[&browser::first_line(&question)],
+                    ));
+                }
+                (selected.min(last.saturating_sub(1)), sort)
+            }
+            KeyCode::Char('f') => {
+                if let Some(&card_index) = order.get(selected) {
+                    self.state = AppState::AttachFile {
+                        topic: topic.to_string(),
+                        card_index,
+                        sort,
+                        input: String::new(),
+                    };
+                }
+                return;
+            }
+            KeyCode::Char('h') => {
+                // Earlier wordings of the selected card
+                if let Some(&card_index) = order.get(selected) {
+                    self.state = AppState::CardHistory {
+                        topic: topic.to_string(),
+                        card_index,
+                        selected: 0,
+                        sort,
+                    };
+                }
+                return;
+            }
+            KeyCode::Char(c @ ('J' | 'K')) => {
+                // Move the selected card down or up within the topic
+                if sort != CardSort::Added {
+                    self.toasts.error(trf(
+                        "Sort {} to move cards",
+                        &[&tr(CardSort::Added.label())],
+                    ));
+                    return;
+                }
+                let to = if c == 'J' {
+                    (selected + 1).min(last)
+                } else {
+                    selected.saturating_sub(1)
+                };
+                if to == selected {
+                    return;
+                }
+                if let Some(cards) = self.topics.topics_map.get_mut(topic) {
+                    cards.swap(selected, to);
+                }
+                debug!(
+                    "moved card {} of {} to {}",
+                    selected,
+                    self.logged(topic),
+                    to
+                );
+                self.save();
+                (to, sort)
+            }
+            KeyCode::Char('o') => {
+                // Next sort, keeping the same card selected
+                let sort = sort.next();
+                let resorted = self.browse_order(topic, sort);
+                let card = order.get(selected);
+                let selected = resorted.iter().position(|i| Some(i) == card);
+                (selected.unwrap_or(0), sort)
+            }
+            _ => return,
+        };
+        self.state = AppState::BrowseCards {
+            topic: topic.to_string(),
+            selected,
+            sort,
+        };
+    }
+
+    fn handle_topic_settings_keys(&mut self, key_event: KeyEvent) {
+        let AppState::TopicSettings {
+            topic,
+            meta,
+            selected,
+        } = &mut self.state
+        else {
+            return;
+        };
+        // Taken out of the state, which is set again once they are edited
+        let (topic, mut meta, mut selected) = (mem::take(topic), mem::take(meta), *selected);
+        let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+        let change = match key_event.code {
+            KeyCode::Esc => {
+                self.state = AppState::TopicSelection;
+                return;
+            }
+            KeyCode::Enter => {
+                self.topics.set_meta(&topic, meta);
+                // Its own new cards a day change its counts
+                self.topic_order.clear();
+                if self.save() {
+                    self.toasts.info(trf("Settings of '{}' saved", &[&topic]));
+                }
+                self.state = AppState::TopicSelection;
+                return;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                selected = (selected + 1).min(TOPIC_SETTINGS - 1);
+                None
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                selected = selected.saturating_sub(1);
+                None
+            }
+            KeyCode::Right | KeyCode::Char('l' | ' ') => Some(true),
+            KeyCode::Left | KeyCode::Char('h') => Some(false),
+            _ => None,
+        };
+        if let Some(up) = change {
+            match selected {
+                0 => {
+                    // The configured order is left unset, to follow the
+                    // config
+                    let configured = self.config.review_order.unwrap_or_default();
+                    let order = self.review_order(&meta).next();
+                    meta.order = (order != configured).then_some(order);
+                }
+                1 => meta.reversed = !meta.reversed,
+                3 => meta.color = cycle(&TOPIC_COLORS, meta.color.as_deref(), up),
+                4 => meta.icon = cycle(&TOPIC_ICONS, meta.icon.as_deref(), up),
+                5 => {
+                    // A day at a time, a week with Shift. Before tomorrow
+                    // there is no exam.
+                    let step = if shift { 7 } else { 1 } * history::DAY;
+                    let today = history::now() / history::DAY * history::DAY;
+                    meta.exam = match (meta.exam, up) {
+                        (None, true) => Some(today + EXAM_DAYS * history::DAY),
+                        (None, false) => None,
+                        (Some(exam), true) => Some(exam + step),
+                        (Some(exam), false) => exam.checked_sub(step).filter(|&exam| exam > today),
+                    };
+                }
+                _ => {
+                    // Steps of 5, below 5 is no limit. Like the order, the
+                    // configured limit is left unset.
+                    let limit = match (self.new_per_day(&meta), up) {
+                        (None, true) => Some(5),
+                        (None, false) => None,
+                        (Some(n), true) => Some(n + 5),
+                        (Some(n), false) => n.checked_sub(5).filter(|&n| n > 0),
+                    };
+                    meta.new_per_day = limit.filter(|_| limit != self.config.new_per_day);
+                }
+            }
+        }
+        self.state = AppState::TopicSettings {
+            topic,
+            meta,
+            selected,
+        };
+    }
+
+    fn finish_drafts(&mut self, topic: &str, kept: usize, total: usize) {
+        info!(
+            "kept {} of {} drafted cards for {}",
+            kept,
+            total,
+            self.logged(topic)
+        );
+        if kept > 0 && self.save() {
+            self.toasts.info(trf(
+                "Added {} of {} cards to '{}'",
+                &[&kept, &total, &topic],
+            ));
+        }
+        self.state = AppState::TopicSelection;
+    }
+
+    fn select_next_topic(&mut self) {
+        let topics_count = self.get_sorted_topics().len();
+        if topics_count == 0 {
+            return;
+        }
+
+        let i = match self.list_state.selected() {
+            Some(i) => (i + 1) % topics_count,
+            None => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    fn select_previous_topic(&mut self) {
+        let topics_count = self.get_sorted_topics().len();
+        if topics_count == 0 {
+            return;
+        }
+
+        let i = match self.list_state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    topics_count - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    // Moves the selection by a page, clamped to the ends of the list
+    fn select_topic_page(&mut self, down: bool) {
+        let topics_count = self.get_sorted_topics().len();
+        if topics_count == 0 {
+            return;
+        }
+
+        let page = usize::from(self.topic_page.get().max(1));
+        let i = self.list_state.selected().unwrap_or(0);
+        let i = if down {
+            (i + page).min(topics_count - 1)
+        } else {
+            i.saturating_sub(page)
+        };
+        self.list_state.select(Some(i));
+    }
+
+    fn update_list_selection(&mut self) {
+        let topics_count = self.get_sorted_topics().len();
+        if topics_count > 0 {
+            self.list_state.select(Some(0));
+        }
+    }
+
+    // Writes where the user is, to pick up there at the next launch
+    fn save_progress(&self) {
+        let Some(path) = &self.resume_path else {
+            return;
+        };
+        if let Err(e) = self.resume_state().save(path, self.key.as_ref()) {
+            warn!("saving resume state: {}", e);
+        }
+    }
+
+    // Where to pick up at the next launch
+    fn resume_state(&self) -> ResumeState {
+        // Nothing has moved before the deck is loaded
+        if let Some(pending) = &self.pending_resume {
+            return pending.clone();
+        }
+        let mut positions = self.review_positions.clone();
+        let (topic, reviewing) = match &self.state {
+            AppState::FlashcardReview {
+                topic,
+                card_index,
+                scroll,
+                ..
+            } => {
+                let position = ReviewPosition {
+                    card_index: *card_index,
+                    scroll: *scroll,
+                };
+                positions.insert(topic.clone(), position);
+                (Some(topic.clone()), true)
+            }
+            _ => {
+                let selected = self.list_state.selected();
+                let topic = selected.and_then(|i| self.get_sorted_topics().get(i).cloned());
+                (topic, false)
+            }
+        };
+        ResumeState {
+            deck: Some(self.deck_path.clone()),
+            topic,
+            reviewing,
+            positions,
+            recent_decks: self.recent_decks.clone(),
+            topic_sort: self.topic_sort,
+            sealed: None,
+        }
+    }
+
+    // Selects the topic from the last run and goes back into review if that's
+    // where it was closed. Topics removed since are skipped, and so is
+    // everything but the recent decks if another deck was open then.
+    fn resume(&mut self, resume: ResumeState) {
+        if self.loader.is_some() {
+            self.pending_resume = Some(resume);
+            return;
+        }
+        self.recent_decks = resume.recent_decks;
+        decks::remember(&mut self.recent_decks, &self.deck_path);
+        if resume.deck.is_some_and(|deck| deck != self.deck_path) {
+            return;
+        }
+
+        self.review_positions = resume.positions;
+        self.topic_sort = resume.topic_sort;
+        let Some(topic) = resume.topic else {
+            return;
+        };
+        let Some(i) = self.get_sorted_topics().iter().position(|t| *t == topic) else {
+            return;
+        };
+        self.list_state.select(Some(i));
+        if resume.reviewing {
+            self.start_review(topic);
+        }
+    }
+
+    // Opens review of `topic` where it was last left, if it has any cards
+    fn start_review(&mut self, topic: String) {
+        self.open_review(topic, 0);
+    }
+
+    // Opens review of `topic`, with the new cards of the next `ahead_days`
+    // days too. Offers to study ahead when there is nothing left for today.
+    fn open_review(&mut self, topic: String, ahead_days: u64) {
+        let count = self.topics.topics_map.get(&topic).map_or(0, Vec::len);
+        if count == 0 {
+            return;
+        }
+        self.mix = None;
+        self.queue = None;
+        self.link_back.clear();
+        self.shuffle_seed = history::now();
+        self.ahead_days = ahead_days;
+        let queue = self.review_queue(&topic);
+        if ahead_days == 0 && self.can_study_ahead(&topic, &queue) {
+            self.state = AppState::StudyAhead { topic };
+            return;
+        }
+        let Some(&first) = queue.first() else {
+            self.toasts
+                .info(trf("No more new cards in '{}' today", &[&topic]));
+            return;
+        };
+
+        // Only review in order picks up where it was left, the others start
+        // from the front of their order
+        let position = match self.review_order(&self.topics.meta_of(&topic)) {
+            ReviewOrder::Sequential => self.review_positions.get(&topic).copied(),
+            _ => None,
+        }
+        .unwrap_or_default();
+        // Cards may have been removed or held back since
+        let (card_index, scroll) = if queue.contains(&position.card_index) {
+            (position.card_index, position.scroll)
+        } else {
+            (first, 0)
+        };
+        self.session = Session::default();
+        self.queue = Some((topic.clone(), queue));
+        self.state = AppState::FlashcardReview {
+            topic,
+            card_index,
+            show_answer: false,
+            scroll,
+        };
+    }
+
+    // Order of the cards of a topic with settings `meta`: its own, or the
+    // configured one
+    fn review_order(&self, meta: &TopicMeta) -> ReviewOrder {
+        meta.order.or(self.config.review_order).unwrap_or_default()
+    }
+
+    fn study_ahead_days(&self) -> u64 {
+        self.config.study_ahead_days.unwrap_or(STUDY_AHEAD_DAYS)
+    }
+
+    // Most new cards a day of a topic with settings `meta`, its own limit or
+    // the configured one
+    fn new_per_day(&self, meta: &TopicMeta) -> Option<usize> {
+        meta.new_per_day.or(self.config.new_per_day)
+    }
+
+    // Indexes of the cards of `topic` to review, in the order the topic's
+    // settings ask for: the one the session opened with while it lasts
+    fn review_queue(&self, topic: &str) -> Vec<usize> {
+        match &self.queue {
+            Some((opened, queue)) if opened == topic => queue.clone(),
+            _ => self.review_queue_ahead(topic, self.ahead_days),
+        }
+    }
+
+    // Whether everything in `queue` has been reviewed today and studying
+    // ahead would bring in more of the topic's new cards
+    fn can_study_ahead(&self, topic: &str, queue: &[usize]) -> bool {
+        let Some(cards) = self.topics.topics_map.get(topic) else {
+            return false;
+        };
+        let reviews = self.history.reviews();
+        schedule::done_today(queue, cards, topic, reviews, history::now())
+            && self
+                .review_queue_ahead(topic, self.study_ahead_days())
+                .len()
+                > queue.len()
+    }
+
+    // The review queue of `topic` with the new cards of the next
+    // `ahead_days` days brought in
+    fn review_queue_ahead(&self, topic: &str, ahead_days: u64) -> Vec<usize> {
+        let Some(cards) = self.topics.topics_map.get(topic) else {
+            return Vec::new();
+        };
+        let meta = self.topics.meta_of(topic);
+        let order = self.review_order(&meta);
+        let reviews = self.history.reviews();
+        let queue = schedule::review_order(cards, topic, reviews, order, self.shuffle_seed);
+        match self.new_per_day(&meta) {
+            Some(limit) => schedule::limit_new(
+                queue,
+                cards,
+                topic,
+                self.history.reviews(),
+                schedule::ahead_limit(limit, ahead_days),
+                history::now(),
+            ),
+            None => queue,
+        }
+    }
+
+    // After grading a card, brings back a missed card that is due again,
+    // else moves on in the queue from where it was left
+    fn next_after_grade(&mut self, topic: &str, card_index: usize) {
+        let key = |index| match &self.mix {
+            Some(mix) => mix.get(index).cloned(),
+            None => Some((topic.to_string(), index)),
+        };
+        match self.session.next(self.session_key(topic, card_index)) {
+            Next::Relearn(index) => {
+                let Some((topic, card_index)) = key(index) else {
+                    return;
+                };
+                self.typed_answer = None;
+                self.state = AppState::FlashcardReview {
+                    topic,
+                    card_index,
+                    show_answer: false,
+                    scroll: 0,
+                };
+            }
+            Next::After(index) => {
+                if let Some((topic, card_index)) = key(index) {
+                    self.step_card(&topic, card_index, true);
+                }
+            }
+        }
+    }
+
+    // Moves review to the card after `card_index` in the queue, or the one
+    // before it, wrapping around
+    fn step_card(&mut self, topic: &str, card_index: usize, forward: bool) {
+        self.typed_answer = None;
+        self.session.leave_relearn();
+        let queue = match &self.mix {
+            Some(mix) => mix.clone(),
+            None => self
+                .review_queue(topic)
+                .into_iter()
+                .map(|i| (topic.to_string(), i))
+                .collect(),
+        };
+        if queue.is_empty() {
+            return;
+        }
+        let position = queue
+            .iter()
+            .position(|(t, i)| t == topic && *i == card_index)
+            .unwrap_or(0);
+        let next = if forward {
+            (position + 1) % queue.len()
+        } else {
+            (position + queue.len() - 1) % queue.len()
+        };
+        let (topic, card_index) = queue[next].clone();
+        self.state = AppState::FlashcardReview {
+            topic,
+            card_index,
+            show_answer: false,
+            scroll: 0,
+        };
+    }
+
+    // Opens the editor on a copy of the card, to save as a variation of it
+    fn duplicate_card(&mut self, topic: &str, card_index: usize) {
+        let Some(card) = self
+            .topics
+            .topics_map
+            .get(topic)
+            .and_then(|cards| cards.get(card_index))
+        else {
+            return;
+        };
+        self.state = AppState::AddCard {
+            topic: topic.to_string(),
+            question_input: card.question.clone(),
+            answer_input: card.answer.clone(),
+            editing_question: true,
+            cursor: card.question.len(),
+            editing: None,
+        };
+    }
+
+    // The links on the sides of the card that are showing
+    fn visible_links(&self, topic: &str, card_index: usize, show_answer: bool) -> Vec<String> {
+        let Some(card) = self
+            .topics
+            .topics_map
+            .get(topic)
+            .and_then(|cards| cards.get(card_index))
+        else {
+            return Vec::new();
+        };
+        let reversed = self.topics.meta_of(topic).reversed;
+        let (front, back) = if reversed {
+            (&card.answer, &card.question)
+        } else {
+            (&card.question, &card.answer)
+        };
+        let mut links = markup::links(front);
+        if show_answer {
+            links.extend(markup::links(back));
+        }
+        links.into_iter().map(str::to_string).collect()
+    }
+
+    // The topic and card a `[[...]]` link points to. The whole target is
+    // tried as a topic first, since topic names may hold a `/` themselves.
+    fn resolve_link(&self, target: &str) -> Option<(String, usize)> {
+        if let Some(cards) = self.topics.topics_map.get(target) {
+            return (!cards.is_empty()).then(|| (target.to_string(), 0));
+        }
+        let (topic, card) = target.rsplit_once('/')?;
+        let (topic, card) = (topic.trim(), card.trim());
+        let cards = self.topics.topics_map.get(topic)?;
+        let card_index = match card.parse::<usize>() {
+            Ok(number) => number.checked_sub(1).filter(|&i| i < cards.len())?,
+            Err(_) => cards
+                .iter()
+                .position(|c| c.question.trim().eq_ignore_ascii_case(card))?,
+        };
+        Some((topic.to_string(), card_index))
+    }
+
+    // Goes to the first card linked from the showing sides, keeping this
+    // one to come back to
+    fn follow_link(&mut self, topic: &str, card_index: usize, show_answer: bool) {
+        let links = self.visible_links(topic, card_index, show_answer);
+        let Some(first) = links.first() else {
+            self.toasts.info(tr("This card links to no other cards"));
+            return;
+        };
+        let Some((target_topic, target_index)) =
+            links.iter().find_map(|link| self.resolve_link(link))
+        else {
+            self.toasts.error(trf("No card for link [[{}]]", &[first]));
+            return;
+        };
+        debug!(
+            "following link to {} card {}",
+            self.logged(&target_topic),
+            target_index
+        );
+        self.link_back.push((topic.to_string(), card_index));
+        self.state = AppState::FlashcardReview {
+            topic: target_topic,
+            card_index: target_index,
+            show_answer: false,
+            scroll: 0,
+        };
+    }
+
+    // Where the card is in the review, and how many cards it has: the
+    // mixed queue when reviewing several topics
+    fn review_position(&self, topic: &str, card_index: usize) -> (usize, usize) {
+        match &self.mix {
+            Some(mix) => {
+                let position = mix.iter().position(|(t, i)| t == topic && *i == card_index);
+                (position.unwrap_or(0), mix.len())
+            }
+            None => {
+                let queue = self.review_queue(topic);
+                let position = queue.iter().position(|&i| i == card_index);
+                (position.unwrap_or(0), queue.len())
+            }
+        }
+    }
+
+    // What the session keeps a card's grade under: its index, or its place
+    // in the mixed queue, where indexes of different topics would clash
+    fn session_key(&self, topic: &str, card_index: usize) -> usize {
+        match &self.mix {
+            Some(_) => self.review_position(topic, card_index).0,
+            None => card_index,
+        }
+    }
+
+    // Opens review of the marked topics together, their cards mixed `how`
+    fn start_mix(&mut self, how: Interleave) {
+        let marked: Vec<String> = self
+            .get_sorted_topics()
+            .iter()
+            .filter(|topic| self.marked_topics.contains(topic))
+            .cloned()
+            .collect();
+        if marked.len() < 2 {
+            self.toasts.error(tr(
+                "Mark two or more topics with Space to review them together",
+            ));
+            return;
+        }
+        self.shuffle_seed = history::now();
+        self.queue = None;
+        let queues = marked
+            .into_iter()
+            .map(|topic| {
+                let queue = self.review_queue(&topic);
+                (topic, queue)
+            })
+            .collect();
+        let mix = schedule::interleave(queues, how, self.shuffle_seed);
+        self.ahead_days = 0;
+        let Some((topic, card_index)) = mix.first().cloned() else {
+            self.toasts
+                .info(tr("No cards to review in the marked topics"));
+            return;
+        };
+        info!("mixing {} cards, {:?}", mix.len(), how);
+        self.mix = Some(mix);
+        self.link_back.clear();
+        self.session = Session::default();
+        self.state = AppState::FlashcardReview {
+            topic,
+            card_index,
+            show_answer: false,
+            scroll: 0,
+        };
+    }
+
+    // Opens review of the cards added in the last few days, of the marked
+    // topics or else of all, whether or not they are due
+    fn start_recent(&mut self) {
+        let topics: Vec<String> = self
+            .get_sorted_topics()
+            .iter()
+            .filter(|topic| self.marked_topics.is_empty() || self.marked_topics.contains(topic))
+            .cloned()
+            .collect();
+        let days = self.config.recent_days.unwrap_or(RECENT_DAYS);
+        let since = history::now().saturating_sub(days * history::DAY);
+        let recent = schedule::added_since(&self.topics, &topics, since);
+        let Some((topic, card_index)) = recent.first().cloned() else {
+            self.toasts
+                .info(trf("No cards added in the last {} days", &[&days]));
+            return;
+        };
+        info!("reviewing {} cards added in {} days", recent.len(), days);
+        self.ahead_days = 0;
+        self.mix = Some(recent);
+        self.link_back.clear();
+        self.session = Session::default();
+        self.state = AppState::FlashcardReview {
+            topic,
+            card_index,
+            show_answer: false,
+            scroll: 0,
+        };
+    }
+
+    fn get_sorted_topics(&self) -> Rc<[String]> {
+        self.topic_order.get(
+            &self.topics,
+            self.history.reviews(),
+            self.topic_sort,
+            self.show_archived,
+            self.config.new_per_day,
+        )
+    }
+
+    // Selects `topic` in the list, if it is there
+    fn select_topic(&mut self, topic: &str) {
+        if let Some(i) = self.get_sorted_topics().iter().position(|t| t == topic) {
+            self.list_state.select(Some(i));
+        }
+    }
+
+    // Saves the deck, keeping track of the outcome for the status bar and
+    // showing a failure in a dialog. Returns whether the save worked.
+    fn save(&mut self) -> bool {
+        // Saving before the whole deck is in would drop the rest of it
+        self.finish_loading();
+        if self.read_only {
+            return true;
+        }
+        // Changes another program made to the file since it was read are
+        // merged in first rather than written over. Conflicts are asked
+        // about, and the deck is saved once they are settled.
+        if self.reload_prompt.is_none() && self.merge_deck_file() {
+            if !self.conflicts.is_empty() {
+                self.settle_conflicts();
+                return false;
+            }
+            self.toasts.info(tr("Merged in changes made elsewhere"));
+        }
+        let days = self.config.trash_days.unwrap_or(trash::DEFAULT_DAYS);
+        let cutoff = history::now().saturating_sub(days * history::DAY);
+        let emptied = self.topics.empty_trash(cutoff);
+        if emptied > 0 {
+            info!(
+                "emptied {} entries older than {} days from the trash",
+                emptied, days
+            );
+        }
+        // What was saved before, to describe the changes in the commit
+        let previous = if self.config.git_commit {
+            Topics::load_with(&self.deck_path, self.key.as_ref()).ok()
+        } else {
+            None
+        };
+        match self.save_to_disk() {
+            Ok(()) => {
+                let count = self.topics.topics_map.len();
+                info!("saved {} topics to {}", count, self.deck_path.display());
+                self.save_state = SaveState::Saved;
+                self.disk_modified = modified_time(&self.deck_path);
+                self.base = self.topics.clone();
+                if let Some(previous) = previous {
+                    self.commit_to_git(&previous);
+                }
+                true
+            }
+            Err(e) => {
+                error!("saving {}: {}", self.deck_path.display(), e);
+                self.save_state = SaveState::Failed;
+                self.dialog = Some(ErrorDialog::new(
+                    tr("Save failed"),
+                    trf("Couldn't write {}: {}", &[&self.deck_path.display(), &e]),
+                ));
+                false
+            }
+        }
+    }
+
+    // Commits the saved deck and its history. Runs in the foreground so a
+    // save on quit is committed before the app exits.
+    fn commit_to_git(&mut self, previous: &Topics) {
+        // Topic names and questions would be readable in the log
+        let message = if self.key.is_some() {
+            git::private_summary(previous, &self.topics)
+        } else {
+            git::change_summary(previous, &self.topics)
+        }
+        .unwrap_or_else(|| "record reviews".to_string());
+        let history_path = History::path_for(&self.deck_path);
+        let mut files = vec![self.deck_path.as_path()];
+        if history_path.is_file() {
+            files.push(&history_path);
+        }
+        let dir = self.deck_path.parent().unwrap_or(Path::new("."));
+        match git::commit(dir, &files, &message) {
+            Ok(true) => info!("committed: {}", message),
+            Ok(false) => debug!("nothing to commit"),
+            Err(e) => {
+                error!("committing {}: {}", self.deck_path.display(), e);
+                self.toasts.error(trf("Commit failed: {}", &[&e]));
+            }
+        }
+    }
+
+    fn due_count(&self) -> usize {
+        schedule::due_count(
+            &self.topics,
+            self.history.reviews(),
+            self.config.new_per_day,
+            history::now(),
+        )
+    }
+
+    // Plans of the topics with an exam coming up, the soonest first.
+    // Archived topics are left out.
+    fn study_plans(&self) -> Vec<(String, stats::StudyPlan)> {
+        let now = history::now();
+        let mut plans: Vec<(String, stats::StudyPlan)> = self
+            .topics
+            .topics_map
+            .iter()
+            .filter(|(topic, _)| !self.topics.is_archived(topic))
+            .filter_map(|(topic, cards)| {
+                let exam = self.topics.meta_of(topic).exam?;
+                let plan = stats::study_plan(cards, topic, self.history.reviews(), exam, now)?;
+                Some((topic.clone(), plan))
+            })
+            .collect();
+        plans.sort_by(|a, b| a.1.days_left.cmp(&b.1.days_left).then(a.0.cmp(&b.0)));
+        plans
+    }
+
+    // Saves only the topics changed since the last save when the deck is
+    // large, except when commits are made, which need the whole deck
+    fn save_to_disk(&mut self) -> io::Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        if let Some(broken) = &self.unreadable {
+            if self.deck_path.exists() {
+                fs::copy(&self.deck_path, broken)?;
+                info!("copied the unreadable deck to {}", broken.display());
+                self.toasts.info(trf(
+                    "The deck that couldn't be read is kept in {}",
+                    &[&broken.display()],
+                ));
+            }
+            self.unreadable = None;
+        }
+        let key = self.key.as_ref();
+        if self.config.git_commit {
+            self.journal.compact(&self.topics, &self.deck_path, key)
+        } else {
+            self.journal.save(&self.topics, &self.deck_path, key)
+        }
+    }
+
+    // Opens the deck switcher, reading the metadata of each recent deck
+    fn show_decks(&mut self) {
+        self.recent_meta = self
+            .recent_decks
+            .iter()
+            .map(|path| {
+                if *path == self.deck_path {
+                    self.topics.meta.clone()
+                } else {
+                    // Missing decks still show up, by file name
+                    DeckMeta::read(path).unwrap_or_default()
+                }
+            })
+            .collect();
+        self.state = AppState::Decks {
+            selected: 0,
+            prompt: None,
+        };
+    }
+
+    // Switches to the deck at `path`, saving the open one first. With
+    // `create` the deck must not exist yet and is written out empty,
+    // otherwise it must exist. Problems are shown as toasts; returns whether
+    // the deck was opened.
+    fn open_deck(&mut self, path: PathBuf, create: bool) -> bool {
+        let path = decks::absolute(&path);
+        let name = path.display().to_string();
+        if create && path.exists() {
+            self.toasts.error(trf("{} already exists", &[&name]));
+            return false;
+        }
+        if !create && !path.is_file() {
+            self.toasts.error(trf("No deck at {}", &[&name]));
+            return false;
+        }
+        // Reopening the open deck keeps its lock
+        let lock = if path == self.deck_path || self.read_only {
+            None
+        } else {
+            match DeckLock::acquire(&path) {
+                Ok(lock) => Some(lock),
+                Err(e) => {
+                    self.toasts.error(trf("Couldn't open {}: {}", &[&name, &e]));
+                    return false;
+                }
+            }
+        };
+        // The passphrase is only asked for before the UI starts
+        if let Ok(Some(_)) = crypt::deck_salt(&path) {
+            self.toasts.error(trf(
+                "{} is encrypted, open it with mem-flip --deck {}",
+                &[&name, &name],
+            ));
+            return false;
+        }
+        // A failed save shows its own dialog
+        if self.save_state != SaveState::Saved && !self.save() {
+            return false;
+        }
+
+        let mut topics = match Topics::load(&path) {
+            Ok(topics) => topics,
+            Err(e) => {
+                error!("loading {}: {}", name, e);
+                self.toasts.error(trf("Couldn't open {}: {}", &[&name, &e]));
+                return false;
+            }
+        };
+        if create {
+            topics.meta.created = Some(stats::format_date(history::now()));
+        }
+        if create && let Err(e) = topics.save(&path) {
+            error!("creating {}: {}", name, e);
+            self.toasts
+                .error(trf("Couldn't create {}: {}", &[&name, &e]));
+            return false;
+        }
+        let history_path = History::path_for(&path);
+        let history = History::load(history_path.clone()).unwrap_or_else(|e| {
+            error!("loading {}: {}", history_path.display(), e);
+            History::empty(history_path)
+        });
+
+        info!("opened {}", name);
+        decks::remember(&mut self.recent_decks, &path);
+        self.toasts.info(trf(
+            "Opened {}",
+            &[&path.file_name().unwrap_or_default().to_string_lossy()],
+        ));
+        self.disk_modified = modified_time(&path);
+        self.deck_path = path;
+        self.key = None;
+        if lock.is_some() {
+            self.lock = lock;
+        }
+        self.journal = Journal::open(&topics, &self.deck_path);
+        self.base = topics.clone();
+        self.loader = None;
+        self.unreadable = None;
+        self.pending_resume = None;
+        self.topics = topics;
+        self.topic_order.clear();
+        self.history = history;
+        self.refresh_accuracy();
+        self.save_state = SaveState::Saved;
+        self.session = Session::default();
+        self.review_positions.clear();
+        self.list_state = ListState::default();
+        self.update_list_selection();
+        self.state = AppState::TopicSelection;
+        true
+    }
+
+    // Merges in what another program changed in the deck file, such as a
+    // sync tool bringing in changes from another computer, asking only
+    // about cards changed there and here alike. Not while tasks run, as
+    // they may be writing it themselves.
+    fn check_deck_file(&mut self) {
+        if self.reload_prompt.is_some() || self.tasks.is_busy() || self.loader.is_some() {
+            return;
+        }
+        // There are no changes here to lose
+        if self.read_only {
+            if self.deck_file_changed() {
+                self.reload_from_disk();
+            }
+            return;
+        }
+        if self.merge_deck_file() {
+            self.settle_conflicts();
+        }
+    }
+
+    fn deck_file_changed(&self) -> bool {
+        let modified = modified_time(&self.deck_path);
+        modified.is_some() && modified != self.disk_modified
+    }
+
+    // Merges what changed in the deck file since it was last read or written
+    // into the cards here, leaving conflicts in `conflicts`. Returns whether
+    // anything was merged.
+    fn merge_deck_file(&mut self) -> bool {
+        if !self.deck_file_changed() {
+            return false;
+        }
+        info!("{} changed on disk", self.deck_path.display());
+        let modified = modified_time(&self.deck_path);
+        // Tried again the next time the file changes, as it may have been
+        // read halfway through being written
+        self.disk_modified = modified;
+        let theirs = match Topics::load_with(&self.deck_path, self.key.as_ref()) {
+            Ok(theirs) => theirs,
+            Err(e) => {
+                error!("reloading {}: {}", self.deck_path.display(), e);
+                self.toasts
+                    .error(trf("Couldn't reload the deck: {}", &[&e]));
+                return false;
+            }
+        };
+        // Only touched, or written back as it was
+        if theirs == self.base {
+            return false;
+        }
+        let merged = merge::three_way(&self.base, &self.topics, &theirs);
+        info!(
+            "merged {} with {} conflicts",
+            self.deck_path.display(),
+            merged.conflicts.len()
+        );
+        let before = std::mem::replace(&mut self.topics, merged.topics);
+        self.journal = Journal::open(&theirs, &self.deck_path);
+        self.base = theirs;
+        self.topic_order.clear();
+        let history_path = History::path_for(&self.deck_path);
+        match History::load_with(history_path.clone(), self.key.clone()) {
+            Ok(history) => {
+                self.history = history;
+                self.refresh_accuracy();
+            }
+            Err(e) => error!("reloading {}: {}", history_path.display(), e),
+        }
+
+        let changed: Vec<String> = before
+            .topics_map
+            .keys()
+            .chain(self.topics.topics_map.keys())
+            .filter(|topic| before.topics_map.get(*topic) != self.topics.topics_map.get(*topic))
+            .cloned()
+            .collect();
+        self.leave_changed_cards(&changed);
+        if before.trash != self.topics.trash && matches!(self.state, AppState::Trash { .. }) {
+            self.state = AppState::TopicSelection;
+        }
+        if self
+            .list_state
+            .selected()
+            .is_none_or(|i| i >= self.get_sorted_topics().len())
+        {
+            self.update_list_selection();
+        }
+        self.conflicts = merged.conflicts;
+        true
+    }
+
+    // Asks about the first conflict left from merging in the deck file,
+    // and saves the merged deck once there are none
+    fn settle_conflicts(&mut self) {
+        if let Some(conflict) = self.conflicts.first() {
+            // Saved once every conflict is settled
+            self.save_state = SaveState::Unsaved;
+            let side = |card: &Option<Flashcard>| match card {
+                Some(card) => format!("“{}”", card.answer),
+                None => tr("removed").to_string(),
+            };
+            self.reload_prompt = Some(ConfirmDialog {
+                title: trf("Changed elsewhere too ({} left)", &[&self.conflicts.len()]),
+                message: trf(
+                    "{} · {}\nHere: {}\nThere: {}",
+                    &[
+                        &conflict.topic,
+                        &conflict.question,
+                        &side(&conflict.ours),
+                        &side(&conflict.theirs),
+                    ],
+                ),
+                yes: "Keep mine",
+                no: "Take theirs",
+            });
+            return;
+        }
+        if self.topics == self.base {
+            self.save_state = SaveState::Saved;
+            self.toasts.info(tr("Reloaded the deck"));
+        } else if self.save() {
+            self.toasts.info(tr("Merged in changes made elsewhere"));
+        }
+    }
+
+    // Leaves a screen on a card of one of `topics`, as the card it is on
+    // may have moved or gone
+    fn leave_changed_cards(&mut self, topics: &[String]) {
+        let on_card = match &self.state {
+            AppState::FlashcardReview { topic, .. } => match &self.mix {
+                Some(mix) => mix.iter().any(|(topic, _)| topics.contains(topic)),
+                None => topics.contains(topic),
+            },
+            AppState::AddCard {
+                topic,
+                editing: Some(_),
+                ..
+            }
+            | AppState::BrowseCards { topic, .. }
+            | AppState::CardHistory { topic, .. }
+            | AppState::AttachFile { topic, .. } => topics.contains(topic),
+            AppState::TypingPractice { typing } => topics.contains(&typing.topic),
+            _ => false,
+        };
+        if on_card {
+            self.state = AppState::TopicSelection;
+        }
+    }
+
+    // Takes in the deck being read in the background, waiting for it if
+    // need be. Cards added in the meantime are kept.
+    fn finish_loading(&mut self) {
+        let Some(loader) = self.loader.take() else {
+            return;
+        };
+        match loader.wait() {
+            Ok(mut topics) => {
+                info!(
+                    "loaded {} topics from {}",
+                    topics.topics_map.len(),
+                    self.deck_path.display()
+                );
+                self.journal = Journal::open(&topics, &self.deck_path);
+                self.base = topics.clone();
+                topics.merge(std::mem::take(&mut self.topics));
+                self.topics = topics;
+                self.topic_order.clear();
+                self.update_list_selection();
+            }
+            Err(e) => {
+                error!("loading {}: {}", self.deck_path.display(), e);
+                self.dialog = Some(self.load_error_dialog(&e));
+            }
+        }
+        if let Some(resume) = self.pending_resume.take() {
+            self.resume(resume);
+        }
+    }
+
+    // Replaces the deck with what is in its file, dropping unsaved changes
+    fn reload_from_disk(&mut self) {
+        match Topics::load_with(&self.deck_path, self.key.as_ref()) {
+            Ok(topics) => {
+                info!("reloaded {}", self.deck_path.display());
+                self.unreadable = None;
+                self.journal = Journal::open(&topics, &self.deck_path);
+                self.base = topics.clone();
+                self.topics = topics;
+                self.topic_order.clear();
+                self.save_state = SaveState::Saved;
+                self.disk_modified = modified_time(&self.deck_path);
+                // Cards being reviewed or added to may be gone
+                self.state = AppState::TopicSelection;
+                self.list_state = ListState::default();
+                self.update_list_selection();
+                self.toasts.info(tr("Reloaded the deck"));
+            }
+            Err(e) => {
+                error!("reloading {}: {}", self.deck_path.display(), e);
+                self.toasts
+                    .error(trf("Couldn't reload the deck: {}", &[&e]));
+            }
+        }
+    }
+
+    // Unpacks the bundle at `path` into a deck next to it and opens that
+    fn open_bundle(&mut self, path: &Path) -> bool {
+        let deck_path = bundle::deck_path(&decks::absolute(path));
+        if let Err(e) = bundle::unpack(path, &deck_path) {
+            error!("unpacking {}: {}", path.display(), e);
+            self.toasts.error(trf("Couldn't unpack bundle: {}", &[&e]));
+            return false;
+        }
+        info!("unpacked {} to {}", path.display(), deck_path.display());
+        self.open_deck(deck_path, false)
+    }
+
+    // Tells the user that what they tried changes the deck, when it is
+    // open read-only. Returns whether it is.
+    fn refuse_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.toasts.error(tr("The deck is open read-only"));
+        } else if self.importing.is_some() {
+            // The deck the import is making would drop the change
+            self.toasts
+                .error(tr("Wait for the import to finish, or cancel it"));
+            return true;
+        }
+        self.read_only
+    }
+
+    // Where the cards go if the app panics, leaving the deck file as it was:
+    // `deck.recovery.json` next to `deck.json`
+    // Tells that the deck couldn't be read, so the app has none, and sets
+    // it to be copied aside before it is saved over
+    fn load_error_dialog(&mut self, e: &io::Error) -> ErrorDialog {
+        let broken = broken_path(&self.deck_path, history::now());
+        let dialog = ErrorDialog::new(
+            tr("Couldn't load cards"),
+            trf(
+                "{}: {}\n\nStarting with an empty deck. The file is copied to {} before \
+                 anything is saved over it.",
+                &[&self.deck_path.display(), &e, &broken.display()],
+            ),
+        );
+        self.unreadable = Some(broken);
+        dialog
+    }
+
+    fn recovery_path(&self) -> PathBuf {
+        self.deck_path.with_extension("recovery.json")
+    }
+
+    fn export_stats(&mut self) {
+        let deck = self.topics.meta.title_or_name(&self.deck_path);
+        let reviews = self.history.reviews().to_vec();
+        self.tasks.spawn(tr("Exporting stats"), move |progress| {
+            let count = reviews.len();
+            match stats::export_csv(&deck, &reviews, Path::new(STATS_FILE), |done| {
+                progress.set(done, count)
+            }) {
+                Ok(()) => Outcome::Done(trf("Exported {} reviews to {}", &[&count, &STATS_FILE])),
+                Err(e) => Outcome::Failed(trf("Export failed: {}", &[&e])),
+            }
+        });
+    }
+
+    // Writes `topic` alone to a file in the current directory
+    fn export_topic(&mut self, topic: String, format: ExportFormat) {
+        let topics = self.topics.clone();
+        self.tasks.spawn(tr("Exporting topic"), move |_| {
+            let path = export::export_path(&topic, format);
+            match export::export_topic(&topics, &topic, format, &path) {
+                Ok(count) => Outcome::Done(trf(
+                    "Exported {} to {}",
+                    &[&git::cards_label(count), &path.display()],
+                )),
+                Err(e) => Outcome::Failed(trf("Export failed: {}", &[&e])),
+            }
+        });
+    }
+
+    // Writes card `card_index` of `topic` to a file of its own, to paste
+    // into a chat
+    fn snapshot_card(&mut self, topic: &str, card_index: usize) {
+        let Some(card) = self
+            .topics
+            .topics_map
+            .get(topic)
+            .and_then(|cards| cards.get(card_index))
+            .cloned()
+        else {
+            return;
+        };
+        let (topic, format) = (topic.to_string(), self.config.snapshot_format);
+        self.tasks.spawn(tr("Writing snapshot"), move |_| {
+            let path = snapshot::snapshot_path(&topic, card_index + 1, format);
+            match snapshot::write_snapshot(&topic, card_index + 1, &card, format, &path) {
+                Ok(()) => Outcome::Done(trf("Wrote the card to {}", &[&path.display()])),
+                Err(e) => Outcome::Failed(trf("Snapshot failed: {}", &[&e])),
+            }
+        });
+    }
+
+    // Writes the open deck as a web page next to it
+    fn export_page(&mut self) {
+        let title = self.topics.meta.title_or_name(&self.deck_path);
+        let topics = self.topics.clone();
+        let out = html::page_path(&self.deck_path);
+        self.tasks.spawn(tr("Writing web page"), move |_| {
+            match html::export_page(&title, &topics, &out) {
+                Ok(()) => Outcome::Done(trf(
+                    "Wrote the deck to {}",
+                    &[&out.file_name().unwrap_or_default().to_string_lossy()],
+                )),
+                Err(e) => Outcome::Failed(trf("Web page failed: {}", &[&e])),
+            }
+        });
+    }
+
+    // Packs the open deck and its images into a bundle next to it
+    fn bundle_deck(&mut self) {
+        let deck_path = self.deck_path.clone();
+        let topics = self.topics.clone();
+        self.tasks.spawn(tr("Bundling deck"), move |_| {
+            let out = bundle::bundle_path(&deck_path);
+            let deck_dir = deck_path.parent().unwrap_or(Path::new(""));
+            match bundle::pack(&topics, deck_dir, &out) {
+                Ok(packed) if packed.missing.is_empty() => Outcome::Done(trf(
+                    "Bundled {} images into {}",
+                    &[
+                        &packed.media,
+                        &out.file_name().unwrap_or_default().to_string_lossy(),
+                    ],
+                )),
+                Ok(packed) => Outcome::Failed(trf(
+                    "Bundled, but {} images weren't found, such as {}",
+                    &[&packed.missing.len(), &packed.missing[0]],
+                )),
+                Err(e) => Outcome::Failed(trf("Bundle failed: {}", &[&e])),
+            }
+        });
+    }
+
+    // Syncs the open deck's files with the server, reloading them once done
+    fn sync_deck(&mut self) {
+        let remote = match sync::Remote::from_config(&self.config) {
+            Ok(remote) => remote,
+            Err(e) => {
+                self.toasts.error(trf("Can't sync: {}", &[&e]));
+                return;
+            }
+        };
+        // The server gets what is on screen
+        self.finish_loading();
+        if self.save_state != SaveState::Saved && !self.save() {
+            return;
+        }
+        let deck = self.deck_path.clone();
+        let topics = self.topics.clone();
+        let key = self.key.clone();
+        self.tasks.spawn(tr("Syncing"), move |_| {
+            match sync::sync(&remote, &deck, topics, key.as_ref()) {
+                Ok(pulled) => Outcome::Synced {
+                    deck,
+                    pulled: Box::new(pulled),
+                },
+                Err(e) => Outcome::Failed(trf("Sync failed: {}", &[&e])),
+            }
+        });
+    }
+
+    // Merges the server's cards and reviews in here, where cards may have
+    // been added and reviews appended since the sync started, and saves them
+    fn finish_sync(&mut self, deck: &Path, mut pulled: sync::Pulled) {
+        if deck != self.deck_path {
+            // The next sync brings it all in again
+            warn!("dropping a sync of {}, which was closed", deck.display());
+            self.toasts.error(tr(
+                "The synced deck was closed, sync it again to take in the changes",
+            ));
+            return;
+        }
+        let before = self.topics.clone();
+        let conflicts = pulled.merge_deck(&mut self.topics);
+        if self.topics != before {
+            self.topic_order.clear();
+            let changed: Vec<String> = before
+                .topics_map
+                .keys()
+                .chain(self.topics.topics_map.keys())
+                .filter(|topic| before.topics_map.get(*topic) != self.topics.topics_map.get(*topic))
+                .cloned()
+                .collect();
+            self.leave_changed_cards(&changed);
+            if self.list_state.selected().is_none() {
+                self.update_list_selection();
+            }
+            self.save_state = SaveState::Unsaved;
+        }
+        // With conflicts to settle first, the next sync merges it all again
+        let saved = if conflicts.is_empty() {
+            self.topics == before || self.save()
+        } else {
+            self.conflicts = conflicts;
+            self.settle_conflicts();
+            false
+        };
+        if saved && let Err(e) = pulled.save_base(deck, self.key.as_ref()) {
+            error!("keeping the synced deck: {}", e);
+        }
+        let merged = pulled.merge_history(&mut self.history);
+        self.refresh_accuracy();
+        match merged {
+            Ok(()) => {
+                info!("synced {}: {:?}", deck.display(), pulled);
+                self.toasts.info(trf("Synced, {}", &[&pulled.describe()]));
+            }
+            Err(e) => {
+                error!("merging the history of {}: {}", deck.display(), e);
+                self.toasts
+                    .error(trf("Synced, but couldn't merge reviews: {}", &[&e]));
+            }
+        }
+    }
+
+    // Sends the text to the configured model, whose drafts come back as an
+    // `Outcome::Drafted`
+    fn generate_cards(&mut self, topic: String, input: String) {
+        let generator = match Generator::from_config(&self.config) {
+            Ok(generator) => generator,
+            Err(e) => {
+                self.toasts.error(trf("Can't generate cards: {}", &[&e]));
+                return;
+            }
+        };
+        let source = input.clone();
+        let drafted_topic = topic.clone();
+        self.tasks.spawn(tr("Generating cards"), move |_| {
+            match generator.generate(&source) {
+                Ok(cards) => Outcome::Drafted {
+                    topic: drafted_topic,
+                    cards,
+                },
+                Err(e) => Outcome::Failed(trf("Generating cards failed: {}", &[&e])),
+            }
+        });
+        self.state = AppState::GenerateCards {
+            topic,
+            input,
+            waiting: true,
+        };
+    }
+
+    // Shows drafts one at a time, if the user is still waiting for them
+    fn show_drafts(&mut self, topic: String, cards: Vec<Flashcard>) {
+        let AppState::GenerateCards {
+            topic: waiting_for,
+            waiting: true,
+            ..
+        } = &self.state
+        else {
+            info!(
+                "dropped {} drafted cards, no longer waited for",
+                cards.len()
+            );
+            return;
+        };
+        if *waiting_for != topic {
+            return;
+        }
+        info!("drafted {} cards for {}", cards.len(), self.logged(&topic));
+        if cards.is_empty() {
+            self.toasts.error(tr("No cards came back, try more text"));
+            self.state = AppState::TopicSelection;
+            return;
+        }
+        self.state = AppState::DraftedCards {
+            topic,
+            cards,
+            index: 0,
+            kept: 0,
         };
-        self.list_state.select(Some(i));
     }
 
-    fn update_list_selection(&mut self) {
-        let topics_count = self.topics.topics_map.len();
-        if topics_count > 0 {
-            self.list_state.select(Some(0));
+    // Imports the cards at `path` into a copy of the deck in the background,
+    // which takes the deck's place once every card is in. Cancelling drops
+    // the copy, leaving the deck as it was.
+    fn import_path(&mut self, path: PathBuf) {
+        if self.importing.is_some() {
+            self.toasts.error(tr("An import is already running"));
+            return;
         }
+        self.finish_loading();
+        let mut topics = self.topics.clone();
+        let deck = self.deck_path.clone();
+        let id = self.tasks.spawn(tr("Importing"), move |progress| {
+            let imported = match import::read_path(&path) {
+                Ok(imported) => imported,
+                Err(e) => return Outcome::Failed(trf("Import failed: {}", &[&e])),
+            };
+            if imported.card_count() == 0 {
+                return Outcome::Failed(trf("No cards found in {}", &[&path.display()]));
+            }
+            let result = import::apply_with(
+                &mut topics,
+                imported,
+                MergeStrategy::default(),
+                |done, total| {
+                    // A message for every card would flood the channel
+                    if done % IMPORT_REPORT_EVERY == 0 {
+                        progress.set(done, total);
+                    }
+                    !progress.is_cancelled()
+                },
+            );
+            match result {
+                Some(result) => Outcome::Imported {
+                    deck,
+                    topics: Box::new(topics),
+                    result,
+                },
+                None => Outcome::Done(tr("Import cancelled, the deck is unchanged").to_string()),
+            }
+        });
+        self.importing = Some(id);
     }
 
-    fn get_sorted_topics(&self) -> Vec<String> {
-        let mut topics: Vec<_> = self.topics.topics_map.keys().cloned().collect();
-        topics.sort();
-        topics
+    // Puts the deck an import made in place of the open one and saves it
+    fn finish_import(&mut self, deck: PathBuf, topics: Topics, result: Imported) {
+        self.importing = None;
+        if deck != self.deck_path {
+            warn!(
+                "dropping an import into {}, which was closed",
+                deck.display()
+            );
+            self.toasts
+                .error(tr("The import was for a deck that is no longer open"));
+            return;
+        }
+        info!(
+            "imported {} added, {} updated, {} skipped",
+            result.added, result.updated, result.skipped
+        );
+        self.topics = topics;
+        self.topic_order.clear();
+        if self.list_state.selected().is_none() {
+            self.update_list_selection();
+        }
+        if self.save() {
+            self.toasts.info(trf(
+                "Imported {}, updated {} and skipped {} already there",
+                &[
+                    &git::cards_label(result.added),
+                    &result.updated,
+                    &result.skipped,
+                ],
+            ));
+        }
     }
 
-    fn save_to_disk(&self) -> io::Result<()> {
-        let file = std::fs::File::create(CARDS_FILE)?;
-        serde_json::to_writer_pretty(file, &self.topics)?;
-        Ok(())
+    // Downloads a shared deck next to the open one, without opening it
+    fn fetch_deck(&mut self, url: String) {
+        let path = self.deck_path.with_file_name(fetch::file_name(&url));
+        self.tasks.spawn(tr("Downloading deck"), move |_| {
+            match fetch::save_new(&url, &path) {
+                Ok(()) => Outcome::NewDeck(path),
+                Err(e) => Outcome::Failed(trf("Download failed: {}", &[&e])),
+            }
+        });
     }
 }
 
 impl Widget for &App {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            self.render_too_small(area, buf);
+            return;
+        }
+
+        // Every screen gets the area above the status bar
+        let [area, status_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(area);
+        StatusBar {
+            deck: &self
+                .deck_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            topics: self.topics.topics_map.len(),
+            cards: self.topics.card_count(),
+            due: self.due_count(),
+            save_state: self.save_state,
+            pending: self.leader.then(keys::pending_hint),
+            busy: self.tasks.summary(self.ticks).or_else(|| {
+                self.loader
+                    .as_ref()
+                    .map(|_| format!("{} {}", tasks::spinner(self.ticks), tr("Loading cards")))
+            }),
+            read_only: self.read_only,
+        }
+        .render(status_area, buf);
+
         match &self.state {
+            AppState::Home => Dashboard {
+                deck: &self.topics.meta.title_or_name(&self.deck_path),
+                topics: self.topics.topics_map.len(),
+                cards: self.topics.card_count(),
+                due: self.due_count(),
+                history: &self.history,
+                continue_topic: self.continue_topic().as_deref(),
+                plans: &self.study_plans(),
+                now: history::now(),
+            }
+            .render(area, buf),
             AppState::TopicSelection => self.render_topic_selection(area, buf),
+            AppState::TypingPractice { typing } => {
+                let cards = self
+                    .topics
+                    .topics_map
+                    .get(&typing.topic)
+                    .map_or(&[][..], Vec::as_slice);
+                if let Some(card) = cards.get(typing.card_index) {
+                    TypingView {
+                        typing,
+                        question: &card.question,
+                        target: &typing::target(&card.answer),
+                        position: typing::position(cards, typing.card_index),
+                        now: Instant::now(),
+                    }
+                    .render(area, buf);
+                }
+            }
             AppState::FlashcardReview {
                 topic,
                 card_index,
                 show_answer,
-            } => self.render_flashcard(area, buf, topic, *card_index, *show_answer),
+                scroll,
+            } => self.render_flashcard(area, buf, topic, *card_index, *show_answer, *scroll),
             AppState::CreateTopic { input } => self.render_create_topic(area, buf, input),
             AppState::AddCard {
                 topic,
                 question_input,
                 answer_input,
                 editing_question,
+                ..
             } => self.render_add_card(
                 area,
                 buf,
@@ -462,32 +4914,262 @@ impl Widget for &App {
                 answer_input,
                 *editing_question,
             ),
+            AppState::Stats => StatsView {
+                history: &self.history,
+                now: history::now(),
+            }
+            .render(area, buf),
+            AppState::GenerateCards {
+                topic,
+                input,
+                waiting,
+            } => self.render_generate(area, buf, topic, input, *waiting),
+            AppState::DraftedCards {
+                topic,
+                cards,
+                index,
+                kept: _,
+            } => self.render_drafted(area, buf, topic, cards, *index),
+            AppState::BrowseCards {
+                topic,
+                selected,
+                sort,
+            } => self.render_browser(area, buf, topic, *selected, *sort),
+            AppState::CardHistory {
+                topic,
+                card_index,
+                selected,
+                sort,
+            } => {
+                let order = self.browse_order(topic, *sort);
+                let position = order.iter().position(|i| i == card_index);
+                self.render_browser(area, buf, topic, position.unwrap_or(0), *sort);
+                if let Some(card) = self
+                    .topics
+                    .topics_map
+                    .get(topic)
+                    .and_then(|cards| cards.get(*card_index))
+                {
+                    render_card_history(area, buf, card, *selected);
+                }
+            }
+            AppState::ShareTopic { topic, code } => render_share(area, buf, topic, code),
+            AppState::StudyAhead { topic } => {
+                self.render_topic_selection(area, buf);
+                let days = self.study_ahead_days();
+                let dialog = ConfirmDialog {
+                    title: tr("Nothing due").to_string(),
+                    message: trf(
+                        "You have reviewed all of today's cards in {}. Study ahead, with the new cards of the next {} days?",
+                        &[topic, &days],
+                    ),
+                    yes: "Study ahead",
+                    no: "Not now",
+                };
+                (&dialog).render(area, buf);
+            }
+            AppState::AttachFile {
+                topic,
+                card_index,
+                sort,
+                input,
+            } => {
+                let order = self.browse_order(topic, *sort);
+                let position = order.iter().position(|i| i == card_index);
+                self.render_browser(area, buf, topic, position.unwrap_or(0), *sort);
+                render_attach_prompt(area, buf, input);
+            }
+            AppState::TopicStats { topic } => {
+                self.render_topic_selection(area, buf);
+                let cards = self
+                    .topics
+                    .topics_map
+                    .get(topic)
+                    .map_or(&[][..], Vec::as_slice);
+                let stats = stats::topic_stats(cards, topic, self.history.reviews());
+                render_topic_stats(area, buf, topic, cards.len(), &stats);
+            }
+            AppState::ExportTopic { topic, format } => {
+                self.render_topic_selection(area, buf);
+                render_export_topic(area, buf, topic, *format);
+            }
+            AppState::Trash { selected } => TrashView {
+                trash: &self.topics.trash,
+                selected: *selected,
+                days: self.config.trash_days.unwrap_or(trash::DEFAULT_DAYS),
+            }
+            .render(area, buf),
+            AppState::TopicSettings {
+                topic,
+                meta,
+                selected,
+            } => {
+                self.render_topic_selection(area, buf);
+                let order = self.review_order(meta);
+                let new_per_day = self.new_per_day(meta);
+                render_topic_settings(area, buf, topic, meta, order, new_per_day, *selected);
+            }
+            AppState::Settings { selected } => SettingsView {
+                config: &self.config,
+                selected: *selected,
+            }
+            .render(area, buf),
+            AppState::Decks { selected, prompt } => {
+                DeckSwitcher {
+                    recent: &self.recent_decks,
+                    meta: &self.recent_meta,
+                    current: &self.deck_path,
+                    selected: *selected,
+                    prompt: prompt.as_ref().map(|(kind, input)| (*kind, input.as_str())),
+                }
+                .render(area, buf);
+                if let Some(progress) = self.importing.and_then(|id| self.tasks.progress(id)) {
+                    render_import_progress(area, buf, progress);
+                }
+            }
+        }
+
+        // Toasts go over the screen, inside its top border
+        self.toasts.render(area.inner(Margin::new(1, 1)), buf);
+        if let Some(dialog) = &self.dialog {
+            dialog.render(area, buf);
+        }
+        if let Some(prompt) = &self.reload_prompt {
+            prompt.render(area, buf);
+        }
+        if let Some(prompt) = &self.quit_prompt {
+            prompt.render(area, buf);
+        }
+        if self.show_debug {
+            self.render_debug(area, buf);
         }
     }
 }
 
 // Separate rendering logic for each state
 impl App {
+    fn render_debug(&self, area: Rect, buf: &mut Buffer) {
+        let (max_scroll, page) = self.review_scroll.get();
+        let mut text = vec![
+            Line::from(format!(
+                "selected: {:?}  offset: {}",
+                self.list_state.selected(),
+                self.list_state.offset()
+            )),
+            Line::from(format!("review scroll: max {} page {}", max_scroll, page)),
+            Line::from(format!("session: {:?}", self.session)),
+            Line::from(format!(
+                "save: {:?}  dialog: {}  ruby: {}",
+                self.save_state,
+                self.dialog.is_some(),
+                self.show_ruby
+            )),
+            Line::from(format!("last event: {:?}", self.last_event)),
+            Line::from(""),
+        ];
+        let state = format!("{:#?}", self.state);
+        text.extend(state.lines().map(Line::from));
+
+        let [_, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(area);
+        Clear.render(right, buf);
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::bordered()
+                    .title(format!(" {} <F12> ", tr("Debug")).bold())
+                    .style(Style::default().fg(Color::Magenta)),
+            )
+            .render(right, buf);
+    }
+
+    fn render_too_small(&self, area: Rect, buf: &mut Buffer) {
+        let text = vec![
+            Line::from(tr("Terminal too small").bold()),
+            Line::from(format!("{}x{}", area.width, area.height)),
+            Line::from(trf("need at least {}x{}", &[&MIN_WIDTH, &MIN_HEIGHT])),
+        ];
+        let [middle] = Layout::vertical([Constraint::Length(text.len() as u16)])
+            .flex(Flex::Center)
+            .areas(area);
+        Paragraph::new(text)
+            .centered()
+            .wrap(Wrap { trim: true })
+            .render(middle, buf);
+    }
+
     fn render_topic_selection(&self, area: Rect, buf: &mut Buffer) {
-        let title = " 💾 Memory Flip Flashcards ";
-        let instructions = vec![
-            " Navigate ".into(),
+        let title = format!(" 💾 {} ", tr("Memory Flip Flashcards"));
+        let mut instructions = vec![
+            hint("Navigate"),
             "<↑↓>".blue().bold(),
-            " Select ".into(),
+            hint("Select"),
             "<Enter>".blue().bold(),
-            " New Topic ".into(),
+            hint("Browse"),
+            "<B>".blue().bold(),
+            hint("Mark to mix"),
+            "<Space>".blue().bold(),
+            hint("Recently added"),
+            "<R>".blue().bold(),
+            hint("Typing practice"),
+            "<P>".blue().bold(),
+            hint("New Topic"),
             "<N>".blue().bold(),
-            " Add Card ".into(),
+            hint("Add Card"),
             "<A>".blue().bold(),
-            " Quit ".into(),
+            hint("Generate"),
+            "<G C>".blue().bold(),
+            hint("Settings"),
+            "<T>".blue().bold(),
+            hint("Info"),
+            "<I>".blue().bold(),
+            hint("Delete"),
+            "<X>".blue().bold(),
+            hint("Archive"),
+            "<Z>".blue().bold(),
+            hint("Show archived"),
+            "<Shift + Z>".blue().bold(),
+            hint("Export"),
+            "<E>".blue().bold(),
+            hint("Share"),
+            "<Y>".blue().bold(),
+            hint("Sort"),
+            "<O>".blue().bold(),
+            hint("Stats"),
+            "<S>".blue().bold(),
+            hint("Decks"),
+            "<D>".blue().bold(),
+            hint("Home"),
+            "<Esc>".blue().bold(),
+            hint("Quit"),
             "<Q> ".blue().bold(),
         ];
+        if !self.marked_topics.is_empty() {
+            instructions = vec![
+                format!(" {} ", trf("{} marked", &[&self.marked_topics.len()])).into(),
+                hint("Mix in turn"),
+                "<M>".blue().bold(),
+                hint("Mix shuffled"),
+                "<Shift + M>".blue().bold(),
+                hint("Recently added"),
+                "<R>".blue().bold(),
+                hint("Mark"),
+                "<Space>".blue().bold(),
+                hint("Navigate"),
+                "<↑↓> ".blue().bold(),
+            ];
+        }
 
         let topics = self.get_sorted_topics();
 
         if topics.is_empty() {
             // Show empty state
-            let empty_text = "No topics yet!\n\nPress 'N' to create your first topic.";
+            let empty_text = tr(if self.topics.topics_map.is_empty() {
+                "No topics yet!\n\nPress 'N' to create your first topic."
+            } else {
+                "Every topic is archived.\n\nPress 'Shift + Z' to show them."
+            });
             Paragraph::new(empty_text)
                 .left_aligned()
                 .block(
@@ -500,36 +5182,105 @@ impl App {
         }
 
         // Create list items
+        let counts = self.topic_order.counts(
+            &self.topics,
+            self.history.reviews(),
+            self.topic_sort,
+            self.show_archived,
+            self.config.new_per_day,
+        );
         let items: Vec<ListItem> = topics
             .iter()
             .map(|topic| {
-                let card_count = self
-                    .topics
-                    .topics_map
-                    .get(topic)
-                    .map(|cards| cards.len())
-                    .unwrap_or(0);
-
-                let content = format!("  {}  ({} cards)", topic, card_count);
-                ListItem::new(content)
+                let meta = self.topics.meta_of(topic);
+                let counts = counts.get(topic).copied().unwrap_or_default();
+                let icon = meta
+                    .icon
+                    .as_deref()
+                    .map_or(String::new(), |icon| format!("{} ", icon));
+                let mark = if self.marked_topics.contains(topic) {
+                    "✓ "
+                } else {
+                    "  "
+                };
+                let archived = if meta.archived {
+                    format!(", {}", tr("archived"))
+                } else {
+                    String::new()
+                };
+                let content = format!(
+                    "{}{}{}  ({}{})",
+                    mark,
+                    icon,
+                    topic,
+                    trf(
+                        "{} due, {} new, {} total",
+                        &[&counts.due, &counts.new, &counts.total]
+                    ),
+                    archived
+                );
+                let mut style = topic_style(&meta);
+                if meta.archived {
+                    style = style.add_modifier(Modifier::DIM);
+                }
+                ListItem::new(content).style(style)
             })
             .collect();
 
+        let mut block = Block::bordered()
+            .title(title.bold().into_left_aligned_line())
+            .title_bottom(Line::from(instructions).left_aligned());
+        if self.topic_sort != TopicSort::Alphabetical {
+            let sort = format!(" ↕ {} ", tr(self.topic_sort.label()));
+            block = block.title(Line::from(sort).right_aligned());
+        }
         let list = List::new(items)
-            .block(
-                Block::bordered()
-                    .title(title.bold().into_left_aligned_line())
-                    .title_bottom(Line::from(instructions).left_aligned()),
-            )
+            .block(block)
             .highlight_style(
                 Style::default()
-                    .bg(Color::DarkGray)
+                    .bg(theme::palette().highlight)
                     .add_modifier(Modifier::BOLD),
             )
             .highlight_symbol("▶ ");
 
         // Use StatefulWidget for list with selection
-        ratatui::widgets::StatefulWidget::render(list, area, buf, &mut self.list_state.clone());
+        let mut list_state = self.list_state.clone();
+        ratatui::widgets::StatefulWidget::render(list, area, buf, &mut list_state);
+
+        // Recent accuracy of each visible topic, right-aligned on its row
+        let inner = area.inner(Margin::new(1, 1));
+        let spark_width = ACCURACY_DAYS as u16;
+        if inner.width >= MIN_WIDTH + spark_width {
+            let rows = topics
+                .iter()
+                .skip(list_state.offset())
+                .take(inner.height.into());
+            for (y, topic) in (inner.y..).zip(rows) {
+                let Some(accuracy) = self.accuracy.get(topic.as_str()) else {
+                    continue;
+                };
+                let spark_area = Rect::new(inner.right() - spark_width - 2, y, spark_width, 1);
+                Sparkline::default()
+                    .data(accuracy)
+                    .max(100)
+                    .style(Style::default().fg(Color::Green))
+                    .absent_value_symbol("·")
+                    .absent_value_style(Style::default().fg(Color::DarkGray))
+                    .render(spark_area, buf);
+            }
+        }
+
+        // Scrollbar over the right border once the list doesn't fit
+        let list_height = area.height.saturating_sub(2);
+        self.topic_page.set(list_height);
+        if topics.len() > usize::from(list_height) {
+            let mut scrollbar_state =
+                ScrollbarState::new(topics.len()).position(self.list_state.selected().unwrap_or(0));
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .render(area.inner(Margin::new(0, 1)), buf, &mut scrollbar_state);
+        }
     }
 
     fn render_flashcard(
@@ -539,101 +5290,449 @@ impl App {
         topic: &str,
         card_index: usize,
         show_answer: bool,
+        scroll: u16,
     ) {
-        let instructions = vec![
-            " Flip ".into(),
-            "<Space>".blue().bold(),
-            " Previous ".into(),
-            "<P/←>".blue().bold(),
-            " Next ".into(),
-            "<N/→>".blue().bold(),
-            " Back ".into(),
-            "<Esc> ".blue().bold(),
-        ];
+        // Nothing of the card shows while paused
+        if self.session.is_paused() {
+            let [_, middle, _] = Layout::vertical([
+                Constraint::Fill(1),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ])
+            .areas(Block::bordered().inner(area));
+            Block::bordered()
+                .title(format!(" ⏸ {} ", topic).bold())
+                .render(area, buf);
+            Paragraph::new(tr("Paused, press any key to go on"))
+                .centered()
+                .dark_gray()
+                .render(middle, buf);
+            return;
+        }
+        let mut instructions = if let Some(input) = &self.jump_input {
+            vec![
+                format!(" {}", tr("Jump to card :")).into(),
+                input.clone().fg(theme::palette().input),
+                "█".fg(theme::palette().input),
+                hint("Go"),
+                "<Enter>".blue().bold(),
+                hint("Cancel"),
+                "<Esc> ".blue().bold(),
+            ]
+        } else if let Some(input) = &self.answer_input {
+            vec![
+                hint("Your answer:"),
+                input.clone().fg(theme::palette().input),
+                "█".fg(theme::palette().input),
+                hint("Check"),
+                "<Enter>".blue().bold(),
+                hint("Cancel"),
+                "<Esc> ".blue().bold(),
+            ]
+        } else if show_answer {
+            vec![
+                hint("Knew it"),
+                "<Y>".green().bold(),
+                hint("Missed it"),
+                "<M>".red().bold(),
+                hint("Rate difficulty"),
+                "<1-5>".blue().bold(),
+                hint("Flip"),
+                "<Space>".blue().bold(),
+                hint("Scroll"),
+                "<J/K>".blue().bold(),
+                hint("Next"),
+                "<N/→>".blue().bold(),
+                hint("Back"),
+                "<Esc> ".blue().bold(),
+            ]
+        } else {
+            vec![
+                hint("Flip"),
+                "<Space>".blue().bold(),
+                hint("Scroll"),
+                "<J/K>".blue().bold(),
+                hint("Previous"),
+                "<P/←>".blue().bold(),
+                hint("Next"),
+                "<N/→>".blue().bold(),
+                hint("Speak"),
+                "<S>".blue().bold(),
+                hint("Furigana"),
+                "<F>".blue().bold(),
+                hint("Jump"),
+                "<:>".blue().bold(),
+                hint("Type answer"),
+                "<T>".blue().bold(),
+                hint("Timer"),
+                "<W>".blue().bold(),
+                hint("Pause"),
+                "<B>".blue().bold(),
+                hint("Duplicate"),
+                "<C>".blue().bold(),
+                hint("Snapshot"),
+                "<E>".blue().bold(),
+                hint("Add Card"),
+                "<CTL + A>".blue().bold(),
+                hint("Back"),
+                "<Esc> ".blue().bold(),
+            ]
+        };
+        if self.jump_input.is_none() && self.answer_input.is_none() {
+            let mut links: Vec<Span> = Vec::new();
+            if !self
+                .visible_links(topic, card_index, show_answer)
+                .is_empty()
+            {
+                links.extend([hint("Follow link"), "<L>".blue().bold()]);
+            }
+            if !self.link_back.is_empty() {
+                links.extend([hint("Link back"), "<⌫>".blue().bold()]);
+            }
+            let attached = self
+                .topics
+                .topics_map
+                .get(topic)
+                .and_then(|cards| cards.get(card_index))
+                .is_some_and(|card| !card.attachments.is_empty());
+            if show_answer && attached {
+                links.extend([hint("Open attachments"), "<O>".blue().bold()]);
+            }
+            // Before the final Back hint, which ends with the border's space
+            let at = instructions.len() - 2;
+            instructions.splice(at..at, links);
+        }
 
-        if let Some(cards) = self.topics.topics_map.get(topic) {
-            if let Some(card) = cards.get(card_index) {
-                let progress = format!(" Card {}/{} ", card_index + 1, cards.len());
+        if let Some(cards) = self.topics.topics_map.get(topic)
+            && let Some(card) = cards.get(card_index)
+        {
+            let graded = match self.session.result(self.session_key(topic, card_index)) {
+                Some(true) => " ✓",
+                Some(false) => " ✗",
+                None => "",
+            };
+            let rating = card.rating.map_or(String::new(), |n| format!(" ◆{}", n));
+            let relearn = match self.session.relearning() {
+                0 => String::new(),
+                n => format!(" ↻ {} to relearn", n),
+            };
 
-                // Split area into two sections
-                let chunks =
-                    Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
-                        .split(area);
+            let [area, gauge_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(area);
+            let (position, total) = self.review_position(topic, card_index);
+            self.render_session_gauge(gauge_area, buf, position, total.max(1));
 
-                // Render question (top half)
-                let question_text = format!("Q: {}", card.question);
-                Paragraph::new(question_text)
-                    .wrap(Wrap { trim: true })
-                    .left_aligned()
-                    .block(
-                        Block::bordered()
-                            .title(
-                                format!(" 📝 {} {} ", topic, progress)
-                                    .bold()
-                                    .into_left_aligned_line(),
-                            )
-                            .style(Style::default().fg(Color::Cyan)),
-                    )
-                    .render(chunks[0], buf);
+            let now = Instant::now();
+            let moving = self
+                .animation
+                .and_then(|animation| Some((animation, animation.progress(now)?)));
+            let area = match moving {
+                Some((animation, progress)) if animation.transition != Transition::Flip => {
+                    animation.area(area, progress)
+                }
+                _ => area,
+            };
 
-                // Render answer (bottom half) - only if show_answer is true
-                let answer_content = if show_answer {
-                    format!("A: {}", card.answer)
-                } else {
-                    "[Press Space to reveal answer]".to_string()
-                };
+            // Reversed topics show the answer first
+            let meta = self.topics.meta_of(topic);
+            let reversed = meta.reversed;
+            let (front, back) = if reversed {
+                (("A: ", &card.answer), ("Q: ", &card.question))
+            } else {
+                (("Q: ", &card.question), ("A: ", &card.answer))
+            };
 
-                let answer_style = if show_answer {
-                    Style::default().fg(Color::Green)
-                } else {
-                    Style::default().fg(Color::DarkGray)
-                };
+            // Split area into two sections, side by side on wide terminals
+            let halves = [Constraint::Percentage(50), Constraint::Percentage(50)];
+            let chunks = if area.width >= SIDE_BY_SIDE_WIDTH && area.width >= area.height * 3 {
+                Layout::horizontal(halves).split(area)
+            } else {
+                Layout::vertical(halves).split(area)
+            };
+
+            // Render question (top half), scrolled until the answer is shown
+            self.render_card_side(
+                chunks[0],
+                buf,
+                front.0,
+                front.1,
+                (!show_answer).then_some(scroll),
+                Block::bordered()
+                    .title(
+                        format!(
+                            " {} {}{}{}{}{} ",
+                            meta.icon.as_deref().unwrap_or("📝"),
+                            topic,
+                            if reversed { " ⇄" } else { "" },
+                            rating,
+                            graded,
+                            relearn
+                        )
+                        .bold()
+                        .patch_style(topic_style(&meta))
+                        .into_left_aligned_line(),
+                    )
+                    .style(Style::default().fg(theme::palette().accent)),
+            );
 
-                Paragraph::new(answer_content)
+            // Render answer (bottom half) - only if show_answer is true
+            let answer_block =
+                Block::bordered().title_bottom(Line::from(instructions).left_aligned());
+            if show_answer {
+                let mut answer_area = match moving {
+                    Some((animation, progress)) if animation.transition == Transition::Flip => {
+                        animation.area(chunks[1], progress)
+                    }
+                    _ => chunks[1],
+                };
+                if let Some((typed_topic, typed_card, typed)) = &self.typed_answer
+                    && typed_topic == topic
+                    && *typed_card == card_index
+                {
+                    answer_area = render_typed_answer(answer_area, buf, typed, back.1);
+                }
+                if !card.attachments.is_empty() {
+                    answer_area = render_attachments(answer_area, buf, &card.attachments);
+                }
+                self.render_card_side(
+                    answer_area,
+                    buf,
+                    back.0,
+                    back.1,
+                    Some(scroll),
+                    answer_block.style(Style::default().fg(Color::Green)),
+                );
+            } else {
+                Paragraph::new(tr("[Press Space to reveal answer]"))
                     .wrap(Wrap { trim: true })
                     .left_aligned()
-                    .block(
-                        Block::bordered()
-                            .title_bottom(Line::from(instructions).left_aligned())
-                            .style(answer_style),
-                    )
+                    .block(answer_block.style(Style::default().fg(Color::DarkGray)))
                     .render(chunks[1], buf);
-
-                return;
             }
+
+            return;
         }
 
         // Fallback if no card found
-        Paragraph::new("No cards available")
+        Paragraph::new(tr("No cards available"))
             .left_aligned()
             .block(Block::bordered())
             .render(area, buf);
     }
 
+    // Position in the review queue on the left, share of graded cards known
+    // on the right
+    fn render_session_gauge(&self, area: Rect, buf: &mut Buffer, position: usize, total: usize) {
+        let timer_width = if self.study_timer.is_some() { 30 } else { 0 };
+        let [area, timer_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(timer_width)]).areas(area);
+        let [position_area, correct_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .spacing(2)
+                .areas(area);
+
+        LineGauge::default()
+            .label(format!(" Card {}/{}", position + 1, total))
+            .ratio((position + 1) as f64 / total as f64)
+            .filled_style(Style::default().fg(theme::palette().accent))
+            .unfilled_style(Style::default().fg(Color::DarkGray))
+            .render(position_area, buf);
+
+        let (answered, correct) = (self.session.answered(), self.session.correct());
+        let ratio = if answered == 0 {
+            0.0
+        } else {
+            correct as f64 / answered as f64
+        };
+        LineGauge::default()
+            .label(format!("Known {}/{}", correct, answered))
+            .ratio(ratio)
+            .filled_style(Style::default().fg(Color::Green))
+            .unfilled_style(Style::default().fg(Color::Red))
+            .render(correct_area, buf);
+
+        // Time left in the phase, then the focus time and cards of the timer
+        if let Some(timer) = &self.study_timer {
+            let now = Instant::now();
+            let (icon, color) = match timer.phase() {
+                Phase::Focus => ("🍅", Color::Red),
+                Phase::Break => ("☕", Color::Green),
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{} {}", icon, timer::clock(timer.remaining(now))),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(
+                        "  {} min, {}",
+                        timer.focused(now).as_secs() / 60,
+                        git::cards_label(timer.graded)
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+            .right_aligned()
+            .render(timer_area, buf);
+        }
+    }
+
+    // Renders one side of a card inside `block`, starting with `label`. The
+    // first image the terminal can draw goes below the text, other images are
+    // listed as text. When `scroll` is set the text is scrolled by that many
+    // lines and its scroll bounds are kept for the key handler.
+    fn render_card_side(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        label: &'static str,
+        text: &str,
+        scroll: Option<u16>,
+        block: Block,
+    ) {
+        let (text, images) = markup::split_images(text);
+        let inner = block.inner(area);
+
+        let ruby = if self.show_ruby {
+            self.config.ruby
+        } else {
+            markup::RubyMode::Hidden
+        };
+        let text_width = inner.width.saturating_sub(label.len() as u16);
+        // The label stays out of the text so it doesn't decide the paragraph direction
+        let mut content: Vec<Line> = markup::render_text(&text, ruby, text_width)
+            .into_iter()
+            .flat_map(|line| bidi::reflow(line, text_width.into()))
+            .collect();
+        if let Some(first) = content.first_mut() {
+            first.spans.insert(0, Span::raw(label));
+        }
+
+        // Image paths are relative to the deck file
+        let deck_dir = self.deck_path.parent().unwrap_or(Path::new(""));
+        let mut drawn = None;
+        for image in &images {
+            let path = deck_dir.join(&image.path);
+            if drawn.is_none() && self.graphics.can_display(&path) {
+                drawn = Some(path);
+            } else {
+                content.push(Line::raw(format!("🖼  {} ({})", image.alt, image.path)));
+            }
+        }
+
+        let mut paragraph = Paragraph::new(content)
+            .wrap(Wrap { trim: true })
+            .left_aligned();
+        let line_count = u16::try_from(paragraph.line_count(inner.width)).unwrap_or(u16::MAX);
+
+        let (text_area, image) = match drawn {
+            Some(path) => {
+                let text_height = line_count.min(inner.height / 2);
+                let [text_area, image_area] =
+                    Layout::vertical([Constraint::Length(text_height), Constraint::Min(1)])
+                        .areas(inner);
+                let image = ImagePlacement {
+                    path,
+                    area: image_area,
+                };
+                (text_area, Some(image))
+            }
+            None => (inner, None),
+        };
+
+        let mut block = block;
+        if let Some(scroll) = scroll {
+            let max_scroll = line_count.saturating_sub(text_area.height);
+            self.review_scroll.set((max_scroll, text_area.height));
+            let scroll = scroll.min(max_scroll);
+            paragraph = paragraph.scroll((scroll, 0));
+            if max_scroll > 0 {
+                let shown = format!(
+                    " Lines {}-{} of {} ",
+                    scroll + 1,
+                    scroll + text_area.height,
+                    line_count
+                );
+                block = block.title_top(Line::from(shown).right_aligned());
+            }
+        }
+
+        block.render(area, buf);
+        paragraph.render(text_area, buf);
+        if image.is_some() {
+            *self.image_slot.borrow_mut() = image;
+        }
+    }
+
     fn render_create_topic(&self, area: Rect, buf: &mut Buffer, input: &str) {
-        let text = vec![
+        let mut text = vec![
             Line::from(""),
-            Line::from("Enter topic name:"),
+            Line::from(tr("Enter topic name:")),
             Line::from(""),
             Line::from(vec![
                 Span::raw("> "),
-                Span::styled(input, Style::default().fg(Color::Yellow)), // Use input directly
-                Span::styled("█", Style::default().fg(Color::Yellow)),
+                Span::styled(input, Style::default().fg(theme::palette().input)), // Use input directly
+                Span::styled("█", Style::default().fg(theme::palette().input)),
             ]),
         ];
+        // Nothing to complain about before anything is typed
+        if !input.is_empty()
+            && let Some(error) = self.topics.new_topic_error(input)
+        {
+            text.push(Line::from(""));
+            let error = match error {
+                TopicNameError::Empty => tr("The name can't be empty").to_string(),
+                TopicNameError::Control => {
+                    tr("The name can't hold tabs or line breaks").to_string()
+                }
+                TopicNameError::Taken(existing) => {
+                    trf("There is already a topic '{}'", &[&existing])
+                }
+            };
+            text.push(Line::from(format!("⚠ {}", error)).red());
+        }
 
-        let instructions = " Press Enter to create | Esc to cancel ";
+        let instructions = format!(" {} ", tr("Press Enter to create | Esc to cancel"));
 
         Paragraph::new(text)
             .left_aligned()
             .block(
                 Block::bordered()
-                    .title(" ➕ New Topic ".bold().into_left_aligned_line())
+                    .title(
+                        format!(" ➕ {} ", tr("New Topic"))
+                            .bold()
+                            .into_left_aligned_line(),
+                    )
                     .title_bottom(instructions),
             )
             .render(area, buf);
     }
 
+    fn render_browser(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        topic: &str,
+        selected: usize,
+        sort: CardSort,
+    ) {
+        let cards = self
+            .topics
+            .topics_map
+            .get(topic)
+            .map_or(&[][..], Vec::as_slice);
+        let difficulty = stats::difficulty(cards, topic, self.history.reviews());
+        CardBrowser {
+            topic,
+            cards,
+            order: &browser::sorted(cards, &difficulty, sort),
+            difficulty: &difficulty,
+            selected,
+            sort,
+        }
+        .render(area, buf);
+    }
+
     fn render_add_card(
         &self,
         area: Rect,
@@ -643,6 +5742,17 @@ impl App {
         answer: &str,
         editing_question: bool,
     ) {
+        let rewording = matches!(
+            self.state,
+            AppState::AddCard {
+                editing: Some(_),
+                ..
+            }
+        );
+        let cursor = match self.state {
+            AppState::AddCard { cursor, .. } => cursor,
+            _ => 0,
+        };
         let chunks = Layout::vertical([
             Constraint::Percentage(40),
             Constraint::Percentage(40),
@@ -652,153 +5762,202 @@ impl App {
 
         // Question input
         let question_style = if editing_question {
-            Style::default().fg(Color::Yellow)
+            Style::default().fg(theme::palette().input)
         } else {
             Style::default().fg(Color::DarkGray)
         };
 
-        // let question_text = if question.is_empty() && editing_question {
-        //     vec![
-        //         Line::from(""),
-        //         Line::from(vec![Span::raw("> "), Span::styled("█", question_style)]),
-        //     ]
-        // } else {
-        //     vec![
-        //         Line::from(""),
-        //         Line::from(vec![
-        //             Span::raw("> "),
-        //             Span::styled(question, question_style),
-        //             if editing_question {
-        //                 Span::styled("█", question_style)
-        //             } else {
-        //                 Span::raw("")
-        //             },
-        //         ]),
-        //     ]
-        // };
-
-        let question_text = if question.is_empty() && editing_question {
-            vec![
-                Line::from(""),
-                Line::from(vec![Span::raw("> "), Span::styled("█", question_style)]),
-            ]
-        } else {
-            let question_lines: Vec<&str> = question.split('\n').collect();
-            let num_lines = question_lines.len();
-
-            std::iter::once(Line::from("")) // Empty line at top
-                .chain(question_lines.iter().enumerate().map(|(i, line)| {
-                    let mut spans = vec![Span::raw("> "), Span::styled(*line, question_style)];
-
-                    // Cursor on last line when editing
-                    if editing_question && i == num_lines - 1 {
-                        spans.push(Span::styled("█", question_style));
-                    }
-
-                    Line::from(spans)
-                }))
-                .collect()
-        };
-
-        Paragraph::new(question_text)
-            .wrap(Wrap { trim: true })
-            .block(
-                Block::bordered()
-                    .title(format!(
-                        " Question {} ",
-                        if editing_question { "✎" } else { "" }
-                    ))
-                    .style(if editing_question {
-                        Style::default().fg(Color::Cyan)
-                    } else {
-                        Style::default()
-                    }),
-            )
-            .render(chunks[0], buf);
+        let question_block = Block::bordered()
+            .title(format!(
+                " {} {} ",
+                tr("Question"),
+                if editing_question { "✎" } else { "" }
+            ))
+            .style(if editing_question {
+                Style::default().fg(theme::palette().accent)
+            } else {
+                Style::default()
+            });
+        let question_block = question_block.title_bottom(
+            Line::from(format!(" {} ", Counts::of(question)))
+                .right_aligned()
+                .dark_gray(),
+        );
+        let question_area = question_block.inner(chunks[0]);
+        question_block.render(chunks[0], buf);
+        self.editor_width.set(question_area.width);
+        let misspelled: Vec<&str> = self.misspelled.iter().map(|m| m.word.as_str()).collect();
+        EditorView::new(
+            question,
+            question_style,
+            editing_question.then_some(cursor),
+            question_area.width,
+        )
+        .misspelled(&misspelled)
+        .render(question_area, buf);
 
         // Answer input
         let answer_style = if !editing_question {
-            Style::default().fg(Color::Yellow)
+            Style::default().fg(theme::palette().input)
         } else {
             Style::default().fg(Color::DarkGray)
         };
 
-        // let answer_text = if answer.is_empty() && !editing_question {
-        //     vec![
-        //         Line::from(""),
-        //         Line::from(vec![Span::raw("> "), Span::styled("█", answer_style)]),
-        //     ]
-        // } else {
-        //     vec![
-        //         Line::from(""),
-        //         Line::from(vec![
-        //             Span::raw("> "),
-        //             Span::styled(answer, answer_style),
-        //             if !editing_question {
-        //                 Span::styled("█", answer_style)
-        //             } else {
-        //                 Span::raw("")
-        //             },
-        //         ]),
-        //     ]
-        // };
-
-        let answer_text = if answer.is_empty() && !editing_question {
-            vec![
-                Line::from(""),
-                Line::from(vec![Span::raw("> "), Span::styled("█", answer_style)]),
-            ]
+        let answer_block = Block::bordered()
+            .title(format!(
+                " {} {} ",
+                tr("Answer"),
+                if !editing_question { "✎" } else { "" }
+            ))
+            .style(if !editing_question {
+                Style::default().fg(theme::palette().accent)
+            } else {
+                Style::default()
+            });
+        // Long answers are hard to recall in one go, so they're flagged
+        let counts = Counts::of(answer);
+        let max_length = self.config.max_answer_length.unwrap_or(MAX_ANSWER_LENGTH);
+        let counts_line = if counts.chars > max_length {
+            Line::from(format!(" ⚠ {}, over {} ", counts, max_length)).red()
         } else {
-            let answer_lines: Vec<&str> = answer.split('\n').collect();
-            let num_lines = answer_lines.len();
-
-            std::iter::once(Line::from("")) // Empty line at top
-                .chain(answer_lines.iter().enumerate().map(|(i, line)| {
-                    let mut spans = vec![Span::raw("> "), Span::styled(*line, answer_style)];
-
-                    // Cursor on last line when editing answer
-                    if !editing_question && i == num_lines - 1 {
-                        spans.push(Span::styled("█", answer_style));
-                    }
-
-                    Line::from(spans)
-                }))
-                .collect()
+            Line::from(format!(" {} ", counts)).dark_gray()
         };
+        let answer_block = answer_block.title_bottom(counts_line.right_aligned());
+        let answer_area = answer_block.inner(chunks[1]);
+        answer_block.render(chunks[1], buf);
+        let answer_cursor = (!editing_question).then_some(cursor);
+        EditorView::new(answer, answer_style, answer_cursor, answer_area.width)
+            .misspelled(&misspelled)
+            .render(answer_area, buf);
 
-        Paragraph::new(answer_text)
-            .wrap(Wrap { trim: true })
-            .block(
-                Block::bordered()
-                    .title(format!(
-                        " Answer {} ",
-                        if !editing_question { "✎" } else { "" }
-                    ))
-                    .style(if !editing_question {
-                        Style::default().fg(Color::Cyan)
-                    } else {
-                        Style::default()
-                    }),
-            )
-            .render(chunks[1], buf);
-
-        // Instructions
-        let instructions = vec![
-            Line::from(""),
-            Line::from(vec![
-                " Switch field ".into(),
-                "<Tab>".blue().bold(),
-                " Save ".into(),
-                // "<Shift + Opt + Enter>".green().bold(),
-                "<CTL + S >".green().bold(),
-                " Cancel ".into(),
-                "<Esc> ".red().bold(),
-            ]),
+        // Instructions, with suggestions for the last misspelled word in
+        // place of the blank line
+        let field = if editing_question { question } else { answer };
+        let suggestion = spell::last_fixable(field, &self.misspelled).map(|(_, m)| m);
+        let mut keys = vec![
+            hint("Switch field"),
+            "<Tab>".blue().bold(),
+            hint("Save"),
+            // "<Shift + Opt + Enter>".green().bold(),
+            "<CTL + S >".green().bold(),
         ];
+        if !rewording {
+            keys.extend([
+                hint("Save & next"),
+                "<CTL + N>".green().bold(),
+                hint("As template"),
+                "<CTL + T>".fg(theme::palette().accent).bold(),
+            ]);
+        }
+        let spelling = match suggestion {
+            Some(misspelling) => {
+                keys.extend([
+                    hint("Fix spelling"),
+                    "<CTL + R>".fg(theme::palette().input).bold(),
+                ]);
+                Line::from(vec![
+                    hint("Spelling:"),
+                    misspelling.word.clone().red().underlined(),
+                    format!(" → {}", misspelling.suggestions.join(", ")).into(),
+                ])
+            }
+            None => Line::from(""),
+        };
+        keys.extend([hint("Cancel"), "<Esc> ".red().bold()]);
+        let instructions = vec![spelling, Line::from(keys)];
 
         Paragraph::new(instructions)
             .left_aligned()
-            .block(Block::bordered().title(format!(" 📝 Add Card to '{}' topic", topic)))
+            .block(Block::bordered().title(if rewording {
+                format!(" 📝 {}", trf("Edit Card in '{}' topic", &[&topic]))
+            } else {
+                format!(" 📝 {}", trf("Add Card to '{}' topic", &[&topic]))
+            }))
             .render(chunks[2], buf);
     }
+
+    fn render_generate(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        topic: &str,
+        input: &str,
+        waiting: bool,
+    ) {
+        let instructions = if waiting {
+            Line::from(vec![
+                hint("Generating cards… Cancel"),
+                "<Esc> ".red().bold(),
+            ])
+        } else {
+            Line::from(vec![
+                hint("Generate"),
+                "<CTL + S>".green().bold(),
+                hint("Cancel"),
+                "<Esc> ".red().bold(),
+            ])
+        };
+        let block = Block::bordered()
+            .title(format!(" ✨ {} ", trf("Generate Cards for '{}'", &[&topic])).bold())
+            .title_bottom(instructions);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [hint_area, input_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+        Paragraph::new(tr("Paste notes to make cards from, or describe a subject:"))
+            .style(Style::default().fg(Color::DarkGray))
+            .render(hint_area, buf);
+        let style = Style::default().fg(if waiting {
+            Color::DarkGray
+        } else {
+            theme::palette().input
+        });
+        let cursor = (!waiting).then_some(input.len());
+        EditorView::new(input, style, cursor, input_area.width).render(input_area, buf);
+    }
+
+    fn render_drafted(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        topic: &str,
+        cards: &[Flashcard],
+        index: usize,
+    ) {
+        let card = &cards[index];
+        let instructions = Line::from(vec![
+            hint("Keep"),
+            "<Y>".green().bold(),
+            hint("Skip"),
+            "<N>".red().bold(),
+            hint("Stop"),
+            "<Esc> ".blue().bold(),
+        ]);
+        let block = Block::bordered()
+            .title(
+                format!(
+                    " ✨ {} ",
+                    trf(
+                        "Card {} of {} for '{}'",
+                        &[&(index + 1), &cards.len(), &topic]
+                    )
+                )
+                .bold(),
+            )
+            .title_bottom(instructions);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [question_area, answer_area] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(inner);
+        Paragraph::new(card.question.as_str())
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(format!(" {} ", tr("Question"))))
+            .render(question_area, buf);
+        Paragraph::new(card.answer.as_str())
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(format!(" {} ", tr("Answer"))))
+            .render(answer_area, buf);
+    }
 }
@@ -0,0 +1,923 @@
+//! Lightweight markup rendering for card text.
+//!
+//! Math is written between `$` delimiters using a small LaTeX-like subset
+//! (`x^2`, `H_2O`, `a_{ij}`, `\alpha`, `\frac{a}{b}`, `\sqrt{x}`, common
+//! operators and arrows) and is converted to plain Unicode so math and
+//! chemistry cards read well in any terminal. Text outside `$...$` is left
+//! untouched, and `\$` produces a literal dollar sign. As in Pandoc, a `$`
+//! only opens math right before a non-space character, and only closes it
+//! right after one when no digit follows, so prices such as "costs $5 and
+//! $10" stay as written.
+//!
+//! Images are referenced Markdown-style with `![alt](path)`.
+//!
+//! Other cards are linked with `[[topic/card]]`, where `card` is the card's
+//! number in its topic or its question, and a whole topic with `[[topic]]`.
+//!
+//! Ruby (furigana) readings follow their base text in brackets: `漢字[かんじ]`.
+//! The base is the run of kanji/hanzi before the bracket, or everything after
+//! a `｜` marker for mixed bases such as `｜お茶[おちゃ]`.
+//!
+//! The card browser's preview also styles a subset of Markdown: headings,
+//! lists, quotes, code blocks, `**bold**`, `*italic*`, `~~struck~~` and
+//! `` `code` ``.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+// Pads ruby rows; unlike a regular space it survives `Wrap { trim: true }`
+const NBSP: char = '\u{a0}';
+// Groups and scripts nested deeper than this are left as written, so a
+// card full of `{` can't run the parser out of stack
+const MAX_MATH_DEPTH: usize = 64;
+
+/// How ruby readings are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RubyMode {
+    /// On a row above the base text, falling back to `Beside` for lines too
+    /// wide for the pane
+    #[default]
+    Above,
+    /// In parentheses after the base text
+    Beside,
+    /// Not at all, only the base text
+    Hidden,
+}
+
+/// An image reference found in card text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    pub alt: String,
+    pub path: String,
+}
+
+/// Removes `![alt](path)` references from `text`, returning the remaining
+/// text and the references in order of appearance.
+pub fn split_images(text: &str) -> (String, Vec<ImageRef>) {
+    let mut out = String::with_capacity(text.len());
+    let mut images = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("![") {
+        let parsed = rest[start + 2..].split_once("](").and_then(|(alt, tail)| {
+            let (path, after) = tail.split_once(')')?;
+            // Alt text and path stay on one line
+            (!alt.contains(['\n', ']']) && !path.contains('\n')).then_some((alt, path, after))
+        });
+
+        match parsed {
+            Some((alt, path, after)) => {
+                out.push_str(&rest[..start]);
+                images.push(ImageRef {
+                    alt: alt.to_string(),
+                    path: path.trim().to_string(),
+                });
+                rest = after;
+            }
+            None => {
+                out.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    (out.trim_end().to_string(), images)
+}
+
+/// The targets of the `[[...]]` links in `text`, in order of appearance.
+pub fn links(text: &str) -> Vec<&str> {
+    let mut links = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("]]") else {
+            break;
+        };
+        let target = after[..end].trim();
+        if !target.is_empty() && !target.contains('\n') {
+            links.push(target);
+        }
+        rest = &after[end + 2..];
+    }
+    links
+}
+
+/// Renders all `$...$` math spans in `text` as Unicode.
+pub fn render_math(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = find_dollar(rest, opens) {
+        out.push_str(&unescape_dollars(&rest[..start]));
+        let after = &rest[start + 1..];
+        match find_dollar(after, closes) {
+            Some(end) => {
+                out.push_str(&MathParser::new(&after[..end]).parse());
+                rest = &after[end + 1..];
+            }
+            None => {
+                // Unterminated math: keep the text as written
+                out.push('$');
+                rest = after;
+            }
+        }
+    }
+
+    out.push_str(&unescape_dollars(rest));
+    out
+}
+
+// Whether a `$` between `before` and `after` opens math
+fn opens(_before: Option<char>, after: Option<char>) -> bool {
+    after.is_some_and(|c| !c.is_whitespace())
+}
+
+// Whether a `$` between `before` and `after` closes math
+fn closes(before: Option<char>, after: Option<char>) -> bool {
+    before.is_some_and(|c| !c.is_whitespace()) && !after.is_some_and(|c| c.is_ascii_digit())
+}
+
+// Byte offset of the next `$` that isn't escaped with a backslash and that
+// `fits` between the characters around it
+fn find_dollar(s: &str, fits: fn(Option<char>, Option<char>) -> bool) -> Option<usize> {
+    let mut escaped = false;
+    let mut before = None;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '$' if !escaped && fits(before, s[i + 1..].chars().next()) => return Some(i),
+            _ => escaped = false,
+        }
+        before = Some(c);
+    }
+    None
+}
+
+fn unescape_dollars(s: &str) -> String {
+    s.replace("\\$", "$")
+}
+
+struct MathParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    // Tokens being parsed inside one another
+    depth: usize,
+}
+
+impl<'a> MathParser<'a> {
+    fn new(src: &'a str) -> Self {
+        MathParser {
+            chars: src.chars().peekable(),
+            depth: 0,
+        }
+    }
+
+    fn parse(&mut self) -> String {
+        let mut out = String::new();
+        while let Some(c) = self.chars.next() {
+            out.push_str(&self.parse_from(c));
+        }
+        out
+    }
+
+    // Contents of a `{...}` group, the opening brace already consumed
+    fn parse_group(&mut self) -> String {
+        let mut out = String::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                '}' => break,
+                _ => out.push_str(&self.parse_from(c)),
+            }
+        }
+        out
+    }
+
+    // Renders the token starting with the already consumed `c`
+    fn parse_from(&mut self, c: char) -> String {
+        if self.depth >= MAX_MATH_DEPTH {
+            return c.to_string();
+        }
+        self.depth += 1;
+        let out = self.parse_token(c);
+        self.depth -= 1;
+        out
+    }
+
+    fn parse_token(&mut self, c: char) -> String {
+        match c {
+            '\\' => self.parse_command(),
+            '{' => self.parse_group(),
+            '^' => {
+                let atom = self.parse_atom();
+                to_script(&atom, superscript).unwrap_or_else(|| format!("^{}", group(&atom)))
+            }
+            '_' => {
+                let atom = self.parse_atom();
+                to_script(&atom, subscript).unwrap_or_else(|| format!("_{}", group(&atom)))
+            }
+            _ => c.to_string(),
+        }
+    }
+
+    // A single argument: `{...}`, `\command` or one character
+    fn parse_atom(&mut self) -> String {
+        match self.chars.next() {
+            Some(c) => self.parse_from(c),
+            None => String::new(),
+        }
+    }
+
+    fn parse_command(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            name.push(c);
+            self.chars.next();
+        }
+
+        if name.is_empty() {
+            // Escaped character such as `\{`, `\$` or a spacing command
+            return match self.chars.next() {
+                Some(',' | ';' | ':' | ' ') => " ".to_string(),
+                Some('!') => String::new(),
+                Some(c) => c.to_string(),
+                None => "\\".to_string(),
+            };
+        }
+
+        match name.as_str() {
+            "frac" => {
+                let num = self.parse_atom();
+                let den = self.parse_atom();
+                fraction(&num, &den)
+            }
+            "sqrt" => {
+                let radicand = self.parse_atom();
+                format!("√{}", group(&radicand))
+            }
+            "text" | "mathrm" | "mathbf" | "mathit" | "operatorname" => self.parse_atom(),
+            _ => match symbol(&name) {
+                Some(s) => s.to_string(),
+                None => format!("\\{}", name),
+            },
+        }
+    }
+}
+
+fn fraction(num: &str, den: &str) -> String {
+    match (to_script(num, superscript), to_script(den, subscript)) {
+        (Some(n), Some(d)) if num.chars().all(|c| c.is_ascii_digit()) => format!("{}⁄{}", n, d),
+        _ => format!("{}/{}", group(num), group(den)),
+    }
+}
+
+// Parenthesizes compound expressions so `a+b` doesn't read as `a + b/c`
+fn group(s: &str) -> String {
+    if s.chars().count() <= 1 || s.chars().all(|c| c.is_alphanumeric()) {
+        s.to_string()
+    } else {
+        format!("({})", s)
+    }
+}
+
+fn to_script(s: &str, map: fn(char) -> Option<char>) -> Option<String> {
+    if s.is_empty() {
+        return None;
+    }
+    s.chars().map(map).collect()
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'A' => 'ᴬ',
+        'B' => 'ᴮ',
+        'D' => 'ᴰ',
+        'E' => 'ᴱ',
+        'G' => 'ᴳ',
+        'H' => 'ᴴ',
+        'I' => 'ᴵ',
+        'J' => 'ᴶ',
+        'K' => 'ᴷ',
+        'L' => 'ᴸ',
+        'M' => 'ᴹ',
+        'N' => 'ᴺ',
+        'O' => 'ᴼ',
+        'P' => 'ᴾ',
+        'R' => 'ᴿ',
+        'T' => 'ᵀ',
+        'U' => 'ᵁ',
+        'V' => 'ⱽ',
+        'W' => 'ᵂ',
+        'α' => 'ᵅ',
+        'β' => 'ᵝ',
+        'γ' => 'ᵞ',
+        'δ' => 'ᵟ',
+        'θ' => 'ᶿ',
+        'φ' => 'ᵠ',
+        'χ' => 'ᵡ',
+        '∗' | '*' => '*',
+        '′' | '\'' => '′',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        'β' => 'ᵦ',
+        'γ' => 'ᵧ',
+        'ρ' => 'ᵨ',
+        'φ' => 'ᵩ',
+        'χ' => 'ᵪ',
+        _ => return None,
+    })
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        // Greek letters
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "omicron" => "ο",
+        "pi" => "π",
+        "varpi" => "ϖ",
+        "rho" => "ρ",
+        "varrho" => "ϱ",
+        "sigma" => "σ",
+        "varsigma" => "ς",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        // Operators and relations
+        "times" => "×",
+        "cdot" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "propto" => "∝",
+        "ll" => "≪",
+        "gg" => "≫",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "oint" => "∮",
+        "circ" => "∘",
+        "degree" => "°",
+        "angle" => "∠",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        // Arrows, including chemistry equilibrium
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "rightleftharpoons" => "⇌",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        // Sets and logic
+        "in" => "∈",
+        "notin" => "∉",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        // Miscellaneous
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "aleph" => "ℵ",
+        "prime" => "′",
+        "quad" => "  ",
+        "qquad" => "    ",
+        _ => return None,
+    })
+}
+
+/// Renders card text with math and ruby annotations into lines for a pane
+/// `width` columns wide.
+pub fn render_text(text: &str, ruby: RubyMode, width: u16) -> Vec<Line<'static>> {
+    let reading_style = Style::default().add_modifier(Modifier::DIM);
+    let mut lines = Vec::new();
+
+    for line in render_math(text).split('\n') {
+        let segments = parse_ruby(line);
+        if segments.iter().all(|s| matches!(s, Segment::Plain(_))) {
+            lines.push(Line::raw(line.to_string()));
+            continue;
+        }
+
+        if ruby == RubyMode::Above
+            && let Some((top, bottom)) = ruby_above(&segments, usize::from(width))
+        {
+            lines.push(Line::styled(top, reading_style));
+            lines.push(Line::raw(bottom));
+            continue;
+        }
+
+        let spans: Vec<Span> = segments
+            .iter()
+            .flat_map(|segment| match segment {
+                Segment::Plain(text) => vec![Span::raw(text.to_string())],
+                Segment::Ruby { base, .. } if ruby == RubyMode::Hidden => {
+                    vec![Span::raw(base.to_string())]
+                }
+                Segment::Ruby { base, reading } => vec![
+                    Span::raw(base.to_string()),
+                    Span::styled(format!("({})", reading), reading_style),
+                ],
+            })
+            .collect();
+        lines.push(Line::from(spans));
+    }
+
+    lines
+}
+
+/// Replaces annotated bases with their readings, e.g. for text-to-speech.
+pub fn ruby_readings(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            parse_ruby(line)
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Plain(text) => *text,
+                    Segment::Ruby { reading, .. } => *reading,
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders card text with math and Markdown styling into lines, for
+/// proofreading cards as they were written. Ruby is left as typed.
+pub fn render_markdown(text: &str) -> Vec<Line<'static>> {
+    let code = Style::default().fg(Color::Cyan);
+    let dim = Style::default().add_modifier(Modifier::DIM);
+    let mut lines = Vec::new();
+    let mut in_code = false;
+
+    for line in render_math(text).split('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push(Line::styled(line.to_string(), code));
+            continue;
+        }
+
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let rest = line.trim_start();
+        let hashes = rest.chars().take_while(|&c| c == '#').count();
+        let line = if (1..=6).contains(&hashes) && rest[hashes..].starts_with(' ') {
+            let mut style = Style::default().add_modifier(Modifier::BOLD);
+            if hashes == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            Line::from(inline_markdown(rest[hashes..].trim(), style))
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .into_iter()
+            .find_map(|bullet| rest.strip_prefix(bullet))
+        {
+            let mut spans = vec![Span::raw(format!("{}• ", indent))];
+            spans.extend(inline_markdown(item, Style::default()));
+            Line::from(spans)
+        } else if let Some(quote) = rest.strip_prefix('>') {
+            let mut spans = vec![Span::styled(format!("{}│ ", indent), dim)];
+            spans.extend(inline_markdown(quote.trim_start(), Style::default()));
+            Line::from(spans)
+        } else {
+            Line::from(inline_markdown(line, Style::default()))
+        };
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Spans of a line with its emphasis, code and strikethrough styled over
+// `base`. Unclosed markers and `\`-escaped ones are kept as text.
+fn inline_markdown(text: &str, base: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\\'
+            && let Some(next) = rest[1..].chars().next().filter(|c| "*`~_\\".contains(*c))
+        {
+            plain.push(next);
+            rest = &rest[1 + next.len_utf8()..];
+            continue;
+        }
+        let styled = [
+            ("`", Style::default().fg(Color::Cyan)),
+            ("**", Style::default().add_modifier(Modifier::BOLD)),
+            ("~~", Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            ("*", Style::default().add_modifier(Modifier::ITALIC)),
+        ]
+        .into_iter()
+        .find_map(|(marker, style)| {
+            let inner = rest.strip_prefix(marker)?;
+            let end = inner.find(marker)?;
+            // `* a *` and `**` alone aren't emphasis
+            let content = &inner[..end];
+            if content.is_empty() || content.starts_with(' ') || content.ends_with(' ') {
+                return None;
+            }
+            Some((marker, content, style))
+        });
+        match styled {
+            Some((marker, content, style)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), base));
+                }
+                if marker == "`" {
+                    spans.push(Span::styled(content.to_string(), base.patch(style)));
+                } else {
+                    spans.extend(inline_markdown(content, base.patch(style)));
+                }
+                rest = &rest[2 * marker.len() + content.len()..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Plain(&'a str),
+    Ruby { base: &'a str, reading: &'a str },
+}
+
+fn parse_ruby(line: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut plain_start = 0;
+    let mut search_from = 0;
+
+    while let Some(offset) = line[search_from..].find('[') {
+        let open = search_from + offset;
+        search_from = open + 1;
+
+        let Some(len) = line[open + 1..].find(']') else {
+            break;
+        };
+        let reading = &line[open + 1..open + 1 + len];
+        if reading.is_empty() || reading.contains('[') {
+            continue;
+        }
+
+        let before = &line[plain_start..open];
+        let (plain_end, base_start) = match before.rfind(['｜', '|']) {
+            // Explicit marker, the base is everything after it up to the bracket
+            Some(marker) if !before[marker..].contains(char::is_whitespace) => {
+                let marker_len = before[marker..].chars().next().map_or(1, char::len_utf8);
+                (marker, marker + marker_len)
+            }
+            _ => {
+                let base_len: usize = before
+                    .chars()
+                    .rev()
+                    .take_while(|&c| is_han(c))
+                    .map(char::len_utf8)
+                    .sum();
+                (before.len() - base_len, before.len() - base_len)
+            }
+        };
+        let base = &before[base_start..];
+        if base.is_empty() {
+            continue;
+        }
+
+        if plain_end > 0 {
+            segments.push(Segment::Plain(&before[..plain_end]));
+        }
+        segments.push(Segment::Ruby { base, reading });
+        plain_start = open + len + 2;
+        search_from = plain_start;
+    }
+
+    if plain_start < line.len() {
+        segments.push(Segment::Plain(&line[plain_start..]));
+    }
+    segments
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2A6DF}'
+        | '々' | '〆' | 'ヶ')
+}
+
+// Lays out readings on a row above their bases, or `None` if the result
+// would be wider than `width` and get wrapped out of alignment
+fn ruby_above(segments: &[Segment], width: usize) -> Option<(String, String)> {
+    let mut top = String::new();
+    let mut bottom = String::new();
+
+    for segment in segments {
+        match segment {
+            Segment::Plain(text) => {
+                top.extend(std::iter::repeat_n(NBSP, text.width()));
+                bottom.push_str(text);
+            }
+            Segment::Ruby { base, reading } => {
+                let cell = base.width().max(reading.width());
+                top.push_str(&centered(reading, cell));
+                bottom.push_str(&centered(base, cell));
+            }
+        }
+    }
+
+    (bottom.width() <= width).then_some((top, bottom))
+}
+
+fn centered(text: &str, cell: usize) -> String {
+    let pad = cell - text.width();
+    let left = pad / 2;
+    let mut out: String = std::iter::repeat_n(NBSP, left).collect();
+    out.push_str(text);
+    out.extend(std::iter::repeat_n(NBSP, pad - left));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_str_eq;
+
+    #[test]
+    fn test_text_outside_math_is_untouched() {
+        assert_str_eq!(render_math("snake_case x^2"), "snake_case x^2");
+        assert_str_eq!(render_math("costs \\$5"), "costs $5");
+        assert_str_eq!(render_math("unterminated $x^2"), "unterminated $x^2");
+        assert_str_eq!(render_math("costs $5 and $10"), "costs $5 and $10");
+        assert_str_eq!(render_math("$x^2$ costs $5"), "x² costs $5");
+        assert_str_eq!(render_math("from $5$10 up"), "from $5$10 up");
+        assert_str_eq!(render_math("a $ b $ c"), "a $ b $ c");
+        assert_str_eq!(render_math("\\$x^2\\$ and $x^2$"), "$x^2$ and x²");
+    }
+
+    #[test]
+    fn test_scripts() {
+        assert_str_eq!(render_math("$H_2O$"), "H₂O");
+        assert_str_eq!(render_math("$E = mc^2$"), "E = mc²");
+        assert_str_eq!(render_math("$x^{n+1}$"), "xⁿ⁺¹");
+        assert_str_eq!(render_math("$SO_4^{2-}$"), "SO₄²⁻");
+        // No Unicode subscript for `y`, fall back to explicit notation
+        assert_str_eq!(render_math("$a_y$"), "a_y");
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let braces = format!("${}x{}$", "{".repeat(100_000), "}".repeat(100_000));
+        assert!(render_math(&braces).contains('x'));
+        let scripts = format!("$x{}2$", "^".repeat(100_000));
+        assert!(render_math(&scripts).starts_with('x'));
+        // Shallow groups are unaffected
+        assert_str_eq!(render_math("$x^{{{2}}}$"), "x²");
+    }
+
+    #[test]
+    fn test_symbols_and_fractions() {
+        assert_str_eq!(render_math("$\\alpha + \\beta \\to \\Omega$"), "α + β → Ω");
+        assert_str_eq!(render_math("$\\frac{1}{2}$"), "¹⁄₂");
+        assert_str_eq!(render_math("$\\frac{a+b}{c}$"), "(a+b)/c");
+        assert_str_eq!(render_math("$\\sqrt{2}$"), "√2");
+        assert_str_eq!(render_math("$\\sqrt{x+1}$"), "√(x+1)");
+        assert_str_eq!(render_math("$\\unknown$"), "\\unknown");
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let lines = render_markdown(
+            "# Verbs\n- **ser**, *to be*\n> `estar` too\n```\n**kept**\n```\n2 * 3 = \\*6\\*",
+        );
+        let text: Vec<String> = lines.iter().map(Line::to_string).collect();
+        assert_eq!(
+            text,
+            [
+                "Verbs",
+                "• ser, to be",
+                "│ estar too",
+                "**kept**",
+                "2 * 3 = *6*"
+            ]
+        );
+        assert!(
+            lines[0].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::UNDERLINED)
+        );
+        let bold = &lines[1].spans[1];
+        assert_eq!(
+            (bold.content.as_ref(), bold.style.add_modifier),
+            ("ser", Modifier::BOLD)
+        );
+        let italic = &lines[1].spans[3];
+        assert_eq!(italic.style.add_modifier, Modifier::ITALIC);
+    }
+
+    #[test]
+    fn test_split_images() {
+        let (text, images) = split_images("The heart:\n![heart diagram](img/heart.png)");
+        assert_str_eq!(text.as_str(), "The heart:");
+        assert_eq!(
+            images,
+            vec![ImageRef {
+                alt: "heart diagram".to_string(),
+                path: "img/heart.png".to_string(),
+            }]
+        );
+
+        let (text, images) = split_images("not an image: ![oops");
+        assert_str_eq!(text.as_str(), "not an image: ![oops");
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(
+            links("See [[Biology/3]] and [[ Chemistry ]], not [[]] or [[open"),
+            vec!["Biology/3", "Chemistry"]
+        );
+        assert!(links("[single] brackets").is_empty());
+    }
+
+    #[test]
+    fn test_parse_ruby() {
+        assert_eq!(
+            parse_ruby("これは漢字[かんじ]です"),
+            vec![
+                Segment::Plain("これは"),
+                Segment::Ruby {
+                    base: "漢字",
+                    reading: "かんじ"
+                },
+                Segment::Plain("です"),
+            ]
+        );
+        assert_eq!(
+            parse_ruby("｜お茶[おちゃ]"),
+            vec![Segment::Ruby {
+                base: "お茶",
+                reading: "おちゃ"
+            }]
+        );
+        // Brackets after non-CJK text are left alone
+        assert_eq!(parse_ruby("see [1]"), vec![Segment::Plain("see [1]")]);
+    }
+
+    #[test]
+    fn test_render_ruby() {
+        let lines = render_text("日本[にほん]", RubyMode::Above, 40);
+        assert_eq!(lines.len(), 2);
+        assert_str_eq!(lines[0].to_string(), "にほん");
+        assert_str_eq!(lines[1].to_string(), "\u{a0}日本\u{a0}");
+
+        let lines = render_text("日本[にほん]", RubyMode::Beside, 40);
+        assert_str_eq!(lines[0].to_string(), "日本(にほん)");
+
+        // Too narrow for a reading row
+        let lines = render_text("日本[にほん]", RubyMode::Above, 4);
+        assert_str_eq!(lines[0].to_string(), "日本(にほん)");
+
+        assert_str_eq!(ruby_readings("日本[にほん]語"), "にほん語");
+    }
+}
@@ -0,0 +1,157 @@
+//! Files and links attached to cards.
+//!
+//! Attached files are copied into a `media` folder next to the deck, so the
+//! deck keeps working when the original is moved, and travel with it in
+//! bundles. Links are kept as they are. Either is opened with the system's
+//! opener: `xdg-open`, `open` on macOS or `explorer` on Windows. Only files
+//! inside the media folder are opened, whatever the deck says.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::bundle;
+
+/// Folder next to the deck that attached files are copied into.
+pub static MEDIA_DIR: &str = "media";
+
+/// Whether an attachment is a link rather than a file.
+pub fn is_url(attachment: &str) -> bool {
+    attachment.starts_with("https://") || attachment.starts_with("http://")
+}
+
+/// Attaches `source`, a URL or the path of a file, to a card of the deck in
+/// `deck_dir`. Files are copied into the media folder, under a new name if
+/// another file has theirs. Returns what to store on the card.
+pub fn attach(deck_dir: &Path, source: &str) -> io::Result<String> {
+    let source = source.trim();
+    if is_url(source) {
+        return Ok(source.to_string());
+    }
+    let path = expand_home(source);
+    if !path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no file {}", path.display()),
+        ));
+    }
+    let dir = deck_dir.join(MEDIA_DIR);
+    fs::create_dir_all(&dir)?;
+    let name = free_name(&dir, &path);
+    fs::copy(&path, dir.join(&name))?;
+    Ok(format!("{}/{}", MEDIA_DIR, name))
+}
+
+/// Short name of an attachment for lists: the file name, or the link.
+pub fn label(attachment: &str) -> &str {
+    if is_url(attachment) {
+        return attachment;
+    }
+    attachment.rsplit('/').next().unwrap_or(attachment)
+}
+
+/// Opens an attachment of a card of the deck in `deck_dir` with the
+/// system's opener, without waiting for it. Files must be in the media
+/// folder, so a shared deck can't have anything else on the system run.
+pub fn open(deck_dir: &Path, attachment: &str) -> io::Result<()> {
+    let target = if is_url(attachment) {
+        attachment.into()
+    } else {
+        if !bundle::is_relative(attachment) || !Path::new(attachment).starts_with(MEDIA_DIR) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} isn't in the {} folder", attachment, MEDIA_DIR),
+            ));
+        }
+        let path = deck_dir.join(attachment);
+        if !path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is missing", attachment),
+            ));
+        }
+        path.into_os_string()
+    };
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // Not through `cmd`, which would read its own syntax in the target
+        Command::new("explorer")
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+// `~/` at the start of a typed path is the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// The file name of `path`, with a number added before the extension if
+// `dir` already has a different file of that name
+fn free_name(dir: &Path, path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let taken = |name: &str| {
+        let existing = dir.join(name);
+        existing.exists() && fs::read(&existing).ok() != fs::read(path).ok()
+    };
+    if !taken(&name) {
+        return name.into_owned();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map_or(String::new(), |ext| format!(".{}", ext.to_string_lossy()));
+    (2..)
+        .map(|n| format!("{}-{}{}", stem, n, extension))
+        .find(|name| !taken(name))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attach() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("hola.mp3");
+        fs::write(&source, "hola").unwrap();
+        let deck_dir = dir.path().join("deck");
+
+        let source = source.to_string_lossy();
+        assert_eq!(attach(&deck_dir, &source).unwrap(), "media/hola.mp3");
+        // The same file again is the same attachment
+        assert_eq!(attach(&deck_dir, &source).unwrap(), "media/hola.mp3");
+        fs::write(dir.path().join("hola.mp3"), "adiós").unwrap();
+        assert_eq!(attach(&deck_dir, &source).unwrap(), "media/hola-2.mp3");
+
+        let url = "https://example.com/notes.pdf";
+        assert_eq!(attach(&deck_dir, url).unwrap(), url);
+        assert!(attach(&deck_dir, "missing.pdf").is_err());
+        assert_eq!(label("media/hola-2.mp3"), "hola-2.mp3");
+    }
+
+    #[test]
+    fn test_open_only_media() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("notes.sh"), "").unwrap();
+        for attachment in ["notes.sh", "media/../notes.sh", "/bin/sh", ""] {
+            let error = open(dir.path(), attachment).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        }
+        let error = open(dir.path(), "media/missing.pdf").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+}
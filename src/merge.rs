@@ -0,0 +1,292 @@
+//! Three-way merge of a deck changed in two places at once, such as in the
+//! app and by a sync tool like Dropbox or Syncthing writing the file.
+//!
+//! Both versions are compared with the one they started from, the base.
+//! Whatever only one side changed is taken from that side, so cards added,
+//! edited or removed on either side all make it into the result. Only a
+//! card changed on both sides in different ways is a conflict: the result
+//! keeps our version of it, and the conflict says what the other one was.
+//!
+//! Cards have no ids, so a card is known by its question, and the second
+//! card with the same question in a topic by that and being the second.
+
+use std::collections::HashMap;
+
+use crate::deck::{Flashcard, Topics, Trashed};
+
+/// A card changed on both sides in different ways.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Topic of the card
+    pub topic: String,
+    /// Question of the card, as it was in the base when it was there
+    pub question: String,
+    /// The card on our side, `None` where it was removed
+    pub ours: Option<Flashcard>,
+    /// The card on the other side, `None` where it was removed
+    pub theirs: Option<Flashcard>,
+}
+
+/// The outcome of a merge.
+#[derive(Debug)]
+pub struct Merged {
+    /// The merged deck, with our side of each conflict
+    pub topics: Topics,
+    /// Cards changed on both sides, in topic and card order
+    pub conflicts: Vec<Conflict>,
+}
+
+// A card's question and how many cards before it in its topic have the
+// same one
+type CardKey<'a> = (&'a str, usize);
+
+/// Merges `ours` and `theirs`, both changed from `base`.
+pub fn three_way(base: &Topics, ours: &Topics, theirs: &Topics) -> Merged {
+    let mut names: Vec<&String> = ours.topics_map.keys().collect();
+    names.extend(
+        theirs
+            .topics_map
+            .keys()
+            .filter(|name| !ours.topics_map.contains_key(*name)),
+    );
+    names.extend(base.topics_map.keys().filter(|name| {
+        !ours.topics_map.contains_key(*name) && !theirs.topics_map.contains_key(*name)
+    }));
+    names.sort();
+
+    let mut topics = Topics {
+        meta: pick(&base.meta, &ours.meta, &theirs.meta).clone(),
+        trash: merge_trash(&base.trash, &ours.trash, &theirs.trash),
+        ..Topics::default()
+    };
+    let mut conflicts = Vec::new();
+    for name in names {
+        let (b, o, t) = (
+            base.topics_map.get(name),
+            ours.topics_map.get(name),
+            theirs.topics_map.get(name),
+        );
+        let empty = Vec::new();
+        let cards = merge_cards(
+            name,
+            b.unwrap_or(&empty),
+            o.unwrap_or(&empty),
+            t.unwrap_or(&empty),
+            &mut conflicts,
+        );
+        let present = match (b.is_some(), o.is_some(), t.is_some()) {
+            (_, true, true) => true,
+            // Added on one side
+            (false, o, t) => o || t,
+            // Removed on one side, kept where the other changed cards
+            (true, o, t) => (o || t) && !cards.is_empty(),
+        };
+        if !present {
+            continue;
+        }
+        topics.topics_map.insert(name.clone(), cards);
+        let metas = [&base.topic_meta, &ours.topic_meta, &theirs.topic_meta].map(|m| m.get(name));
+        if let Some(meta) = pick(&metas[0], &metas[1], &metas[2]) {
+            topics.topic_meta.insert(name.clone(), (*meta).clone());
+        }
+    }
+    Merged { topics, conflicts }
+}
+
+/// Puts the other side of `conflict` in place of ours in `topics`.
+pub fn take_theirs(topics: &mut Topics, conflict: &Conflict) {
+    let cards = topics.topics_map.entry(conflict.topic.clone()).or_default();
+    let position = conflict
+        .ours
+        .as_ref()
+        .and_then(|ours| cards.iter().position(|card| card == ours));
+    match (position, &conflict.theirs) {
+        (Some(i), Some(theirs)) => cards[i] = theirs.clone(),
+        (Some(i), None) => {
+            cards.remove(i);
+        }
+        (None, Some(theirs)) => cards.push(theirs.clone()),
+        (None, None) => {}
+    }
+}
+
+// Our version where only we changed it or both changed it alike, theirs
+// where only they did, and ours again where both changed it differently
+fn pick<'a, T: PartialEq>(base: &'a T, ours: &'a T, theirs: &'a T) -> &'a T {
+    if ours == base { theirs } else { ours }
+}
+
+fn merge_cards(
+    topic: &str,
+    base: &[Flashcard],
+    ours: &[Flashcard],
+    theirs: &[Flashcard],
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<Flashcard> {
+    let base_cards = keyed(base);
+    let their_cards = keyed(theirs);
+    let our_keys: Vec<(CardKey, &Flashcard)> = keys(ours);
+    let our_cards: HashMap<CardKey, &Flashcard> = our_keys.iter().copied().collect();
+
+    let mut merged = Vec::new();
+    let mut decide = |key: CardKey, merged: &mut Vec<Flashcard>| {
+        let (b, o, t) = (
+            base_cards.get(&key).copied(),
+            our_cards.get(&key).copied(),
+            their_cards.get(&key).copied(),
+        );
+        if o == t || t == b {
+            merged.extend(o.cloned());
+        } else if o == b {
+            merged.extend(t.cloned());
+        } else {
+            conflicts.push(Conflict {
+                topic: topic.to_string(),
+                question: key.0.to_string(),
+                ours: o.cloned(),
+                theirs: t.cloned(),
+            });
+            merged.extend(o.cloned());
+        }
+    };
+    // Our order, with cards only they have after ours, in theirs. That
+    // covers cards removed on one side, being on the other
+    for (key, _) in &our_keys {
+        decide(*key, &mut merged);
+    }
+    for (key, _) in keys(theirs) {
+        if !our_cards.contains_key(&key) {
+            decide(key, &mut merged);
+        }
+    }
+    merged
+}
+
+fn keys(cards: &[Flashcard]) -> Vec<(CardKey<'_>, &Flashcard)> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    cards
+        .iter()
+        .map(|card| {
+            let count = seen.entry(card.question.as_str()).or_default();
+            let key = (card.question.as_str(), *count);
+            *count += 1;
+            (key, card)
+        })
+        .collect()
+}
+
+fn keyed(cards: &[Flashcard]) -> HashMap<CardKey<'_>, &Flashcard> {
+    keys(cards).into_iter().collect()
+}
+
+// Entries on both sides, and those added on either
+fn merge_trash(base: &[Trashed], ours: &[Trashed], theirs: &[Trashed]) -> Vec<Trashed> {
+    let mut trash: Vec<Trashed> = ours
+        .iter()
+        .filter(|entry| theirs.contains(entry) || !base.contains(entry))
+        .cloned()
+        .collect();
+    trash.extend(
+        theirs
+            .iter()
+            .filter(|entry| !ours.contains(entry) && !base.contains(entry))
+            .cloned(),
+    );
+    trash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_deck::deck;
+
+    fn card(question: &str, answer: &str) -> Flashcard {
+        Flashcard::new(question, answer)
+    }
+
+    #[test]
+    fn test_three_way() {
+        let base = deck(&[
+            (
+                "Spanish",
+                &[("perro", "dog"), ("gato", "cat"), ("pez", "fish")],
+            ),
+            ("French", &[("chien", "dog")]),
+        ]);
+        // Here perro and gato were reworded, pez removed and a card and a
+        // topic added
+        let ours = deck(&[
+            (
+                "Spanish",
+                &[("perro", "doggy"), ("gato", "cat, tomcat"), ("oso", "bear")],
+            ),
+            ("French", &[("chien", "dog")]),
+            ("German", &[]),
+        ]);
+        // There perro was reworded differently from here, a card was added
+        // and French removed
+        let theirs = deck(&[(
+            "Spanish",
+            &[
+                ("perro", "hound"),
+                ("gato", "cat"),
+                ("pez", "fish"),
+                ("vaca", "cow"),
+            ],
+        )]);
+
+        let merged = three_way(&base, &ours, &theirs);
+        assert_eq!(merged.topics.names(), vec!["German", "Spanish"]);
+        let spanish: Vec<(&str, &str)> = merged.topics.topics_map["Spanish"]
+            .iter()
+            .map(|card| (card.question.as_str(), card.answer.as_str()))
+            .collect();
+        assert_eq!(
+            spanish,
+            [
+                ("perro", "doggy"),
+                ("gato", "cat, tomcat"),
+                ("oso", "bear"),
+                ("vaca", "cow"),
+            ]
+        );
+        assert_eq!(
+            merged.conflicts,
+            [Conflict {
+                topic: "Spanish".to_string(),
+                question: "perro".to_string(),
+                ours: Some(card("perro", "doggy")),
+                theirs: Some(card("perro", "hound")),
+            }]
+        );
+
+        let mut topics = merged.topics;
+        take_theirs(&mut topics, &merged.conflicts[0]);
+        assert_eq!(topics.topics_map["Spanish"][0].answer, "hound");
+    }
+
+    #[test]
+    fn test_removed_and_changed() {
+        let base = deck(&[("Spanish", &[("perro", "dog"), ("gato", "cat")])]);
+        let ours = deck(&[("Spanish", &[("perro", "dog")])]);
+        let theirs = deck(&[("Spanish", &[("perro", "dog"), ("gato", "tomcat")])]);
+        let merged = three_way(&base, &ours, &theirs);
+        assert_eq!(merged.topics.topics_map["Spanish"].len(), 1);
+        assert_eq!(merged.conflicts[0].ours, None);
+
+        let mut topics = merged.topics;
+        take_theirs(&mut topics, &merged.conflicts[0]);
+        assert_eq!(topics.topics_map["Spanish"][1].answer, "tomcat");
+
+        // A topic removed there and changed here stays, with what changed
+        let theirs = Topics::default();
+        let ours = deck(&[("Spanish", &[("perro", "hound"), ("gato", "cat")])]);
+        let merged = three_way(&base, &ours, &theirs);
+        assert_eq!(
+            merged.topics.topics_map["Spanish"],
+            [card("perro", "hound")]
+        );
+        assert_eq!(merged.conflicts.len(), 1);
+    }
+}
@@ -0,0 +1,152 @@
+//! Desktop notifications about cards due for review, sent by
+//! `mem-flip notify` from cron or a systemd timer.
+//!
+//! The title and body are passed as arguments to a user configured shell
+//! command (as `$1` and `$2`), or else to `notify-send` or macOS
+//! `osascript`, whichever is found in `PATH`.
+
+use std::io;
+use std::process::{Command, Stdio};
+
+use mem_flip::deck::Topics;
+use mem_flip::history::{self, Review};
+use mem_flip::schedule;
+
+use crate::paths::in_path;
+
+// Topics named in the notification, the rest are summed up
+const MAX_TOPICS: usize = 3;
+
+/// The notification body for the cards due in `topics`, such as
+/// "Spanish: 23 cards due", with the topics with the most due cards first.
+/// Archived topics are left out, and new cards beyond `new_per_day` a day
+/// aren't due. `None` when nothing is due.
+pub fn due_message(
+    topics: &Topics,
+    reviews: &[Review],
+    new_per_day: Option<usize>,
+) -> Option<String> {
+    let now = history::now();
+    let mut due: Vec<(&str, usize)> = topics
+        .topics_map
+        .keys()
+        .filter(|name| !topics.is_archived(name))
+        .map(|name| {
+            let due = schedule::due_today(topics, name, reviews, new_per_day, now);
+            (name.as_str(), due.len())
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    if due.is_empty() {
+        return None;
+    }
+    due.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let mut lines: Vec<String> = due
+        .iter()
+        .take(MAX_TOPICS)
+        .map(|(name, count)| match count {
+            1 => format!("{}: 1 card due", name),
+            _ => format!("{}: {} cards due", name, count),
+        })
+        .collect();
+    let rest: usize = due.iter().skip(MAX_TOPICS).map(|(_, count)| count).sum();
+    if rest > 0 {
+        lines.push(format!("and {} more in other topics", rest));
+    }
+    Some(lines.join("\n"))
+}
+
+/// Shows a desktop notification with `command`, or the system's own tool
+/// when it is `None`.
+pub fn send(command: Option<&str>, title: &str, body: &str) -> io::Result<()> {
+    let mut command = match command {
+        Some(custom) => {
+            let mut command = Command::new("sh");
+            // `sh` is `$0`, so the title and body are `$1` and `$2`
+            command.arg("-c").arg(custom).args(["sh", title, body]);
+            command
+        }
+        None => default_backend(title, body).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "no notification tool found, set notify_command in config.toml",
+            )
+        })?,
+    };
+
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "the notification command failed: {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+fn default_backend(title: &str, body: &str) -> Option<Command> {
+    if in_path("notify-send") {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name", "mem-flip", title, body]);
+        Some(command)
+    } else if in_path("osascript") {
+        // Passed as arguments rather than spliced into the script, so quotes
+        // in topic names can't break it
+        let mut command = Command::new("osascript");
+        command
+            .arg("-e")
+            .arg("on run argv")
+            .arg("-e")
+            .arg("display notification (item 2 of argv) with title (item 1 of argv)")
+            .arg("-e")
+            .arg("end run")
+            .args([title, body]);
+        Some(command)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mem_flip::deck::{Flashcard, TopicMeta};
+
+    use super::*;
+
+    fn topic(cards: usize) -> Vec<Flashcard> {
+        let card = Flashcard::new("q", "a");
+        vec![card; cards]
+    }
+
+    #[test]
+    fn test_due_message() {
+        let mut topics = Topics::default();
+        assert_eq!(due_message(&topics, &[], None), None);
+
+        for (name, cards) in [("French", 4), ("Spanish", 23), ("German", 1), ("Latin", 2)] {
+            topics.topics_map.insert(name.to_string(), topic(cards));
+        }
+        topics.topics_map.insert("Empty".to_string(), Vec::new());
+        topics.topics_map.insert("Old".to_string(), topic(50));
+        let archived = TopicMeta {
+            archived: true,
+            ..TopicMeta::default()
+        };
+        topics.set_meta("Old", archived);
+        assert_eq!(
+            due_message(&topics, &[], None).unwrap(),
+            "Spanish: 23 cards due\nFrench: 4 cards due\nLatin: 2 cards due\n\
+             and 1 more in other topics"
+        );
+        // Only as many new cards as may be started today
+        assert_eq!(
+            due_message(&topics, &[], Some(3)).unwrap(),
+            "French: 3 cards due\nSpanish: 3 cards due\nLatin: 2 cards due\n\
+             and 1 more in other topics"
+        );
+    }
+}
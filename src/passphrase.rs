@@ -0,0 +1,67 @@
+//! Asking for the passphrase of an encrypted deck before the UI starts.
+
+use std::env;
+use std::io::{self, Write};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+
+// Lets scripts and the `stats` command run without a prompt
+static PASSPHRASE_VAR: &str = "MEM_FLIP_PASSPHRASE";
+
+/// Reads a passphrase from the terminal without echoing it, after showing
+/// `label`. `MEM_FLIP_PASSPHRASE` is used instead when it is set.
+pub fn ask(label: &str) -> io::Result<String> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_VAR) {
+        return Ok(passphrase);
+    }
+
+    eprint!("{}: ", label);
+    io::stderr().flush()?;
+    terminal::enable_raw_mode()?;
+    let result = read_hidden();
+    terminal::disable_raw_mode()?;
+    eprintln!();
+    result
+}
+
+/// Asks twice, for setting a new passphrase.
+pub fn ask_new() -> io::Result<String> {
+    let passphrase = ask("New passphrase")?;
+    if passphrase.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the passphrase can't be empty",
+        ));
+    }
+    if env::var(PASSPHRASE_VAR).is_err() && ask("Repeat passphrase")? != passphrase {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the passphrases don't match",
+        ));
+    }
+    Ok(passphrase)
+}
+
+fn read_hidden() -> io::Result<String> {
+    let mut passphrase = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(passphrase),
+            KeyCode::Esc => break,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+            KeyCode::Backspace => {
+                passphrase.pop();
+            }
+            KeyCode::Char(c) => passphrase.push(c),
+            _ => {}
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"))
+}
@@ -0,0 +1,142 @@
+//! Per-user directories for mem-flip's files.
+//!
+//! With a profile, each directory gets a `profiles/<name>` subdirectory, so
+//! people sharing a computer don't share decks, history or settings. In
+//! portable mode they are all kept next to the executable instead. Also
+//! finds the helper programs mem-flip runs, such as `espeak`.
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static APP_DIR: &str = "mem-flip";
+static PROFILES_DIR: &str = "profiles";
+// Next to the executable, turns on portable mode without the flag
+static PORTABLE_FILE: &str = "mem-flip.portable";
+
+// Set once at startup from `--profile`
+static PROFILE: OnceLock<String> = OnceLock::new();
+// Directory of the executable, in portable mode
+static PORTABLE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Uses the profile `name` for every directory from now on. Names are kept
+/// to letters, digits, `-` and `_`, as they become directory names.
+pub fn set_profile(name: &str) -> io::Result<()> {
+    check_profile_name(name)?;
+    PROFILE
+        .set(name.to_string())
+        .map_err(|_| io::Error::other("the profile is already set"))
+}
+
+fn check_profile_name(name: &str) -> io::Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "invalid profile name {:?}: use letters, digits, - and _",
+                name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// The profile in use, if any.
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// Keeps every file in the executable's directory from now on, for
+/// running from a USB stick. Done when `forced` or when a
+/// `mem-flip.portable` file sits next to the executable. Returns whether
+/// mem-flip is portable.
+pub fn init_portable(forced: bool) -> io::Result<bool> {
+    let exe = env::current_exe()?;
+    let Some(dir) = exe.parent() else {
+        return Ok(false);
+    };
+    if !forced && !dir.join(PORTABLE_FILE).exists() {
+        return Ok(false);
+    }
+    PORTABLE
+        .set(dir.to_path_buf())
+        .map_err(|_| io::Error::other("portable mode is already set"))?;
+    Ok(true)
+}
+
+/// `path` relative to the executable's directory in portable mode, so it
+/// still points at the same file when the drive has another letter or
+/// mount point on the next machine.
+pub fn to_portable(path: &Path) -> PathBuf {
+    match PORTABLE.get().and_then(|root| path.strip_prefix(root).ok()) {
+        Some(relative) => relative.to_path_buf(),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Undoes `to_portable`.
+pub fn from_portable(path: &Path) -> PathBuf {
+    match PORTABLE.get() {
+        Some(root) if path.is_relative() => root.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+// Settings, `~/.config/mem-flip` on Linux
+pub fn config_dir() -> Option<PathBuf> {
+    app_dir("config", dirs::config_dir())
+}
+
+// Logs and other state worth keeping but not backing up,
+// `~/.local/state/mem-flip` on Linux. macOS and Windows have no state dir,
+// local data is the closest match.
+pub fn state_dir() -> Option<PathBuf> {
+    app_dir("state", dirs::state_dir().or_else(dirs::data_local_dir))
+}
+
+// Where the default deck goes, `~/.local/share/mem-flip/profiles/<name>` on
+// Linux for a profile. `None` without a profile or portable mode, as decks
+// then live wherever the user keeps them.
+pub fn data_dir() -> Option<PathBuf> {
+    if profile().is_none() && PORTABLE.get().is_none() {
+        return None;
+    }
+    app_dir("decks", dirs::data_dir())
+}
+
+// Whether `program` is in a directory of `PATH`
+pub fn in_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+// `portable` names the directory used instead of `base` in portable mode
+fn app_dir(portable: &str, base: Option<PathBuf>) -> Option<PathBuf> {
+    let dir = match PORTABLE.get() {
+        Some(root) => root.join(portable),
+        None => base?.join(APP_DIR),
+    };
+    Some(match profile() {
+        Some(name) => dir.join(PROFILES_DIR).join(name),
+        None => dir,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_names() {
+        assert!(check_profile_name("anna").is_ok());
+        assert!(check_profile_name("year_2-b").is_ok());
+        assert!(check_profile_name("").is_err());
+        assert!(check_profile_name("../anna").is_err());
+        assert!(check_profile_name("an na").is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! `--plain`: the deck through a line by line prompt, for screen readers.
+//!
+//! Nothing is drawn: no alternate screen, no boxes and no colors, only
+//! lines of text and a prompt, so everything is read out in order. It covers
+//! reviewing a topic and adding cards and topics; the rest of the app needs
+//! the full screen.
+
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use mem_flip::crypt::Key;
+use mem_flip::deck::{Flashcard, TopicMeta, Topics};
+use mem_flip::history::{self, History, Review};
+use mem_flip::schedule::{self, ReviewOrder};
+
+use crate::diff::{self, Word};
+use crate::git;
+use crate::markup;
+
+static HELP: &str = "\
+Commands:
+  list, or l: list the topics
+  a number or a topic's name: review that topic
+  add, or a, then a number or name: add cards to that topic
+  new, then a name: create a topic
+  help, or h: show this again
+  quit, or q: leave";
+
+pub struct Plain<R, W> {
+    pub deck_path: PathBuf,
+    pub key: Option<Key>,
+    pub read_only: bool,
+    pub topics: Topics,
+    pub history: History,
+    // Order and new cards a day of topics that don't set their own
+    pub review_order: Option<ReviewOrder>,
+    pub new_per_day: Option<usize>,
+    pub input: R,
+    pub out: W,
+}
+
+impl<R: BufRead, W: Write> Plain<R, W> {
+    pub fn run(&mut self) -> io::Result<()> {
+        writeln!(
+            self.out,
+            "mem-flip, plain mode, {}.",
+            self.deck_path.display()
+        )?;
+        self.list()?;
+        writeln!(self.out, "Type help for the commands.")?;
+        while let Some(line) = self.prompt("> ")? {
+            let (command, rest) = line.split_once(' ').unwrap_or((&line, ""));
+            let rest = rest.trim();
+            match command {
+                "" => {}
+                "q" | "quit" | "exit" => break,
+                "h" | "help" | "?" => writeln!(self.out, "{}", HELP)?,
+                "l" | "list" => self.list()?,
+                "a" | "add" => match self.find_topic(rest) {
+                    Some(topic) => self.add_cards(&topic)?,
+                    None => writeln!(self.out, "No topic {:?}. Type list for the topics.", rest)?,
+                },
+                "n" | "new" => self.new_topic(rest)?,
+                _ => match self.find_topic(&line) {
+                    Some(topic) => self.review(&topic)?,
+                    None => writeln!(self.out, "Unknown command. Type help for the commands.")?,
+                },
+            }
+        }
+        writeln!(self.out, "Bye.")
+    }
+
+    // Writes `prompt` and reads a line, without its line break. `None` at
+    // the end of the input.
+    fn prompt(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        write!(self.out, "{}", prompt)?;
+        self.out.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            writeln!(self.out)?;
+            return Ok(None);
+        }
+        Ok(Some(line.trim().to_string()))
+    }
+
+    fn topic_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.topics.topics_map.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn list(&mut self) -> io::Result<()> {
+        let names = self.topic_names();
+        if names.is_empty() {
+            return writeln!(
+                self.out,
+                "No topics yet. Type new and a name to create one."
+            );
+        }
+        writeln!(self.out, "{} topics:", names.len())?;
+        let now = history::now();
+        for (number, name) in (1..).zip(&names) {
+            let cards = &self.topics.topics_map[name];
+            let counts = schedule::topic_counts(
+                cards,
+                name,
+                self.history.reviews(),
+                self.topics.meta_of(name).new_per_day.or(self.new_per_day),
+                now,
+            );
+            writeln!(
+                self.out,
+                "{}. {}: {} due, {} new, {}.",
+                number,
+                name,
+                counts.due,
+                counts.new,
+                git::cards_label(counts.total)
+            )?;
+        }
+        Ok(())
+    }
+
+    // A topic by its number in the list or its name, in any case
+    fn find_topic(&self, given: &str) -> Option<String> {
+        let names = self.topic_names();
+        if let Ok(number) = given.parse::<usize>() {
+            return names.get(number.checked_sub(1)?).cloned();
+        }
+        names
+            .into_iter()
+            .find(|name| name.to_lowercase() == given.to_lowercase())
+    }
+
+    fn refuse_read_only(&mut self) -> io::Result<bool> {
+        if self.read_only {
+            writeln!(
+                self.out,
+                "The deck is open read-only, nothing can be changed."
+            )?;
+        }
+        Ok(self.read_only)
+    }
+
+    fn new_topic(&mut self, name: &str) -> io::Result<()> {
+        if self.refuse_read_only()? {
+            return Ok(());
+        }
+        if let Some(error) = self.topics.new_topic_error(name) {
+            return writeln!(self.out, "{}.", error);
+        }
+        self.topics.topics_map.insert(name.to_string(), Vec::new());
+        let meta = TopicMeta {
+            created: Some(history::now()),
+            ..self.topics.meta_of(name)
+        };
+        self.topics.set_meta(name, meta);
+        self.topics.save_with(&self.deck_path, self.key.as_ref())?;
+        writeln!(
+            self.out,
+            "Created {}. Type add {} to add cards.",
+            name, name
+        )
+    }
+
+    fn add_cards(&mut self, topic: &str) -> io::Result<()> {
+        if self.refuse_read_only()? {
+            return Ok(());
+        }
+        writeln!(
+            self.out,
+            "Adding cards to {}. Leave the question empty to stop.",
+            topic
+        )?;
+        loop {
+            let question = match self.prompt("Question: ")? {
+                Some(question) if !question.is_empty() => question,
+                _ => break,
+            };
+            let Some(answer) = self.prompt("Answer: ")? else {
+                break;
+            };
+            let cards = self.topics.topics_map.entry(topic.to_string()).or_default();
+            cards.push(Flashcard {
+                added: Some(history::now()),
+                ..Flashcard::new(question, answer)
+            });
+            let count = cards.len();
+            self.topics.save_with(&self.deck_path, self.key.as_ref())?;
+            writeln!(self.out, "Added, {} now.", git::cards_label(count))?;
+        }
+        writeln!(self.out, "Done adding cards to {}.", topic)
+    }
+
+    fn review(&mut self, topic: &str) -> io::Result<()> {
+        let cards = self.topics.topics_map[topic].clone();
+        let meta = self.topics.meta_of(topic);
+        let order = meta.order.or(self.review_order).unwrap_or_default();
+        let now = history::now();
+        let mut queue = schedule::review_order(&cards, topic, self.history.reviews(), order, now);
+        if let Some(limit) = meta.new_per_day.or(self.new_per_day) {
+            queue = schedule::limit_new(queue, &cards, topic, self.history.reviews(), limit, now);
+        }
+        if queue.is_empty() {
+            return writeln!(self.out, "No cards to review in {} today.", topic);
+        }
+        writeln!(
+            self.out,
+            "Reviewing {}, {}. Type your answer or press Enter to show it, q to stop.",
+            topic,
+            git::cards_label(queue.len())
+        )?;
+
+        let (mut graded, mut known) = (0, 0);
+        for (position, &index) in (1..).zip(&queue) {
+            let card = &cards[index];
+            let (front, back) = if meta.reversed {
+                (&card.answer, &card.question)
+            } else {
+                (&card.question, &card.answer)
+            };
+            let shown = Instant::now();
+            writeln!(
+                self.out,
+                "Card {} of {}. Question: {}",
+                position,
+                queue.len(),
+                plain_text(front)
+            )?;
+            let Some(typed) = self.prompt("Your answer: ")? else {
+                break;
+            };
+            if typed == "q" {
+                break;
+            }
+            if !typed.is_empty() {
+                writeln!(self.out, "{}", compare(&typed, back))?;
+            }
+            writeln!(self.out, "Answer: {}", plain_text(back))?;
+            let grade = loop {
+                match self.prompt("Did you know it? y or n, Enter to skip, q to stop: ")? {
+                    None => break None,
+                    Some(line) => match line.as_str() {
+                        "y" | "yes" => break Some(Some(true)),
+                        "n" | "no" => break Some(Some(false)),
+                        "" => break Some(None),
+                        "q" => break None,
+                        _ => writeln!(self.out, "Type y, n or q, or press Enter.")?,
+                    },
+                }
+            };
+            let Some(grade) = grade else {
+                break;
+            };
+            let Some(correct) = grade else {
+                continue;
+            };
+            graded += 1;
+            known += usize::from(correct);
+            if !self.read_only {
+                self.history.append(Review {
+                    time: history::now(),
+                    topic: topic.to_string(),
+                    question: card.question.clone(),
+                    correct,
+                    duration_ms: shown.elapsed().as_millis() as u64,
+                    count: 1,
+                })?;
+            }
+        }
+        writeln!(
+            self.out,
+            "Done with {}: knew {} of {} graded.",
+            topic, known, graded
+        )
+    }
+}
+
+// Card text as it reads aloud: math as symbols, readings instead of ruby,
+// and images by their descriptions
+fn plain_text(text: &str) -> String {
+    let (text, images) = markup::split_images(text);
+    let mut text = markup::ruby_readings(&markup::render_math(&text))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    for image in images {
+        text.push_str(&format!(" Image: {}.", image.alt));
+    }
+    text
+}
+
+// What a typed answer got right, in words rather than colors
+fn compare(typed: &str, expected: &str) -> String {
+    let words = diff::word_diff(typed, expected);
+    if diff::matches(&words) {
+        return "Your answer matches.".to_string();
+    }
+    let (mut missing, mut extra) = (Vec::new(), Vec::new());
+    for word in words {
+        match word {
+            Word::Missing(word) => missing.push(word),
+            Word::Extra(word) => extra.push(word),
+            Word::Same(_) => {}
+        }
+    }
+    let mut said = "Not quite.".to_string();
+    if !missing.is_empty() {
+        said.push_str(&format!(" Missing: {}.", missing.join(" ")));
+    }
+    if !extra.is_empty() {
+        said.push_str(&format!(" Not in the answer: {}.", extra.join(" ")));
+    }
+    said
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_review_and_add() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let deck_path = dir.path().join("deck.json");
+        let mut topics = Topics::default();
+        topics.topics_map.insert(
+            "Spanish".to_string(),
+            vec![Flashcard::new("perro", "the dog")],
+        );
+        let input = "1\ndog\ny\nadd spanish\ngato\ncat\n\nnew German\nquit\n";
+        let mut plain = Plain {
+            deck_path: deck_path.clone(),
+            key: None,
+            read_only: false,
+            topics,
+            history: History::empty(History::path_for(&deck_path)),
+            review_order: None,
+            new_per_day: None,
+            input: Cursor::new(input),
+            out: Vec::new(),
+        };
+        plain.run().unwrap();
+        let out = String::from_utf8(plain.out).unwrap();
+
+        assert!(out.contains("1. Spanish: 1 due, 1 new, 1 card."));
+        assert!(out.contains("Card 1 of 1. Question: perro"));
+        assert!(out.contains("Not quite. Missing: the."));
+        assert!(out.contains("Answer: the dog"));
+        assert!(out.contains("Done with Spanish: knew 1 of 1 graded."));
+        assert!(out.contains("Added, 2 cards now."));
+        assert_eq!(plain.history.reviews().len(), 1);
+
+        let saved = Topics::load_with(&deck_path, None).unwrap();
+        assert_eq!(saved.topics_map["Spanish"][1].answer, "cat");
+        assert!(saved.topics_map.contains_key("German"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Where the user left off, restored at the next launch.
+//!
+//! For an encrypted deck the topic and the review positions are sealed with
+//! the deck's key, since they name its topics.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use mem_flip::crypt::Key;
+use serde::{Deserialize, Serialize};
+
+use crate::paths;
+use crate::topic_sort::TopicSort;
+
+static RESUME_FILE: &str = "resume.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResumeState {
+    // Deck open when the app was closed, which `topic` and `positions` are in
+    pub deck: Option<PathBuf>,
+    // Topic selected in the list, or being reviewed
+    pub topic: Option<String>,
+    // Whether the app was closed during review of `topic`
+    pub reviewing: bool,
+    // Last place in each topic's review
+    pub positions: HashMap<String, ReviewPosition>,
+    // Decks opened lately, most recent first
+    pub recent_decks: Vec<PathBuf>,
+    // How the topic list was sorted
+    pub topic_sort: TopicSort,
+    // `topic` and `positions` sealed with the deck's key, which they are
+    // left empty for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sealed: Option<String>,
+}
+
+// The part of the state that names topics
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Place {
+    topic: Option<String>,
+    positions: HashMap<String, ReviewPosition>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewPosition {
+    pub card_index: usize,
+    pub scroll: u16,
+}
+
+impl ResumeState {
+    pub fn path() -> Option<PathBuf> {
+        paths::state_dir().map(|dir| dir.join(RESUME_FILE))
+    }
+
+    pub fn load() -> ResumeState {
+        // Nothing to resume if the file is missing or has bad data
+        ResumeState::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str::<ResumeState>(&text).ok())
+            .map(|state| state.map_paths(paths::from_portable))
+            .unwrap_or_default()
+    }
+
+    /// Writes the state to `path`, with the topics sealed with `key` when
+    /// the deck is encrypted.
+    pub fn save(&self, path: &Path, key: Option<&Key>) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut state = self.clone().map_paths(paths::to_portable);
+        if let Some(key) = key {
+            let place = Place {
+                topic: state.topic.take(),
+                positions: std::mem::take(&mut state.positions),
+            };
+            state.sealed = Some(key.seal_line(&serde_json::to_string(&place)?)?);
+        }
+        fs::write(path, serde_json::to_string_pretty(&state)?)
+    }
+
+    /// Restores the sealed topic and positions once the deck's `key` is
+    /// known. Without the key, or with another deck's, they are dropped.
+    pub fn open(&mut self, key: Option<&Key>) {
+        let Some(sealed) = self.sealed.take() else {
+            return;
+        };
+        let place = key
+            .and_then(|key| key.open_line(&sealed).ok())
+            .and_then(|text| serde_json::from_str::<Place>(&text).ok())
+            .unwrap_or_default();
+        self.topic = place.topic;
+        self.positions = place.positions;
+    }
+
+    // Applies `f` to every deck path
+    fn map_paths(mut self, f: impl Fn(&Path) -> PathBuf) -> ResumeState {
+        self.deck = self.deck.as_deref().map(&f);
+        self.recent_decks = self.recent_decks.iter().map(|p| f(p)).collect();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let state = ResumeState {
+            deck: Some(PathBuf::from("/decks/spanish.json")),
+            topic: Some("Spanish".to_string()),
+            reviewing: true,
+            positions: HashMap::from([(
+                "Spanish".to_string(),
+                ReviewPosition {
+                    card_index: 41,
+                    scroll: 3,
+                },
+            )]),
+            recent_decks: vec![PathBuf::from("/decks/spanish.json")],
+            topic_sort: TopicSort::MostDue,
+            sealed: None,
+        };
+        let text = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<ResumeState>(&text).unwrap(), state);
+
+        // Older or partial files fall back field by field
+        let partial: ResumeState = serde_json::from_str(r#"{"topic":"Spanish"}"#).unwrap();
+        assert!(!partial.reviewing);
+        assert!(partial.positions.is_empty());
+        assert_eq!(partial.deck, None);
+    }
+
+    #[test]
+    fn test_sealed_topics() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(RESUME_FILE);
+        let key = Key::new("pw").unwrap();
+        let state = ResumeState {
+            topic: Some("Secret topic".to_string()),
+            positions: HashMap::from([("Secret topic".to_string(), ReviewPosition::default())]),
+            reviewing: true,
+            ..ResumeState::default()
+        };
+        state.save(&path, Some(&key)).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("Secret topic"));
+
+        let mut loaded: ResumeState = serde_json::from_str(&text).unwrap();
+        assert_eq!(loaded.topic, None);
+        loaded.open(Some(&key));
+        assert_eq!(loaded, state);
+
+        // Without the key the place is lost, not the rest
+        let mut locked: ResumeState = serde_json::from_str(&text).unwrap();
+        locked.open(None);
+        assert_eq!(locked.topic, None);
+        assert!(locked.reviewing);
+    }
+}
@@ -0,0 +1,566 @@
+//! When cards come up for review.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::deck::{Flashcard, Topics};
+use crate::history::{DAY, Review};
+use crate::stats;
+
+/// Order the cards of a topic come up in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewOrder {
+    /// The order the cards were added in
+    #[default]
+    Sequential,
+    /// A random order, the same for the whole session
+    Shuffled,
+    /// The most difficult cards first, as rated or from missed reviews
+    Hardest,
+    /// The cards not reviewed for the longest first, never reviewed ones
+    /// before all
+    LeastRecent,
+    /// The cards with the smallest share of correct reviews first, never
+    /// reviewed ones after all
+    LowestAccuracy,
+}
+
+impl ReviewOrder {
+    /// The order after this one, back to sequential after the last.
+    pub fn next(self) -> ReviewOrder {
+        match self {
+            ReviewOrder::Sequential => ReviewOrder::Shuffled,
+            ReviewOrder::Shuffled => ReviewOrder::Hardest,
+            ReviewOrder::Hardest => ReviewOrder::LeastRecent,
+            ReviewOrder::LeastRecent => ReviewOrder::LowestAccuracy,
+            ReviewOrder::LowestAccuracy => ReviewOrder::Sequential,
+        }
+    }
+}
+
+/// Number of cards due for review today, archived topics left out. Each
+/// topic has at most its own number of new cards a day due, or else
+/// `new_per_day`, as in [`topic_counts`].
+pub fn due_count(
+    topics: &Topics,
+    reviews: &[Review],
+    new_per_day: Option<usize>,
+    now: u64,
+) -> usize {
+    topics
+        .topics_map
+        .keys()
+        .filter(|name| !topics.is_archived(name))
+        .map(|name| due_today(topics, name, reviews, new_per_day, now).len())
+        .sum()
+}
+
+/// Indexes of the cards in `topic` due for review today, in review order,
+/// with at most the topic's own number of new cards a day, or else
+/// `new_per_day`.
+pub fn due_today(
+    topics: &Topics,
+    topic: &str,
+    reviews: &[Review],
+    new_per_day: Option<usize>,
+    now: u64,
+) -> Vec<usize> {
+    let cards = topics.topics_map.get(topic).map_or(&[][..], Vec::as_slice);
+    let own = topics
+        .topic_meta
+        .get(topic)
+        .and_then(|meta| meta.new_per_day);
+    let limit = own.or(new_per_day);
+    limited(cards, topic, reviews, limit, now)
+}
+
+// The due cards of `topic`, with at most `limit` new ones
+fn limited(
+    cards: &[Flashcard],
+    topic: &str,
+    reviews: &[Review],
+    limit: Option<usize>,
+    now: u64,
+) -> Vec<usize> {
+    let due = due_cards(cards);
+    match limit {
+        Some(limit) => limit_new(due, cards, topic, reviews, limit, now),
+        None => due,
+    }
+}
+
+/// Indexes of the cards in a topic that are due for review, in review
+/// order. Every card is due until cards are scheduled.
+pub fn due_cards(cards: &[Flashcard]) -> Vec<usize> {
+    (0..cards.len()).collect()
+}
+
+/// Where the cards of a topic stand, for the topic list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TopicCounts {
+    /// Cards to review today
+    pub due: usize,
+    /// Cards never reviewed
+    pub new: usize,
+    /// All the cards of the topic
+    pub total: usize,
+}
+
+/// Counts of `cards` in `topic`, with at most `new_per_day` new cards a
+/// day due when the topic limits them.
+pub fn topic_counts(
+    cards: &[Flashcard],
+    topic: &str,
+    reviews: &[Review],
+    new_per_day: Option<usize>,
+    now: u64,
+) -> TopicCounts {
+    let seen: HashSet<&str> = reviews
+        .iter()
+        .filter(|r| r.topic == topic)
+        .map(|r| r.question.as_str())
+        .collect();
+    TopicCounts {
+        due: limited(cards, topic, reviews, new_per_day, now).len(),
+        new: cards
+            .iter()
+            .filter(|card| !seen.contains(card.question.as_str()))
+            .count(),
+        total: cards.len(),
+    }
+}
+
+/// How the cards of several topics reviewed together are mixed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interleave {
+    /// One card of each topic in turn, while the topic has cards left
+    #[default]
+    RoundRobin,
+    /// All the cards in a random order
+    Shuffled,
+}
+
+/// Indexes of `cards` in `topic` in `order`, which may depend on their
+/// `reviews`. Shuffling is driven by `seed`, so the same seed gives the
+/// same order.
+pub fn review_order(
+    cards: &[Flashcard],
+    topic: &str,
+    reviews: &[Review],
+    order: ReviewOrder,
+    seed: u64,
+) -> Vec<usize> {
+    let mut indexes: Vec<usize> = (0..cards.len()).collect();
+    match order {
+        ReviewOrder::Sequential => {}
+        ReviewOrder::Shuffled => shuffle(&mut indexes, seed),
+        ReviewOrder::Hardest => {
+            // Cards without a difficulty are taken as middling
+            let difficulty = stats::difficulty(cards, topic, reviews);
+            indexes.sort_by_key(|&i| Reverse(difficulty[i].unwrap_or(3)));
+        }
+        ReviewOrder::LeastRecent => {
+            let mut last: HashMap<&str, u64> = HashMap::new();
+            for review in reviews.iter().filter(|r| r.topic == topic) {
+                let time = last.entry(review.question.as_str()).or_default();
+                *time = (*time).max(review.time);
+            }
+            indexes.sort_by_key(|&i| last.get(cards[i].question.as_str()).copied());
+        }
+        ReviewOrder::LowestAccuracy => {
+            let mut totals: HashMap<&str, (u64, u64)> = HashMap::new();
+            for review in reviews.iter().filter(|r| r.topic == topic) {
+                let (correct, total) = totals.entry(review.question.as_str()).or_default();
+                if review.correct {
+                    *correct += u64::from(review.count);
+                }
+                *total += u64::from(review.count);
+            }
+            let totals = |i: usize| totals.get(cards[i].question.as_str()).copied();
+            // Compare shares without dividing
+            indexes.sort_by(|&a, &b| match (totals(a), totals(b)) {
+                (Some((ca, ta)), Some((cb, tb))) => (ca * tb).cmp(&(cb * ta)),
+                (a, b) => a.is_none().cmp(&b.is_none()),
+            });
+        }
+    }
+    indexes
+}
+
+/// The cards of several topics as one queue, each topic's cards given as
+/// the topic's name and its queue of card indexes.
+pub fn interleave(
+    queues: Vec<(String, Vec<usize>)>,
+    how: Interleave,
+    seed: u64,
+) -> Vec<(String, usize)> {
+    let mut mixed = Vec::new();
+    match how {
+        Interleave::RoundRobin => {
+            let longest = queues.iter().map(|(_, queue)| queue.len()).max();
+            for turn in 0..longest.unwrap_or_default() {
+                for (topic, queue) in &queues {
+                    if let Some(&i) = queue.get(turn) {
+                        mixed.push((topic.clone(), i));
+                    }
+                }
+            }
+        }
+        Interleave::Shuffled => {
+            for (topic, queue) in queues {
+                mixed.extend(queue.into_iter().map(|i| (topic.clone(), i)));
+            }
+            shuffle(&mut mixed, seed);
+        }
+    }
+    mixed
+}
+
+/// One card of `topics`, or of the topic `only`, for passive review: the
+/// one not reviewed for the longest when `overdue`, never reviewed ones
+/// first, otherwise one picked at random by `seed`. Returns its topic too.
+pub fn pick_card<'a>(
+    topics: &'a Topics,
+    only: Option<&str>,
+    reviews: &[Review],
+    overdue: bool,
+    seed: u64,
+) -> Option<(&'a str, &'a Flashcard)> {
+    let mut names: Vec<&String> = topics
+        .topics_map
+        .keys()
+        .filter(|name| only.is_none_or(|only| only == name.as_str()))
+        .collect();
+    names.sort();
+    let mut cards: Vec<(&str, &Flashcard)> = names
+        .into_iter()
+        .flat_map(|name| {
+            topics.topics_map[name]
+                .iter()
+                .map(|card| (name.as_str(), card))
+        })
+        .collect();
+    if overdue {
+        let mut last: HashMap<(&str, &str), u64> = HashMap::new();
+        for review in reviews {
+            let time = last
+                .entry((review.topic.as_str(), review.question.as_str()))
+                .or_default();
+            *time = (*time).max(review.time);
+        }
+        return cards
+            .into_iter()
+            .min_by_key(|(topic, card)| last.get(&(*topic, card.question.as_str())).copied());
+    }
+    shuffle(&mut cards, seed);
+    cards.into_iter().next()
+}
+
+/// The cards of the topics `names` added at `since` or later, oldest first,
+/// as topic and card index, whether or not they are due.
+pub fn added_since(topics: &Topics, names: &[String], since: u64) -> Vec<(String, usize)> {
+    let mut added: Vec<(u64, String, usize)> = names
+        .iter()
+        .filter_map(|name| Some((name, topics.topics_map.get(name)?)))
+        .flat_map(|(name, cards)| {
+            cards.iter().enumerate().filter_map(move |(i, card)| {
+                let time = card.added.filter(|&time| time >= since)?;
+                Some((time, name.clone(), i))
+            })
+        })
+        .collect();
+    // Stable, so cards added at once stay in their order
+    added.sort_by_key(|(time, _, _)| *time);
+    added.into_iter().map(|(_, topic, i)| (topic, i)).collect()
+}
+
+// Fisher-Yates with xorshift, good enough to mix cards
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed | 1;
+    for i in (1..items.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        items.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+/// Whether each of `indexes` of `cards` in `topic` has been reviewed on the
+/// day of `now`, so there is nothing left for today.
+pub fn done_today(
+    indexes: &[usize],
+    cards: &[Flashcard],
+    topic: &str,
+    reviews: &[Review],
+    now: u64,
+) -> bool {
+    let today = now / DAY;
+    let reviewed: HashSet<&str> = reviews
+        .iter()
+        .filter(|r| r.topic == topic && r.time / DAY == today)
+        .map(|r| r.question.as_str())
+        .collect();
+    indexes
+        .iter()
+        .all(|&i| reviewed.contains(cards[i].question.as_str()))
+}
+
+/// The new cards a day limit for studying `days` ahead: the new cards of
+/// today and of each of those days all come up today.
+pub fn ahead_limit(limit: usize, days: u64) -> usize {
+    let days = usize::try_from(days).unwrap_or(usize::MAX);
+    limit.saturating_mul(days.saturating_add(1))
+}
+
+/// `indexes` of `cards` in `topic` without the never reviewed cards beyond
+/// `limit` new ones a day. Cards first reviewed on the day of `now` count
+/// against the limit.
+pub fn limit_new(
+    indexes: Vec<usize>,
+    cards: &[Flashcard],
+    topic: &str,
+    reviews: &[Review],
+    limit: usize,
+    now: u64,
+) -> Vec<usize> {
+    // Day each card of the topic was first reviewed
+    let mut first_seen: HashMap<&str, u64> = HashMap::new();
+    for review in reviews.iter().filter(|r| r.topic == topic) {
+        first_seen
+            .entry(review.question.as_str())
+            .or_insert(review.time / DAY);
+    }
+    let today = now / DAY;
+    let introduced = cards
+        .iter()
+        .filter(|card| first_seen.get(card.question.as_str()) == Some(&today))
+        .count();
+    let mut allowed = limit.saturating_sub(introduced);
+    indexes
+        .into_iter()
+        .filter(|&i| {
+            if first_seen.contains_key(cards[i].question.as_str()) {
+                return true;
+            }
+            let keep = allowed > 0;
+            allowed = allowed.saturating_sub(1);
+            keep
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(question: &str) -> Flashcard {
+        Flashcard::new(question, "")
+    }
+
+    #[test]
+    fn test_review_order() {
+        let cards: Vec<Flashcard> = (0..20).map(|i| card(&i.to_string())).collect();
+        let order = |order| review_order(&cards, "es", &[], order, 7);
+        assert_eq!(order(ReviewOrder::Sequential)[..4], [0, 1, 2, 3]);
+        let mut shuffled = order(ReviewOrder::Shuffled);
+        assert_eq!(shuffled, order(ReviewOrder::Shuffled));
+        assert_ne!(shuffled, order(ReviewOrder::Sequential));
+        shuffled.sort();
+        assert_eq!(shuffled, order(ReviewOrder::Sequential));
+
+        let mut cards = vec![card("easy"), card("unrated"), card("hard")];
+        cards[0].rating = Some(1);
+        cards[2].rating = Some(5);
+        assert_eq!(
+            review_order(&cards, "es", &[], ReviewOrder::Hardest, 7),
+            [2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_review_order_from_reviews() {
+        let cards = vec![card("a"), card("b"), card("c"), card("new")];
+        let review = |question: &str, time: u64, correct: bool| Review {
+            time,
+            topic: "es".to_string(),
+            question: question.to_string(),
+            correct,
+            duration_ms: 0,
+            count: 1,
+        };
+        let reviews = [
+            review("a", 30, true),
+            review("b", 10, false),
+            review("b", 20, true),
+            review("c", 25, false),
+        ];
+        let order = |order| review_order(&cards, "es", &reviews, order, 7);
+        assert_eq!(order(ReviewOrder::LeastRecent), [3, 1, 2, 0]);
+        assert_eq!(order(ReviewOrder::LowestAccuracy), [2, 1, 0, 3]);
+    }
+
+    #[test]
+    fn test_interleave() {
+        let queues = vec![
+            ("es".to_string(), vec![0, 1, 2]),
+            ("fr".to_string(), vec![5]),
+        ];
+        let mixed = interleave(queues.clone(), Interleave::RoundRobin, 7);
+        let expected = [("es", 0), ("fr", 5), ("es", 1), ("es", 2)];
+        let expected: Vec<(String, usize)> = expected
+            .into_iter()
+            .map(|(topic, i)| (topic.to_string(), i))
+            .collect();
+        assert_eq!(mixed, expected);
+
+        // The same cards, in some order
+        let mut shuffled = interleave(queues, Interleave::Shuffled, 7);
+        let mut expected = expected;
+        shuffled.sort();
+        expected.sort();
+        assert_eq!(shuffled, expected);
+    }
+
+    #[test]
+    fn test_pick_card() {
+        let mut topics = Topics::default();
+        topics
+            .topics_map
+            .insert("es".to_string(), vec![card("perro"), card("gato")]);
+        topics
+            .topics_map
+            .insert("fr".to_string(), vec![card("chat")]);
+        let review = |topic: &str, question: &str, time: u64| Review {
+            time,
+            topic: topic.to_string(),
+            question: question.to_string(),
+            correct: true,
+            duration_ms: 0,
+            count: 1,
+        };
+        let reviews = [
+            review("es", "perro", 5),
+            review("fr", "chat", 3),
+            review("es", "gato", 1),
+            review("es", "gato", 8),
+        ];
+
+        let (topic, card) = pick_card(&topics, None, &reviews, true, 0).unwrap();
+        assert_eq!((topic, card.question.as_str()), ("fr", "chat"));
+        let (_, card) = pick_card(&topics, Some("es"), &reviews, true, 0).unwrap();
+        assert_eq!(card.question, "perro");
+        for seed in 0..10 {
+            let (topic, _) = pick_card(&topics, Some("fr"), &reviews, false, seed).unwrap();
+            assert_eq!(topic, "fr");
+        }
+        assert!(pick_card(&topics, Some("de"), &reviews, false, 0).is_none());
+    }
+
+    #[test]
+    fn test_added_since() {
+        let added = |question: &str, time| Flashcard {
+            added: time,
+            ..card(question)
+        };
+        let mut topics = Topics::default();
+        topics.topics_map.insert(
+            "es".to_string(),
+            vec![
+                added("perro", Some(50)),
+                added("gato", None),
+                added("pez", Some(5)),
+            ],
+        );
+        topics
+            .topics_map
+            .insert("fr".to_string(), vec![added("chat", Some(20))]);
+        let names = ["es".to_string(), "fr".to_string()];
+        assert_eq!(
+            added_since(&topics, &names, 10),
+            [("fr".to_string(), 0), ("es".to_string(), 0)]
+        );
+        assert_eq!(
+            added_since(&topics, &names[..1], 0),
+            [("es".to_string(), 2), ("es".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn test_study_ahead() {
+        let cards = [card("perro"), card("gato")];
+        let now = 10 * DAY + 3600;
+        let reviews = [Review {
+            time: now - 60,
+            topic: "es".to_string(),
+            question: "perro".to_string(),
+            correct: true,
+            duration_ms: 0,
+            count: 1,
+        }];
+        assert!(done_today(&[0], &cards, "es", &reviews, now));
+        assert!(!done_today(&[0, 1], &cards, "es", &reviews, now));
+        assert!(!done_today(&[0], &cards, "es", &reviews, now + DAY));
+
+        assert_eq!(ahead_limit(5, 0), 5);
+        assert_eq!(ahead_limit(5, 3), 20);
+        assert_eq!(ahead_limit(usize::MAX, 1), usize::MAX);
+    }
+
+    #[test]
+    fn test_limit_new() {
+        let cards: Vec<Flashcard> = ["old", "today", "new 1", "new 2", "new 3"]
+            .into_iter()
+            .map(card)
+            .collect();
+        let now = 10 * DAY + 100;
+        let review = |question: &str, time: u64| Review {
+            time,
+            topic: "es".to_string(),
+            question: question.to_string(),
+            correct: true,
+            duration_ms: 0,
+            count: 1,
+        };
+        let reviews = [review("old", 2 * DAY), review("today", now - 50)];
+        let indexes = (0..cards.len()).collect::<Vec<_>>();
+        // "today" already used one of the two new cards of the day
+        assert_eq!(
+            limit_new(indexes.clone(), &cards, "es", &reviews, 2, now),
+            [0, 1, 2]
+        );
+        assert_eq!(
+            limit_new(indexes.clone(), &cards, "fr", &reviews, 2, now),
+            [0, 1]
+        );
+        assert_eq!(limit_new(indexes, &cards, "es", &reviews, 0, now), [0, 1]);
+
+        let counts = |limit| topic_counts(&cards, "es", &reviews, limit, now);
+        assert_eq!(
+            counts(None),
+            TopicCounts {
+                due: 5,
+                new: 3,
+                total: 5,
+            }
+        );
+        assert_eq!(counts(Some(2)).due, 3);
+
+        // The deck's count agrees with its topics', each with its own limit
+        let mut topics = Topics::default();
+        topics.topics_map.insert("es".to_string(), cards.clone());
+        topics.topics_map.insert("fr".to_string(), cards);
+        assert_eq!(due_count(&topics, &reviews, None, now), 10);
+        assert_eq!(due_count(&topics, &reviews, Some(2), now), 3 + 2);
+        let limited = crate::deck::TopicMeta {
+            new_per_day: Some(1),
+            ..Default::default()
+        };
+        topics.set_meta("fr", limited);
+        assert_eq!(due_count(&topics, &reviews, Some(2), now), 3 + 1);
+        assert_eq!(due_today(&topics, "fr", &reviews, None, now), [0]);
+    }
+}
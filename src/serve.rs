@@ -0,0 +1,501 @@
+//! `mem-flip serve`: a small JSON API over one deck, for web pages and
+//! other tools.
+//!
+//! | Request                      | Does                                   |
+//! |------------------------------|----------------------------------------|
+//! | `GET /topics`                | Topic names with card and due counts   |
+//! | `GET /topics/<name>/due`     | Cards of a topic due for review        |
+//! | `POST /topics/<name>/cards`  | Adds `{"question", "answer"}`          |
+//! | `POST /reviews`              | Records `{"topic", "card", "correct"}` |
+//!
+//! Cards are addressed by their index in the topic. Every request but a
+//! browser's preflight needs the token printed when the server starts, as
+//! `Authorization: Bearer <token>`, and bodies are sent as
+//! `application/json`. Web pages can only call the server from the origins
+//! in `serve_origins` of the config; the server only listens on localhost
+//! unless told otherwise.
+
+use std::io::{self, Read};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use mem_flip::crypt::Key;
+use mem_flip::deck::{Flashcard, Topics};
+use mem_flip::history::{self, History, Review};
+use mem_flip::schedule;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Response, Server};
+use tracing::{info, warn};
+
+// Larger request bodies are refused
+const MAX_BODY: u64 = 1024 * 1024;
+
+/// The deck being served and where it is saved.
+pub struct Store {
+    deck_path: PathBuf,
+    topics: Topics,
+    history: History,
+    key: Option<Key>,
+    // Refuses every request that would change the deck or its history
+    read_only: bool,
+    // New cards a day due in topics with no limit of their own
+    new_per_day: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct NewCard {
+    question: String,
+    answer: String,
+}
+
+#[derive(Deserialize)]
+struct Grade {
+    topic: String,
+    card: usize,
+    correct: bool,
+    #[serde(default)]
+    duration_ms: u64,
+}
+
+// Status code and JSON body of a response
+type Reply = (u16, Value);
+
+/// Who may use the server: holders of its token, and web pages from its
+/// origins.
+pub struct Access {
+    token: String,
+    origins: Vec<String>,
+}
+
+impl Access {
+    /// Access with a new random token, for web pages from `origins`.
+    pub fn new(origins: Vec<String>) -> io::Result<Access> {
+        let mut bytes = [0u8; 24];
+        OsRng
+            .try_fill_bytes(&mut bytes)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(Access {
+            token: URL_SAFE_NO_PAD.encode(bytes),
+            origins,
+        })
+    }
+
+    // The reply refusing a request with these headers, `None` if it may go
+    // ahead
+    fn refuse(
+        &self,
+        method: &Method,
+        authorization: Option<&str>,
+        content_type: Option<&str>,
+    ) -> Option<Reply> {
+        let token = authorization.and_then(|value| value.strip_prefix("Bearer "));
+        if !token.is_some_and(|token| same_secret(token, &self.token)) {
+            return Some(error(401, "send the server's token as a bearer token"));
+        }
+        let json = content_type.is_some_and(|value| {
+            let media_type = value.split(';').next().unwrap_or_default().trim();
+            media_type.eq_ignore_ascii_case("application/json")
+        });
+        if *method == Method::Post && !json {
+            return Some(error(415, "send the body as application/json"));
+        }
+        None
+    }
+
+    // `origin` if web pages from it may read the replies
+    fn allowed<'a>(&self, origin: Option<&'a str>) -> Option<&'a str> {
+        origin.filter(|origin| self.origins.iter().any(|allowed| allowed == origin))
+    }
+}
+
+impl Store {
+    pub fn new(
+        deck_path: PathBuf,
+        topics: Topics,
+        history: History,
+        key: Option<Key>,
+        read_only: bool,
+        new_per_day: Option<usize>,
+    ) -> Store {
+        Store {
+            deck_path,
+            topics,
+            history,
+            key,
+            read_only,
+            new_per_day,
+        }
+    }
+
+    // Answers one request, `path` being the URL without the query
+    fn route(&mut self, method: &Method, path: &str, body: &str) -> Reply {
+        let segments: Vec<String> = match path
+            .trim_matches('/')
+            .split('/')
+            .map(percent_decode)
+            .collect()
+        {
+            Some(segments) => segments,
+            None => return error(400, "malformed URL"),
+        };
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        if *method == Method::Post && self.read_only {
+            return error(403, "the deck is served read-only");
+        }
+        match (method, segments.as_slice()) {
+            (Method::Get, ["topics"]) => self.list_topics(),
+            (Method::Get, ["topics", topic, "due"]) => self.due_cards(topic),
+            (Method::Post, ["topics", topic, "cards"]) => match serde_json::from_str(body) {
+                Ok(card) => self.add_card(topic, card),
+                Err(e) => error(400, &e.to_string()),
+            },
+            (Method::Post, ["reviews"]) => match serde_json::from_str(body) {
+                Ok(grade) => self.record(grade),
+                Err(e) => error(400, &e.to_string()),
+            },
+            _ => error(404, "no such endpoint"),
+        }
+    }
+
+    // The cards of `topic` due today
+    fn due_today(&self, topic: &str) -> Vec<usize> {
+        let reviews = self.history.reviews();
+        schedule::due_today(
+            &self.topics,
+            topic,
+            reviews,
+            self.new_per_day,
+            history::now(),
+        )
+    }
+
+    fn list_topics(&self) -> Reply {
+        let topics: Vec<Value> = self
+            .topics
+            .names()
+            .into_iter()
+            .map(|name| {
+                let cards = &self.topics.topics_map[&name];
+                json!({
+                    "name": name,
+                    "cards": cards.len(),
+                    "due": self.due_today(&name).len(),
+                })
+            })
+            .collect();
+        (200, Value::from(topics))
+    }
+
+    fn due_cards(&self, topic: &str) -> Reply {
+        let Some(cards) = self.topics.topics_map.get(topic) else {
+            return error(404, "no such topic");
+        };
+        let due: Vec<Value> = self
+            .due_today(topic)
+            .into_iter()
+            .map(|index| {
+                json!({
+                    "card": index,
+                    "question": cards[index].question,
+                    "answer": cards[index].answer,
+                })
+            })
+            .collect();
+        (200, Value::from(due))
+    }
+
+    fn add_card(&mut self, topic: &str, card: NewCard) -> Reply {
+        if card.question.trim().is_empty() || card.answer.trim().is_empty() {
+            return error(400, "a card needs a question and an answer");
+        }
+        let Some(cards) = self.topics.topics_map.get_mut(topic) else {
+            return error(404, "no such topic");
+        };
+        cards.push(Flashcard {
+            added: Some(history::now()),
+            ..Flashcard::new(card.question, card.answer)
+        });
+        let index = cards.len() - 1;
+        if let Err(e) = self.topics.save_with(&self.deck_path, self.key.as_ref()) {
+            warn!("saving {}: {}", self.deck_path.display(), e);
+            // Not kept, so the deck in memory matches the file
+            if let Some(cards) = self.topics.topics_map.get_mut(topic) {
+                cards.pop();
+            }
+            return error(500, &e.to_string());
+        }
+        info!(
+            "added card {} to {}",
+            index,
+            crate::logged(topic, self.key.as_ref())
+        );
+        (201, json!({ "card": index }))
+    }
+
+    fn record(&mut self, grade: Grade) -> Reply {
+        let Some(card) = self
+            .topics
+            .topics_map
+            .get(&grade.topic)
+            .and_then(|cards| cards.get(grade.card))
+        else {
+            return error(404, "no such card");
+        };
+        let review = Review {
+            time: history::now(),
+            topic: grade.topic,
+            question: card.question.clone(),
+            correct: grade.correct,
+            duration_ms: grade.duration_ms,
+            count: 1,
+        };
+        match self.history.append(review) {
+            Ok(()) => (201, json!({})),
+            Err(e) => {
+                warn!("recording review: {}", e);
+                error(500, &e.to_string())
+            }
+        }
+    }
+}
+
+/// Answers requests on `addr` until killed.
+pub fn serve(mut store: Store, access: Access, addr: SocketAddr) -> io::Result<()> {
+    let server = Server::http(addr).map_err(io::Error::other)?;
+    info!("serving {} on {}", store.deck_path.display(), addr);
+    eprintln!(
+        "Serving {} on http://{}\nToken: {}",
+        store.deck_path.display(),
+        server.server_addr(),
+        access.token
+    );
+
+    for mut request in server.incoming_requests() {
+        let value = |name: &'static str| {
+            request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv(name))
+                .map(|header| header.value.as_str().to_string())
+        };
+        let origin = value("Origin");
+        let allowed = access.allowed(origin.as_deref()).map(str::to_string);
+        let (status, body) = if *request.method() == Method::Options {
+            // Preflight of a web page on another origin
+            match allowed {
+                Some(_) => (204, Value::Null),
+                None => error(403, "origin not allowed"),
+            }
+        } else if let Some(refusal) = access.refuse(
+            request.method(),
+            value("Authorization").as_deref(),
+            value("Content-Type").as_deref(),
+        ) {
+            refusal
+        } else {
+            // A body said to be too large isn't read at all
+            let declared = request.body_length().map_or(0, |length| length as u64);
+            let body = if declared > MAX_BODY {
+                Err(too_large())
+            } else {
+                read_body(request.as_reader())
+            };
+            match body {
+                Ok(body) => {
+                    let path = request.url().split('?').next().unwrap_or_default();
+                    store.route(request.method(), path, &body)
+                }
+                Err(refusal) => refusal,
+            }
+        };
+        info!("{} {} {}", request.method(), request.url(), status);
+
+        let text = if body.is_null() {
+            String::new()
+        } else {
+            body.to_string()
+        };
+        let mut response = Response::from_string(text)
+            .with_status_code(status)
+            .with_header(header("Content-Type", "application/json"))
+            .with_header(header("Vary", "Origin"));
+        if let Some(origin) = allowed {
+            response = response
+                .with_header(header("Access-Control-Allow-Origin", &origin))
+                .with_header(header("Access-Control-Allow-Methods", "GET, POST"))
+                .with_header(header(
+                    "Access-Control-Allow-Headers",
+                    "Authorization, Content-Type",
+                ));
+        }
+        if let Err(e) = request.respond(response) {
+            warn!("responding: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn error(status: u16, message: &str) -> Reply {
+    (status, json!({ "error": message }))
+}
+
+fn too_large() -> Reply {
+    error(413, &format!("send at most {} bytes", MAX_BODY))
+}
+
+// The body of a request, refused when it is larger than `MAX_BODY`. One
+// byte more is read to tell a body of exactly that size from a larger one.
+fn read_body(reader: impl Read) -> Result<String, Reply> {
+    let mut body = Vec::new();
+    reader
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| error(400, &e.to_string()))?;
+    if body.len() as u64 > MAX_BODY {
+        return Err(too_large());
+    }
+    String::from_utf8(body).map_err(|_| error(400, "send the body as UTF-8"))
+}
+
+// Whether `given` is `secret`, taking as long wherever they first differ so
+// the time taken doesn't give the secret away a byte at a time
+fn same_secret(given: &str, secret: &str) -> bool {
+    let (given, secret) = (given.as_bytes(), secret.as_bytes());
+    given.len() == secret.len()
+        && given
+            .iter()
+            .zip(secret)
+            .fold(0, |differ, (a, b)| differ | (a ^ b))
+            == 0
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name, value).expect("valid header")
+}
+
+// Decodes `%20` and the like in a URL segment, `None` if it isn't valid
+// UTF-8 once decoded
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_routes() {
+        let dir = TempDir::new().unwrap();
+        let deck_path = dir.path().join("deck.json");
+        let mut topics = Topics::default();
+        topics.topics_map.insert("Big Cats".to_string(), Vec::new());
+        let history = History::empty(History::path_for(&deck_path));
+        let mut store = Store::new(deck_path.clone(), topics, history, None, false, None);
+
+        let card = r#"{"question": "tigre", "answer": "tiger"}"#;
+        let (status, body) = store.route(&Method::Post, "/topics/Big%20Cats/cards", card);
+        assert_eq!((status, body), (201, json!({ "card": 0 })));
+        assert_eq!(Topics::load(&deck_path).unwrap().card_count(), 1);
+
+        let (status, body) = store.route(&Method::Get, "/topics", "");
+        assert_eq!(status, 200);
+        assert_eq!(body, json!([{ "name": "Big Cats", "cards": 1, "due": 1 }]));
+
+        let (status, body) = store.route(&Method::Get, "/topics/Big%20Cats/due", "");
+        assert_eq!(status, 200);
+        assert_eq!(body[0]["question"], "tigre");
+
+        let grade = r#"{"topic": "Big Cats", "card": 0, "correct": true}"#;
+        assert_eq!(store.route(&Method::Post, "/reviews", grade).0, 201);
+        assert_eq!(store.history.reviews()[0].question, "tigre");
+
+        let grade = r#"{"topic": "Big Cats", "card": 7, "correct": true}"#;
+        assert_eq!(store.route(&Method::Post, "/reviews", grade).0, 404);
+        assert_eq!(store.route(&Method::Get, "/cards", "").0, 404);
+
+        store.read_only = true;
+        assert_eq!(
+            store
+                .route(&Method::Post, "/topics/Big%20Cats/cards", card)
+                .0,
+            403
+        );
+    }
+
+    #[test]
+    fn test_access() {
+        let access = Access::new(vec!["http://localhost:3000".to_string()]).unwrap();
+        let bearer = format!("Bearer {}", access.token);
+        let json = Some("application/json; charset=utf-8");
+        assert_eq!(access.refuse(&Method::Post, Some(&bearer), json), None);
+        assert_eq!(access.refuse(&Method::Get, Some(&bearer), None), None);
+        assert_eq!(access.refuse(&Method::Get, None, None).unwrap().0, 401);
+        assert_eq!(
+            access
+                .refuse(&Method::Get, Some("Bearer guess"), None)
+                .unwrap()
+                .0,
+            401
+        );
+        // A form or `text/plain` post skips the browser's preflight
+        let text = Some("text/plain");
+        assert_eq!(
+            access.refuse(&Method::Post, Some(&bearer), text).unwrap().0,
+            415
+        );
+
+        assert_eq!(
+            access.allowed(Some("http://localhost:3000")),
+            Some("http://localhost:3000")
+        );
+        assert_eq!(access.allowed(Some("https://evil.example")), None);
+        assert_eq!(access.allowed(None), None);
+        assert_ne!(Access::new(Vec::new()).unwrap().token, access.token);
+
+        // Off by the last character
+        let mut near = access.token.clone();
+        let last = if near.pop() == Some('A') { 'B' } else { 'A' };
+        near.push(last);
+        let bearer = format!("Bearer {}", near);
+        assert_eq!(
+            access.refuse(&Method::Get, Some(&bearer), None).unwrap().0,
+            401
+        );
+    }
+
+    #[test]
+    fn test_read_body() {
+        let body = vec![b'a'; MAX_BODY as usize];
+        assert_eq!(read_body(&body[..]).unwrap().len(), body.len());
+        let body = vec![b'a'; MAX_BODY as usize + 1];
+        assert_eq!(read_body(&body[..]).unwrap_err().0, 413);
+        assert_eq!(read_body(&[0xff, 0xfe][..]).unwrap_err().0, 400);
+    }
+}
@@ -0,0 +1,181 @@
+//! Progress through one review of a topic, from opening it until going back
+//! to the topic list.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Longer gaps between grades are breaks, not time spent on the card
+const MAX_CARD_TIME: Duration = Duration::from_secs(5 * 60);
+
+/// Grades of other cards before a missed card comes up again.
+pub const RELEARN_GAP: usize = 3;
+
+/// Where review goes after a grade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Next {
+    /// Back to this missed card, by card index
+    Relearn(usize),
+    /// On with the queue, from this card
+    After(usize),
+}
+
+/// Grades given in the current review of a topic.
+#[derive(Debug)]
+pub struct Session {
+    // Whether each graded card was known, by card index. Grading a card
+    // again replaces its result.
+    results: HashMap<usize, bool>,
+    // Start of the session or the last grade
+    last_grade: Instant,
+    // Cards missed and not known since, with the grades left before each
+    // comes up again, in the order they were missed
+    relearn: Vec<(usize, usize)>,
+    // Card the queue carries on from once the missed cards are relearned
+    resume: Option<usize>,
+    // When the session was paused, while it is
+    paused: Option<Instant>,
+}
+
+impl Default for Session {
+    fn default() -> Session {
+        Session {
+            results: HashMap::new(),
+            last_grade: Instant::now(),
+            relearn: Vec::new(),
+            resume: None,
+            paused: None,
+        }
+    }
+}
+
+impl Session {
+    /// Records a grade and returns the time spent on the card since the
+    /// previous one.
+    pub fn grade(&mut self, card_index: usize, correct: bool) -> Duration {
+        self.results.insert(card_index, correct);
+        for (_, left) in &mut self.relearn {
+            *left = left.saturating_sub(1);
+        }
+        self.relearn.retain(|&(index, _)| index != card_index);
+        if !correct {
+            self.relearn.push((card_index, RELEARN_GAP));
+        }
+        let now = Instant::now();
+        let spent = now.duration_since(self.last_grade).min(MAX_CARD_TIME);
+        self.last_grade = now;
+        spent
+    }
+
+    /// Stops the clock, so time away isn't counted as time on the card.
+    pub fn pause(&mut self, now: Instant) {
+        self.paused.get_or_insert(now);
+    }
+
+    /// Starts the clock again and returns how long the session was paused.
+    pub fn resume(&mut self, now: Instant) -> Duration {
+        let Some(paused) = self.paused.take() else {
+            return Duration::ZERO;
+        };
+        let away = now.saturating_duration_since(paused);
+        self.last_grade += away;
+        away
+    }
+
+    /// Whether the session is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Whether the card at `card_index` was known, if it has been graded.
+    pub fn result(&self, card_index: usize) -> Option<bool> {
+        self.results.get(&card_index).copied()
+    }
+
+    /// Number of cards graded.
+    pub fn answered(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Number of cards graded as known.
+    pub fn correct(&self) -> usize {
+        self.results.values().filter(|&&correct| correct).count()
+    }
+
+    /// Number of missed cards still to be known.
+    pub fn relearning(&self) -> usize {
+        self.relearn.len()
+    }
+
+    /// Where to go after grading the card at `card_index`: a missed card
+    /// that is due again, or else on from where the queue was left for it.
+    pub fn next(&mut self, card_index: usize) -> Next {
+        let from = self.resume.take().unwrap_or(card_index);
+        match self.relearn.iter().find(|&&(_, left)| left == 0) {
+            Some(&(index, _)) => {
+                self.resume = Some(from);
+                Next::Relearn(index)
+            }
+            None => Next::After(from),
+        }
+    }
+
+    /// Forgets where the queue was left, once review moves on by hand.
+    pub fn leave_relearn(&mut self) {
+        self.resume = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regrading_replaces_result() {
+        let mut session = Session::default();
+        session.grade(0, false);
+        session.grade(1, true);
+        session.grade(0, true);
+        assert_eq!(session.answered(), 2);
+        assert_eq!(session.correct(), 2);
+        assert_eq!(session.result(0), Some(true));
+        assert_eq!(session.result(2), None);
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut session = Session::default();
+        let now = Instant::now();
+        session.pause(now);
+        assert!(session.is_paused());
+        let away = Duration::from_secs(10 * 60);
+        assert_eq!(session.resume(now + away), away);
+        assert!(!session.is_paused());
+        // The time away isn't spent on the card
+        assert!(session.grade(0, true) < Duration::from_secs(1));
+        assert_eq!(session.resume(now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_missed_cards_come_back() {
+        let mut session = Session::default();
+        session.grade(0, false);
+        assert_eq!(session.relearning(), 1);
+        for index in 1..RELEARN_GAP {
+            session.grade(index, true);
+            assert_eq!(session.next(index), Next::After(index));
+        }
+        session.grade(RELEARN_GAP, true);
+        assert_eq!(session.next(RELEARN_GAP), Next::Relearn(0));
+
+        // Missed again, then known, and the queue goes on where it was
+        session.grade(0, false);
+        assert_eq!(session.next(0), Next::After(RELEARN_GAP));
+        for index in 4..7 {
+            session.grade(index, true);
+            session.next(index);
+        }
+        session.grade(0, true);
+        assert_eq!(session.relearning(), 0);
+        assert_eq!(session.next(0), Next::After(6));
+    }
+}
@@ -0,0 +1,326 @@
+//! The settings screen: the config file's values, changed with the arrow
+//! keys. Each change takes effect at once and is written to the file.
+
+use mem_flip::schedule::ReviewOrder;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Padding, Paragraph, Widget},
+};
+
+use crate::config::Config;
+use crate::l10n::{Language, hint, tr, trf};
+use crate::markup::RubyMode;
+use crate::snapshot::SnapshotFormat;
+use crate::theme::{self, ThemeSetting};
+use crate::{MAX_ANSWER_LENGTH, RECENT_DAYS, STUDY_AHEAD_DAYS, order_label, timer, trash};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Theme,
+    Language,
+    ReviewOrder,
+    NewPerDay,
+    StudyAheadDays,
+    RecentDays,
+    Ruby,
+    ReduceMotion,
+    StudyTimer,
+    FocusMinutes,
+    BreakMinutes,
+    MaxAnswerLength,
+    TrashDays,
+    SnapshotFormat,
+    HistoryMonths,
+    GitCommit,
+}
+
+/// The settings in the order they are listed.
+pub const SETTINGS: [Setting; 16] = [
+    Setting::Theme,
+    Setting::Language,
+    Setting::ReviewOrder,
+    Setting::NewPerDay,
+    Setting::StudyAheadDays,
+    Setting::RecentDays,
+    Setting::Ruby,
+    Setting::ReduceMotion,
+    Setting::StudyTimer,
+    Setting::FocusMinutes,
+    Setting::BreakMinutes,
+    Setting::MaxAnswerLength,
+    Setting::TrashDays,
+    Setting::SnapshotFormat,
+    Setting::HistoryMonths,
+    Setting::GitCommit,
+];
+
+const THEMES: [ThemeSetting; 3] = [ThemeSetting::Auto, ThemeSetting::Dark, ThemeSetting::Light];
+const LANGUAGES: [Option<Language>; 5] = [
+    None,
+    Some(Language::English),
+    Some(Language::Spanish),
+    Some(Language::German),
+    Some(Language::French),
+];
+const RUBY_MODES: [RubyMode; 3] = [RubyMode::Above, RubyMode::Beside, RubyMode::Hidden];
+const SNAPSHOT_FORMATS: [SnapshotFormat; 3] = [
+    SnapshotFormat::Text,
+    SnapshotFormat::Ansi,
+    SnapshotFormat::Svg,
+];
+
+impl Setting {
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::Theme => "Theme",
+            Setting::Language => "Language",
+            Setting::ReviewOrder => "Review order",
+            Setting::NewPerDay => "New cards a day",
+            Setting::StudyAheadDays => "Study ahead days",
+            Setting::RecentDays => "Recent days",
+            Setting::Ruby => "Ruby readings",
+            Setting::ReduceMotion => "Reduce motion",
+            Setting::StudyTimer => "Timer at start",
+            Setting::FocusMinutes => "Focus minutes",
+            Setting::BreakMinutes => "Break minutes",
+            Setting::MaxAnswerLength => "Longest answer",
+            Setting::TrashDays => "Days in trash",
+            Setting::SnapshotFormat => "Card snapshots",
+            Setting::HistoryMonths => "Compact history",
+            Setting::GitCommit => "Commit to git",
+        }
+    }
+
+    pub fn value(self, config: &Config) -> String {
+        let yes_no = |on: bool| tr(if on { "yes" } else { "no" }).to_string();
+        match self {
+            Setting::Theme => tr(match config.theme {
+                ThemeSetting::Auto => "auto",
+                ThemeSetting::Dark => "dark",
+                ThemeSetting::Light => "light",
+            })
+            .to_string(),
+            Setting::Language => match config.language {
+                None => tr("from the locale").to_string(),
+                Some(Language::English) => "English".to_string(),
+                Some(Language::Spanish) => "Español".to_string(),
+                Some(Language::German) => "Deutsch".to_string(),
+                Some(Language::French) => "Français".to_string(),
+            },
+            Setting::ReviewOrder => {
+                tr(order_label(config.review_order.unwrap_or_default())).to_string()
+            }
+            Setting::NewPerDay => config
+                .new_per_day
+                .map_or(tr("no limit").to_string(), |n| n.to_string()),
+            Setting::StudyAheadDays => config
+                .study_ahead_days
+                .unwrap_or(STUDY_AHEAD_DAYS)
+                .to_string(),
+            Setting::RecentDays => config.recent_days.unwrap_or(RECENT_DAYS).to_string(),
+            Setting::Ruby => tr(match config.ruby {
+                RubyMode::Above => "above",
+                RubyMode::Beside => "beside",
+                RubyMode::Hidden => "hidden",
+            })
+            .to_string(),
+            Setting::ReduceMotion => yes_no(config.reduce_motion),
+            Setting::StudyTimer => yes_no(config.study_timer),
+            Setting::FocusMinutes => config
+                .focus_minutes
+                .unwrap_or(timer::DEFAULT_FOCUS_MINUTES)
+                .to_string(),
+            Setting::BreakMinutes => config
+                .break_minutes
+                .unwrap_or(timer::DEFAULT_BREAK_MINUTES)
+                .to_string(),
+            Setting::MaxAnswerLength => config
+                .max_answer_length
+                .unwrap_or(MAX_ANSWER_LENGTH)
+                .to_string(),
+            Setting::TrashDays => config.trash_days.unwrap_or(trash::DEFAULT_DAYS).to_string(),
+            Setting::SnapshotFormat => tr(config.snapshot_format.label()).to_string(),
+            Setting::HistoryMonths => config
+                .history_months
+                .map_or(tr("never").to_string(), |n| trf("after {} months", &[&n])),
+            Setting::GitCommit => yes_no(config.git_commit),
+        }
+    }
+
+    /// Changes the setting in `config` to the next value, or the previous
+    /// one, and applies it where that takes more than the config.
+    pub fn change(self, config: &mut Config, up: bool) {
+        match self {
+            Setting::Theme => {
+                config.theme = step(&THEMES, config.theme, up);
+                theme::init(config.theme);
+            }
+            Setting::Language => {
+                config.language = step(&LANGUAGES, config.language, up);
+                crate::l10n::set_language(config.language.unwrap_or_else(Language::from_env));
+            }
+            Setting::ReviewOrder => {
+                let orders = [
+                    ReviewOrder::Sequential,
+                    ReviewOrder::Shuffled,
+                    ReviewOrder::Hardest,
+                    ReviewOrder::LeastRecent,
+                    ReviewOrder::LowestAccuracy,
+                ];
+                let order = step(&orders, config.review_order.unwrap_or_default(), up);
+                config.review_order = Some(order);
+            }
+            Setting::NewPerDay => {
+                // Steps of 5, below 5 is no limit, as in a topic's settings
+                config.new_per_day = match (config.new_per_day, up) {
+                    (None, true) => Some(5),
+                    (None, false) => None,
+                    (Some(n), true) => Some(n + 5),
+                    (Some(n), false) => n.checked_sub(5).filter(|&n| n > 0),
+                };
+            }
+            Setting::StudyAheadDays => {
+                let days = config.study_ahead_days.unwrap_or(STUDY_AHEAD_DAYS);
+                config.study_ahead_days = Some(add(days, 1, up).max(1));
+            }
+            Setting::RecentDays => {
+                let days = config.recent_days.unwrap_or(RECENT_DAYS);
+                config.recent_days = Some(add(days, 1, up).max(1));
+            }
+            Setting::Ruby => config.ruby = step(&RUBY_MODES, config.ruby, up),
+            Setting::ReduceMotion => config.reduce_motion = !config.reduce_motion,
+            Setting::StudyTimer => config.study_timer = !config.study_timer,
+            Setting::FocusMinutes => {
+                let minutes = config.focus_minutes.unwrap_or(timer::DEFAULT_FOCUS_MINUTES);
+                config.focus_minutes = Some(add(minutes, 5, up).max(5));
+            }
+            Setting::BreakMinutes => {
+                let minutes = config.break_minutes.unwrap_or(timer::DEFAULT_BREAK_MINUTES);
+                config.break_minutes = Some(add(minutes, 1, up).max(1));
+            }
+            Setting::MaxAnswerLength => {
+                let length = config.max_answer_length.unwrap_or(MAX_ANSWER_LENGTH) as u64;
+                config.max_answer_length = Some(add(length, 50, up).max(50) as usize);
+            }
+            Setting::TrashDays => {
+                let days = config.trash_days.unwrap_or(trash::DEFAULT_DAYS);
+                config.trash_days = Some(add(days, 5, up).max(1));
+            }
+            Setting::SnapshotFormat => {
+                config.snapshot_format = step(&SNAPSHOT_FORMATS, config.snapshot_format, up);
+            }
+            Setting::HistoryMonths => {
+                // Steps of 3, below 3 is never
+                config.history_months = match (config.history_months, up) {
+                    (None, true) => Some(3),
+                    (None, false) => None,
+                    (Some(n), true) => Some(n + 3),
+                    (Some(n), false) => n.checked_sub(3).filter(|&n| n > 0),
+                };
+            }
+            Setting::GitCommit => config.git_commit = !config.git_commit,
+        }
+    }
+}
+
+// The choice after `current`, or before it, wrapping around
+fn step<T: Copy + PartialEq>(choices: &[T], current: T, up: bool) -> T {
+    let i = choices.iter().position(|&c| c == current).unwrap_or(0);
+    let len = choices.len();
+    choices[if up {
+        (i + 1) % len
+    } else {
+        (i + len - 1) % len
+    }]
+}
+
+fn add(value: u64, by: u64, up: bool) -> u64 {
+    if up {
+        value.saturating_add(by)
+    } else {
+        value.saturating_sub(by)
+    }
+}
+
+pub struct SettingsView<'a> {
+    pub config: &'a Config,
+    pub selected: usize,
+}
+
+impl Widget for SettingsView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let hints = Line::from(vec![
+            hint("Navigate"),
+            "<↑↓>".blue().bold(),
+            hint("Change"),
+            "<←→>".blue().bold(),
+            hint("Back"),
+            "<Esc> ".red().bold(),
+        ]);
+        // Values line up after the longest label
+        let width = SETTINGS
+            .iter()
+            .map(|setting| tr(setting.label()).chars().count() + 1)
+            .max()
+            .unwrap_or_default()
+            .max(18);
+        let lines: Vec<Line> = SETTINGS
+            .iter()
+            .enumerate()
+            .map(|(i, setting)| {
+                let line = Line::from(format!(
+                    "{:<width$} ◀ {} ▶",
+                    tr(setting.label()),
+                    setting.value(self.config)
+                ));
+                if i == self.selected {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+        // Scrolled just enough to show the selected setting, inside the
+        // borders and padding
+        let rows = usize::from(area.height.saturating_sub(4)).max(1);
+        let offset = self.selected.saturating_sub(rows - 1) as u16;
+        Paragraph::new(lines)
+            .scroll((offset, 0))
+            .block(
+                Block::bordered()
+                    .title(format!(" ⚙ {} ", tr("Settings")).bold())
+                    .title_bottom(hints)
+                    .padding(Padding::uniform(1))
+                    .style(Style::default().fg(theme::palette().accent)),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change() {
+        let mut config = Config::default();
+        Setting::NewPerDay.change(&mut config, true);
+        assert_eq!(config.new_per_day, Some(5));
+        Setting::NewPerDay.change(&mut config, false);
+        assert_eq!(config.new_per_day, None);
+
+        Setting::ReviewOrder.change(&mut config, false);
+        assert_eq!(config.review_order, Some(ReviewOrder::LowestAccuracy));
+        Setting::BreakMinutes.change(&mut config, false);
+        Setting::BreakMinutes.change(&mut config, false);
+        Setting::BreakMinutes.change(&mut config, false);
+        Setting::BreakMinutes.change(&mut config, false);
+        Setting::BreakMinutes.change(&mut config, false);
+        assert_eq!(config.break_minutes, Some(1));
+        Setting::Ruby.change(&mut config, false);
+        assert_eq!(config.ruby, RubyMode::Hidden);
+    }
+}
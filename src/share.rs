@@ -0,0 +1,144 @@
+//! Share codes: a topic packed into one line of text, to beam a handful of
+//! cards to a phone or another machine.
+//!
+//! A code is `mf1:` followed by the topic's questions and answers as JSON,
+//! deflated and base64 encoded. It is shown as a QR code in the terminal,
+//! and `mem-flip import code` reads it back.
+
+use std::io::{self, Read, Write};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use mem_flip::deck::{Flashcard, Topics};
+use qrcodegen::{QrCode, QrCodeEcc};
+use serde::{Deserialize, Serialize};
+
+static PREFIX: &str = "mf1:";
+
+// Decoded codes bigger than this are refused, as no topic worth beaming is
+const MAX_DECODED: u64 = 1 << 20;
+
+// Only what a card is, not how the sender did with it
+#[derive(Debug, Serialize, Deserialize)]
+struct Shared {
+    topic: String,
+    cards: Vec<(String, String)>,
+}
+
+/// The code for the topic `name` with `cards`.
+pub fn encode(name: &str, cards: &[Flashcard]) -> io::Result<String> {
+    let shared = Shared {
+        topic: name.to_string(),
+        cards: cards
+            .iter()
+            .map(|card| (card.question.clone(), card.answer.clone()))
+            .collect(),
+    };
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    serde_json::to_writer(&mut encoder, &shared)?;
+    let packed = encoder.finish()?;
+    Ok(format!("{}{}", PREFIX, URL_SAFE_NO_PAD.encode(packed)))
+}
+
+/// The topic a code holds, as a deck of just that topic.
+pub fn decode(code: &str) -> io::Result<Topics> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let packed = code
+        .trim()
+        .strip_prefix(PREFIX)
+        .ok_or_else(|| invalid("not a mem-flip share code"))?;
+    let packed = URL_SAFE_NO_PAD
+        .decode(packed)
+        .map_err(|_| invalid("the share code is damaged"))?;
+    let mut json = Vec::new();
+    DeflateDecoder::new(packed.as_slice())
+        .take(MAX_DECODED)
+        .read_to_end(&mut json)?;
+    let shared: Shared = serde_json::from_slice(&json)?;
+    let mut topics = Topics::default();
+    let cards = shared
+        .cards
+        .into_iter()
+        .map(|(question, answer)| Flashcard::new(question, answer))
+        .collect();
+    topics.topics_map.insert(shared.topic, cards);
+    topics.check()?;
+    Ok(topics)
+}
+
+/// `text` as a QR code drawn with half blocks, two rows of modules to a
+/// line, with a quiet zone around it. Meant for dark text on a light
+/// background. `None` when the text is too long for a QR code.
+pub fn qr_lines(text: &str) -> Option<Vec<String>> {
+    const QUIET: i32 = 2;
+    let qr = QrCode::encode_text(text, QrCodeEcc::Low).ok()?;
+    let size = qr.size();
+    let dark = |x: i32, y: i32| qr.get_module(x, y);
+    let lines = (-QUIET..size + QUIET)
+        .step_by(2)
+        .map(|y| {
+            (-QUIET..size + QUIET)
+                .map(|x| match (dark(x, y), dark(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                })
+                .collect()
+        })
+        .collect();
+    Some(lines)
+}
+
+/// Writes the code and its QR code for a terminal, for `mem-flip share`.
+pub fn print(code: &str, mut out: impl Write) -> io::Result<()> {
+    if let Some(lines) = qr_lines(code) {
+        // Dark modules on a light background, whatever the terminal's colors
+        for line in lines {
+            writeln!(out, "\x1b[30;47m{}\x1b[0m", line)?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(out, "{}", code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let card = Flashcard {
+            rating: Some(3),
+            ..Flashcard::new("perro", "dog")
+        };
+        let code = encode("Spanish", std::slice::from_ref(&card)).unwrap();
+        assert!(code.starts_with("mf1:"));
+
+        let topics = decode(&format!(" {}\n", code)).unwrap();
+        let cards = &topics.topics_map["Spanish"];
+        assert_eq!(cards[0].question, "perro");
+        assert_eq!(cards[0].rating, None);
+
+        assert!(decode("perro").is_err());
+        assert!(decode("mf1:!!").is_err());
+
+        // Well formed, but not a topic a deck could hold
+        let code = encode(" ", std::slice::from_ref(&card)).unwrap();
+        assert!(decode(&code).is_err());
+        let code = encode("Spanish", &[Flashcard::new("", "dog")]).unwrap();
+        assert!(decode(&code).is_err());
+    }
+
+    #[test]
+    fn test_qr_lines() {
+        let lines = qr_lines("mf1:abc").unwrap();
+        // Version 1 is 21 modules, with the quiet zone 25, two to a line
+        assert_eq!(lines.len(), 13);
+        assert!(lines.iter().all(|line| line.chars().count() == 25));
+        assert!(qr_lines(&"x".repeat(5000)).is_none());
+    }
+}
@@ -0,0 +1,298 @@
+//! Snapshots of one card, to paste into a chat when going over it with
+//! others.
+//!
+//! The card is drawn into a buffer the way a pane of the app would draw
+//! it, framed and with math and ruby rendered, and the buffer is written
+//! out as plain text, as text with ANSI colors for terminals and chats that
+//! show them, or as an SVG image.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use mem_flip::deck::Flashcard;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Padding, Paragraph, Widget, Wrap},
+};
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+use crate::export;
+use crate::l10n::{tr, trf};
+use crate::markup::{self, RubyMode};
+
+// Columns a snapshot takes, frame included
+const WIDTH: u16 = 64;
+// Size of a character cell in an SVG snapshot, in pixels, for a 14 pixel
+// monospace font
+const CELL_WIDTH: f32 = 8.4;
+const CELL_HEIGHT: f32 = 18.0;
+const MARGIN: f32 = 12.0;
+static BACKGROUND: &str = "#1e1e1e";
+static FOREGROUND: &str = "#d4d4d4";
+
+/// What a snapshot is written as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    /// Plain text with a box drawn around the card
+    #[default]
+    Text,
+    /// The same with ANSI color codes
+    Ansi,
+    /// An image
+    Svg,
+}
+
+impl SnapshotFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            SnapshotFormat::Text => "text",
+            SnapshotFormat::Ansi => "ANSI text",
+            SnapshotFormat::Svg => "SVG image",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            SnapshotFormat::Text => "txt",
+            SnapshotFormat::Ansi => "ans",
+            SnapshotFormat::Svg => "svg",
+        }
+    }
+}
+
+/// File card `number` of `topic` is snapshot to, in the current directory:
+/// `Spanish 12.txt` for the twelfth card of Spanish.
+pub fn snapshot_path(topic: &str, number: usize, format: SnapshotFormat) -> PathBuf {
+    let name = export::file_name(topic);
+    PathBuf::from(format!("{} {}.{}", name, number, format.extension()))
+}
+
+/// Writes card `number` of `topic` to `path` as `format`.
+pub fn write_snapshot(
+    topic: &str,
+    number: usize,
+    card: &Flashcard,
+    format: SnapshotFormat,
+    path: &Path,
+) -> io::Result<()> {
+    fs::write(path, snapshot(topic, number, card, format))
+}
+
+/// Card `number` of `topic` as `format`.
+pub fn snapshot(topic: &str, number: usize, card: &Flashcard, format: SnapshotFormat) -> String {
+    let buf = draw(topic, number, card);
+    let rows = runs(&buf);
+    match format {
+        SnapshotFormat::Text => to_text(&rows, false),
+        SnapshotFormat::Ansi => to_text(&rows, true),
+        SnapshotFormat::Svg => to_svg(&rows, buf.area),
+    }
+}
+
+// The card framed, as tall as its text
+fn draw(topic: &str, number: usize, card: &Flashcard) -> Buffer {
+    // Borders and padding take two columns on each side
+    let width = WIDTH - 4;
+    let mut lines = vec![Line::from(tr("Question").cyan().bold())];
+    lines.extend(side_lines(&card.question, width));
+    lines.push(Line::from(""));
+    lines.push(Line::from(tr("Answer").green().bold()));
+    lines.extend(side_lines(&card.answer, width));
+
+    let block = Block::bordered()
+        .title(format!(" {} ", trf("{} · card {}", &[&topic, &number])).bold())
+        .padding(Padding::horizontal(1));
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+    let height = paragraph.line_count(width) as u16 + 2;
+    let area = Rect::new(0, 0, WIDTH, height);
+    let mut buf = Buffer::empty(area);
+    paragraph.block(block).render(area, &mut buf);
+    buf
+}
+
+// Lines of one side of a card, images by their descriptions since a
+// snapshot can't hold them
+fn side_lines(text: &str, width: u16) -> Vec<Line<'static>> {
+    let (text, images) = markup::split_images(text);
+    let mut lines = markup::render_text(&text, RubyMode::Beside, width);
+    for image in images {
+        lines.push(Line::from(format!("[Image: {}]", image.alt).italic()));
+    }
+    lines
+}
+
+// Each row of `buf` as runs of text in one style, with the column each
+// starts at. Cells hidden under wide characters are left out.
+fn runs(buf: &Buffer) -> Vec<Vec<(u16, Style, String)>> {
+    let area = buf.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let mut row: Vec<(u16, Style, String)> = Vec::new();
+            let mut hidden = 0;
+            for x in area.left()..area.right() {
+                if hidden > 0 {
+                    hidden -= 1;
+                    continue;
+                }
+                let cell = &buf[(x, y)];
+                let symbol = cell.symbol();
+                hidden = symbol.width().saturating_sub(1);
+                let style = Style::default().fg(cell.fg).add_modifier(cell.modifier);
+                match row.last_mut() {
+                    Some((_, last, text)) if *last == style => text.push_str(symbol),
+                    _ => row.push((x, style, symbol.to_string())),
+                }
+            }
+            row
+        })
+        .collect()
+}
+
+fn to_text(rows: &[Vec<(u16, Style, String)>], ansi: bool) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let mut line = String::new();
+        for (_, style, text) in row {
+            if ansi && *style != Style::default().fg(Color::Reset) {
+                line.push_str(&format!("{}{}\x1b[0m", sgr(*style), text));
+            } else {
+                line.push_str(text);
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+// The escape sequence that starts text in `style`
+fn sgr(style: Style) -> String {
+    let mut codes: Vec<String> = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+    ]
+    .iter()
+    .filter(|(modifier, _)| style.add_modifier.contains(*modifier))
+    .map(|(_, code)| code.to_string())
+    .collect();
+    let color = match style.fg.unwrap_or(Color::Reset) {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some(format!("38;2;{};{};{}", r, g, b)),
+        Color::Indexed(i) => Some(format!("38;5;{}", i)),
+        color => named_color(color).map(|(code, _)| code.to_string()),
+    };
+    codes.extend(color);
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+// The ANSI code and the SVG fill of the sixteen named colors
+fn named_color(color: Color) -> Option<(u8, &'static str)> {
+    Some(match color {
+        Color::Black => (30, "#000000"),
+        Color::Red => (31, "#cd3131"),
+        Color::Green => (32, "#0dbc79"),
+        Color::Yellow => (33, "#e5e510"),
+        Color::Blue => (34, "#2472c8"),
+        Color::Magenta => (35, "#bc3fbc"),
+        Color::Cyan => (36, "#11a8cd"),
+        Color::Gray => (37, "#e5e5e5"),
+        Color::DarkGray => (90, "#666666"),
+        Color::LightRed => (91, "#f14c4c"),
+        Color::LightGreen => (92, "#23d18b"),
+        Color::LightYellow => (93, "#f5f543"),
+        Color::LightBlue => (94, "#3b8eea"),
+        Color::LightMagenta => (95, "#d670d6"),
+        Color::LightCyan => (96, "#29b8db"),
+        Color::White => (97, "#ffffff"),
+        _ => return None,
+    })
+}
+
+fn to_svg(rows: &[Vec<(u16, Style, String)>], area: Rect) -> String {
+    let width = f32::from(area.width) * CELL_WIDTH + 2.0 * MARGIN;
+    let height = f32::from(area.height) * CELL_HEIGHT + 2.0 * MARGIN;
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+         viewBox=\"0 0 {0} {1}\">\n\
+         <rect width=\"100%\" height=\"100%\" rx=\"6\" fill=\"{2}\"/>\n\
+         <g font-family=\"monospace\" font-size=\"14\" fill=\"{3}\" xml:space=\"preserve\">\n",
+        width, height, BACKGROUND, FOREGROUND
+    );
+    for (y, row) in (0u16..).zip(rows) {
+        // Text sits on its baseline, a little above the bottom of the row
+        let baseline = MARGIN + (f32::from(y) + 0.75) * CELL_HEIGHT;
+        out.push_str(&format!("<text y=\"{}\">", baseline));
+        for (x, style, text) in row {
+            if text.trim().is_empty() {
+                continue;
+            }
+            let mut attributes = format!(" x=\"{}\"", MARGIN + f32::from(*x) * CELL_WIDTH);
+            let fill = match style.fg.unwrap_or(Color::Reset) {
+                Color::Rgb(r, g, b) => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+                color => named_color(color).map(|(_, fill)| fill.to_string()),
+            };
+            if let Some(fill) = fill {
+                attributes.push_str(&format!(" fill=\"{}\"", fill));
+            }
+            for (modifier, attribute) in [
+                (Modifier::BOLD, " font-weight=\"bold\""),
+                (Modifier::DIM, " opacity=\"0.6\""),
+                (Modifier::ITALIC, " font-style=\"italic\""),
+                (Modifier::UNDERLINED, " text-decoration=\"underline\""),
+            ] {
+                if style.add_modifier.contains(modifier) {
+                    attributes.push_str(attribute);
+                }
+            }
+            out.push_str(&format!(
+                "<tspan{}>{}</tspan>",
+                attributes,
+                escape_xml(text)
+            ));
+        }
+        out.push_str("</text>\n");
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let card = Flashcard::new("猫[ねこ] & <dog>?", "cat");
+        let text = snapshot("Japanese", 2, &card, SnapshotFormat::Text);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("┌ Japanese · card 2 ─"));
+        assert!(lines[2].starts_with("│ 猫(ねこ) & <dog>?  "));
+        assert!(lines[5].starts_with("│ cat  "));
+        assert!(lines.last().unwrap().starts_with("└─"));
+
+        let ansi = snapshot("Japanese", 2, &card, SnapshotFormat::Ansi);
+        assert!(ansi.contains("\x1b[1;36mQuestion\x1b[0m"));
+
+        let svg = snapshot("Japanese", 2, &card, SnapshotFormat::Svg);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("&amp; &lt;dog&gt;?"));
+        assert_eq!(
+            snapshot_path("a/b", 3, SnapshotFormat::Svg),
+            PathBuf::from("a_b 3.svg")
+        );
+    }
+}
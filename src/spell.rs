@@ -0,0 +1,191 @@
+//! Spell checking of the card editor with hunspell, so typos aren't
+//! memorized along with the card.
+//!
+//! One `hunspell -a` process is kept running and spoken to over its
+//! ispell-compatible pipe protocol: each line of text is sent prefixed with
+//! `^`, and hunspell answers with one line per misspelled word, followed by
+//! a blank line.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// A word hunspell doesn't know, with its guesses at the right spelling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Misspelling {
+    pub word: String,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Speller {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Speller {
+    /// Starts hunspell with the dictionary for `language`, such as `en_US`.
+    pub fn start(language: &str) -> io::Result<Speller> {
+        let mut child = Command::new("hunspell")
+            .args(["-a", "-d", language])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => {
+                    io::Error::new(e.kind(), "hunspell isn't installed".to_string())
+                }
+                _ => e,
+            })?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other("hunspell has no pipes"));
+        };
+        let mut speller = Speller {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        };
+        // Hunspell introduces itself, or exits without a dictionary
+        let mut banner = String::new();
+        if speller.stdout.read_line(&mut banner)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("hunspell has no {} dictionary", language),
+            ));
+        }
+        Ok(speller)
+    }
+
+    /// The misspelled words in `text`, each once, in order.
+    pub fn check(&mut self, text: &str) -> io::Result<Vec<Misspelling>> {
+        let mut found: Vec<Misspelling> = Vec::new();
+        for line in text.lines() {
+            // `^` keeps a line starting with a command character from being
+            // taken as a command
+            writeln!(self.stdin, "^{}", line)?;
+            self.stdin.flush()?;
+            loop {
+                let mut answer = String::new();
+                if self.stdout.read_line(&mut answer)? == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "hunspell stopped",
+                    ));
+                }
+                let answer = answer.trim_end();
+                if answer.is_empty() {
+                    break;
+                }
+                if let Some(misspelling) = parse_answer(answer)
+                    && !found.iter().any(|m| m.word == misspelling.word)
+                {
+                    found.push(misspelling);
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+impl Drop for Speller {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// `& word count offset: guess, guess` for a misspelling with guesses,
+// `# word offset` for one without. Anything else is a correct word.
+fn parse_answer(answer: &str) -> Option<Misspelling> {
+    let (kind, rest) = answer.split_at_checked(1)?;
+    let mut fields = rest.trim_start().splitn(2, ' ');
+    let word = fields.next()?.to_string();
+    let suggestions = match kind {
+        "&" => fields
+            .next()?
+            .split_once(": ")?
+            .1
+            .split(", ")
+            .map(str::to_string)
+            .collect(),
+        "#" => Vec::new(),
+        _ => return None,
+    };
+    Some(Misspelling { word, suggestions })
+}
+
+/// The misspelled word with suggestions that comes last in `text`, and
+/// where it is.
+pub fn last_fixable<'a>(
+    text: &str,
+    misspelled: &'a [Misspelling],
+) -> Option<(usize, &'a Misspelling)> {
+    misspelled
+        .iter()
+        .filter(|m| !m.suggestions.is_empty())
+        .filter_map(|m| rfind_word(text, &m.word).map(|at| (at, m)))
+        .max_by_key(|(at, _)| *at)
+}
+
+/// `text` with the last misspelled word that has suggestions replaced by
+/// the first of them, `None` if there is nothing to fix.
+pub fn fix_last(text: &str, misspelled: &[Misspelling]) -> Option<String> {
+    let (at, misspelling) = last_fixable(text, misspelled)?;
+    let mut fixed = text.to_string();
+    fixed.replace_range(at..at + misspelling.word.len(), &misspelling.suggestions[0]);
+    Some(fixed)
+}
+
+/// Whether `c` can be part of a word, as far as checking goes.
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\''
+}
+
+// Byte offset of the last whole-word `word` in `text`
+fn rfind_word(text: &str, word: &str) -> Option<usize> {
+    text.rmatch_indices(word).map(|(at, _)| at).find(|&at| {
+        let before = text[..at].chars().next_back();
+        let after = text[at + word.len()..].chars().next();
+        !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answer() {
+        assert_eq!(
+            parse_answer("& teh 3 0: the, ten, tea"),
+            Some(Misspelling {
+                word: "teh".to_string(),
+                suggestions: vec!["the".to_string(), "ten".to_string(), "tea".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_answer("# qzx 4"),
+            Some(Misspelling {
+                word: "qzx".to_string(),
+                suggestions: Vec::new(),
+            })
+        );
+        assert_eq!(parse_answer("*"), None);
+        assert_eq!(parse_answer("+ walk"), None);
+    }
+
+    #[test]
+    fn test_fix_last() {
+        let misspelled = vec![Misspelling {
+            word: "teh".to_string(),
+            suggestions: vec!["the".to_string()],
+        }];
+        assert_eq!(
+            fix_last("teh cat and teh dog", &misspelled).as_deref(),
+            Some("teh cat and the dog")
+        );
+        // Not inside another word
+        assert_eq!(fix_last("tehran", &misspelled), None);
+    }
+}
@@ -0,0 +1,524 @@
+//! Statistics over the review history.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::deck::Flashcard;
+use crate::history::{DAY, Review};
+
+// Rows written between progress reports
+const PROGRESS_ROWS: usize = 1000;
+// Longest pause between two reviews of the same session, in seconds
+const SESSION_GAP: u64 = 30 * 60;
+// Correct answers in a row from which a card counts as mature
+const MATURE_STREAK: usize = 3;
+
+/// Number of reviews on each of the last `days` days up to the day of `now`,
+/// oldest first.
+pub fn reviews_per_day(reviews: &[Review], days: usize, now: u64) -> Vec<u64> {
+    let mut counts = vec![0; days];
+    for review in reviews {
+        if let Some(i) = day_slot(review.time, days, now) {
+            counts[i] += u64::from(review.count);
+        }
+    }
+    counts
+}
+
+/// Percentage answered correctly on each of the last `days` days, oldest
+/// first, `None` for days without reviews.
+pub fn accuracy_per_day(reviews: &[Review], days: usize, now: u64) -> Vec<Option<f64>> {
+    let mut totals = vec![(0, 0); days];
+    for review in reviews {
+        if let Some(i) = day_slot(review.time, days, now) {
+            if review.correct {
+                totals[i].0 += review.count;
+            }
+            totals[i].1 += review.count;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(correct, total)| (total > 0).then(|| f64::from(correct) * 100.0 / f64::from(total)))
+        .collect()
+}
+
+// Index of the day of `time` among the last `days` days, oldest first
+fn day_slot(time: u64, days: usize, now: u64) -> Option<usize> {
+    let age = (now / DAY).checked_sub(time / DAY)? as usize;
+    (age < days).then(|| days - 1 - age)
+}
+
+/// How often a card was missed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CardStats {
+    /// Topic of the card
+    pub topic: String,
+    /// Question of the card
+    pub question: String,
+    /// Number of times the card was graded
+    pub reviews: usize,
+    /// Number of those grades where the answer wasn't known
+    pub missed: usize,
+}
+
+/// Cards missed at least once, the largest share of misses first.
+pub fn hardest_cards(reviews: &[Review], count: usize) -> Vec<CardStats> {
+    let mut cards: HashMap<(&str, &str), (usize, usize)> = HashMap::new();
+    for review in reviews {
+        let (total, missed) = cards
+            .entry((review.topic.as_str(), review.question.as_str()))
+            .or_default();
+        *total += review.count as usize;
+        if !review.correct {
+            *missed += review.count as usize;
+        }
+    }
+
+    let mut cards: Vec<CardStats> = cards
+        .into_iter()
+        .filter(|(_, (_, missed))| *missed > 0)
+        .map(|((topic, question), (reviews, missed))| CardStats {
+            topic: topic.to_string(),
+            question: question.to_string(),
+            reviews,
+            missed,
+        })
+        .collect();
+    // Compare miss rates without dividing, then more misses, then by name
+    cards.sort_by(|a, b| {
+        (b.missed * a.reviews)
+            .cmp(&(a.missed * b.reviews))
+            .then(b.missed.cmp(&a.missed))
+            .then_with(|| (&a.topic, &a.question).cmp(&(&b.topic, &b.question)))
+    });
+    cards.truncate(count);
+    cards
+}
+
+/// How hard each of `cards` in `topic` is, from 1 (easy) to 5 (hard): the
+/// card's own rating when it has one, otherwise 1 plus 4 times its share of
+/// missed reviews, rounded. `None` for unrated cards never reviewed.
+pub fn difficulty(cards: &[Flashcard], topic: &str, reviews: &[Review]) -> Vec<Option<u8>> {
+    let mut totals: HashMap<&str, (usize, usize)> = HashMap::new();
+    for review in reviews.iter().filter(|r| r.topic == topic) {
+        let (total, missed) = totals.entry(review.question.as_str()).or_default();
+        *total += review.count as usize;
+        if !review.correct {
+            *missed += review.count as usize;
+        }
+    }
+    cards
+        .iter()
+        .map(|card| {
+            if let Some(rating) = card.rating {
+                return Some(rating.clamp(1, 5));
+            }
+            let (total, missed) = totals.get(card.question.as_str())?;
+            Some(1 + ((4 * missed + total / 2) / total) as u8)
+        })
+        .collect()
+}
+
+/// How far along the cards of a topic are.
+#[derive(Debug, Default, PartialEq)]
+pub struct TopicStats {
+    /// Cards never reviewed
+    pub new: usize,
+    /// Cards reviewed, but not known a few times in a row yet
+    pub learning: usize,
+    /// Cards known the last three times they came up
+    pub mature: usize,
+    /// Number of reviews of the topic, including of cards since removed
+    pub reviews: usize,
+    /// Percentage of those answered correctly, `None` without reviews
+    pub accuracy: Option<f64>,
+    /// Time of the latest review of the topic
+    pub last_studied: Option<u64>,
+}
+
+/// Stats of `cards` in `topic` from the review history.
+pub fn topic_stats(cards: &[Flashcard], topic: &str, reviews: &[Review]) -> TopicStats {
+    let mut stats = TopicStats::default();
+    // Grades of each card, latest last
+    let mut grades: HashMap<&str, Vec<bool>> = HashMap::new();
+    let mut correct = 0;
+    for review in reviews.iter().filter(|r| r.topic == topic) {
+        // A streak needs no more grades than its length
+        let times = (review.count as usize).min(MATURE_STREAK);
+        grades
+            .entry(review.question.as_str())
+            .or_default()
+            .extend(std::iter::repeat_n(review.correct, times));
+        stats.reviews += review.count as usize;
+        if review.correct {
+            correct += review.count as usize;
+        }
+        stats.last_studied = stats.last_studied.max(Some(review.time));
+    }
+    stats.accuracy = (stats.reviews > 0).then(|| correct as f64 * 100.0 / stats.reviews as f64);
+    for card in cards {
+        match grades.get(card.question.as_str()) {
+            None => stats.new += 1,
+            Some(grades)
+                if grades.len() >= MATURE_STREAK
+                    && grades[grades.len() - MATURE_STREAK..].iter().all(|&g| g) =>
+            {
+                stats.mature += 1
+            }
+            Some(_) => stats.learning += 1,
+        }
+    }
+    stats
+}
+
+/// What getting through a topic before its exam takes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StudyPlan {
+    /// Days before the day of the exam, today included
+    pub days_left: u64,
+    /// Correct reviews still needed for every card to be mature
+    pub needed: usize,
+    /// Cards to review each day to get there by the exam
+    pub per_day: usize,
+    /// Reviews of the topic given today
+    pub done_today: usize,
+}
+
+/// The plan for `cards` of `topic` to all be mature, known three times in a
+/// row, by the day of `exam`: the correct reviews each card still needs,
+/// spread over the days left. `None` once the day of the exam is over.
+pub fn study_plan(
+    cards: &[Flashcard],
+    topic: &str,
+    reviews: &[Review],
+    exam: u64,
+    now: u64,
+) -> Option<StudyPlan> {
+    let today = now / DAY;
+    let days_left = (exam / DAY).checked_sub(today)?;
+    // Correct answers in a row at the end of each card's grades
+    let mut streaks: HashMap<&str, usize> = HashMap::new();
+    let mut done_today = 0;
+    for review in reviews.iter().filter(|r| r.topic == topic) {
+        let streak = streaks.entry(review.question.as_str()).or_default();
+        *streak = if review.correct {
+            *streak + review.count as usize
+        } else {
+            0
+        };
+        if review.time / DAY == today {
+            done_today += review.count as usize;
+        }
+    }
+    let needed: usize = cards
+        .iter()
+        .map(|card| {
+            let streak = streaks.get(card.question.as_str()).copied().unwrap_or(0);
+            MATURE_STREAK.saturating_sub(streak)
+        })
+        .sum();
+    // On the day of the exam itself, whatever is left is for today
+    let days = days_left.max(1) as usize;
+    Some(StudyPlan {
+        days_left,
+        needed,
+        per_day: needed.div_ceil(days),
+        done_today,
+    })
+}
+
+/// Days in a row with reviews up to the day of `now`, or up to the day
+/// before while nothing has been reviewed today yet.
+pub fn streak(reviews: &[Review], now: u64) -> u64 {
+    let days: HashSet<u64> = reviews.iter().map(|review| review.time / DAY).collect();
+    let today = now / DAY;
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        today.saturating_sub(1)
+    };
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        let Some(before) = day.checked_sub(1) else {
+            break;
+        };
+        day = before;
+    }
+    streak
+}
+
+/// The reviews of the latest session, which ends with the last review and
+/// goes back until a pause of more than half an hour. Empty without reviews.
+pub fn last_session(reviews: &[Review]) -> &[Review] {
+    let start = reviews
+        .windows(2)
+        .rposition(|pair| pair[1].time.saturating_sub(pair[0].time) > SESSION_GAP)
+        .map_or(0, |i| i + 1);
+    &reviews[start..]
+}
+
+/// Total time spent grading cards of each topic in milliseconds, the most
+/// studied topic first.
+pub fn time_per_topic(reviews: &[Review]) -> Vec<(String, u64)> {
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    for review in reviews {
+        *totals.entry(review.topic.as_str()).or_default() += review.duration_ms;
+    }
+    let mut totals: Vec<(String, u64)> = totals
+        .into_iter()
+        .map(|(topic, ms)| (topic.to_string(), ms))
+        .collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    totals
+}
+
+/// Year, month and day of the month (UTC) of a day counted from the Unix
+/// epoch.
+pub fn civil_date(days: u64) -> (i64, u32, u32) {
+    // Howard Hinnant's civil_from_days, with years starting in March
+    let z = days as i64 + 719_468;
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = z.div_euclid(146_097) * 400 + yoe + i64::from(month <= 2);
+    (year, month as u32, day as u32)
+}
+
+/// Day of `time`, seconds since the Unix epoch, as `YYYY-MM-DD` (UTC).
+pub fn format_date(time: u64) -> String {
+    let (year, month, day) = civil_date(time / DAY);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Writes one row per review, with a header, as CSV. Every row starts with
+/// `deck`, the name of the deck, so exports of several decks can be combined.
+pub fn write_csv(deck: &str, reviews: &[Review], out: impl Write) -> io::Result<()> {
+    write_csv_rows(deck, reviews, out, |_| {})
+}
+
+/// Writes the reviews as CSV to a new file at `path`. `on_progress` is
+/// called with the number of rows written so far every now and then.
+pub fn export_csv(
+    deck: &str,
+    reviews: &[Review],
+    path: &Path,
+    on_progress: impl FnMut(usize),
+) -> io::Result<()> {
+    let out = BufWriter::new(File::create(path)?);
+    write_csv_rows(deck, reviews, out, on_progress)
+}
+
+fn write_csv_rows(
+    deck: &str,
+    reviews: &[Review],
+    mut out: impl Write,
+    mut on_progress: impl FnMut(usize),
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "deck,time,date,topic,question,correct,duration_ms,count"
+    )?;
+    for (i, review) in reviews.iter().enumerate() {
+        if i % PROGRESS_ROWS == 0 {
+            on_progress(i);
+        }
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            csv_field(deck),
+            review.time,
+            format_date(review.time),
+            csv_field(&review.topic),
+            csv_field(&review.question),
+            review.correct,
+            review.duration_ms,
+            review.count
+        )?;
+    }
+    out.flush()
+}
+
+/// `text` as a CSV field: quoted when it has separators, quotes or line
+/// breaks, as RFC 4180 does.
+pub fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_difficulty() {
+        let card = |question: &str, rating| Flashcard {
+            rating,
+            ..Flashcard::new(question, "")
+        };
+        let cards = [card("a", None), card("b", Some(2)), card("c", None)];
+        let reviews = [
+            review("es", "a", false, 0),
+            review("es", "a", false, 0),
+            review("es", "a", true, 0),
+            review("es", "b", false, 0),
+        ];
+        assert_eq!(difficulty(&cards, "es", &reviews), [Some(4), Some(2), None]);
+    }
+
+    fn review(topic: &str, question: &str, correct: bool, duration_ms: u64) -> Review {
+        Review {
+            time: 0,
+            topic: topic.to_string(),
+            question: question.to_string(),
+            correct,
+            duration_ms,
+            count: 1,
+        }
+    }
+
+    #[test]
+    fn test_hardest_cards() {
+        let reviews = vec![
+            review("es", "perro", false, 0),
+            review("es", "perro", true, 0),
+            review("es", "gato", false, 0),
+            review("fr", "chat", true, 0),
+        ];
+        let hardest = hardest_cards(&reviews, 5);
+        let questions: Vec<&str> = hardest.iter().map(|c| c.question.as_str()).collect();
+        // Never missed cards are left out
+        assert_eq!(questions, vec!["gato", "perro"]);
+        assert_eq!(hardest[1].reviews, 2);
+    }
+
+    #[test]
+    fn test_topic_stats() {
+        let card = |question: &str| Flashcard::new(question, "");
+        let cards = [card("perro"), card("gato"), card("pez")];
+        let mut reviews = vec![
+            review("es", "perro", false, 0),
+            review("es", "perro", true, 0),
+            review("es", "perro", true, 0),
+            review("es", "perro", true, 0),
+            review("es", "gato", true, 0),
+            review("fr", "pez", true, 0),
+        ];
+        reviews[4].time = 500;
+        let stats = topic_stats(&cards, "es", &reviews);
+        assert_eq!((stats.new, stats.learning, stats.mature), (1, 1, 1));
+        assert_eq!(stats.reviews, 5);
+        assert_eq!(stats.accuracy, Some(80.0));
+        assert_eq!(stats.last_studied, Some(500));
+        assert_eq!(topic_stats(&cards, "de", &reviews).accuracy, None);
+    }
+
+    #[test]
+    fn test_study_plan() {
+        let card = |question: &str| Flashcard::new(question, "");
+        let cards = [card("perro"), card("gato"), card("pez")];
+        let now = 100 * DAY + 60;
+        let mut reviews = vec![
+            review("es", "perro", true, 0),
+            review("es", "perro", true, 0),
+            review("es", "gato", true, 0),
+            review("es", "gato", false, 0),
+        ];
+        reviews[3].time = now - 30;
+        // Perro needs one more, gato and pez three each, over three days
+        let plan = study_plan(&cards, "es", &reviews, 103 * DAY, now).unwrap();
+        assert_eq!(
+            plan,
+            StudyPlan {
+                days_left: 3,
+                needed: 7,
+                per_day: 3,
+                done_today: 1,
+            }
+        );
+        assert_eq!(
+            study_plan(&cards, "es", &reviews, 100 * DAY, now).map(|plan| plan.per_day),
+            Some(7)
+        );
+        assert_eq!(study_plan(&cards, "es", &reviews, 99 * DAY, now), None);
+    }
+
+    #[test]
+    fn test_time_per_topic() {
+        let reviews = vec![
+            review("es", "a", true, 1000),
+            review("fr", "b", true, 5000),
+            review("es", "c", true, 2000),
+        ];
+        assert_eq!(
+            time_per_topic(&reviews),
+            vec![("fr".to_string(), 5000), ("es".to_string(), 3000)]
+        );
+    }
+
+    #[test]
+    fn test_streak() {
+        let on_day = |day: u64| Review {
+            time: day * DAY + 100,
+            ..review("es", "a", true, 0)
+        };
+        let reviews = [on_day(1), on_day(3), on_day(4), on_day(5)];
+        assert_eq!(streak(&reviews, 5 * DAY), 3);
+        // Today still counts as part of the streak until it's over
+        assert_eq!(streak(&reviews, 6 * DAY), 3);
+        assert_eq!(streak(&reviews, 7 * DAY), 0);
+    }
+
+    #[test]
+    fn test_last_session() {
+        let at = |time: u64| Review {
+            time,
+            ..review("es", "a", true, 0)
+        };
+        let reviews = [at(0), at(10_000), at(10_060), at(11_000)];
+        assert_eq!(last_session(&reviews).len(), 3);
+        assert!(last_session(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(19_782), (2024, 2, 29));
+        assert_eq!(civil_date(20_453), (2025, 12, 31));
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut tricky = review("es", "say \"hola\", then\nwave", false, 1500);
+        tricky.time = 19_782 * DAY + 60;
+        let mut out = Vec::new();
+        write_csv("Languages", &[tricky], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "deck,time,date,topic,question,correct,duration_ms,count\n\
+             Languages,1709164860,2024-02-29,es,\"say \"\"hola\"\", then\nwave\",false,1500,1\n"
+        );
+    }
+
+    #[test]
+    fn test_reviews_per_day() {
+        let mut old = review("es", "a", true, 0);
+        old.time = 3 * DAY;
+        let mut recent = review("es", "b", true, 0);
+        recent.time = 5 * DAY + 100;
+        assert_eq!(
+            reviews_per_day(&[old, recent], 3, 5 * DAY + 200),
+            vec![1, 0, 1]
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! The statistics screen, charting the review history.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Chart, Dataset, GraphType, List, ListItem, Paragraph,
+        Widget,
+    },
+};
+
+use mem_flip::history::{DAY, History, Review};
+use mem_flip::stats::{
+    accuracy_per_day, civil_date, hardest_cards, reviews_per_day, time_per_topic,
+};
+
+use crate::l10n::{hint, tr, trf};
+use crate::theme;
+
+// Days covered by the per-day charts
+const CHART_DAYS: usize = 14;
+const HARDEST_CARDS: usize = 10;
+
+pub fn format_duration(ms: u64) -> String {
+    let minutes = ms / 60_000;
+    if minutes >= 60 {
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", ms / 1000)
+    }
+}
+
+/// The statistics screen.
+pub struct StatsView<'a> {
+    pub history: &'a History,
+    pub now: u64,
+}
+
+impl Widget for StatsView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            hint("Export CSV"),
+            "<E>".blue().bold(),
+            hint("Back"),
+            "<Esc> ".blue().bold(),
+        ]);
+        let block = Block::bordered()
+            .title(format!(" 📊 {} ", tr("Statistics")).bold())
+            .title_bottom(instructions);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let reviews = self.history.reviews();
+        if reviews.is_empty() {
+            Paragraph::new(tr(
+                "No reviews yet.\n\nGrade cards with Y or M after flipping them in review.",
+            ))
+            .render(inner, buf);
+            return;
+        }
+
+        let [top, bottom] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(inner);
+        let [per_day, accuracy] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(top);
+        let [hardest, time] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(bottom);
+
+        self.render_reviews_per_day(reviews, per_day, buf);
+        self.render_accuracy(reviews, accuracy, buf);
+        render_hardest(reviews, hardest, buf);
+        render_time(reviews, time, buf);
+    }
+}
+
+impl StatsView<'_> {
+    fn render_reviews_per_day(&self, reviews: &[Review], area: Rect, buf: &mut Buffer) {
+        let first_day = self.now / DAY - (CHART_DAYS as u64 - 1);
+        let bars: Vec<Bar> = reviews_per_day(reviews, CHART_DAYS, self.now)
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let (_, _, day) = civil_date(first_day + i as u64);
+                Bar::default()
+                    .value(count)
+                    .label(Line::from(day.to_string()))
+            })
+            .collect();
+
+        BarChart::default()
+            .block(Block::bordered().title(format!(" {} ", tr("Reviews per day"))))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(3)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(theme::palette().accent))
+            .render(area, buf);
+    }
+
+    fn render_accuracy(&self, reviews: &[Review], area: Rect, buf: &mut Buffer) {
+        let points: Vec<(f64, f64)> = accuracy_per_day(reviews, CHART_DAYS, self.now)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, accuracy)| Some((i as f64, accuracy?)))
+            .collect();
+        let dataset = Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&points);
+
+        Chart::new(vec![dataset])
+            .block(Block::bordered().title(format!(" {} ", tr("Accuracy"))))
+            .x_axis(
+                Axis::default()
+                    .bounds([0.0, (CHART_DAYS - 1) as f64])
+                    .labels([
+                        trf("{}d ago", &[&(CHART_DAYS - 1)]),
+                        tr("today").to_string(),
+                    ]),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, 100.0])
+                    .labels(["0%", "50%", "100%"]),
+            )
+            .render(area, buf);
+    }
+}
+
+fn render_hardest(reviews: &[Review], area: Rect, buf: &mut Buffer) {
+    let items: Vec<ListItem> = hardest_cards(reviews, HARDEST_CARDS)
+        .into_iter()
+        .map(|card| {
+            let question = card.question.lines().next().unwrap_or_default().to_string();
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("✗ {}/{} ", card.missed, card.reviews),
+                    Style::default().fg(Color::Red),
+                ),
+                Span::styled(
+                    format!("{}: ", card.topic),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(question),
+            ]))
+        })
+        .collect();
+
+    List::new(items)
+        .block(Block::bordered().title(format!(" {} ", tr("Hardest cards"))))
+        .render(area, buf);
+}
+
+fn render_time(reviews: &[Review], area: Rect, buf: &mut Buffer) {
+    let bars: Vec<Bar> = time_per_topic(reviews)
+        .into_iter()
+        .map(|(topic, ms)| {
+            Bar::default()
+                .value(ms)
+                .text_value(format_duration(ms))
+                .label(Line::from(topic))
+        })
+        .collect();
+
+    BarChart::default()
+        .block(Block::bordered().title(format!(" {} ", tr("Time per topic"))))
+        .direction(Direction::Horizontal)
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(Color::Magenta))
+        .render(area, buf);
+}
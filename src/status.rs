@@ -0,0 +1,101 @@
+//! One-line status bar drawn under every screen.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::l10n::{tr, trf};
+
+/// Whether the deck on disk matches the one in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveState {
+    Saved,
+    Unsaved,
+    Failed,
+}
+
+#[derive(Debug)]
+pub struct StatusBar<'a> {
+    pub deck: &'a str,
+    pub topics: usize,
+    pub cards: usize,
+    pub due: usize,
+    pub save_state: SaveState,
+    // Keys of an unfinished sequence, with what can follow them
+    pub pending: Option<String>,
+    // What is running in the background, if anything
+    pub busy: Option<String>,
+    // Shown instead of the save state, as nothing is ever saved
+    pub read_only: bool,
+}
+
+impl Widget for StatusBar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, Style::default().bg(Color::DarkGray).fg(Color::White));
+
+        let summary = Line::from(vec![
+            Span::raw(format!(" 📁 {} ", self.deck)).bold(),
+            Span::raw(format!(
+                "│ {} ",
+                trf(
+                    "{} topics │ {} cards │ {} due",
+                    &[&self.topics, &self.cards, &self.due]
+                )
+            )),
+        ]);
+        let save_state = match self.save_state {
+            _ if self.read_only => Span::raw(format!(" 🔒 {} ", tr("read-only"))).magenta(),
+            SaveState::Saved => Span::raw(format!(" ● {} ", tr("saved"))).green(),
+            SaveState::Unsaved => Span::raw(format!(" ● {} ", tr("unsaved"))).yellow(),
+            SaveState::Failed => Span::raw(format!(" ✗ {} ", tr("save failed"))).red().bold(),
+        };
+
+        let mut status = Line::default();
+        if let Some(pending) = self.pending {
+            status.push_span(Span::raw(format!(" {} ", pending)).yellow().bold());
+        }
+        if let Some(busy) = self.busy {
+            status.push_span(Span::raw(format!(" {} ", busy)).cyan());
+        }
+        status.push_span(save_state);
+
+        let [left, right] = Layout::horizontal([
+            Constraint::Min(0),
+            Constraint::Length(status.width() as u16),
+        ])
+        .areas(area);
+        summary.render(left, buf);
+        status.render(right, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_bar_line() {
+        let bar = StatusBar {
+            deck: "deck.json",
+            topics: 2,
+            cards: 7,
+            due: 7,
+            save_state: SaveState::Unsaved,
+            pending: None,
+            busy: Some("⠋ Exporting".to_string()),
+            read_only: false,
+        };
+        let area = Rect::new(0, 0, 80, 1);
+        let mut buf = Buffer::empty(area);
+        bar.render(area, &mut buf);
+
+        let text: String = buf.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("deck.json"));
+        assert!(text.contains("│ 2 topics │ 7 cards │ 7 due"));
+        assert!(text.trim_end().ends_with("⠋ Exporting  ● unsaved"));
+    }
+}
@@ -0,0 +1,117 @@
+//! `mem-flip status`: one line about a deck for tmux status lines, waybar
+//! modules and the like.
+
+use std::io;
+
+use mem_flip::deck::Topics;
+use mem_flip::history::{self, History};
+use mem_flip::{schedule, stats};
+use serde::Serialize;
+
+// Placeholders a format can use, for the error message
+static PLACEHOLDERS: &str = "{deck}, {topics}, {cards}, {due} and {today}";
+
+/// The numbers a summary line is made of.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub deck: String,
+    pub topics: usize,
+    pub cards: usize,
+    pub due: usize,
+    // Reviews given since midnight UTC
+    pub today: u64,
+}
+
+impl Summary {
+    /// The numbers for `topics`, with at most `new_per_day` new cards a day
+    /// due in topics with no limit of their own.
+    pub fn new(
+        deck: String,
+        topics: &Topics,
+        history: &History,
+        new_per_day: Option<usize>,
+    ) -> Summary {
+        let now = history::now();
+        let today = stats::reviews_per_day(history.reviews(), 1, now);
+        Summary {
+            deck,
+            topics: topics.topics_map.len(),
+            cards: topics.card_count(),
+            due: schedule::due_count(topics, history.reviews(), new_per_day, now),
+            today: today.first().copied().unwrap_or_default(),
+        }
+    }
+
+    /// `format` with each `{name}` replaced by that number, `{{` and `}}`
+    /// standing for braces.
+    pub fn format(&self, format: &str) -> io::Result<String> {
+        let mut line = String::new();
+        let mut rest = format;
+        while let Some(start) = rest.find(['{', '}']) {
+            line.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("{{") {
+                line.push('{');
+                rest = after;
+                continue;
+            }
+            if let Some(after) = rest.strip_prefix("}}") {
+                line.push('}');
+                rest = after;
+                continue;
+            }
+            let end = rest.find('}').filter(|_| rest.starts_with('{'));
+            let Some(end) = end else {
+                return Err(invalid(format!("unmatched brace in {:?}", format)));
+            };
+            let value = match &rest[1..end] {
+                "deck" => self.deck.clone(),
+                "topics" => self.topics.to_string(),
+                "cards" => self.cards.to_string(),
+                "due" => self.due.to_string(),
+                "today" => self.today.to_string(),
+                name => {
+                    return Err(invalid(format!(
+                        "unknown placeholder {{{}}}, use {}",
+                        name, PLACEHOLDERS
+                    )));
+                }
+            };
+            line.push_str(&value);
+            rest = &rest[end + 1..];
+        }
+        line.push_str(rest);
+        Ok(line)
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let summary = Summary {
+            deck: "Spanish".to_string(),
+            topics: 2,
+            cards: 40,
+            due: 23,
+            today: 5,
+        };
+        assert_eq!(summary.format("{due} due").unwrap(), "23 due");
+        assert_eq!(
+            summary.format("{{{deck}}} {today}/{cards}").unwrap(),
+            "{Spanish} 5/40"
+        );
+        assert!(summary.format("{overdue}").is_err());
+        assert!(summary.format("{due").is_err());
+        assert_eq!(
+            serde_json::to_string(&summary).unwrap(),
+            r#"{"deck":"Spanish","topics":2,"cards":40,"due":23,"today":5}"#
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Keeping a deck and its review history in step with a copy on a server,
+//! for studying on more than one machine.
+//!
+//! The server is anything that serves files with GET and stores them with
+//! PUT under a base URL, such as a WebDAV folder. Syncing merges both ways.
+//! The deck is merged with the server's from the copy both had at the last
+//! sync, kept next to the deck, so cards added, edited or deleted on either
+//! side all carry over. A card changed differently on both sides keeps this
+//! machine's version. Reviews missing on either side are added to it.
+//!
+//! An encrypted deck stays encrypted: the deck and history go to the server
+//! sealed as they are on disk, and so does the copy kept next to the deck.
+//!
+//! A sync only reads the deck and its history. What came from the server is
+//! handed back to be merged in by whoever holds them, so cards added and
+//! reviews made while it runs aren't written over.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use mem_flip::atomic;
+use mem_flip::crypt::Key;
+use mem_flip::deck::Topics;
+use mem_flip::history::{self, History, Review};
+use mem_flip::merge::{self, Conflict};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// Where decks are synced to.
+#[derive(Debug, Clone)]
+pub struct Remote {
+    // Ends with a slash, file names are appended to it
+    base: String,
+    authorization: Option<String>,
+}
+
+/// What a sync brought in from the server.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Pulled {
+    // Cards added or changed from the server's copy
+    pub cards: usize,
+    // Reviews new here, counted once merged
+    pub reviews: usize,
+    // Cards changed both here and on the server, kept as they are here
+    pub conflicts: usize,
+    // The deck as it was synced and as merged with the server's, when the
+    // server changed it, still to be merged into the deck
+    remote_deck: Option<(Topics, Topics)>,
+    // The server's reviews, when they were compacted up to and when the
+    // last sync read the history, still to be merged into the history
+    remote_history: Option<(Vec<Review>, u64, u64)>,
+    // Kept as the base of the next sync once the deck is saved
+    base: Option<Base>,
+}
+
+// The deck as of the last sync, what both sides have changed since
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Base {
+    deck: Topics,
+    // When the last sync read the history: reviews made here since have
+    // yet to reach the server
+    #[serde(default)]
+    synced_at: u64,
+}
+
+// Where the deck as of the last sync is kept: `deck.sync.json` for
+// `deck.json`
+fn base_path_for(deck: &Path) -> PathBuf {
+    deck.with_extension("sync.json")
+}
+
+impl Base {
+    // Sealed as one line for an encrypted deck
+    fn read(path: &Path, key: Option<&Key>) -> io::Result<Base> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Base::default()),
+            Err(e) => return Err(e),
+        };
+        match key {
+            Some(key) => Ok(serde_json::from_str(&key.open_line(&text)?)?),
+            None => Ok(serde_json::from_str(&text)?),
+        }
+    }
+
+    fn write(&self, path: &Path, key: Option<&Key>) -> io::Result<()> {
+        let json = serde_json::to_string(self)?;
+        match key {
+            Some(key) => atomic::write(path, key.seal_line(&json)?),
+            None => atomic::write(path, json),
+        }
+    }
+}
+
+impl Pulled {
+    /// Merges the cards the server brought into `topics`, keeping what was
+    /// changed in them since the sync started. Returns the cards changed
+    /// there and on the server alike, which are kept as they are in
+    /// `topics`.
+    pub fn merge_deck(&mut self, topics: &mut Topics) -> Vec<Conflict> {
+        let Some((synced, merged)) = self.remote_deck.take() else {
+            return Vec::new();
+        };
+        let merged = merge::three_way(&synced, topics, &merged);
+        *topics = merged.topics;
+        merged.conflicts
+    }
+
+    /// Keeps the deck as uploaded for the next sync of `deck_path` to merge
+    /// from, sealed with the deck's `key`. Only once what came from the
+    /// server is saved, or what the deck lacks would look deleted here.
+    pub fn save_base(&mut self, deck_path: &Path, key: Option<&Key>) -> io::Result<()> {
+        match self.base.take() {
+            Some(base) => base.write(&base_path_for(deck_path), key),
+            None => Ok(()),
+        }
+    }
+
+    /// Merges the server's reviews into `history`. Left to whoever holds
+    /// the history, as reviews may be appended to it while a sync runs.
+    pub fn merge_history(&mut self, history: &mut History) -> io::Result<()> {
+        if let Some((reviews, compacted_before, synced_at)) = self.remote_history.take() {
+            self.reviews = history.merge(reviews, compacted_before, synced_at)?;
+        }
+        Ok(())
+    }
+
+    /// What was pulled, such as "2 new or changed cards and 5 new reviews".
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{} new or changed cards and {} new reviews",
+            self.cards, self.reviews
+        );
+        if self.conflicts > 0 {
+            text.push_str(&format!(
+                ", {} cards changed on both sides kept as they are here",
+                self.conflicts
+            ));
+        }
+        text
+    }
+}
+
+impl Remote {
+    /// A remote at `url`, which must be HTTPS, logging in with `user` and
+    /// `password` when given.
+    pub fn new(url: &str, user: Option<&str>, password: Option<&str>) -> io::Result<Remote> {
+        if !url.starts_with("https://") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "sync_url must be an https:// URL",
+            ));
+        }
+        let base = if url.ends_with('/') {
+            url.to_string()
+        } else {
+            format!("{}/", url)
+        };
+        let authorization = user.map(|user| {
+            let credentials = format!("{}:{}", user, password.unwrap_or_default());
+            format!("Basic {}", STANDARD.encode(credentials))
+        });
+        Ok(Remote {
+            base,
+            authorization,
+        })
+    }
+
+    /// The remote set up in `config`.
+    pub fn from_config(config: &Config) -> io::Result<Remote> {
+        let url = config.sync_url.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "set sync_url in config.toml to sync",
+            )
+        })?;
+        Remote::new(
+            url,
+            config.sync_user.as_deref(),
+            config.sync_password.as_deref(),
+        )
+    }
+
+    // The file's contents, `None` if the server doesn't have it yet
+    fn get(&self, name: &str) -> io::Result<Option<String>> {
+        let mut request = ureq::get(format!("{}{}", self.base, name));
+        if let Some(authorization) = &self.authorization {
+            request = request.header("Authorization", authorization);
+        }
+        match request.call() {
+            Ok(mut response) => response
+                .body_mut()
+                .read_to_string()
+                .map(Some)
+                .map_err(ureq::Error::into_io),
+            Err(ureq::Error::StatusCode(404)) => Ok(None),
+            Err(e) => Err(e.into_io()),
+        }
+    }
+
+    fn put(&self, name: &str, contents: &str) -> io::Result<()> {
+        let mut request = ureq::put(format!("{}{}", self.base, name));
+        if let Some(authorization) = &self.authorization {
+            request = request.header("Authorization", authorization);
+        }
+        request.send(contents).map_err(ureq::Error::into_io)?;
+        Ok(())
+    }
+}
+
+/// Merges `topics`, the deck at `deck_path` as saved, and its history with
+/// the server's copies and uploads the result, so both sides end up the
+/// same. An encrypted deck needs its `key`. Nothing is written here: the server's cards and reviews come back
+/// to be merged in with [`Pulled::merge_deck`] and [`Pulled::merge_history`],
+/// then [`Pulled::save_base`] keeps the result for the next sync.
+pub fn sync(
+    remote: &Remote,
+    deck_path: &Path,
+    mut topics: Topics,
+    key: Option<&Key>,
+) -> io::Result<Pulled> {
+    let mut pulled = Pulled::default();
+    let deck_name = file_name(deck_path)?;
+    let history_path = History::path_for(deck_path);
+    let history_name = file_name(&history_path)?;
+
+    let base = Base::read(&base_path_for(deck_path), key)?;
+    if let Some(text) = remote.get(&deck_name)? {
+        let remote_deck = match key {
+            Some(_) => {
+                let deck = Topics::from_json_with(&text, key)?;
+                deck.check()?;
+                deck
+            }
+            None => Topics::from_shared_json(&text)?,
+        };
+        let local = topics;
+        (topics, pulled.cards, pulled.conflicts) = merge_decks(&base.deck, &local, &remote_deck);
+        if topics != local {
+            pulled.remote_deck = Some((local, topics.clone()));
+        }
+    }
+    let synced_at = history::now();
+    let mut history = History::load_with(history_path, key.cloned())?;
+    if let Some(text) = remote.get(&history_name)? {
+        let (reviews, compacted_before) = history::parse_with(&text, key);
+        history.absorb(reviews.clone(), compacted_before, base.synced_at);
+        pulled.remote_history = Some((reviews, compacted_before, base.synced_at));
+    }
+
+    remote.put(&deck_name, &topics.to_json_with(key)?)?;
+    remote.put(&history_name, &history.to_jsonl())?;
+    // Only once the server has it, or what it lacks would look deleted there
+    pulled.base = Some(Base {
+        deck: topics,
+        synced_at,
+    });
+    Ok(pulled)
+}
+
+// The deck merged from `base`, with how many cards came from `remote` and
+// how many were changed on both sides
+fn merge_decks(base: &Topics, local: &Topics, remote: &Topics) -> (Topics, usize, usize) {
+    let merged = merge::three_way(base, local, remote);
+    let pulled = merged
+        .topics
+        .topics_map
+        .iter()
+        .map(|(name, cards)| {
+            let here = local.topics_map.get(name);
+            cards
+                .iter()
+                .filter(|card| !here.is_some_and(|here| here.contains(card)))
+                .count()
+        })
+        .sum();
+    (merged.topics, pulled, merged.conflicts.len())
+}
+
+fn file_name(path: &Path) -> io::Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} has no file name", path.display()),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mem_flip::test_deck::deck;
+
+    #[test]
+    fn test_remote_url() {
+        let remote = Remote::new("https://dav.example.com/decks", Some("me"), Some("pw")).unwrap();
+        assert_eq!(remote.base, "https://dav.example.com/decks/");
+        assert_eq!(remote.authorization.as_deref(), Some("Basic bWU6cHc="));
+
+        let error = Remote::new("http://dav.example.com/", None, None).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_sealed_base() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = base_path_for(&dir.path().join("deck.json"));
+        let key = Key::new("pw").unwrap();
+        let base = Base {
+            deck: deck(&[("Secret topic", &[("q", "a")])]),
+            synced_at: 7,
+        };
+        base.write(&path, Some(&key)).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("Secret topic"));
+        assert_eq!(Base::read(&path, Some(&key)).unwrap(), base);
+        assert!(Base::read(&path, None).is_err());
+    }
+
+    #[test]
+    fn test_merge_decks() {
+        let base = deck(&[("Spanish", &[("perro", "dog"), ("gato", "cat")])]);
+        // Edited here, deleted and added to on the server
+        let local = deck(&[("Spanish", &[("perro", "hound"), ("gato", "cat")])]);
+        let remote = deck(&[("Spanish", &[("perro", "dog"), ("pez", "fish")])]);
+        let (merged, pulled, conflicts) = merge_decks(&base, &local, &remote);
+        assert_eq!(
+            merged,
+            deck(&[("Spanish", &[("perro", "hound"), ("pez", "fish")])])
+        );
+        assert_eq!((pulled, conflicts), (1, 0));
+
+        // Syncing again brings nothing back
+        let (again, pulled, _) = merge_decks(&merged, &merged, &merged);
+        assert_eq!(again, merged);
+        assert_eq!(pulled, 0);
+    }
+
+    #[test]
+    fn test_merge_history() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let review = |time| Review {
+            time,
+            topic: "Spanish".to_string(),
+            question: "perro".to_string(),
+            correct: true,
+            duration_ms: 1000,
+            count: 1,
+        };
+        let mut pulled = Pulled {
+            remote_history: Some((vec![review(10)], 0, 0)),
+            ..Pulled::default()
+        };
+        let path = dir.path().join("deck.history.jsonl");
+        let mut history = History::empty(path.clone());
+        // Made while the sync ran
+        history.append(review(20)).unwrap();
+
+        pulled.merge_history(&mut history).unwrap();
+        assert_eq!(pulled.reviews, 1);
+        assert_eq!(
+            History::load(path).unwrap().reviews(),
+            [review(10), review(20)]
+        );
+    }
+
+    #[test]
+    fn test_merge_deck() {
+        let synced = deck(&[("Spanish", &[("perro", "dog")])]);
+        let from_server = deck(&[("Spanish", &[("perro", "dog"), ("pez", "fish")])]);
+        let mut pulled = Pulled {
+            remote_deck: Some((synced, from_server)),
+            ..Pulled::default()
+        };
+        // Added while the sync ran
+        let mut topics = deck(&[("Spanish", &[("perro", "dog"), ("gato", "cat")])]);
+
+        assert!(pulled.merge_deck(&mut topics).is_empty());
+        assert_eq!(
+            topics,
+            deck(&[(
+                "Spanish",
+                &[("perro", "dog"), ("gato", "cat"), ("pez", "fish")]
+            )])
+        );
+        // Taken once
+        assert!(pulled.merge_deck(&mut topics).is_empty());
+        assert_eq!(topics.topics_map["Spanish"].len(), 3);
+    }
+}
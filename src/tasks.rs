@@ -0,0 +1,300 @@
+//! Slow work run on background threads so the UI keeps responding.
+//!
+//! Tasks report progress and their outcome over a channel that the UI loop
+//! drains every tick. A task can be asked to stop, which it notices the next
+//! time it reports progress. Quitting stops every task and waits for it, so
+//! none is cut off halfway through writing a file.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use mem_flip::deck::{Flashcard, Topics};
+
+use crate::import::Imported;
+use crate::l10n::trf;
+use crate::sync::Pulled;
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// What a task ends with.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Worked, with a message for the user
+    Done(String),
+    /// Went wrong, with a message for the user
+    Failed(String),
+    /// Wrote a new deck file, which the app can now offer to open
+    NewDeck(PathBuf),
+    /// Synced a deck with the server, whose reviews are still to be merged
+    /// into the deck's history
+    Synced { deck: PathBuf, pulled: Box<Pulled> },
+    /// Drafted cards for a topic, for the user to keep or drop
+    Drafted {
+        topic: String,
+        cards: Vec<Flashcard>,
+    },
+    /// Imported cards into a copy of the deck, which takes the place of the
+    /// open one
+    Imported {
+        deck: PathBuf,
+        topics: Box<Topics>,
+        result: Imported,
+    },
+}
+
+#[derive(Debug)]
+enum Message {
+    Progress { id: u64, done: usize, total: usize },
+    Finished { id: u64, outcome: Outcome },
+}
+
+/// Lets a task report how far along it is, and learn that it should stop.
+pub struct Progress {
+    id: u64,
+    sender: Sender<Message>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Progress {
+    pub fn set(&self, done: usize, total: usize) {
+        // Nobody is listening once the app has quit
+        let _ = self.sender.send(Message::Progress {
+            id: self.id,
+            done,
+            total,
+        });
+    }
+
+    /// Whether the task was cancelled and should stop, leaving everything
+    /// as it was.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug)]
+struct Running {
+    id: u64,
+    label: String,
+    progress: Option<(usize, usize)>,
+    cancelled: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+#[derive(Debug)]
+pub struct Finished {
+    pub label: String,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug)]
+pub struct Tasks {
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    running: Vec<Running>,
+    next_id: u64,
+}
+
+impl Default for Tasks {
+    fn default() -> Tasks {
+        let (sender, receiver) = mpsc::channel();
+        Tasks {
+            sender,
+            receiver,
+            running: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl Tasks {
+    /// Runs `work` on a new thread. `label` describes it while it runs.
+    /// Returns the task's id, to follow or cancel it by.
+    pub fn spawn<F>(&mut self, label: impl Into<String>, work: F) -> u64
+    where
+        F: FnOnce(&Progress) -> Outcome + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let progress = Progress {
+            id,
+            sender: self.sender.clone(),
+            cancelled: Arc::clone(&cancelled),
+        };
+        let thread = thread::spawn(move || {
+            let outcome = work(&progress);
+            let _ = progress.sender.send(Message::Finished { id, outcome });
+        });
+        self.running.push(Running {
+            id,
+            label: label.into(),
+            progress: None,
+            cancelled,
+            thread,
+        });
+        id
+    }
+
+    /// Asks the task `id` to stop. It still finishes, with what it says
+    /// about stopping.
+    pub fn cancel(&mut self, id: u64) {
+        if let Some(task) = self.running.iter().find(|task| task.id == id) {
+            task.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// How far the task `id` is, as done and total, `None` once it has
+    /// finished. The inner `None` is for a task yet to report.
+    pub fn progress(&self, id: u64) -> Option<Option<(usize, usize)>> {
+        self.running
+            .iter()
+            .find(|task| task.id == id)
+            .map(|task| task.progress)
+    }
+
+    /// Takes in the reports sent since the last call and returns the tasks
+    /// that have finished.
+    pub fn poll(&mut self) -> Vec<Finished> {
+        let mut finished = Vec::new();
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                Message::Progress { id, done, total } => {
+                    if let Some(task) = self.running.iter_mut().find(|task| task.id == id) {
+                        task.progress = Some((done, total));
+                    }
+                }
+                Message::Finished { id, outcome } => {
+                    if let Some(i) = self.running.iter().position(|task| task.id == id) {
+                        let task = self.running.remove(i);
+                        // Its last act was sending this
+                        let _ = task.thread.join();
+                        finished.push(Finished {
+                            label: task.label,
+                            outcome,
+                        });
+                    }
+                }
+            }
+        }
+        finished
+    }
+
+    /// Cancels every task and waits for each to finish, dropping what they
+    /// end with. For quitting, which would otherwise cut them off.
+    pub fn stop(&mut self) {
+        for task in &self.running {
+            task.cancelled.store(true, Ordering::Relaxed);
+        }
+        for task in self.running.drain(..) {
+            let _ = task.thread.join();
+        }
+        while self.receiver.try_recv().is_ok() {}
+    }
+
+    /// How many tasks are still running.
+    pub fn count(&self) -> usize {
+        self.running.len()
+    }
+
+    /// Whether any task is still running.
+    pub fn is_busy(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    /// One line on what is running, with a spinner that turns with `tick`.
+    pub fn summary(&self, tick: u64) -> Option<String> {
+        let spinner = spinner(tick);
+        match self.running.as_slice() {
+            [] => None,
+            [task] => Some(match task.progress {
+                Some((done, total)) if total > 0 => {
+                    format!("{} {} {}%", spinner, task.label, done * 100 / total)
+                }
+                _ => format!("{} {}", spinner, task.label),
+            }),
+            tasks => Some(format!("{} {}", spinner, trf("{} tasks", &[&tasks.len()]))),
+        }
+    }
+}
+
+/// Frame of a spinner that turns with `tick`.
+pub fn spinner(tick: u64) -> char {
+    SPINNER[tick as usize % SPINNER.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_task_reports_back() {
+        let mut tasks = Tasks::default();
+        tasks.spawn("Counting", |progress| {
+            progress.set(1, 2);
+            Outcome::Done("Counted".to_string())
+        });
+        assert_eq!(tasks.summary(0).as_deref(), Some("⠋ Counting"));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let finished = loop {
+            let finished = tasks.poll();
+            if !finished.is_empty() || Instant::now() > deadline {
+                break finished;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].label, "Counting");
+        assert_eq!(finished[0].outcome, Outcome::Done("Counted".to_string()));
+        assert_eq!(tasks.summary(0), None);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut tasks = Tasks::default();
+        let id = tasks.spawn("Waiting", |progress| {
+            while !progress.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Outcome::Done("Stopped".to_string())
+        });
+        assert_eq!(tasks.progress(id), Some(None));
+        tasks.cancel(id);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let finished = loop {
+            let finished = tasks.poll();
+            if !finished.is_empty() || Instant::now() > deadline {
+                break finished;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(finished[0].outcome, Outcome::Done("Stopped".to_string()));
+        assert_eq!(tasks.progress(id), None);
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut tasks = Tasks::default();
+        let wrote = Arc::new(AtomicBool::new(false));
+        let written = Arc::clone(&wrote);
+        tasks.spawn("Writing", move |progress| {
+            while !progress.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            written.store(true, Ordering::Relaxed);
+            Outcome::Done("Stopped".to_string())
+        });
+        tasks.stop();
+        // The task has run to its end
+        assert!(wrote.load(Ordering::Relaxed));
+        assert!(!tasks.is_busy());
+        assert!(tasks.poll().is_empty());
+    }
+}
@@ -0,0 +1,17 @@
+//! Decks for tests, written out topic by topic. The app's tests get it
+//! through the `test-util` feature.
+
+use crate::deck::{Flashcard, Topics};
+
+/// A deck with one topic per `(name, cards)`, cards as question and answer.
+pub fn deck(topics: &[(&str, &[(&str, &str)])]) -> Topics {
+    let mut deck = Topics::default();
+    for (name, cards) in topics {
+        let cards = cards
+            .iter()
+            .map(|(question, answer)| Flashcard::new(*question, *answer))
+            .collect();
+        deck.topics_map.insert(name.to_string(), cards);
+    }
+    deck
+}
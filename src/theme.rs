@@ -0,0 +1,201 @@
+//! Colors that read well on the terminal's background.
+//!
+//! The background is asked of the terminal with an OSC 11 query at startup,
+//! falling back to the `COLORFGBG` variable some terminals set, and to a
+//! dark background when neither answers.
+
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(unix)]
+use std::time::Duration;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+// How long the terminal gets to answer the background query
+#[cfg(unix)]
+const QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeSetting {
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+/// Colors of the parts of the screen that depend on the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    // Borders and titles of the main panes, progress
+    pub accent: Color,
+    // Text being typed, and the pane it goes in
+    pub input: Color,
+    // Background of the selected row of a list
+    pub highlight: Color,
+}
+
+impl Palette {
+    pub const DARK: Palette = Palette {
+        accent: Color::Cyan,
+        input: Color::Yellow,
+        highlight: Color::DarkGray,
+    };
+    pub const LIGHT: Palette = Palette {
+        accent: Color::Blue,
+        input: Color::Magenta,
+        highlight: Color::Gray,
+    };
+}
+
+static LIGHT: AtomicBool = AtomicBool::new(false);
+// What the terminal said its background is, asked only once
+static DETECTED: OnceLock<bool> = OnceLock::new();
+
+/// Picks the palette for `setting`, at startup or when the setting is
+/// changed. Has to be called with the terminal in raw mode, for the answer
+/// to the query to be readable.
+pub fn init(setting: ThemeSetting) {
+    let light = match setting {
+        ThemeSetting::Auto => *DETECTED.get_or_init(detect_light),
+        ThemeSetting::Dark => false,
+        ThemeSetting::Light => true,
+    };
+    LIGHT.store(light, Ordering::Relaxed);
+}
+
+/// The palette in use, the dark one until `init` is called.
+pub fn palette() -> Palette {
+    if LIGHT.load(Ordering::Relaxed) {
+        Palette::LIGHT
+    } else {
+        Palette::DARK
+    }
+}
+
+fn detect_light() -> bool {
+    let light = query_background()
+        .or_else(|| {
+            std::env::var("COLORFGBG")
+                .ok()
+                .and_then(|value| colorfgbg_is_light(&value))
+        })
+        .unwrap_or(false);
+    debug!("light terminal background: {}", light);
+    light
+}
+
+// `COLORFGBG` is `fg;bg` or `fg;default;bg` in ANSI color numbers, where 7
+// and 15 are the light grays and white
+fn colorfgbg_is_light(value: &str) -> Option<bool> {
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    Some(matches!(background, 7 | 15))
+}
+
+// The answer to OSC 11 is `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` ended by BEL or
+// ST, with one to four hex digits per component
+fn osc11_is_light(answer: &str) -> Option<bool> {
+    let rgb = answer.split("rgb:").nth(1)?;
+    let rgb = rgb.trim_end_matches(['\x07', '\\', '\x1b']);
+    let mut components = rgb.split('/').map(|hex| {
+        let digits = hex.get(..hex.len().min(4))?;
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        Some(value as f64 / ((1u32 << (4 * digits.len())) - 1) as f64)
+    });
+    let (r, g, b) = (
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    );
+    // Relative luminance, by how bright each component looks
+    Some(0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5)
+}
+
+#[cfg(unix)]
+fn query_background() -> Option<bool> {
+    use std::io::Write;
+    use std::time::Instant;
+
+    // The background query is followed by a device attributes query, which
+    // every terminal answers, and answers in order. Once that answer is in,
+    // so is the background, if the terminal knows the query at all.
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]11;?\x07\x1b[c").ok()?;
+    stdout.flush().ok()?;
+
+    // Read the answers straight from the file descriptor, so a terminal that
+    // doesn't answer can't hang a blocking read
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut answer = Vec::new();
+    while attributes_at(&answer).is_none() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let mut fd = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a single valid pollfd
+        let ready = unsafe { libc::poll(&mut fd, 1, left.as_millis() as libc::c_int) };
+        let mut byte = 0u8;
+        // SAFETY: reads at most one byte into `byte`
+        let read = (ready > 0)
+            .then(|| unsafe { libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) });
+        if read != Some(1) {
+            // What came of the answers so far, and whatever is on its way
+            // in, would otherwise reach the app as key presses
+            // SAFETY: only discards input not read yet
+            unsafe { libc::tcflush(libc::STDIN_FILENO, libc::TCIFLUSH) };
+            return None;
+        }
+        answer.push(byte);
+    }
+    let end = attributes_at(&answer)?;
+    osc11_is_light(&String::from_utf8_lossy(&answer[..end]))
+}
+
+// Where the answer to the device attributes query, `ESC [ ? ... c`, starts
+// in `answer`, once all of it is there
+#[cfg(unix)]
+fn attributes_at(answer: &[u8]) -> Option<usize> {
+    let start = answer.windows(3).position(|window| window == b"\x1b[?")?;
+    answer[start..].contains(&b'c').then_some(start)
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc11_is_light() {
+        assert_eq!(osc11_is_light("\x1b]11;rgb:ffff/ffff/ffff\x07"), Some(true));
+        assert_eq!(
+            osc11_is_light("\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\"),
+            Some(false)
+        );
+        assert_eq!(osc11_is_light("\x1b]11;rgb:fd/f6/e3\x07"), Some(true));
+        assert_eq!(osc11_is_light("\x1b]11;?\x07"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_attributes_at() {
+        let answer = b"\x1b]11;rgb:ffff/ffff/ffff\x07\x1b[?62;22c";
+        assert_eq!(attributes_at(answer), Some(24));
+        assert_eq!(attributes_at(&answer[..30]), None);
+        assert_eq!(attributes_at(b"\x1b[?1;2c"), Some(0));
+    }
+
+    #[test]
+    fn test_colorfgbg_is_light() {
+        assert_eq!(colorfgbg_is_light("0;15"), Some(true));
+        assert_eq!(colorfgbg_is_light("15;default;0"), Some(false));
+        assert_eq!(colorfgbg_is_light("default"), None);
+    }
+}
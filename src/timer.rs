@@ -0,0 +1,139 @@
+//! Pomodoro study timer: stretches of focus with short breaks between them.
+//!
+//! The timer runs on the wall clock whatever screen is open, so a break
+//! taken away from the cards still counts as one. Only focus time is added
+//! up, along with the cards graded during it, for the session summary.
+
+use std::time::{Duration, Instant};
+
+/// Minutes of focus when the config doesn't say.
+pub const DEFAULT_FOCUS_MINUTES: u64 = 25;
+/// Minutes of each break when the config doesn't say.
+pub const DEFAULT_BREAK_MINUTES: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Focus,
+    Break,
+}
+
+#[derive(Debug)]
+pub struct StudyTimer {
+    focus: Duration,
+    rest: Duration,
+    phase: Phase,
+    phase_start: Instant,
+    // Focus time of the phases that have ended
+    focused: Duration,
+    // Focus phases completed
+    pub rounds: u32,
+    // Cards graded since the timer started
+    pub graded: usize,
+}
+
+impl StudyTimer {
+    pub fn new(focus: Duration, rest: Duration, now: Instant) -> StudyTimer {
+        StudyTimer {
+            // A zero length phase would never end
+            focus: focus.max(Duration::from_secs(60)),
+            rest: rest.max(Duration::from_secs(60)),
+            phase: Phase::Focus,
+            phase_start: now,
+            focused: Duration::ZERO,
+            rounds: 0,
+            graded: 0,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    fn length(&self) -> Duration {
+        match self.phase {
+            Phase::Focus => self.focus,
+            Phase::Break => self.rest,
+        }
+    }
+
+    /// Moves on to the phase `now` falls in. Returns it when it changed, to
+    /// let the user know.
+    pub fn tick(&mut self, now: Instant) -> Option<Phase> {
+        let before = self.phase;
+        let mut changed = false;
+        while now.saturating_duration_since(self.phase_start) >= self.length() {
+            self.phase_start += self.length();
+            if self.phase == Phase::Focus {
+                self.focused += self.focus;
+                self.rounds += 1;
+                self.phase = Phase::Break;
+            } else {
+                self.phase = Phase::Focus;
+            }
+            changed = true;
+        }
+        // Back in the same phase after a whole round away says nothing new
+        (changed && self.phase != before).then_some(self.phase)
+    }
+
+    /// Pushes the current phase back by `away`, for a pause.
+    pub fn delay(&mut self, away: Duration) {
+        self.phase_start += away;
+    }
+
+    /// Time left in the current phase.
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.length()
+            .saturating_sub(now.saturating_duration_since(self.phase_start))
+    }
+
+    /// Focus time so far, the current phase included.
+    pub fn focused(&self, now: Instant) -> Duration {
+        match self.phase {
+            Phase::Focus => {
+                self.focused
+                    + now
+                        .saturating_duration_since(self.phase_start)
+                        .min(self.focus)
+            }
+            Phase::Break => self.focused,
+        }
+    }
+}
+
+/// `12:04` for a duration, in minutes and seconds.
+pub fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases() {
+        let start = Instant::now();
+        let minutes = |n: u64| start + Duration::from_secs(n * 60);
+        let mut timer = StudyTimer::new(
+            Duration::from_secs(25 * 60),
+            Duration::from_secs(5 * 60),
+            start,
+        );
+
+        assert_eq!(timer.tick(minutes(10)), None);
+        assert_eq!(timer.remaining(minutes(10)), Duration::from_secs(15 * 60));
+        assert_eq!(timer.focused(minutes(10)), Duration::from_secs(10 * 60));
+
+        assert_eq!(timer.tick(minutes(26)), Some(Phase::Break));
+        assert_eq!(timer.rounds, 1);
+        assert_eq!(timer.focused(minutes(28)), Duration::from_secs(25 * 60));
+        assert_eq!(clock(timer.remaining(minutes(26))), "4:00");
+
+        assert_eq!(timer.tick(minutes(31)), Some(Phase::Focus));
+        // Away for a whole round, through a break and back into focus
+        assert_eq!(timer.tick(minutes(61)), None);
+        assert_eq!(timer.rounds, 2);
+        assert_eq!(timer.focused(minutes(61)), Duration::from_secs(51 * 60));
+    }
+}
@@ -0,0 +1,123 @@
+//! Short-lived notifications stacked in the top right corner.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Clear, Padding, Paragraph, Widget, Wrap},
+};
+
+const INFO_DURATION: Duration = Duration::from_secs(3);
+const ERROR_DURATION: Duration = Duration::from_secs(6);
+const MAX_SHOWN: usize = 3;
+const MAX_WIDTH: u16 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Error,
+}
+
+#[derive(Debug)]
+struct Toast {
+    message: String,
+    level: ToastLevel,
+    expires: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct Toasts {
+    // Oldest first
+    items: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(message.into(), ToastLevel::Info, INFO_DURATION);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(message.into(), ToastLevel::Error, ERROR_DURATION);
+    }
+
+    fn push(&mut self, message: String, level: ToastLevel, duration: Duration) {
+        self.items.push_back(Toast {
+            message,
+            level,
+            expires: Instant::now() + duration,
+        });
+        if self.items.len() > MAX_SHOWN {
+            self.items.pop_front();
+        }
+    }
+
+    /// Drops toasts that have timed out by `now`.
+    pub fn prune(&mut self, now: Instant) {
+        self.items.retain(|toast| toast.expires > now);
+    }
+}
+
+impl Widget for &Toasts {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut y = area.y;
+        // Newest on top
+        for toast in self.items.iter().rev() {
+            let color = match toast.level {
+                ToastLevel::Info => Color::Green,
+                ToastLevel::Error => Color::Red,
+            };
+            let line = Line::raw(toast.message.as_str());
+            let width = (line.width() as u16 + 4).min(MAX_WIDTH).min(area.width);
+            let paragraph = Paragraph::new(line).wrap(Wrap { trim: true }).block(
+                Block::bordered()
+                    .padding(Padding::horizontal(1))
+                    .style(Style::default().fg(color)),
+            );
+            // Counted at the inner width, the borders are included
+            let height = paragraph.line_count(width.saturating_sub(4)) as u16;
+            if y + height > area.bottom() {
+                break;
+            }
+
+            let toast_area = Rect::new(area.right() - width, y, width, height);
+            Clear.render(toast_area, buf);
+            paragraph.render(toast_area, buf);
+            y += height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire() {
+        let mut toasts = Toasts::default();
+        toasts.info("Card saved");
+        toasts.error("save failed");
+        assert_eq!(toasts.items.len(), 2);
+
+        let first = toasts.items[0].expires;
+        toasts.prune(first);
+        assert_eq!(toasts.items.len(), 1);
+        assert_eq!(toasts.items[0].level, ToastLevel::Error);
+
+        toasts.prune(first + ERROR_DURATION);
+        assert!(toasts.items.is_empty());
+    }
+
+    #[test]
+    fn test_oldest_toast_is_dropped() {
+        let mut toasts = Toasts::default();
+        for i in 0..5 {
+            toasts.info(format!("toast {}", i));
+        }
+        assert_eq!(toasts.items.len(), MAX_SHOWN);
+        assert_eq!(toasts.items[0].message, "toast 2");
+    }
+}
@@ -0,0 +1,310 @@
+//! Orders the topic list can be sorted in, switched through with `o`.
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use mem_flip::deck::Topics;
+use mem_flip::history::{self, Review};
+use mem_flip::schedule::{self, TopicCounts};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopicSort {
+    #[default]
+    Alphabetical,
+    MostCards,
+    MostDue,
+    RecentlyStudied,
+    RecentlyCreated,
+}
+
+impl TopicSort {
+    /// The sort after this one, back to alphabetical after the last.
+    pub fn next(self) -> TopicSort {
+        match self {
+            TopicSort::Alphabetical => TopicSort::MostCards,
+            TopicSort::MostCards => TopicSort::MostDue,
+            TopicSort::MostDue => TopicSort::RecentlyStudied,
+            TopicSort::RecentlyStudied => TopicSort::RecentlyCreated,
+            TopicSort::RecentlyCreated => TopicSort::Alphabetical,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TopicSort::Alphabetical => "name",
+            TopicSort::MostCards => "most cards",
+            TopicSort::MostDue => "most due",
+            TopicSort::RecentlyStudied => "recently studied",
+            TopicSort::RecentlyCreated => "recently created",
+        }
+    }
+}
+
+/// Names of `topics` in the order `sort` asks for, archived topics only
+/// if `archived`. Due cards count at most `new_per_day` new cards, unless a
+/// topic has its own limit. Ties, and topics with no review or creation
+/// time, are in alphabetical order.
+pub fn sorted(
+    topics: &Topics,
+    reviews: &[Review],
+    sort: TopicSort,
+    archived: bool,
+    new_per_day: Option<usize>,
+) -> Vec<String> {
+    let mut names = topics.names();
+    names.retain(|name| archived || !topics.is_archived(name));
+    let cards = |name: &String| topics.topics_map.get(name).map_or(&[][..], Vec::as_slice);
+    match sort {
+        TopicSort::Alphabetical => {}
+        TopicSort::MostCards => names.sort_by_key(|name| Reverse(cards(name).len())),
+        TopicSort::MostDue => {
+            let now = history::now();
+            names.sort_by_cached_key(|name| {
+                Reverse(schedule::due_today(topics, name, reviews, new_per_day, now).len())
+            })
+        }
+        TopicSort::RecentlyStudied => {
+            let mut last: HashMap<&str, u64> = HashMap::new();
+            for review in reviews {
+                let time = last.entry(review.topic.as_str()).or_default();
+                *time = (*time).max(review.time);
+            }
+            names.sort_by_key(|name| Reverse(last.get(name.as_str()).copied()));
+        }
+        TopicSort::RecentlyCreated => {
+            names.sort_by_key(|name| Reverse(topics.meta_of(name).created))
+        }
+    }
+    names
+}
+
+/// The topic list in its sort order, with the counts shown on each row,
+/// kept between key presses and frames. It is worked out again when the
+/// sort, the number of reviews, whether archived topics are shown, the new
+/// cards a day or the day change, and after [`TopicOrder::clear`], which is
+/// for when topics, their settings or cards change.
+#[derive(Debug, Default)]
+pub struct TopicOrder {
+    cached: RefCell<Option<Sorted>>,
+}
+
+// Topic names and their counts, and the sort, number of reviews, archived
+// topics shown, new cards a day and day they were worked out with
+#[derive(Debug)]
+struct Sorted {
+    sort: TopicSort,
+    reviews: usize,
+    archived: bool,
+    new_per_day: Option<usize>,
+    day: u64,
+    names: Rc<[String]>,
+    counts: Rc<HashMap<String, TopicCounts>>,
+}
+
+impl TopicOrder {
+    /// Names of `topics` in the order `sort` asks for, as `sorted` gives.
+    pub fn get(
+        &self,
+        topics: &Topics,
+        reviews: &[Review],
+        sort: TopicSort,
+        archived: bool,
+        new_per_day: Option<usize>,
+    ) -> Rc<[String]> {
+        self.cached(topics, reviews, sort, archived, new_per_day).0
+    }
+
+    /// Counts of each topic `get` lists, with at most the topic's own
+    /// number of new cards a day due, or else `new_per_day`.
+    pub fn counts(
+        &self,
+        topics: &Topics,
+        reviews: &[Review],
+        sort: TopicSort,
+        archived: bool,
+        new_per_day: Option<usize>,
+    ) -> Rc<HashMap<String, TopicCounts>> {
+        self.cached(topics, reviews, sort, archived, new_per_day).1
+    }
+
+    fn cached(
+        &self,
+        topics: &Topics,
+        reviews: &[Review],
+        sort: TopicSort,
+        archived: bool,
+        new_per_day: Option<usize>,
+    ) -> (Rc<[String]>, Rc<HashMap<String, TopicCounts>>) {
+        let now = history::now();
+        let day = now / history::DAY;
+        let mut cached = self.cached.borrow_mut();
+        if let Some(hit) = &*cached
+            && hit.sort == sort
+            && hit.reviews == reviews.len()
+            && hit.archived == archived
+            && hit.new_per_day == new_per_day
+            && hit.day == day
+        {
+            return (Rc::clone(&hit.names), Rc::clone(&hit.counts));
+        }
+        let names: Rc<[String]> = sorted(topics, reviews, sort, archived, new_per_day).into();
+        let counts: Rc<HashMap<String, TopicCounts>> = Rc::new(
+            names
+                .iter()
+                .map(|name| {
+                    let cards = topics.topics_map.get(name).map_or(&[][..], Vec::as_slice);
+                    let limit = topics.meta_of(name).new_per_day.or(new_per_day);
+                    let counts = schedule::topic_counts(cards, name, reviews, limit, now);
+                    (name.clone(), counts)
+                })
+                .collect(),
+        );
+        *cached = Some(Sorted {
+            sort,
+            reviews: reviews.len(),
+            archived,
+            new_per_day,
+            day,
+            names: Rc::clone(&names),
+            counts: Rc::clone(&counts),
+        });
+        (names, counts)
+    }
+
+    /// Forgets the order, after topics or their cards changed.
+    pub fn clear(&mut self) {
+        *self.cached.get_mut() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mem_flip::deck::{Flashcard, TopicMeta};
+
+    use super::*;
+
+    #[test]
+    fn test_sorted() {
+        let mut topics = Topics::default();
+        let card = Flashcard::new("q", "a");
+        topics
+            .topics_map
+            .insert("Art".to_string(), vec![card.clone()]);
+        topics
+            .topics_map
+            .insert("Biology".to_string(), vec![card.clone(), card]);
+        topics.topics_map.insert("Chess".to_string(), Vec::new());
+        topics.set_meta(
+            "Chess",
+            TopicMeta {
+                created: Some(100),
+                ..TopicMeta::default()
+            },
+        );
+        let reviews = [Review {
+            time: 50,
+            topic: "Chess".to_string(),
+            question: "q".to_string(),
+            correct: true,
+            duration_ms: 0,
+            count: 1,
+        }];
+
+        topics.topics_map.insert("Dance".to_string(), Vec::new());
+        topics.set_meta(
+            "Dance",
+            TopicMeta {
+                archived: true,
+                ..TopicMeta::default()
+            },
+        );
+
+        let sort = |sort| sorted(&topics, &reviews, sort, false, None);
+        assert_eq!(sort(TopicSort::Alphabetical), ["Art", "Biology", "Chess"]);
+        assert_eq!(sort(TopicSort::MostCards), ["Biology", "Art", "Chess"]);
+        assert_eq!(
+            sort(TopicSort::RecentlyStudied),
+            ["Chess", "Art", "Biology"]
+        );
+        assert_eq!(
+            sort(TopicSort::RecentlyCreated),
+            ["Chess", "Art", "Biology"]
+        );
+        assert_eq!(
+            sorted(&topics, &reviews, TopicSort::Alphabetical, true, None),
+            ["Art", "Biology", "Chess", "Dance"]
+        );
+    }
+
+    #[test]
+    fn test_most_due_counts_new_card_limit() {
+        let mut topics = Topics::default();
+        let cards = |count| vec![Flashcard::new("q", "a"); count];
+        topics.topics_map.insert("Art".to_string(), cards(2));
+        topics.topics_map.insert("Biology".to_string(), cards(5));
+        topics.topics_map.insert("Chess".to_string(), cards(4));
+        topics.set_meta(
+            "Chess",
+            TopicMeta {
+                new_per_day: Some(3),
+                ..TopicMeta::default()
+            },
+        );
+
+        let sort = |new_per_day| sorted(&topics, &[], TopicSort::MostDue, false, new_per_day);
+        assert_eq!(sort(None), ["Biology", "Chess", "Art"]);
+        // Only as many new cards as a day brings in are due
+        assert_eq!(sort(Some(1)), ["Chess", "Art", "Biology"]);
+    }
+
+    #[test]
+    fn test_order_is_kept_until_cleared() {
+        let mut topics = Topics::default();
+        topics.topics_map.insert("Art".to_string(), Vec::new());
+        let mut order = TopicOrder::default();
+        assert_eq!(
+            *order.get(&topics, &[], TopicSort::Alphabetical, false, None),
+            ["Art"]
+        );
+
+        topics.topics_map.insert("Biology".to_string(), Vec::new());
+        assert_eq!(
+            *order.get(&topics, &[], TopicSort::Alphabetical, false, None),
+            ["Art"]
+        );
+        order.clear();
+        assert_eq!(
+            *order.get(&topics, &[], TopicSort::Alphabetical, false, None),
+            ["Art", "Biology"]
+        );
+    }
+
+    #[test]
+    fn test_counts_are_kept_until_cleared() {
+        let mut topics = Topics::default();
+        let cards = |count| vec![Flashcard::new("q", "a"); count];
+        topics.topics_map.insert("Art".to_string(), cards(3));
+        topics.set_meta(
+            "Art",
+            TopicMeta {
+                new_per_day: Some(2),
+                ..TopicMeta::default()
+            },
+        );
+        let mut order = TopicOrder::default();
+        let counts = |order: &TopicOrder, topics: &Topics| {
+            order.counts(topics, &[], TopicSort::Alphabetical, false, None)["Art"]
+        };
+        let art = counts(&order, &topics);
+        assert_eq!((art.due, art.new, art.total), (2, 3, 3));
+
+        topics.topics_map.insert("Art".to_string(), cards(4));
+        assert_eq!(counts(&order, &topics).total, 3);
+        order.clear();
+        assert_eq!(counts(&order, &topics).total, 4);
+    }
+}
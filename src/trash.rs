@@ -0,0 +1,87 @@
+//! The screen listing removed cards and topics, from which they can be
+//! put back.
+
+use mem_flip::deck::Trashed;
+use mem_flip::stats;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
+};
+
+use crate::browser;
+use crate::git;
+use crate::l10n::{hint, tr, trf};
+
+/// Days removed cards are kept when the config doesn't say.
+pub const DEFAULT_DAYS: u64 = 30;
+
+pub struct TrashView<'a> {
+    // Oldest first, as kept in the deck; listed newest first
+    pub trash: &'a [Trashed],
+    // Position in the list, newest first
+    pub selected: usize,
+    pub days: u64,
+}
+
+impl Widget for TrashView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            hint("Navigate"),
+            "<↑↓>".blue().bold(),
+            hint("Restore"),
+            "<Enter>".green().bold(),
+            hint("Back"),
+            "<Esc> ".red().bold(),
+        ]);
+        let block = Block::bordered()
+            .title(format!(" 🗑 {} ", trf("Trash, kept {} days", &[&self.days])).bold())
+            .title_bottom(instructions);
+
+        if self.trash.is_empty() {
+            Paragraph::new(Line::from(tr("The trash is empty")).dark_gray())
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = self
+            .trash
+            .iter()
+            .rev()
+            .map(|trashed| {
+                let what = match trashed.cards.first() {
+                    _ if trashed.whole_topic => trf(
+                        "Topic {} ({})",
+                        &[&trashed.topic, &git::cards_label(trashed.cards.len())],
+                    ),
+                    Some(card) => trf(
+                        "{}  in {}",
+                        &[&browser::first_line(&card.question), &trashed.topic],
+                    ),
+                    None => trashed.topic.clone(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{}  ", stats::format_date(trashed.deleted)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(what),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ ");
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(list, area, buf, &mut state);
+    }
+}
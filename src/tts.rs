@@ -0,0 +1,157 @@
+//! Text-to-speech playback of card text.
+//!
+//! Text is piped on stdin to either a user configured shell command (e.g.
+//! `piper --model es_ES.onnx --output-raw | aplay -r 22050 -f S16_LE`) or the
+//! first of `say`/`espeak-ng`/`espeak` found in `PATH`. A custom command
+//! runs in its own process group, so stopping it stops every program in its
+//! pipeline rather than only the shell.
+
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+
+use crate::paths::in_path;
+
+#[derive(Debug)]
+pub struct Speaker {
+    command: Option<String>,
+    // Utterance still playing, stopped when a new one starts
+    playing: Option<Child>,
+}
+
+impl Speaker {
+    pub fn new(command: Option<String>) -> Speaker {
+        Speaker {
+            command,
+            playing: None,
+        }
+    }
+
+    pub fn speak(&mut self, text: &str) -> io::Result<()> {
+        self.stop();
+
+        let mut command = match &self.command {
+            Some(custom) => {
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(custom);
+                #[cfg(unix)]
+                command.process_group(0);
+                command
+            }
+            None => default_backend().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "no text-to-speech backend found")
+            })?,
+        };
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        // Kept before writing, so a failed write still stops and reaps it
+        self.playing = Some(child);
+
+        if let Some(mut stdin) = stdin
+            && let Err(e) = stdin.write_all(text.as_bytes())
+        {
+            self.stop();
+            return Err(e);
+        }
+        // Dropping stdin closed the pipe so the backend starts speaking
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.playing.take() {
+            kill(&mut child);
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for Speaker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Kills `child` and, when it leads a process group, the whole group
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // Fails with nothing done when the child isn't a group leader
+        // SAFETY: kill only sends a signal, a negative pid names the group
+        if unsafe { libc::kill(-pid, libc::SIGKILL) } == 0 {
+            return;
+        }
+    }
+    let _ = child.kill();
+}
+
+fn default_backend() -> Option<Command> {
+    if in_path("say") {
+        // macOS `say` reads stdin when given no text
+        Some(Command::new("say"))
+    } else if in_path("espeak-ng") {
+        let mut command = Command::new("espeak-ng");
+        command.arg("--stdin");
+        Some(command)
+    } else if in_path("espeak") {
+        let mut command = Command::new("espeak");
+        command.arg("--stdin");
+        Some(command)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stop_kills_pipeline() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pid_file = dir.path().join("pid");
+        let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+        let mut speaker = Speaker::new(Some(command));
+        speaker.speak("hola").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let pid = loop {
+            let text = fs::read_to_string(&pid_file).unwrap_or_default();
+            if text.ends_with('\n') {
+                break text.trim().to_string();
+            }
+            assert!(Instant::now() < deadline, "the command never started");
+            thread::sleep(Duration::from_millis(5));
+        };
+        speaker.stop();
+
+        // Gone, or dead and waiting to be reaped by whoever inherited it
+        let alive = || {
+            fs::read_to_string(format!("/proc/{}/stat", pid))
+                .is_ok_and(|stat| !stat.split(") ").nth(1).unwrap_or_default().starts_with('Z'))
+        };
+        while alive() {
+            assert!(Instant::now() < deadline, "sleep outlived the shell");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_failed_write_reaps_child() {
+        // Exits without reading, so writing a lot hits a closed pipe
+        let mut speaker = Speaker::new(Some("exit 0".to_string()));
+        let text = "hola ".repeat(1 << 16);
+        assert!(speaker.speak(&text).is_err());
+        assert!(speaker.playing.is_none());
+    }
+}
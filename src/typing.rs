@@ -0,0 +1,289 @@
+//! Typing practice: the answer of each card of a topic is shown, and typed
+//! out exactly as it is written, every character marked right or wrong as
+//! it is typed. Speed and accuracy are kept over the run, for commands,
+//! shortcuts and verse that have to be known letter for letter.
+//!
+//! Nothing is graded, so the review history is left as it is.
+
+use std::time::{Duration, Instant};
+
+use mem_flip::deck::Flashcard;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Padding, Paragraph, Widget, Wrap},
+};
+
+use crate::l10n::{hint, tr, trf};
+use crate::markup;
+use crate::theme;
+
+// Characters that make a word, for words a minute
+const WORD_LENGTH: f64 = 5.0;
+// Shortest typing time a speed is given for, as shorter ones are noise
+const MIN_TIME: Duration = Duration::from_secs(1);
+// Shown for a line break still to be typed
+static LINE_BREAK: &str = "⏎";
+
+/// The text of `answer` that is typed out: images are left out, and so are
+/// spaces at its ends.
+pub fn target(answer: &str) -> String {
+    let (text, _) = markup::split_images(answer);
+    text.replace("\r\n", "\n").trim().to_string()
+}
+
+/// The first of `cards` from `from` on with an answer to type.
+pub fn next_to_type(cards: &[Flashcard], from: usize) -> Option<usize> {
+    (from..cards.len()).find(|&i| !target(&cards[i].answer).is_empty())
+}
+
+/// Number of the card at `card_index` among `cards` with an answer to type,
+/// and how many of them there are.
+pub fn position(cards: &[Flashcard], card_index: usize) -> (usize, usize) {
+    let typed: Vec<usize> = (0..cards.len())
+        .filter(|&i| !target(&cards[i].answer).is_empty())
+        .collect();
+    let number = typed.iter().take_while(|&&i| i <= card_index).count();
+    (number, typed.len())
+}
+
+/// A run of typing practice through a topic.
+#[derive(Debug, Clone)]
+pub struct Typing {
+    pub topic: String,
+    // Card being typed, as an index into the topic's cards
+    pub card_index: usize,
+    // What has been typed of the card's answer so far
+    pub typed: String,
+    // Cards typed out in full
+    pub done: usize,
+    // Keys typed over the run, and how many of them were wrong
+    keys: usize,
+    mistakes: usize,
+    // Characters of the cards typed out in full, and the time they took
+    chars: usize,
+    time: Duration,
+    // When the first key of the current card was typed
+    card_start: Option<Instant>,
+}
+
+impl Typing {
+    pub fn new(topic: String, card_index: usize) -> Typing {
+        Typing {
+            topic,
+            card_index,
+            typed: String::new(),
+            done: 0,
+            keys: 0,
+            mistakes: 0,
+            chars: 0,
+            time: Duration::ZERO,
+            card_start: None,
+        }
+    }
+
+    /// Types `c` where the typing has got to in `target`.
+    pub fn type_char(&mut self, c: char, target: &str, now: Instant) {
+        self.card_start.get_or_insert(now);
+        self.keys += 1;
+        if target.chars().nth(self.typed.chars().count()) != Some(c) {
+            self.mistakes += 1;
+        }
+        self.typed.push(c);
+    }
+
+    pub fn backspace(&mut self) {
+        self.typed.pop();
+    }
+
+    /// Whether `target` has been typed out, with every mistake corrected.
+    pub fn is_complete(&self, target: &str) -> bool {
+        self.typed == target
+    }
+
+    /// Moves on to the card at `card_index`. The card left counts towards
+    /// the speed if it was typed out in full, and not if it was skipped.
+    pub fn next_card(&mut self, card_index: usize, target: &str, now: Instant) {
+        if self.is_complete(target)
+            && let Some(start) = self.card_start
+        {
+            self.chars += target.chars().count();
+            self.time += now.saturating_duration_since(start);
+            self.done += 1;
+        }
+        self.card_index = card_index;
+        self.typed.clear();
+        self.card_start = None;
+    }
+
+    /// Words a minute, of five characters, over the cards typed out and the
+    /// characters typed right of `target` so far. `None` before a second
+    /// of typing.
+    pub fn wpm(&self, target: &str, now: Instant) -> Option<f64> {
+        let current = self
+            .card_start
+            .map_or(Duration::ZERO, |start| now.saturating_duration_since(start));
+        let time = self.time + current;
+        if time < MIN_TIME {
+            return None;
+        }
+        let right = self
+            .typed
+            .chars()
+            .zip(target.chars())
+            .filter(|(typed, expected)| typed == expected)
+            .count();
+        let words = (self.chars + right) as f64 / WORD_LENGTH;
+        Some(words * 60.0 / time.as_secs_f64())
+    }
+
+    /// Percentage of the keys typed that were right, `None` before any.
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.keys > 0).then(|| (self.keys - self.mistakes) as f64 * 100.0 / self.keys as f64)
+    }
+
+    /// The speed and accuracy, such as "42 WPM, 96% accurate".
+    pub fn stats(&self, target: &str, now: Instant) -> String {
+        let wpm = self
+            .wpm(target, now)
+            .map_or("–".to_string(), |wpm| format!("{:.0}", wpm));
+        let accuracy = self
+            .accuracy()
+            .map_or("–".to_string(), |accuracy| format!("{:.0}", accuracy));
+        format!("{} WPM, {}% accurate", wpm, accuracy)
+    }
+}
+
+pub struct TypingView<'a> {
+    pub typing: &'a Typing,
+    pub question: &'a str,
+    pub target: &'a str,
+    // Number of the card among those with an answer to type, and how many
+    // there are
+    pub position: (usize, usize),
+    pub now: Instant,
+}
+
+impl TypingView<'_> {
+    // The target, each character styled by whether it was typed right,
+    // typed wrong, is next or is still to come
+    fn target_lines(&self) -> Vec<Line<'static>> {
+        let typed: Vec<char> = self.typing.typed.chars().collect();
+        let mut lines = vec![Line::default()];
+        for (i, expected) in self.target.chars().enumerate() {
+            let shown = match expected {
+                '\n' => LINE_BREAK.to_string(),
+                // A space typed wrong has to be seen
+                ' ' if typed.get(i).is_some_and(|&c| c != ' ') => "_".to_string(),
+                c => c.to_string(),
+            };
+            let span = match typed.get(i) {
+                Some(&c) if c == expected => Span::from(shown).green(),
+                Some(_) => Span::from(shown).red().underlined(),
+                None if i == typed.len() => Span::from(shown).reversed(),
+                None => Span::from(shown).dim(),
+            };
+            lines.last_mut().unwrap().push_span(span);
+            if expected == '\n' {
+                lines.push(Line::default());
+            }
+        }
+        // Typed past the end, to be taken back
+        let extra: String = typed.iter().skip(self.target.chars().count()).collect();
+        if !extra.is_empty() {
+            let extra = extra.replace('\n', LINE_BREAK);
+            lines
+                .last_mut()
+                .unwrap()
+                .push_span(extra.red().crossed_out());
+        }
+        lines
+    }
+}
+
+impl Widget for TypingView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let hints = Line::from(vec![
+            hint("Skip"),
+            "<Tab>".blue().bold(),
+            hint("Back"),
+            "<Esc> ".red().bold(),
+        ]);
+        let title = format!(
+            " ⌨ {} ",
+            trf(
+                "{} · card {} of {}",
+                &[&self.typing.topic, &self.position.0, &self.position.1]
+            )
+        );
+
+        let mut lines = vec![Line::from(tr("Question").cyan().bold())];
+        lines.extend(
+            self.question
+                .lines()
+                .map(|line| Line::from(line.to_string())),
+        );
+        lines.push(Line::default());
+        lines.push(Line::from(tr("Type the answer").green().bold()));
+        lines.extend(self.target_lines());
+        lines.push(Line::default());
+        lines.push(Line::from(
+            trf(
+                "{} · {} typed",
+                &[&self.typing.stats(self.target, self.now), &self.typing.done],
+            )
+            .dim(),
+        ));
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::bordered()
+                    .title(title.bold())
+                    .title_bottom(hints)
+                    .padding(Padding::horizontal(1))
+                    .style(Style::default().fg(theme::palette().accent)),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typing() {
+        let start = Instant::now();
+        let target = target("  git status\n![diagram](a.png)  ");
+        assert_eq!(target, "git status");
+
+        let mut typing = Typing::new("Git".to_string(), 0);
+        for c in "git x".chars() {
+            typing.type_char(c, &target, start);
+        }
+        assert!(!typing.is_complete(&target));
+        typing.backspace();
+        for c in "status".chars() {
+            typing.type_char(c, &target, start);
+        }
+        assert!(typing.is_complete(&target));
+        // One wrong key of the eleven typed
+        assert_eq!(typing.accuracy().map(f64::round), Some(91.0));
+        assert_eq!(typing.wpm(&target, start), None);
+
+        typing.next_card(1, &target, start + Duration::from_secs(6));
+        assert_eq!(typing.done, 1);
+        assert!(typing.typed.is_empty());
+        // Ten characters in six seconds
+        let later = start + Duration::from_secs(10);
+        assert_eq!(typing.wpm("ls", later).map(f64::round), Some(20.0));
+        assert_eq!(typing.stats("ls", later), "20 WPM, 91% accurate");
+
+        // A skipped card doesn't count
+        typing.type_char('l', "ls", later);
+        typing.next_card(2, "ls", later + Duration::from_secs(60));
+        assert_eq!(typing.done, 1);
+    }
+}